@@ -0,0 +1,9 @@
+use std::env;
+
+fn main() {
+    // Used to select the matching release asset in `tyedev self-update`.
+    if let Ok(target) = env::var("TARGET") {
+        println!("cargo:rustc-env=TYEDEV_TARGET={target}");
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
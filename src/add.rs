@@ -0,0 +1,327 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::Args;
+use serde_json::{Map, Value};
+
+use crate::github;
+use crate::lock::{self, Lockfile};
+use crate::oci_ref::OciReference;
+use crate::policy;
+use crate::registry;
+use crate::workspace;
+
+#[derive(Debug, Args)]
+pub struct AddArgs {
+    /// A devcontainer.json to copy the features of, as a path or an `https://` URL of the raw file.
+    #[arg(long, value_name = "PATH_OR_URL")]
+    from: String,
+
+    /// Copy only these features, by id or by the key as written, rather than all of them.
+    #[arg(value_name = "FEATURE")]
+    features: Vec<String>,
+
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Replace the options of features the workspace already has, rather than keeping them.
+    #[arg(long)]
+    replace: bool,
+
+    /// Record the versions and digests of the features added in `tyedev.lock`.
+    #[arg(long)]
+    lock: bool,
+
+    /// Refuse features that `tyedev.lock` does not have, or that resolve to another digest.
+    #[arg(long)]
+    locked: bool,
+}
+
+/// The id of a key of the `features` object, or the key itself when it is not an OCI reference.
+fn feature_id(key: &str) -> String {
+    key.parse::<OciReference>()
+        .map_or_else(|_| key.to_string(), |reference| reference.id())
+}
+
+/// Contents of the devcontainer.json to copy from.
+async fn read_source(from: &str) -> anyhow::Result<String> {
+    if from.starts_with("https://") || from.starts_with("http://") {
+        let bytes = github::download(from).await?;
+
+        Ok(String::from_utf8(bytes).with_context(|| format!("{from} is not text"))?)
+    } else {
+        Ok(fs::read_to_string(from).with_context(|| format!("Unable to read {from}"))?)
+    }
+}
+
+/// The features of the source to copy, in the order of the source. Features in a folder next to the source are left
+/// out, since the workspace does not have the folder.
+fn select_features(source: &Value, filters: &[String], from: &str) -> anyhow::Result<Map<String, Value>> {
+    let Some(Value::Object(features)) = source.get("features") else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{from} has no features"),
+        ))?
+    };
+    let unmatched: Vec<&str> = filters
+        .iter()
+        .filter(|filter| !features.keys().any(|key| key == *filter || feature_id(key) == **filter))
+        .map(String::as_str)
+        .collect();
+
+    if !unmatched.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{from} does not have {}", unmatched.join(", ")),
+        ))?
+    }
+
+    let selected = features
+        .iter()
+        .filter(|(key, _)| {
+            filters.is_empty()
+                || filters
+                    .iter()
+                    .any(|filter| filter == *key || *filter == feature_id(key))
+        })
+        .filter(|(key, _)| {
+            let is_folder = key.starts_with("./") || key.starts_with("../");
+
+            if is_folder {
+                log::warn!("{key} is a folder next to {from}, skipped");
+            }
+
+            !is_folder
+        })
+        .map(|(key, options)| (key.clone(), options.clone()))
+        .collect();
+
+    Ok(selected)
+}
+
+/// Add `features` to the `features` object of `config`, returning the keys added and the keys of features it already
+/// had. A feature the config has under another version is kept as is, unless `replace` is given.
+fn merge_features(config: &mut Value, features: Map<String, Value>, replace: bool) -> (Vec<String>, Vec<String>) {
+    let mut added = Vec::new();
+    let mut kept = Vec::new();
+    let Some(config) = config.as_object_mut() else {
+        return (added, kept);
+    };
+    let entries = config.entry("features").or_insert_with(|| Value::Object(Map::new()));
+
+    if !entries.is_object() {
+        *entries = Value::Object(Map::new());
+    }
+
+    let Some(entries) = entries.as_object_mut() else {
+        return (added, kept);
+    };
+
+    for (key, options) in features {
+        let existing = entries
+            .keys()
+            .find(|existing| feature_id(existing) == feature_id(&key))
+            .cloned();
+
+        match existing {
+            Some(existing) if !replace => kept.push(existing),
+            Some(existing) => {
+                entries.remove(&existing);
+                entries.insert(key.clone(), options);
+                added.push(key);
+            },
+            None => {
+                entries.insert(key.clone(), options);
+                added.push(key);
+            },
+        }
+    }
+
+    (added, kept)
+}
+
+/// The lockfile entries of the added features that come from a registry, checked against the lock of the workspace
+/// when `locked` is given.
+async fn pulled_features(workspace: &Path, added: &[String], locked: bool) -> anyhow::Result<Lockfile> {
+    let lockfile = match locked {
+        true => Some(lock::require_lockfile(workspace)?),
+        false => None,
+    };
+    let references: Vec<OciReference> = added
+        .iter()
+        .filter_map(|key| key.parse::<OciReference>().ok())
+        .filter(|reference| !reference.is_local())
+        .collect();
+
+    if let Some(lockfile) = &lockfile {
+        for reference in &references {
+            lockfile.require(reference)?;
+        }
+    }
+
+    let mut pulled = Lockfile::default();
+
+    for reference in references {
+        let feature = registry::pull_feature_configuration(&reference).await?;
+
+        if let Some(artifact) = lock::locked_artifact(&reference, &feature.version, reference.digest()).await? {
+            pulled.features.insert(reference.to_string(), artifact);
+        }
+    }
+
+    if let Some(lockfile) = &lockfile {
+        lockfile.verify(&pulled)?;
+    }
+
+    Ok(pulled)
+}
+
+/// Copy the features of another devcontainer.json into the one of the workspace.
+pub async fn add(
+    AddArgs {
+        from,
+        features,
+        workspace_folder,
+        replace,
+        lock,
+        locked,
+    }: AddArgs,
+) -> anyhow::Result<()> {
+    log::debug!("add");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let Some(file) = workspace::devcontainer_files(&workspace)?.into_iter().next() else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "No devcontainer.json in {}, create one with `{} init`",
+                workspace.display(),
+                env!("CARGO_BIN_NAME")
+            ),
+        ))?
+    };
+    let source: Value =
+        serde_jsonc::from_str(&read_source(&from).await?).with_context(|| format!("Invalid JSON in {from}"))?;
+    let selected = select_features(&source, &features, &from)?;
+    let contents = fs::read_to_string(&file)?;
+    let mut config = workspace::read_config(&file)?;
+    let (added, kept) = merge_features(&mut config, selected, replace);
+    let display_name = file.strip_prefix(&workspace).unwrap_or(&file).display().to_string();
+
+    for key in &kept {
+        println!("Kept {key}, already in {display_name}");
+    }
+
+    if added.is_empty() {
+        println!("No features added to {display_name}");
+        return Ok(());
+    }
+
+    policy::read_policy()?.enforce_feature_keys(&added).await?;
+
+    let pulled = match lock || locked {
+        true => Some(pulled_features(&workspace, &added, locked).await?),
+        false => None,
+    };
+
+    workspace::write_config(&file, &contents, &config, &display_name)?;
+
+    if let Some(pulled) = pulled.filter(|_| lock) {
+        let mut lockfile = lock::read_lockfile(&workspace)?.unwrap_or_default();
+        lockfile.features.extend(pulled.features);
+        lock::write_lockfile(&workspace, &lockfile)?;
+    }
+
+    for key in &added {
+        println!("Added {key}");
+    }
+
+    log::debug!("add: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_features, select_features};
+    use serde_json::json;
+
+    #[test]
+    fn test_select_features() -> anyhow::Result<()> {
+        let source = json!({
+            "features": {
+                "ghcr.io/devcontainers/features/node:1": {"version": "20"},
+                "ghcr.io/devcontainers/features/go:1": {},
+                "./local-feature": {}
+            }
+        });
+
+        let all = select_features(&source, &[], "other.json")?;
+        assert_eq!(
+            all.keys().collect::<Vec<_>>(),
+            [
+                "ghcr.io/devcontainers/features/node:1",
+                "ghcr.io/devcontainers/features/go:1"
+            ]
+        );
+
+        let filtered = select_features(
+            &source,
+            &["ghcr.io/devcontainers/features/node".to_string()],
+            "other.json",
+        )?;
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(
+            filtered["ghcr.io/devcontainers/features/node:1"],
+            json!({"version": "20"})
+        );
+
+        assert!(select_features(
+            &source,
+            &["ghcr.io/devcontainers/features/rust".to_string()],
+            "other.json"
+        )
+        .is_err());
+        assert!(select_features(&json!({"image": "rust"}), &[], "other.json").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_features() -> anyhow::Result<()> {
+        let features = select_features(
+            &json!({
+                "features": {
+                    "ghcr.io/devcontainers/features/node:2": {"version": "22"},
+                    "ghcr.io/devcontainers/features/go:1": {}
+                }
+            }),
+            &[],
+            "other.json",
+        )?;
+
+        let mut config = json!({"features": {"ghcr.io/devcontainers/features/node:1": {}}});
+        let (added, kept) = merge_features(&mut config, features.clone(), false);
+        assert_eq!(added, ["ghcr.io/devcontainers/features/go:1"]);
+        assert_eq!(kept, ["ghcr.io/devcontainers/features/node:1"]);
+        assert_eq!(config["features"]["ghcr.io/devcontainers/features/node:1"], json!({}));
+
+        let mut config = json!({"image": "rust", "features": {"ghcr.io/devcontainers/features/node:1": {}}});
+        let (added, kept) = merge_features(&mut config, features, true);
+        assert_eq!(added.len(), 2);
+        assert!(kept.is_empty());
+        assert_eq!(
+            config["features"],
+            json!({
+                "ghcr.io/devcontainers/features/node:2": {"version": "22"},
+                "ghcr.io/devcontainers/features/go:1": {}
+            })
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::atomic_write;
+use crate::init::InitReport;
+
+/// The choices of an init, read by `tyedev init --answers-file` and written by `--save-answers`.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Answers {
+    /// Reference of the template, a bare image is used when missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Display name of the devcontainer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Values of template options by name, the default of the template for the others.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub options: Map<String, Value>,
+    /// Features to add with the values of their options, as in the `features` object of devcontainer.json.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub features: Map<String, Value>,
+}
+
+impl Answers {
+    /// The answers that repeat the init of the report.
+    pub fn from_report(report: &InitReport) -> Self {
+        // The image start point is what a missing template replays as, starting from scratch is not.
+        if report.template.is_none() && !report.template_options.contains_key("image") {
+            log::warn!("Starting from scratch is not saved, the answers start from a bare image instead");
+        }
+
+        Answers {
+            template: report.template.clone(),
+            name: report.name.clone(),
+            options: (report.template_options.iter())
+                .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                .collect(),
+            features: (report.features.iter())
+                .map(|feature| (feature.reference.clone(), feature.options.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Read an answers file.
+pub fn read_answers(filename: &Path) -> anyhow::Result<Answers> {
+    log::debug!("read_answers: {}", filename.display());
+
+    let contents = fs::read_to_string(filename)?;
+    let answers = serde_yaml_ng::from_str(&contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid answers file {}: {error}", filename.display()),
+        )
+    })?;
+
+    Ok(answers)
+}
+
+/// Write an answers file, replacing any file already there.
+pub fn save_answers(filename: &Path, answers: &Answers) -> anyhow::Result<()> {
+    log::debug!("save_answers: {}", filename.display());
+
+    atomic_write::write(filename, serde_yaml_ng::to_string(answers)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Answers;
+    use serde_json::json;
+
+    #[test]
+    fn test_answers_yaml() -> anyhow::Result<()> {
+        let answers: Answers = serde_yaml_ng::from_str(
+            "template: ghcr.io/devcontainers/templates/rust:5\n\
+             options:\n  imageVariant: bookworm\n\
+             features:\n  ghcr.io/devcontainers/features/node:1:\n    version: lts\n    installYarn: false\n",
+        )?;
+
+        assert_eq!(
+            answers,
+            Answers {
+                template: Some("ghcr.io/devcontainers/templates/rust:5".into()),
+                name: None,
+                options: json!({ "imageVariant": "bookworm" })
+                    .as_object()
+                    .cloned()
+                    .unwrap_or_default(),
+                features:
+                    json!({ "ghcr.io/devcontainers/features/node:1": { "version": "lts", "installYarn": false } })
+                        .as_object()
+                        .cloned()
+                        .unwrap_or_default(),
+            }
+        );
+        assert_eq!(
+            serde_yaml_ng::from_str::<Answers>(&serde_yaml_ng::to_string(&answers)?)?,
+            answers
+        );
+        assert!(serde_yaml_ng::from_str::<Answers>("template: x\nworkspace: .\n").is_err());
+
+        Ok(())
+    }
+}
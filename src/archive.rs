@@ -0,0 +1,293 @@
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use tar::{Archive, EntryType};
+
+/// Largest single file accepted from an archive.
+pub const MAX_ENTRY_SIZE: u64 = 16 * 1024 * 1024;
+
+/// An entry of a tar archive, without its contents.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchiveEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub entry_type: EntryType,
+}
+
+/// Validate that an archive entry path stays inside the directory it is extracted to.
+fn safe_relative_path(path: &Path) -> io::Result<PathBuf> {
+    let mut relative_path = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative_path.push(part),
+            Component::CurDir => (),
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Refusing to extract {} from the archive, it points outside of the folder it is extracted to",
+                    path.display()
+                ),
+            ))?,
+        }
+    }
+
+    Ok(relative_path)
+}
+
+/// What an entry handed out by [`extract_to`] holds.
+#[derive(Debug, PartialEq)]
+pub enum Extracted {
+    Directory,
+    File(Vec<u8>),
+    /// A symlink or hard link, which is never followed or created.
+    Link,
+    Other(EntryType),
+}
+
+/// An entry of an archive with its path resolved below the destination of [`extract_to`].
+#[derive(Debug, PartialEq)]
+pub struct ExtractedEntry {
+    pub path: PathBuf,
+    pub mode: Option<u32>,
+    pub contents: Extracted,
+}
+
+/// Hand every entry of the archive to `extract`, in archive order, with its path joined onto `destination`. The
+/// caller writes each entry, so that it can render or skip it. An entry whose path leaves `destination`, or a file
+/// larger than [`MAX_ENTRY_SIZE`], fails the extraction. The whole archive is read and checked first, so nothing is
+/// handed out from an archive that fails.
+pub fn extract_to<R: Read>(
+    reader: R,
+    destination: &Path,
+    extract: impl FnMut(ExtractedEntry) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut entries = Vec::new();
+
+    for entry in Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        let path = destination.join(safe_relative_path(&entry.path()?)?);
+        let contents = match entry.header().entry_type() {
+            EntryType::Directory => Extracted::Directory,
+            EntryType::Regular | EntryType::Continuous if entry.size() > MAX_ENTRY_SIZE => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Refusing to extract {} from the archive, it is larger than {MAX_ENTRY_SIZE} bytes",
+                    path.display()
+                ),
+            ))?,
+            EntryType::Regular | EntryType::Continuous => {
+                let mut data: Vec<u8> = Vec::with_capacity(entry.size() as usize);
+                entry.read_to_end(&mut data)?;
+                Extracted::File(data)
+            },
+            EntryType::Symlink | EntryType::Link => Extracted::Link,
+            entry_type => Extracted::Other(entry_type),
+        };
+
+        entries.push(ExtractedEntry {
+            path,
+            mode: entry.header().mode().ok(),
+            contents,
+        });
+    }
+
+    entries.into_iter().try_for_each(extract)
+}
+
+/// Path of an entry as shown to users, without the leading `./` that packaging tools add.
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().trim_start_matches("./").to_string()
+}
+
+/// Read the contents of the first entry whose path `matches`, along with that path. The archive is read only as far
+/// as the entry.
+pub fn find_entry<R: Read>(
+    reader: R,
+    mut matches: impl FnMut(&Path) -> bool,
+) -> io::Result<Option<(PathBuf, Vec<u8>)>> {
+    for entry in Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+
+        if matches(&path) {
+            let mut data: Vec<u8> = Vec::with_capacity(entry.size() as usize);
+            entry.read_to_end(&mut data)?;
+
+            return Ok(Some((path, data)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Every entry of the archive, in archive order.
+pub fn list_entries<R: Read>(reader: R) -> io::Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+
+    for entry in Archive::new(reader).entries()? {
+        let entry = entry?;
+
+        entries.push(ArchiveEntry {
+            path: entry.path()?.into_owned(),
+            size: entry.size(),
+            entry_type: entry.header().entry_type(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// The contents of the regular files that `select` accepts, given the path and size of each, by path without the
+/// leading `./`.
+pub fn read_files<R: Read>(
+    reader: R,
+    mut select: impl FnMut(&Path, u64) -> bool,
+) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+
+    for entry in Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+
+        if !entry.header().entry_type().is_file() || !select(&path, entry.size()) {
+            continue;
+        }
+
+        let mut data: Vec<u8> = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        files.push((display_path(&path), data));
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_to, find_entry, list_entries, read_files, safe_relative_path, Extracted};
+    use std::path::{Path, PathBuf};
+    use tar::{Builder, EntryType, Header};
+
+    fn archive() -> std::io::Result<Vec<u8>> {
+        let mut builder = Builder::new(Vec::new());
+        let mut append = |path: &str, data: &[u8], entry_type: EntryType| {
+            let mut header = Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, data)
+        };
+
+        append("./.devcontainer/", b"", EntryType::Directory)?;
+        append("./.devcontainer/devcontainer.json", b"{}", EntryType::Regular)?;
+        append(
+            "./devcontainer-template.json",
+            b"{\"id\": \"rust\"}",
+            EntryType::Regular,
+        )?;
+
+        builder.into_inner()
+    }
+
+    #[test]
+    fn test_find_entry() -> std::io::Result<()> {
+        let bytes = archive()?;
+        let found = find_entry(bytes.as_slice(), |path| path.ends_with("devcontainer-template.json"))?;
+
+        let (path, data) = found.unwrap_or_default();
+
+        assert!(path.ends_with("devcontainer-template.json"));
+        assert_eq!(data, b"{\"id\": \"rust\"}");
+        assert_eq!(find_entry(bytes.as_slice(), |path| path.ends_with("install.sh"))?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_entries() -> std::io::Result<()> {
+        let entries = list_entries(archive()?.as_slice())?;
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].entry_type, EntryType::Directory);
+        assert_eq!(entries[1].size, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_relative_path() -> std::io::Result<()> {
+        assert_eq!(
+            safe_relative_path(Path::new("./.devcontainer/devcontainer.json"))?,
+            PathBuf::from(".devcontainer/devcontainer.json")
+        );
+        assert!(safe_relative_path(Path::new("../outside.sh")).is_err());
+        assert!(safe_relative_path(Path::new(".devcontainer/../../outside.sh")).is_err());
+        assert!(safe_relative_path(Path::new("/etc/passwd")).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_to() -> anyhow::Result<()> {
+        let mut entries = Vec::new();
+
+        extract_to(archive()?.as_slice(), Path::new("/workspace"), |entry| {
+            entries.push((entry.path, entry.contents));
+            Ok(())
+        })?;
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[1],
+            (
+                PathBuf::from("/workspace/.devcontainer/devcontainer.json"),
+                Extracted::File(b"{}".to_vec())
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_to_checks_every_entry_first() -> anyhow::Result<()> {
+        let mut builder = Builder::new(Vec::new());
+        let mut append = |path: &[u8], data: &[u8]| {
+            let mut header = Header::new_gnu();
+            // Written into the header directly, `Header::set_path` refuses `..` itself.
+            header.as_old_mut().name[..path.len()].copy_from_slice(path);
+            header.set_entry_type(EntryType::Regular);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, data)
+        };
+
+        append(b".devcontainer/devcontainer.json", b"{}")?;
+        append(b"../escape.txt", b"outside")?;
+
+        let bytes = builder.into_inner()?;
+        let mut extracted = 0;
+        let result = extract_to(bytes.as_slice(), Path::new("/workspace"), |_| {
+            extracted += 1;
+            Ok(())
+        });
+
+        assert!(result.is_err());
+        assert_eq!(extracted, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_files() -> std::io::Result<()> {
+        let files = read_files(archive()?.as_slice(), |_, size| size < 10)?;
+
+        assert_eq!(
+            files,
+            vec![(".devcontainer/devcontainer.json".to_string(), b"{}".to_vec())]
+        );
+
+        Ok(())
+    }
+}
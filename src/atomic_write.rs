@@ -0,0 +1,136 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Sibling path used as the staging file, so the final rename never crosses a filesystem boundary.
+fn temporary_path(path: &Path) -> io::Result<PathBuf> {
+    let filename = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot write to a path without a filename: {}", path.display()),
+        )
+    })?;
+    let mut temporary_name = std::ffi::OsString::from(".");
+    temporary_name.push(filename);
+    temporary_name.push(format!(".tyedev-{}.tmp", std::process::id()));
+
+    Ok(path.with_file_name(temporary_name))
+}
+
+/// Flush the directory entry itself, otherwise a crash may lose the rename.
+#[cfg(unix)]
+fn sync_directory(directory: &Path) -> io::Result<()> {
+    File::open(directory)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_directory(_directory: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Write `contents` to a temporary file next to `path`, then rename it into place.
+///
+/// Readers of `path` observe either the previous content or the complete new content, never a truncated file.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    write_inner(path.as_ref(), contents.as_ref(), None, None)
+}
+
+/// Same as `write`, with the file being replaced moved to `previous` just before the new content takes its place. The
+/// new content is staged first, so a failed write leaves `path` as it was.
+pub fn write_keeping_previous<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    contents: C,
+    previous: &Path,
+) -> io::Result<()> {
+    write_inner(path.as_ref(), contents.as_ref(), None, Some(previous))
+}
+
+/// Same as `write`, with the given permissions applied before the file is moved into place.
+pub fn write_with_permissions<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    contents: C,
+    permissions: fs::Permissions,
+) -> io::Result<()> {
+    write_inner(path.as_ref(), contents.as_ref(), Some(permissions), None)
+}
+
+fn write_inner(
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<fs::Permissions>,
+    previous: Option<&Path>,
+) -> io::Result<()> {
+    let temporary = temporary_path(path)?;
+    log::debug!("atomic_write::write: staging {}", temporary.display());
+
+    let result = File::create(&temporary).and_then(|mut file| {
+        file.write_all(contents)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.sync_all()
+    });
+    let result = result.and_then(|_| match previous.filter(|_| path.exists()) {
+        Some(previous) => {
+            fs::rename(path, previous)?;
+            fs::rename(&temporary, path).inspect_err(|_| {
+                // Best effort, put the replaced file back so that it is not lost along with the new content.
+                let _ = fs::rename(previous, path);
+            })
+        },
+        None => fs::rename(&temporary, path),
+    });
+
+    if result.is_err() {
+        // Best effort, the original error is more interesting than a failure to clean up.
+        let _ = fs::remove_file(&temporary);
+        return result;
+    }
+
+    match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        Some(parent) => sync_directory(parent),
+        None => sync_directory(Path::new(".")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write, write_keeping_previous};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_write_replaces_content() -> std::io::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-atomic-write-{}", std::process::id()));
+        fs::create_dir_all(&directory)?;
+        let filename = directory.join("devcontainer.json");
+
+        write(&filename, b"{\"name\": \"first\"}")?;
+        write(&filename, b"{}")?;
+
+        assert_eq!(fs::read(&filename)?, b"{}");
+        assert_eq!(fs::read_dir(&directory)?.count(), 1);
+
+        fs::remove_dir_all(&directory)
+    }
+
+    #[test]
+    fn test_write_keeping_previous() -> std::io::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-atomic-write-previous-{}", std::process::id()));
+        fs::create_dir_all(&directory)?;
+        let filename = directory.join("index.json");
+        let previous = directory.join("index.previous.json");
+
+        write_keeping_previous(&filename, b"1", &previous)?;
+        assert!(!previous.exists());
+
+        write_keeping_previous(&filename, b"2", &previous)?;
+        assert_eq!(fs::read(&filename)?, b"2");
+        assert_eq!(fs::read(&previous)?, b"1");
+
+        assert!(write_keeping_previous(directory.join("missing").join("index.json"), b"3", &previous).is_err());
+        assert_eq!(fs::read(&previous)?, b"1");
+
+        fs::remove_dir_all(&directory)
+    }
+}
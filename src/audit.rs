@@ -0,0 +1,379 @@
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::archive;
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry;
+use crate::theme;
+use crate::workspace::{self, FeatureSource};
+
+/// Scripts larger than this are not scanned, an install script is a few kilobytes.
+const MAX_SCRIPT_SIZE: u64 = 1024 * 1024;
+
+/// Longest excerpt of a line shown with a finding.
+const MAX_EXCERPT_LENGTH: usize = 100;
+
+#[derive(Debug, Args)]
+pub struct AuditArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Exit with an error when there is a finding of at least this severity.
+    #[arg(long, value_name = "SEVERITY")]
+    fail_on: Option<Severity>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Low => write!(f, "low"),
+            Self::Medium => write!(f, "medium"),
+            Self::High => write!(f, "high"),
+        }
+    }
+}
+
+/// A risky pattern of shell scripts.
+struct Rule {
+    name: &'static str,
+    severity: Severity,
+    pattern: &'static str,
+    /// Lines also matching this pattern are fine, such as installs with pinned versions.
+    unless: Option<&'static str>,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        name: "pipe-to-shell",
+        severity: Severity::High,
+        pattern: r"\b(curl|wget)\b[^|]*\|\s*(sudo(\s+-\S+)*\s+)?(\S*/)?(ba|da|z)?sh\b",
+        unless: None,
+    },
+    Rule {
+        name: "insecure-download",
+        severity: Severity::High,
+        pattern: r"\b(curl\b.*\s(-k|--insecure)\b|wget\b.*\s--no-check-certificate\b)",
+        unless: None,
+    },
+    Rule {
+        name: "sudo",
+        severity: Severity::Medium,
+        pattern: r"\bsudo\b|\bsu\s+(-\s+)?root\b",
+        unless: None,
+    },
+    Rule {
+        name: "write-etc",
+        severity: Severity::Medium,
+        pattern: r"(>>?|\btee\s+(-a\s+)?)\s*/etc/",
+        unless: None,
+    },
+    Rule {
+        name: "world-writable",
+        severity: Severity::Medium,
+        pattern: r"\bchmod\s+(-R\s+)?(0?777|a\+w)\b",
+        unless: None,
+    },
+    Rule {
+        name: "unpinned-install",
+        severity: Severity::Low,
+        pattern: r"\b(apt-get|apt|apk|yum|dnf|pip3?|npm)\s+(-\S+\s+)*(install|add)\s+(-\S+\s+)*[a-z]",
+        unless: Some(r"[=@]\d|==|[=@]\$\{?\w+|\$\{?\w*(?i:version)"),
+    },
+];
+
+/// One risky line of a feature script.
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    /// The feature as written in the devcontainer.json.
+    pub feature: String,
+    pub file: String,
+    pub line: usize,
+    pub severity: Severity,
+    pub rule: String,
+    pub excerpt: String,
+}
+
+/// A feature that could not be audited, such as one that failed to download.
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditError {
+    /// The feature as written in the devcontainer.json.
+    pub feature: String,
+    pub message: String,
+}
+
+/// Findings of the features that were audited, and the features that could not be.
+#[derive(Clone, Debug, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditReport {
+    pub findings: Vec<Finding>,
+    pub errors: Vec<AuditError>,
+}
+
+/// Lines of a script matching a rule, without the feature filled in. Comment lines are skipped.
+fn scan_script(file: &str, contents: &str) -> anyhow::Result<Vec<Finding>> {
+    let mut rules = Vec::with_capacity(RULES.len());
+    for rule in RULES {
+        let unless = rule.unless.map(Regex::new).transpose()?;
+        rules.push((rule, Regex::new(rule.pattern)?, unless));
+    }
+
+    let mut findings = Vec::new();
+
+    for (number, line) in contents.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        for (rule, pattern, unless) in &rules {
+            if pattern.is_match(trimmed) && !unless.as_ref().is_some_and(|unless| unless.is_match(trimmed)) {
+                findings.push(Finding {
+                    feature: String::new(),
+                    file: file.to_string(),
+                    line: number + 1,
+                    severity: rule.severity,
+                    rule: rule.name.to_string(),
+                    excerpt: trimmed.chars().take(MAX_EXCERPT_LENGTH).collect(),
+                });
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Shell scripts are recognized by extension, or by a shebang line.
+fn is_script(name: &str, contents: &str) -> bool {
+    name.ends_with(".sh") || name.ends_with(".bash") || contents.starts_with("#!")
+}
+
+/// Scripts of a feature archive, by their path within the archive.
+fn archive_scripts(bytes: &[u8]) -> io::Result<Vec<(String, String)>> {
+    let mut scripts = Vec::new();
+
+    for (name, contents) in archive::read_files(bytes, |_, size| size <= MAX_SCRIPT_SIZE)? {
+        // Binaries are not scripts.
+        if let Ok(contents) = String::from_utf8(contents) {
+            if is_script(&name, &contents) {
+                scripts.push((name, contents));
+            }
+        }
+    }
+
+    Ok(scripts)
+}
+
+/// Scripts of a feature folder, by their path within the folder.
+fn folder_scripts(folder: &Path) -> io::Result<Vec<(String, String)>> {
+    fn walk(root: &Path, directory: &Path, scripts: &mut Vec<(String, String)>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+
+            if file_type.is_dir() {
+                walk(root, &path, scripts)?;
+            } else if file_type.is_file() && entry.metadata()?.len() <= MAX_SCRIPT_SIZE {
+                let name = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().to_string();
+
+                if let Ok(contents) = fs::read_to_string(&path) {
+                    if is_script(&name, &contents) {
+                        scripts.push((name, contents));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut scripts = Vec::new();
+    walk(folder, folder, &mut scripts)?;
+    scripts.sort();
+
+    Ok(scripts)
+}
+
+async fn feature_scripts(source: &FeatureSource) -> anyhow::Result<Vec<(String, String)>> {
+    match source {
+        FeatureSource::Registry(reference) => Ok(archive_scripts(&registry::pull_archive_bytes(reference).await?)?),
+        FeatureSource::Folder(folder) => Ok(folder_scripts(folder)?),
+        FeatureSource::Url(url) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Features downloaded from {url} are not supported"),
+        ))?,
+    }
+}
+
+fn print_report(AuditReport { findings, errors }: &AuditReport, audited: usize) {
+    let theme = theme::current();
+    let mut feature = None;
+
+    for finding in findings {
+        if feature != Some(&finding.feature) {
+            println!("{}", theme.header.paint(&finding.feature));
+            feature = Some(&finding.feature);
+        }
+
+        let severity = format!("{:<6}", finding.severity.to_string());
+        let severity = match finding.severity {
+            Severity::High => theme.error.paint(severity),
+            Severity::Medium => theme.warning.paint(severity),
+            Severity::Low => severity,
+        };
+
+        println!(
+            "  {severity} {}:{} {}: {}",
+            finding.file, finding.line, finding.rule, finding.excerpt
+        );
+    }
+
+    if !errors.is_empty() {
+        println!("{}", theme.header.paint("Not audited"));
+
+        for error in errors {
+            println!("  {} {}: {}", theme.error.paint("error "), error.feature, error.message);
+        }
+    }
+
+    let count = |severity| findings.iter().filter(|finding| finding.severity == severity).count();
+
+    println!(
+        "{audited} features audited, {} high, {} medium, {} low",
+        count(Severity::High),
+        count(Severity::Medium),
+        count(Severity::Low)
+    );
+}
+
+pub async fn audit(
+    AuditArgs {
+        workspace_folder,
+        fail_on,
+    }: AuditArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("audit");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let features = workspace::workspace_features(&workspace)?;
+    let mut report = AuditReport::default();
+
+    for feature in &features {
+        let scripts = match feature_scripts(&feature.source).await {
+            Ok(scripts) => scripts,
+            Err(error) => {
+                log::debug!("audit: {} not audited: {error:#}", feature.key);
+                report.errors.push(AuditError {
+                    feature: feature.key.clone(),
+                    message: format!("{error:#}"),
+                });
+                continue;
+            },
+        };
+
+        for (file, contents) in scripts {
+            report
+                .findings
+                .extend(scan_script(&file, &contents)?.into_iter().map(|finding| Finding {
+                    feature: feature.key.clone(),
+                    ..finding
+                }));
+        }
+    }
+
+    report
+        .findings
+        .sort_by(|a, b| a.feature.cmp(&b.feature).then(b.severity.cmp(&a.severity)));
+
+    match format {
+        OutputFormat::Json => output::print_json(Kind::AuditFindings, &report)?,
+        OutputFormat::Text => print_report(&report, features.len() - report.errors.len()),
+    }
+
+    if let Some(threshold) = fail_on {
+        // A feature that was not audited may well hide a finding above the threshold.
+        if !report.errors.is_empty() {
+            Err(output::failed(
+                format,
+                io::Error::other(format!("Unable to audit {} features", report.errors.len())),
+            ))?
+        }
+
+        let failing = (report.findings.iter())
+            .filter(|finding| finding.severity >= threshold)
+            .count();
+
+        if failing > 0 {
+            Err(output::failed(
+                format,
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{failing} findings of {threshold} severity or above"),
+                ),
+            ))?
+        }
+    }
+
+    log::debug!("audit: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::scan_script;
+
+    #[test]
+    fn test_scan_script() -> anyhow::Result<()> {
+        let script = r#"#!/usr/bin/env bash
+# curl https://example.com/install.sh | bash
+curl -fsSL https://deb.nodesource.com/setup_lts.x | sudo -E bash -
+apt-get install -y --no-install-recommends curl
+apt-get install -y "nodejs=${NODE_VERSION}"
+apt-get install -y git "${ADDITIONAL_PACKAGES}"
+npm install -g "typescript@$TS_VERSION"
+pip install requests==2.32.3
+echo "export PATH=/usr/local/bin:$PATH" >> /etc/bash.bashrc
+chmod 777 /opt/tools
+"#;
+        let findings = scan_script("install.sh", script)?;
+        let rules: Vec<(usize, &str)> = findings
+            .iter()
+            .map(|finding| (finding.line, finding.rule.as_str()))
+            .collect();
+
+        assert_eq!(
+            rules,
+            vec![
+                (3, "pipe-to-shell"),
+                (3, "sudo"),
+                (4, "unpinned-install"),
+                (6, "unpinned-install"),
+                (9, "write-etc"),
+                (10, "world-writable"),
+            ]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+
+use oci_client::secrets::RegistryAuth;
+use serde::Deserialize;
+
+use crate::credentials;
+
+/// The registry configuration of this run, with the credentials of each registry once they are looked up.
+struct AuthState {
+    registries: BTreeMap<String, RegistryConfig>,
+    credentials_file: PathBuf,
+    resolved: Mutex<HashMap<String, RegistryAuth>>,
+}
+
+static STATE: OnceLock<AuthState> = OnceLock::new();
+
+/// Registries accept a personal access token as the password of any user name.
+pub const TOKEN_USERNAME: &str = "token";
+
+/// How to authenticate with one registry, an entry of the `[registries]` section of `config.toml` keyed by host.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "auth", rename_all = "kebab-case", deny_unknown_fields)]
+pub enum RegistryConfig {
+    /// Pull without credentials, the default for every registry.
+    Anonymous,
+    /// A token read from the given environment variable.
+    #[serde(rename_all = "kebab-case")]
+    Token {
+        token_env: String,
+        /// Some registries require the user name that owns the token.
+        username: Option<String>,
+    },
+    /// A user name, with the password read from the given environment variable.
+    #[serde(rename_all = "kebab-case")]
+    Basic { username: String, password_env: String },
+    /// A Docker credential helper, such as `desktop` for the `docker-credential-desktop` executable.
+    CredentialHelper { helper: String },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HelperCredentials {
+    username: String,
+    secret: String,
+}
+
+fn environment_secret(variable: &str, registry: &str) -> io::Result<String> {
+    match env::var(variable) {
+        Ok(value) if !value.is_empty() => Ok(value),
+        _ => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("The credentials of {registry} are configured to be read from ${variable}, which is not set"),
+        )),
+    }
+}
+
+/// Ask a Docker credential helper for the credentials of `registry`, following the `get` protocol.
+fn helper_credentials(helper: &str, registry: &str) -> io::Result<HelperCredentials> {
+    let program = format!("docker-credential-{helper}");
+    let mut child = Command::new(&program)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| io::Error::new(error.kind(), format!("Unable to run {program}: {error}")))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(registry.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        Err(io::Error::other(format!(
+            "{program} found no credentials for {registry}: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        )))?
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+impl RegistryConfig {
+    fn registry_auth(&self, registry: &str) -> io::Result<RegistryAuth> {
+        match self {
+            Self::Anonymous => Ok(RegistryAuth::Anonymous),
+            Self::Token { token_env, username } => Ok(RegistryAuth::Basic(
+                username.clone().unwrap_or_else(|| TOKEN_USERNAME.to_string()),
+                environment_secret(token_env, registry)?,
+            )),
+            Self::Basic { username, password_env } => Ok(RegistryAuth::Basic(
+                username.clone(),
+                environment_secret(password_env, registry)?,
+            )),
+            Self::CredentialHelper { helper } => {
+                let credentials = helper_credentials(helper, registry)?;
+                Ok(RegistryAuth::Basic(credentials.username, credentials.secret))
+            },
+        }
+    }
+}
+
+/// Choose the registry configuration for this run, along with the file `tyedev login --file-store` writes. Only the
+/// first call has an effect.
+pub fn init(registries: &BTreeMap<String, RegistryConfig>, credentials_file: &Path) {
+    let _ = STATE.set(AuthState {
+        registries: registries.clone(),
+        credentials_file: credentials_file.to_path_buf(),
+        resolved: Mutex::new(HashMap::new()),
+    });
+}
+
+/// Credentials saved by `tyedev login`, when there are any.
+fn stored_auth(file: &Path, registry: &str) -> io::Result<RegistryAuth> {
+    match credentials::load(file, registry) {
+        Ok(Some(credentials)) => {
+            log::debug!("registry_auth: {registry} uses stored credentials");
+            Ok(RegistryAuth::Basic(credentials.username, credentials.secret))
+        },
+        Ok(None) => Ok(RegistryAuth::Anonymous),
+        Err(error) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unable to read the stored credentials of {registry}: {error}"),
+        )),
+    }
+}
+
+/// Credentials for the given registry host. The configuration takes precedence over credentials saved by `tyedev
+/// login`, and without either the registry is pulled from anonymously. Each registry is only looked up once a run.
+pub fn registry_auth(registry: &str) -> io::Result<RegistryAuth> {
+    let Some(state) = STATE.get() else {
+        return Ok(RegistryAuth::Anonymous);
+    };
+
+    if let Some(auth) = (state.resolved.lock().ok()).and_then(|resolved| resolved.get(registry).cloned()) {
+        return Ok(auth);
+    }
+
+    let auth = match state.registries.get(registry) {
+        Some(config) => {
+            log::debug!("registry_auth: {registry} uses {config:?}");
+            config.registry_auth(registry)?
+        },
+        None => stored_auth(&state.credentials_file, registry)?,
+    };
+
+    if let Ok(mut resolved) = state.resolved.lock() {
+        resolved.insert(registry.to_string(), auth.clone());
+    }
+
+    Ok(auth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegistryConfig;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_registry_config() -> anyhow::Result<()> {
+        let registries: BTreeMap<String, RegistryConfig> = toml::from_str(
+            r#"
+            ["ghcr.io"]
+            auth = "token"
+            token-env = "TYEDEV_TEST_GHCR_TOKEN"
+
+            ["registry.example.com"]
+            auth = "basic"
+            username = "me"
+            password-env = "TYEDEV_TEST_EXAMPLE_PASSWORD"
+
+            ["mcr.microsoft.com"]
+            auth = "anonymous"
+            "#,
+        )?;
+
+        assert!(matches!(
+            registries.get("ghcr.io"),
+            Some(RegistryConfig::Token { username: None, .. })
+        ));
+        assert!(matches!(
+            registries.get("registry.example.com"),
+            Some(RegistryConfig::Basic { .. })
+        ));
+        assert!(registries["registry.example.com"]
+            .registry_auth("registry.example.com")
+            .is_err());
+        assert!(toml::from_str::<BTreeMap<String, RegistryConfig>>("[\"ghcr.io\"]\nauth = \"token\"").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::init::{self, InitArgs, InitReport};
+use crate::policy;
+use crate::prompt::DefaultPrompter;
+use crate::registry::DevcontainerIndex;
+use crate::theme;
+
+/// A manifest of the projects to initialize with `tyedev init --batch`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BatchManifest {
+    #[serde(rename = "project")]
+    pub projects: Vec<Project>,
+}
+
+/// One `[[project]]` of a batch manifest.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    /// Workspace folder, relative to the manifest.
+    pub workspace: PathBuf,
+    /// Reference of the template, a bare image is used when missing.
+    pub template: Option<String>,
+    /// Display name of the devcontainer.
+    pub name: Option<String>,
+    /// Values of template options by name, the default of the template for the others.
+    #[serde(default)]
+    pub options: Map<String, Value>,
+    /// Features to add with the values of their options, as in the `features` object of devcontainer.json.
+    #[serde(default)]
+    pub features: Map<String, Value>,
+}
+
+/// The outcome of one project of a batch.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProjectReport {
+    pub workspace: PathBuf,
+    /// The summary of the init, missing when it failed.
+    pub report: Option<InitReport>,
+    pub error: Option<String>,
+}
+
+/// Result of `tyedev init --batch`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct BatchReport {
+    pub projects: Vec<ProjectReport>,
+}
+
+impl BatchReport {
+    /// An error naming the number of failed projects, when any did.
+    pub fn result(&self) -> anyhow::Result<()> {
+        let failed = self.projects.iter().filter(|project| project.error.is_some()).count();
+
+        if failed > 0 {
+            Err(io::Error::other(format!(
+                "{failed} of {} projects failed",
+                self.projects.len()
+            )))?
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a batch manifest.
+pub fn read_manifest(filename: &Path) -> anyhow::Result<BatchManifest> {
+    log::debug!("read_manifest: {}", filename.display());
+
+    let contents = fs::read_to_string(filename)?;
+    let manifest = toml::from_str(&contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid batch manifest {}: {error}", filename.display()),
+        )
+    })?;
+
+    Ok(manifest)
+}
+
+/// Initialize every project of the manifest in turn, carrying on past failures so that one bad entry does not stop
+/// the others. `args` holds the flags shared by every project.
+pub async fn init_batch(
+    index: &DevcontainerIndex,
+    filename: &Path,
+    args: &InitArgs,
+    config: &Config,
+) -> anyhow::Result<BatchReport> {
+    log::debug!("init_batch");
+
+    let manifest = read_manifest(filename)?;
+    let directory = filename.parent().unwrap_or(Path::new(""));
+    let policy = policy::read_policy()?;
+    let mut projects = Vec::with_capacity(manifest.projects.len());
+
+    for project in &manifest.projects {
+        let workspace = directory.join(&project.workspace);
+
+        log::info!("Initializing {}", workspace.display());
+
+        let result = match args.for_project(project, workspace.clone()) {
+            Ok(project_args) => {
+                fs::create_dir_all(&workspace)?;
+                let prompter = &mut DefaultPrompter;
+                init::init_with_prompter(index, project_args, &config.download, &config.impact, &policy, prompter)
+                    .await
+            },
+            Err(error) => Err(error),
+        };
+        let (report, error) = match result {
+            Ok(report) => (Some(report), None),
+            Err(error) => {
+                log::error!("{}: {error:#}", workspace.display());
+                (None, Some(format!("{error:#}")))
+            },
+        };
+
+        projects.push(ProjectReport {
+            workspace,
+            report,
+            error,
+        });
+    }
+
+    log::debug!("init_batch: done");
+
+    Ok(BatchReport { projects })
+}
+
+pub fn print_batch_report(batch: &BatchReport) {
+    let theme = theme::current();
+
+    for project in &batch.projects {
+        println!();
+
+        match (&project.report, &project.error) {
+            (Some(report), _) => {
+                println!("{}", theme.header.paint(project.workspace.display().to_string()));
+                init::print_report(report);
+            },
+            (None, error) => {
+                println!("{}", theme.error.paint(project.workspace.display().to_string()));
+                println!("  {}", error.as_deref().unwrap_or_default());
+            },
+        }
+    }
+
+    let failed = batch.projects.iter().filter(|project| project.error.is_some()).count();
+
+    println!(
+        "\n{} projects initialized, {failed} failed",
+        batch.projects.len() - failed
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BatchManifest;
+    use serde_json::json;
+
+    #[test]
+    fn test_manifest() -> anyhow::Result<()> {
+        let manifest: BatchManifest = toml::from_str(
+            r#"
+            [[project]]
+            workspace = "services/api"
+            template = "ghcr.io/devcontainers/templates/go:3"
+            name = "API"
+            options = { imageVariant = "bookworm", nodeVersion = "none" }
+
+            [project.features."ghcr.io/devcontainers/features/node:1"]
+            version = "20"
+            nodeGypDependencies = false
+
+            [[project]]
+            workspace = "web"
+            "#,
+        )?;
+
+        assert_eq!(manifest.projects.len(), 2);
+        assert_eq!(manifest.projects[0].options["imageVariant"], json!("bookworm"));
+        assert_eq!(
+            manifest.projects[0].features["ghcr.io/devcontainers/features/node:1"],
+            json!({"version": "20", "nodeGypDependencies": false})
+        );
+        assert!(manifest.projects[1].template.is_none());
+        assert!(manifest.projects[1].features.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+use std::io;
+
+use clap::Args;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::config::Config;
+use crate::init::{self, InitArgs};
+use crate::inspect::Displayable;
+use crate::oci_ref::OciReference;
+use crate::output::OutputFormat;
+use crate::registry::{Collection, DevcontainerIndex, Feature, Template};
+
+#[derive(Debug, Args)]
+pub struct BrowseArgs {
+    /// Include deprecated features and templates.
+    #[arg(long)]
+    include_deprecated: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Item<'i> {
+    Feature(&'i Feature),
+    Template(&'i Template),
+}
+
+impl<'i> Item<'i> {
+    fn id(&self) -> &'i str {
+        match self {
+            Item::Feature(feature) => &feature.id,
+            Item::Template(template) => &template.id,
+        }
+    }
+
+    fn name(&self) -> &'i str {
+        match self {
+            Item::Feature(feature) => &feature.name,
+            Item::Template(template) => &template.name,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Item::Feature(_) => "feature",
+            Item::Template(_) => "template",
+        }
+    }
+
+    fn rows(&self) -> Vec<[String; 2]> {
+        match self {
+            Item::Feature(feature) => feature.table_rows(),
+            Item::Template(template) => template.table_rows(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Collections,
+    Items,
+    Filter,
+}
+
+/// Greedy subsequence match of `query` within `candidate`, beginning at `start`.
+fn subsequence_score(query: &[char], candidate: &[char], start: usize) -> Option<i64> {
+    let mut score = 0;
+    let mut position = start;
+    let mut previous: Option<usize> = None;
+
+    for &q in query {
+        let offset = candidate.get(position..)?.iter().position(|&c| c == q)?;
+        let found = position + offset;
+
+        score += 1;
+
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 5;
+        }
+
+        if found == 0 || matches!(candidate[found - 1], '/' | '-' | ' ' | '_') {
+            score += 3;
+        }
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    Some(score)
+}
+
+/// Score how well `query` matches `candidate` as a case-insensitive subsequence. Higher is better.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+
+    let Some(&first) = query.first() else {
+        return Some(0);
+    };
+
+    // The leftmost match is not always the best one, so try every place the query could begin.
+    candidate
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == first)
+        .filter_map(|(start, _)| subsequence_score(&query, &candidate, start))
+        .max()
+}
+
+#[derive(Default)]
+struct Selection {
+    template: Option<String>,
+    features: Vec<String>,
+}
+
+enum Outcome {
+    Quit,
+    Init,
+}
+
+struct Browser<'i> {
+    collections: Vec<&'i Collection>,
+    include_deprecated: bool,
+    collection_state: ListState,
+    item_state: ListState,
+    items: Vec<Item<'i>>,
+    filter: String,
+    focus: Focus,
+    show_detail: bool,
+    selection: Selection,
+}
+
+impl<'i> Browser<'i> {
+    fn new(index: &'i DevcontainerIndex, include_deprecated: bool) -> Self {
+        let collections = index
+            .collections()
+            .iter()
+            .filter(|collection| include_deprecated || !collection.is_deprecated())
+            .collect();
+        let mut browser = Browser {
+            collections,
+            include_deprecated,
+            collection_state: ListState::default().with_selected(Some(0)),
+            item_state: ListState::default(),
+            items: Vec::new(),
+            filter: String::new(),
+            focus: Focus::Collections,
+            show_detail: false,
+            selection: Selection::default(),
+        };
+
+        browser.refresh_items();
+        browser
+    }
+
+    /// The first entry of the collections list stands for "all collections".
+    fn current_collections(&self) -> Vec<&'i Collection> {
+        match self.collection_state.selected() {
+            Some(0) | None => self.collections.clone(),
+            Some(i) => self.collections.get(i - 1).into_iter().copied().collect(),
+        }
+    }
+
+    fn refresh_items(&mut self) {
+        let include_deprecated = self.include_deprecated;
+        let all = self.current_collections().into_iter().flat_map(|collection| {
+            let features = collection
+                .features
+                .iter()
+                .filter(move |feature| include_deprecated || !feature.is_deprecated())
+                .map(Item::Feature);
+            let templates = collection.templates.iter().map(Item::Template);
+            templates.chain(features)
+        });
+        let mut scored: Vec<(i64, Item)> = all
+            .filter_map(|item| {
+                let text = format!("{} {}", item.id(), item.name());
+                fuzzy_score(&self.filter, &text).map(|score| (score, item))
+            })
+            .collect();
+
+        if !self.filter.is_empty() {
+            scored.sort_by(|(a, _), (b, _)| b.cmp(a));
+        }
+
+        self.items = scored.into_iter().map(|(_, item)| item).collect();
+        self.item_state
+            .select(if self.items.is_empty() { None } else { Some(0) });
+    }
+
+    fn current_item(&self) -> Option<Item<'i>> {
+        self.item_state.selected().and_then(|i| self.items.get(i).copied())
+    }
+
+    fn toggle_selection(&mut self) {
+        let Some(item) = self.current_item() else {
+            return;
+        };
+        let id = item.id().to_string();
+
+        match item {
+            Item::Template(_) if self.selection.template.as_ref() == Some(&id) => self.selection.template = None,
+            // Only one template can be used as a starting point.
+            Item::Template(_) => self.selection.template = Some(id),
+            Item::Feature(_) => match self.selection.features.iter().position(|f| *f == id) {
+                Some(position) => {
+                    self.selection.features.remove(position);
+                },
+                None => self.selection.features.push(id),
+            },
+        }
+    }
+
+    fn is_selected(&self, item: &Item) -> bool {
+        let id = item.id();
+        self.selection.template.as_deref() == Some(id) || self.selection.features.iter().any(|f| f == id)
+    }
+
+    fn move_cursor(&mut self, down: bool) {
+        let (state, len) = match self.focus {
+            Focus::Collections => (&mut self.collection_state, self.collections.len() + 1),
+            Focus::Items | Focus::Filter => (&mut self.item_state, self.items.len()),
+        };
+
+        if len == 0 {
+            return;
+        }
+
+        let current = state.selected().unwrap_or_default();
+        let next = if down {
+            (current + 1).min(len - 1)
+        } else {
+            current.saturating_sub(1)
+        };
+
+        state.select(Some(next));
+
+        if self.focus == Focus::Collections {
+            self.refresh_items();
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode) -> Option<Outcome> {
+        if self.focus == Focus::Filter {
+            match code {
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.focus = Focus::Items;
+                    self.refresh_items();
+                },
+                KeyCode::Enter => self.focus = Focus::Items,
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.refresh_items();
+                },
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.refresh_items();
+                },
+                KeyCode::Down => self.move_cursor(true),
+                KeyCode::Up => self.move_cursor(false),
+                _ => (),
+            }
+
+            return None;
+        }
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc if self.show_detail => self.show_detail = false,
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Outcome::Quit),
+            KeyCode::Char('i') => return Some(Outcome::Init),
+            KeyCode::Char('/') => self.focus = Focus::Filter,
+            KeyCode::Char(' ') => self.toggle_selection(),
+            KeyCode::Enter => self.show_detail = !self.show_detail,
+            KeyCode::Tab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    Focus::Collections => Focus::Items,
+                    _ => Focus::Collections,
+                }
+            },
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(true),
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(false),
+            _ => (),
+        }
+
+        None
+    }
+
+    fn detail(&self) -> Paragraph<'static> {
+        let lines: Vec<Line> = match self.current_item() {
+            Some(item) => item
+                .rows()
+                .into_iter()
+                .map(|[label, value]| {
+                    Line::from(vec![
+                        Span::styled(format!("{label}: "), Style::new().add_modifier(Modifier::BOLD)),
+                        Span::raw(value),
+                    ])
+                })
+                .collect(),
+            None => vec![Line::from("No results")],
+        };
+
+        Paragraph::new(lines)
+            .block(Block::bordered().title("Details"))
+            .wrap(Wrap { trim: false })
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let highlight = Style::new().add_modifier(Modifier::REVERSED);
+        let focused = |focus: bool| {
+            if focus {
+                Style::new().add_modifier(Modifier::BOLD)
+            } else {
+                Style::new()
+            }
+        };
+
+        if self.show_detail {
+            frame.render_widget(self.detail(), main);
+        } else {
+            let [left, middle, right] = Layout::horizontal([
+                Constraint::Percentage(25),
+                Constraint::Percentage(40),
+                Constraint::Percentage(35),
+            ])
+            .areas(main);
+
+            let collection_items: Vec<ListItem> = std::iter::once(String::from("All collections"))
+                .chain(self.collections.iter().map(|c| c.source_information.name.clone()))
+                .map(ListItem::new)
+                .collect();
+            let collections = List::new(collection_items)
+                .block(
+                    Block::bordered()
+                        .title("Collections")
+                        .border_style(focused(self.focus == Focus::Collections)),
+                )
+                .highlight_style(highlight);
+
+            frame.render_stateful_widget(collections, left, &mut self.collection_state);
+
+            let item_rows: Vec<ListItem> = self
+                .items
+                .iter()
+                .map(|item| {
+                    let marker = if self.is_selected(item) { "[x]" } else { "[ ]" };
+                    ListItem::new(format!("{marker} {:<8} {}", item.kind(), item.id()))
+                })
+                .collect();
+            let title = if self.filter.is_empty() && self.focus != Focus::Filter {
+                format!("Results ({})", self.items.len())
+            } else {
+                format!("Results ({}) /{}", self.items.len(), self.filter)
+            };
+            let items = List::new(item_rows)
+                .block(
+                    Block::bordered()
+                        .title(title)
+                        .border_style(focused(self.focus != Focus::Collections)),
+                )
+                .highlight_style(highlight);
+
+            frame.render_stateful_widget(items, middle, &mut self.item_state);
+            frame.render_widget(self.detail(), right);
+        }
+
+        let help = match self.focus {
+            Focus::Filter => "type to filter  enter: done  esc: clear",
+            _ => "tab: switch pane  /: filter  space: select  enter: details  i: init with selection  q: quit",
+        };
+
+        frame.render_widget(
+            Paragraph::new(help).style(Style::new().add_modifier(Modifier::DIM)),
+            status,
+        );
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<Outcome> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    if let Some(outcome) = self.handle_key(key.code) {
+                        return Ok(outcome);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub async fn browse(
+    index: &DevcontainerIndex,
+    BrowseArgs { include_deprecated }: BrowseArgs,
+    config: &Config,
+) -> anyhow::Result<()> {
+    log::debug!("browse");
+
+    let mut browser = Browser::new(index, include_deprecated);
+    let mut terminal = ratatui::init();
+    let outcome = browser.run(&mut terminal);
+
+    ratatui::restore();
+
+    let Selection { template, features } = browser.selection;
+
+    match outcome? {
+        Outcome::Init => {
+            log::debug!("browse: init");
+            let template_id = template.map(|id| id.parse::<OciReference>()).transpose()?;
+            let include_features = features
+                .iter()
+                .map(|id| id.parse::<OciReference>())
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let args = InitArgs::with_selection(template_id, include_features);
+            init::init(index, args, OutputFormat::Text, config).await?;
+        },
+        Outcome::Quit => {
+            for id in template.iter().chain(features.iter()) {
+                println!("{id}");
+            }
+        },
+    }
+
+    log::debug!("browse: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzzy_score;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "ghcr.io/devcontainers/features/node"), None);
+
+        let exact = fuzzy_score("node", "ghcr.io/devcontainers/features/node");
+        let scattered = fuzzy_score("node", "ghcr.io/devcontainers/features/nix-overlay-devbox");
+
+        assert!(exact > scattered);
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use sha2::{Digest, Sha256};
+
+use crate::atomic_write;
+use crate::oci_layout;
+
+/// Name of the archive cache, within the data directory.
+pub const ARCHIVES_DIRECTORY: &str = "archives";
+
+/// Environment alternative to the `--no-cache` flag.
+pub const NO_CACHE_VARIABLE: &str = "TYEDEV_NO_CACHE";
+
+static ARCHIVE_CACHE: OnceLock<ArchiveCache> = OnceLock::new();
+
+/// Feature and template archives pulled before, keyed by the digest of their manifest.
+///
+/// Archives are stored as `blobs/sha256/{layer}`, next to `manifests/sha256/{manifest}` files that hold the digest of
+/// the archive layer. A manifest digest identifies its layers, so an entry stays valid for as long as the registry
+/// returns the same manifest digest.
+///
+/// The `devcontainer-feature.json` read from a feature archive is kept as `configurations/sha256/{manifest}`, so
+/// looking up a feature that is missing from the index does not read the archive again.
+#[derive(Debug)]
+pub struct ArchiveCache {
+    directory: PathBuf,
+    /// Whether cached archives are used. Fresh pulls are stored either way.
+    reuse: bool,
+}
+
+impl ArchiveCache {
+    pub fn new(directory: PathBuf, reuse: bool) -> Self {
+        ArchiveCache { directory, reuse }
+    }
+
+    pub fn reuse(&self) -> bool {
+        self.reuse
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn manifest_path(&self, manifest_digest: &str) -> io::Result<PathBuf> {
+        oci_layout::digest_path(&self.directory.join("manifests"), manifest_digest)
+    }
+
+    /// The cached archive of the given manifest, verified against its digest. Any problem is a cache miss.
+    pub fn get(&self, manifest_digest: &str) -> Option<Vec<u8>> {
+        log::debug!("ArchiveCache::get");
+
+        let read = || -> io::Result<Vec<u8>> {
+            let layer_digest = fs::read_to_string(self.manifest_path(manifest_digest)?)?;
+            oci_layout::read_blob(&self.directory, layer_digest.trim())
+        };
+
+        match read() {
+            Ok(bytes) => Some(bytes),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => {
+                log::warn!("Ignoring cached archive of {manifest_digest}: {error}");
+                None
+            },
+        }
+    }
+
+    fn configuration_path(&self, manifest_digest: &str) -> io::Result<PathBuf> {
+        oci_layout::digest_path(&self.directory.join("configurations"), manifest_digest)
+    }
+
+    /// The cached feature configuration of the given manifest. Any problem is a cache miss.
+    pub fn get_configuration(&self, manifest_digest: &str) -> Option<Vec<u8>> {
+        log::debug!("ArchiveCache::get_configuration");
+
+        match self.configuration_path(manifest_digest).and_then(fs::read) {
+            Ok(bytes) => Some(bytes),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => {
+                log::warn!("Ignoring cached configuration of {manifest_digest}: {error}");
+                None
+            },
+        }
+    }
+
+    /// Store the feature configuration read from the archive of the given manifest.
+    pub fn put_configuration(&self, manifest_digest: &str, bytes: &[u8]) -> io::Result<()> {
+        log::debug!("ArchiveCache::put_configuration");
+
+        let path = self.configuration_path(manifest_digest)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        atomic_write::write(&path, bytes)
+    }
+
+    /// Store the archive of the given manifest.
+    pub fn put(&self, manifest_digest: &str, bytes: &[u8]) -> io::Result<()> {
+        log::debug!("ArchiveCache::put");
+
+        let layer_digest = format!("sha256:{:x}", Sha256::digest(bytes));
+        let blob_path = oci_layout::digest_path(&self.directory.join("blobs"), &layer_digest)?;
+        let manifest_path = self.manifest_path(manifest_digest)?;
+
+        for path in [&blob_path, &manifest_path] {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        atomic_write::write(&blob_path, bytes)?;
+        atomic_write::write(&manifest_path, &layer_digest)?;
+
+        Ok(())
+    }
+}
+
+/// Choose the archive cache for this run. Only the first call has an effect.
+pub fn init(cache: ArchiveCache) {
+    let _ = ARCHIVE_CACHE.set(cache);
+}
+
+/// The cache chosen by [`init`], if any.
+pub fn current() -> Option<&'static ArchiveCache> {
+    ARCHIVE_CACHE.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArchiveCache;
+    use std::{env, fs};
+
+    #[test]
+    fn test_archive_cache() -> anyhow::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-archive-cache-{}", std::process::id()));
+        let cache = ArchiveCache::new(directory.clone(), true);
+        let manifest_digest = "sha256:0123456789abcdef";
+
+        assert_eq!(cache.get(manifest_digest), None);
+
+        cache.put(manifest_digest, b"feature archive")?;
+        assert_eq!(cache.get(manifest_digest), Some(b"feature archive".to_vec()));
+
+        // A corrupt blob is a cache miss, not an error.
+        let layer_digest = fs::read_to_string(directory.join("manifests/sha256/0123456789abcdef"))?;
+        let encoded = layer_digest.trim_start_matches("sha256:");
+        fs::write(directory.join("blobs/sha256").join(encoded), b"tampered")?;
+        assert_eq!(cache.get(manifest_digest), None);
+
+        assert!(cache.put("../escape", b"").is_err());
+
+        assert_eq!(cache.get_configuration(manifest_digest), None);
+        cache.put_configuration(manifest_digest, b"{}")?;
+        assert_eq!(cache.get_configuration(manifest_digest), Some(b"{}".to_vec()));
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+}
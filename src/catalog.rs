@@ -0,0 +1,348 @@
+use std::fmt::{self, Write};
+use std::io;
+
+use clap::{Args, ValueEnum};
+
+use crate::registry::{Collection, DevcontainerIndex, Feature, Template};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum CatalogFormat {
+    /// A Markdown document, rendered by most code hosts and wikis.
+    #[default]
+    Markdown,
+    /// A standalone HTML page.
+    Html,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportCatalogArgs {
+    /// Document format.
+    #[arg(long, value_name = "FORMAT", default_value = "markdown")]
+    format: CatalogFormat,
+
+    /// Only export the given collection, may be repeated. Defaults to the whole index.
+    #[arg(short = 'C', long, value_name = "OCI_REF")]
+    collection_id: Vec<String>,
+
+    /// Include deprecated collections and features.
+    #[arg(long)]
+    include_deprecated: bool,
+
+    /// Title of the document.
+    #[arg(long, default_value = "Devcontainer Catalog")]
+    title: String,
+}
+
+/// One row of the features or templates table of a collection.
+struct Entry<'e> {
+    id: &'e str,
+    name: &'e str,
+    version: &'e str,
+    description: &'e str,
+    documentation_url: Option<&'e str>,
+}
+
+impl<'e> From<&'e Feature> for Entry<'e> {
+    fn from(feature: &'e Feature) -> Self {
+        Entry {
+            id: &feature.id,
+            name: &feature.name,
+            version: &feature.version,
+            description: first_line(feature.description.as_deref()),
+            documentation_url: web_url(feature.documentation_url.as_deref()),
+        }
+    }
+}
+
+impl<'e> From<&'e Template> for Entry<'e> {
+    fn from(template: &'e Template) -> Self {
+        Entry {
+            id: &template.id,
+            name: &template.name,
+            version: &template.version,
+            description: first_line(template.description.as_deref()),
+            documentation_url: web_url(template.documentation_url.as_deref()),
+        }
+    }
+}
+
+/// A collection with the entries to list.
+struct Section<'s> {
+    collection: &'s Collection,
+    features: Vec<Entry<'s>>,
+    templates: Vec<Entry<'s>>,
+}
+
+fn first_line(description: Option<&str>) -> &str {
+    description.and_then(|d| d.lines().next()).unwrap_or_default()
+}
+
+/// The URL when it is an http or https link. Any other scheme, such as `javascript:`, is not linked and the name is
+/// listed as plain text.
+fn web_url(url: Option<&str>) -> Option<&str> {
+    url.filter(|url| {
+        let url = url.trim_start().to_ascii_lowercase();
+
+        url.starts_with("https://") || url.starts_with("http://")
+    })
+}
+
+/// Escape text for a cell of a Markdown table.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Escape text for HTML content and attribute values.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Anchor of a heading, following the convention of GitHub Markdown.
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn markdown_table(document: &mut String, heading: &str, entries: &[Entry]) -> fmt::Result {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(document, "### {heading}\n")?;
+    writeln!(document, "| Name | ID | Version | Description |")?;
+    writeln!(document, "| ---- | -- | ------- | ----------- |")?;
+
+    for entry in entries {
+        let name = match entry.documentation_url {
+            Some(url) => format!("[{}]({})", escape_markdown(entry.name), url.replace(' ', "%20")),
+            None => escape_markdown(entry.name),
+        };
+
+        writeln!(
+            document,
+            "| {name} | `{}` | {} | {} |",
+            entry.id,
+            escape_markdown(entry.version),
+            escape_markdown(entry.description)
+        )?;
+    }
+
+    writeln!(document)
+}
+
+fn markdown(title: &str, sections: &[Section]) -> Result<String, fmt::Error> {
+    let mut document = String::new();
+
+    writeln!(document, "# {}\n", escape_markdown(title))?;
+
+    for section in sections {
+        let name = &section.collection.source_information.name;
+        writeln!(document, "- [{}](#{})", escape_markdown(name), anchor(name))?;
+    }
+
+    writeln!(document)?;
+
+    for section in sections {
+        let source = &section.collection.source_information;
+
+        writeln!(document, "## {}\n", escape_markdown(&source.name))?;
+        writeln!(document, "- Maintainer: {}", escape_markdown(&source.maintainer))?;
+        writeln!(document, "- Repository: <{}>", source.repository)?;
+        writeln!(document, "- OCI Reference: `{}`\n", source.oci_reference)?;
+        markdown_table(&mut document, "Features", &section.features)?;
+        markdown_table(&mut document, "Templates", &section.templates)?;
+    }
+
+    Ok(document.trim_end().to_string() + "\n")
+}
+
+fn html_table(document: &mut String, heading: &str, entries: &[Entry]) -> fmt::Result {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(document, "<h3>{heading}</h3>")?;
+    writeln!(
+        document,
+        "<table>\n<thead><tr><th>Name</th><th>ID</th><th>Version</th><th>Description</th></tr></thead>\n<tbody>"
+    )?;
+
+    for entry in entries {
+        let name = match entry.documentation_url {
+            Some(url) => format!("<a href=\"{}\">{}</a>", escape_html(url), escape_html(entry.name)),
+            None => escape_html(entry.name),
+        };
+
+        writeln!(
+            document,
+            "<tr><td>{name}</td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+            escape_html(entry.id),
+            escape_html(entry.version),
+            escape_html(entry.description)
+        )?;
+    }
+
+    writeln!(document, "</tbody>\n</table>")
+}
+
+fn html(title: &str, sections: &[Section]) -> Result<String, fmt::Error> {
+    let mut document = String::new();
+    let title = escape_html(title);
+
+    writeln!(
+        document,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">"
+    )?;
+    writeln!(document, "<title>{title}</title>")?;
+    writeln!(
+        document,
+        "<style>body {{ font-family: sans-serif; margin: 2em; }} table {{ border-collapse: collapse; }} \
+         th, td {{ border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }}</style>"
+    )?;
+    writeln!(document, "</head>\n<body>\n<h1>{title}</h1>\n<ul>")?;
+
+    for section in sections {
+        let name = &section.collection.source_information.name;
+        writeln!(
+            document,
+            "<li><a href=\"#{}\">{}</a></li>",
+            escape_html(&anchor(name)),
+            escape_html(name)
+        )?;
+    }
+
+    writeln!(document, "</ul>")?;
+
+    for section in sections {
+        let source = &section.collection.source_information;
+
+        writeln!(
+            document,
+            "<h2 id=\"{}\">{}</h2>",
+            escape_html(&anchor(&source.name)),
+            escape_html(&source.name)
+        )?;
+        writeln!(
+            document,
+            "<ul>\n<li>Maintainer: {}</li>\n<li>Repository: <a href=\"{repository}\">{repository}</a></li>\n\
+             <li>OCI Reference: <code>{}</code></li>\n</ul>",
+            escape_html(&source.maintainer),
+            escape_html(&source.oci_reference),
+            repository = escape_html(&source.repository),
+        )?;
+        html_table(&mut document, "Features", &section.features)?;
+        html_table(&mut document, "Templates", &section.templates)?;
+    }
+
+    writeln!(document, "</body>\n</html>")?;
+
+    Ok(document)
+}
+
+/// The collections to export, in index order, with deprecated entries left out unless asked for.
+fn sections<'i>(
+    index: &'i DevcontainerIndex,
+    collection_ids: &[String],
+    include_deprecated: bool,
+) -> io::Result<Vec<Section<'i>>> {
+    if let Some(missing) = collection_ids.iter().find(|id| index.get_collection(id).is_none()) {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No collection found by the given OCI Reference: {missing}"),
+        ))?
+    }
+
+    let sections = index
+        .collections()
+        .iter()
+        .filter(|collection| {
+            if collection_ids.is_empty() {
+                include_deprecated || !collection.is_deprecated()
+            } else {
+                collection_ids.contains(&collection.source_information.oci_reference)
+            }
+        })
+        .map(|collection| Section {
+            collection,
+            features: collection
+                .features
+                .iter()
+                .filter(|feature| include_deprecated || !feature.is_deprecated())
+                .map(Entry::from)
+                .collect(),
+            templates: collection.templates.iter().map(Entry::from).collect(),
+        })
+        .collect();
+
+    Ok(sections)
+}
+
+/// Render the index, or selected collections of it, as a browsable document.
+pub fn export_catalog(
+    index: &DevcontainerIndex,
+    ExportCatalogArgs {
+        format,
+        collection_id,
+        include_deprecated,
+        title,
+    }: ExportCatalogArgs,
+) -> anyhow::Result<()> {
+    log::debug!("export_catalog");
+
+    let sections = sections(index, &collection_id, include_deprecated)?;
+    let document = match format {
+        CatalogFormat::Markdown => markdown(&title, &sections)?,
+        CatalogFormat::Html => html(&title, &sections)?,
+    };
+
+    print!("{document}");
+
+    log::debug!("export_catalog: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{anchor, escape_html, escape_markdown, web_url};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape_markdown("a | b_c"), "a \\| b\\_c");
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+        assert_eq!(anchor("Dev Container Features"), "dev-container-features");
+        assert_eq!(anchor("CodeMan99's Features!"), "codeman99s-features");
+    }
+
+    #[test]
+    fn test_web_url() {
+        assert_eq!(web_url(Some("https://example.com")), Some("https://example.com"));
+        assert_eq!(web_url(Some("HTTP://example.com")), Some("HTTP://example.com"));
+        assert_eq!(web_url(Some("javascript:alert(1)")), None);
+        assert_eq!(web_url(Some("data:text/html,x")), None);
+        assert_eq!(web_url(None), None);
+    }
+}
@@ -0,0 +1,168 @@
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ascii_table::{Align, AsciiTable};
+use clap::Args;
+
+use crate::cache;
+use crate::download::ByteSize;
+use crate::registry;
+use crate::theme;
+use crate::update_check;
+
+#[derive(Debug, Args)]
+pub struct CleanArgs {
+    /// Remove the pulled index of features & templates.
+    #[arg(long)]
+    index: bool,
+
+    /// Remove the cached template and feature archives.
+    #[arg(long)]
+    archives: bool,
+
+    /// Remove all of the data managed by tyedev.
+    #[arg(long)]
+    all: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Category {
+    Index,
+    Archives,
+    UpdateCheck,
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index => write!(f, "index"),
+            Self::Archives => write!(f, "archives"),
+            Self::UpdateCheck => write!(f, "update check"),
+        }
+    }
+}
+
+const CATEGORIES: [Category; 3] = [Category::Index, Category::Archives, Category::UpdateCheck];
+
+impl Category {
+    fn paths(&self, data_dir: &Path) -> Vec<PathBuf> {
+        match self {
+            Self::Index => {
+                let index_file = data_dir.join(registry::INDEX_FILENAME);
+                vec![registry::previous_index_file(&index_file), index_file]
+            },
+            Self::Archives => vec![data_dir.join(cache::ARCHIVES_DIRECTORY)],
+            Self::UpdateCheck => vec![data_dir.join(update_check::CACHE_FILENAME)],
+        }
+    }
+}
+
+/// Total size of the files at `path`, recursively. A missing path uses no space.
+fn disk_usage(path: &Path) -> io::Result<u64> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => Err(error)?,
+    };
+
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut total = 0;
+
+    for entry in fs::read_dir(path)? {
+        total += disk_usage(&entry?.path())?;
+    }
+
+    Ok(total)
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+
+    match result {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Report the disk usage of each category of managed data, then remove the selected categories.
+pub fn clean(data_dir: &Path, CleanArgs { index, archives, all }: CleanArgs) -> anyhow::Result<()> {
+    log::debug!("clean");
+
+    let selected = |category: &Category| match category {
+        Category::Index => all || index,
+        Category::Archives => all || archives,
+        Category::UpdateCheck => all,
+    };
+    let mut rows = Vec::with_capacity(CATEGORIES.len());
+    let mut removed = Vec::new();
+
+    for category in CATEGORIES {
+        let paths = category.paths(data_dir);
+        let mut size = ByteSize(0);
+
+        for path in &paths {
+            size.0 += disk_usage(path)?;
+        }
+
+        // The last path is the main one, such as the current index rather than the previous one.
+        let location = paths.last().map(|path| path.display().to_string()).unwrap_or_default();
+        rows.push([category.to_string(), size.to_string(), location]);
+
+        if selected(&category) {
+            for path in &paths {
+                remove(path)?;
+            }
+
+            removed.push((category, size));
+        }
+    }
+
+    let mut table = AsciiTable::default();
+    let header = &theme::current().header;
+
+    table.column(0).set_header(header.paint("Data"));
+    table.column(1).set_header(header.paint("Size")).set_align(Align::Right);
+    table.column(2).set_header(header.paint("Location"));
+    table.print(rows);
+
+    if removed.is_empty() {
+        println!("Use --index, --archives, or --all to reclaim space.");
+    }
+
+    for (category, size) in removed {
+        println!("Removed {category}, freeing {size}");
+    }
+
+    log::debug!("clean: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disk_usage;
+    use std::{env, fs};
+
+    #[test]
+    fn test_disk_usage() -> std::io::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-clean-{}", std::process::id()));
+        fs::create_dir_all(directory.join("blobs/sha256"))?;
+        fs::write(directory.join("blobs/sha256/abc"), b"archive")?;
+        fs::write(directory.join("index.json"), b"{}")?;
+
+        assert_eq!(disk_usage(&directory)?, 9);
+        assert_eq!(disk_usage(&directory.join("missing"))?, 0);
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+}
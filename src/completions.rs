@@ -0,0 +1,226 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Command};
+use clap_complete::{generate, shells::Shell};
+
+use crate::atomic_write;
+
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    #[arg(value_name = "SHELL")]
+    shell: Shell,
+
+    /// Write the completions where the shell loads them from, instead of printing them.
+    #[arg(long)]
+    install: bool,
+}
+
+/// Directories that completions are installed below.
+struct Directories {
+    home: PathBuf,
+    config: PathBuf,
+    data: PathBuf,
+}
+
+impl Directories {
+    fn find() -> io::Result<Self> {
+        let missing = || io::Error::new(io::ErrorKind::NotFound, "Unable to determine the home directory");
+
+        Ok(Directories {
+            home: dirs::home_dir().ok_or_else(missing)?,
+            config: dirs::config_dir().ok_or_else(missing)?,
+            data: dirs::data_dir().ok_or_else(missing)?,
+        })
+    }
+}
+
+/// Where the shell looks for the completions of `bin_name`, or for PowerShell, where the script sourced by the
+/// profile is kept.
+fn install_path(shell: Shell, bin_name: &str, directories: &Directories) -> Option<PathBuf> {
+    match shell {
+        // Loaded on demand by bash-completion, which also searches the user's data directory.
+        Shell::Bash => Some(directories.data.join("bash-completion/completions").join(bin_name)),
+        Shell::Fish => Some(
+            directories
+                .config
+                .join("fish/completions")
+                .join(format!("{bin_name}.fish")),
+        ),
+        Shell::Zsh => Some(directories.home.join(".zfunc").join(format!("_{bin_name}"))),
+        Shell::PowerShell => Some(directories.data.join(bin_name).join(format!("_{bin_name}.ps1"))),
+        _ => None,
+    }
+}
+
+/// The current user's PowerShell profile for all hosts, which is where PowerShell 7 reads it on every platform.
+fn powershell_profile(directories: &Directories) -> PathBuf {
+    if cfg!(windows) {
+        directories.home.join("Documents/PowerShell/profile.ps1")
+    } else {
+        directories.config.join("powershell/profile.ps1")
+    }
+}
+
+/// Source the completion script from the profile, unless it already is. A profile that is a symlink, as in a
+/// dotfiles repository, is written through to its target rather than replaced.
+fn add_to_profile(profile: &Path, script: &Path) -> io::Result<bool> {
+    let profile = match fs::canonicalize(profile) {
+        Ok(target) => target,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => profile.to_path_buf(),
+        Err(error) => Err(error)?,
+    };
+    let profile = profile.as_path();
+    let line = format!(". \"{}\"", script.display());
+    let contents = match fs::read_to_string(profile) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(error) => Err(error)?,
+    };
+
+    if contents.lines().any(|existing| existing.trim() == line) {
+        return Ok(false);
+    }
+
+    let separator = if contents.is_empty() || contents.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+
+    if let Some(parent) = profile.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    atomic_write::write(profile, format!("{contents}{separator}{line}\n"))?;
+
+    Ok(true)
+}
+
+fn install_completions(shell: Shell, command: &mut Command, bin_name: &str) -> anyhow::Result<()> {
+    log::debug!("install_completions: {shell}");
+
+    let directories = Directories::find()?;
+    let Some(path) = install_path(shell, bin_name, &directories) else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Installing completions for {shell} is not supported, redirect the printed script instead"),
+        ))?
+    };
+    let mut script: Vec<u8> = Vec::new();
+
+    generate(shell, command, bin_name, &mut script);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    atomic_write::write(&path, script)?;
+    println!("Wrote {shell} completions to {}", path.display());
+
+    match shell {
+        Shell::Zsh => println!(
+            "Add `fpath+=~/.zfunc` before `compinit` in ~/.zshrc, unless it is there already, then start a new shell."
+        ),
+        Shell::PowerShell => {
+            let profile = powershell_profile(&directories);
+
+            if add_to_profile(&profile, &path)? {
+                println!("Added the completions to {}", profile.display());
+            }
+
+            println!("Start a new shell to use them.");
+        },
+        _ => println!("Start a new shell to use them."),
+    }
+
+    Ok(())
+}
+
+/// Print the completions of the shell, or install them.
+pub fn completions(CompletionsArgs { shell, install }: CompletionsArgs, mut command: Command) -> anyhow::Result<()> {
+    log::debug!("completions");
+
+    let bin_name = command.get_name().to_string();
+
+    if install {
+        install_completions(shell, &mut command, &bin_name)
+    } else {
+        generate(shell, &mut command, bin_name, &mut io::stdout());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_to_profile, install_path, Directories};
+    use clap_complete::shells::Shell;
+    use std::path::PathBuf;
+    use std::{env, fs};
+
+    #[test]
+    fn test_install_path() {
+        let directories = Directories {
+            home: PathBuf::from("/home/me"),
+            config: PathBuf::from("/home/me/.config"),
+            data: PathBuf::from("/home/me/.local/share"),
+        };
+        let path = |shell| install_path(shell, "tyedev", &directories);
+
+        assert_eq!(
+            path(Shell::Bash),
+            Some(PathBuf::from(
+                "/home/me/.local/share/bash-completion/completions/tyedev"
+            ))
+        );
+        assert_eq!(
+            path(Shell::Fish),
+            Some(PathBuf::from("/home/me/.config/fish/completions/tyedev.fish"))
+        );
+        assert_eq!(path(Shell::Zsh), Some(PathBuf::from("/home/me/.zfunc/_tyedev")));
+        assert_eq!(path(Shell::Elvish), None);
+    }
+
+    #[test]
+    fn test_add_to_profile() -> std::io::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-test-profile-{}", std::process::id()));
+        let profile = directory.join("profile.ps1");
+        let script = directory.join("_tyedev.ps1");
+
+        fs::create_dir_all(&directory)?;
+        fs::write(&profile, "Set-PSReadLineOption -EditMode Emacs")?;
+
+        assert!(add_to_profile(&profile, &script)?);
+        assert!(!add_to_profile(&profile, &script)?);
+        assert_eq!(
+            fs::read_to_string(&profile)?,
+            format!("Set-PSReadLineOption -EditMode Emacs\n. \"{}\"\n", script.display())
+        );
+
+        fs::remove_dir_all(&directory)?;
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_add_to_symlinked_profile() -> std::io::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-test-profile-link-{}", std::process::id()));
+        let target = directory.join("dotfiles/profile.ps1");
+        let profile = directory.join("profile.ps1");
+        let script = directory.join("_tyedev.ps1");
+
+        fs::create_dir_all(directory.join("dotfiles"))?;
+        fs::write(&target, "")?;
+        std::os::unix::fs::symlink(&target, &profile)?;
+
+        assert!(add_to_profile(&profile, &script)?);
+        assert!(fs::symlink_metadata(&profile)?.file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target)?, format!(". \"{}\"\n", script.display()));
+
+        fs::remove_dir_all(&directory)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::auth::RegistryConfig;
+use crate::download::DownloadConfig;
+use crate::impact::ImpactConfig;
+use crate::index::IndexConfig;
+use crate::init::{InitConfig, Preset};
+use crate::lint::LintConfig;
+use crate::messages::MessagesConfig;
+use crate::theme::ThemeConfig;
+
+/// Name of the user configuration file, inside the `tyedev` config directory.
+const CONFIG_FILENAME: &str = "config.toml";
+
+/// Environment alternative to the default config file location.
+pub const CONFIG_VARIABLE: &str = "TYEDEV_CONFIG";
+
+/// User preferences read from `config.toml`. Every section is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub download: DownloadConfig,
+    pub impact: ImpactConfig,
+    pub index: IndexConfig,
+    pub init: InitConfig,
+    pub lint: LintConfig,
+    pub messages: MessagesConfig,
+    /// Features to add together with `tyedev init --preset`, by name.
+    pub preset: BTreeMap<String, Preset>,
+    /// Authentication by registry host.
+    pub registries: BTreeMap<String, RegistryConfig>,
+    pub theme: ThemeConfig,
+}
+
+/// Location of `config.toml`, either given by the environment or within the platform config directory.
+pub fn config_file() -> Option<PathBuf> {
+    match std::env::var_os(CONFIG_VARIABLE) {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => dirs::config_dir().map(|path| path.join(env!("CARGO_BIN_NAME")).join(CONFIG_FILENAME)),
+    }
+}
+
+/// Read the user configuration. A missing file is the same as an empty one.
+pub fn read_config() -> anyhow::Result<Config> {
+    log::debug!("read_config");
+
+    let Some(filename) = config_file() else {
+        return Ok(Config::default());
+    };
+
+    let contents = match fs::read_to_string(&filename) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(error) => Err(error)?,
+    };
+
+    let config = toml::from_str(&contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid config file {}: {error}", filename.display()),
+        )
+    })?;
+
+    Ok(config)
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write;
+
+/// Name of the plaintext credentials file, within the data directory. Only written when asked for.
+pub const CREDENTIALS_FILENAME: &str = "credentials.json";
+
+/// Name of the file listing the registries with credentials in the keyring, next to the credentials file. The keyring
+/// is only asked about these, since every call to it may block or prompt.
+const KEYRING_REGISTRIES_FILENAME: &str = "keyring-registries.json";
+
+/// Service name of the keyring entries, each entry is named after a registry host.
+const KEYRING_SERVICE: &str = env!("CARGO_BIN_NAME");
+
+/// A user name and the token or password stored by `tyedev login`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Credentials {
+    pub username: String,
+    pub secret: String,
+}
+
+/// Where credentials were stored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Store {
+    Keyring,
+    File,
+}
+
+/// The keyring of the operating system, such as the Secret Service, the macOS Keychain, or the Windows Credential
+/// Manager. Every call may block on the keyring daemon, so none of them run on an async worker thread.
+fn keyring_entry(registry: &str) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, registry)
+}
+
+fn keyring_get(registry: &str) -> keyring::Result<Option<Credentials>> {
+    let secret = tokio::task::block_in_place(|| keyring_entry(registry)?.get_password());
+
+    match secret {
+        Ok(secret) => Ok(serde_json::from_str(&secret).ok()),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+fn keyring_set(registry: &str, credentials: &Credentials) -> anyhow::Result<()> {
+    let secret = serde_json::to_string(credentials)?;
+    tokio::task::block_in_place(|| keyring_entry(registry)?.set_password(&secret))?;
+
+    Ok(())
+}
+
+fn keyring_delete(registry: &str) -> keyring::Result<bool> {
+    match tokio::task::block_in_place(|| keyring_entry(registry)?.delete_credential()) {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+fn read_file(file: &Path) -> anyhow::Result<BTreeMap<String, Credentials>> {
+    match fs::read(file) {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(error) => Err(error)?,
+    }
+}
+
+fn keyring_registries_file(file: &Path) -> PathBuf {
+    file.with_file_name(KEYRING_REGISTRIES_FILENAME)
+}
+
+fn read_keyring_registries(file: &Path) -> anyhow::Result<Vec<String>> {
+    match fs::read(keyring_registries_file(file)) {
+        Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error)?,
+    }
+}
+
+fn write_keyring_registries(file: &Path, registries: &[String]) -> anyhow::Result<()> {
+    atomic_write::write(keyring_registries_file(file), serde_json::to_vec_pretty(registries)?)?;
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn write_file(file: &Path, entries: &BTreeMap<String, Credentials>) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let contents = serde_json::to_vec_pretty(entries)?;
+    atomic_write::write_with_permissions(file, contents, fs::Permissions::from_mode(0o600))?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_file(file: &Path, entries: &BTreeMap<String, Credentials>) -> anyhow::Result<()> {
+    atomic_write::write(file, serde_json::to_vec_pretty(entries)?)?;
+
+    Ok(())
+}
+
+/// Credentials of the registry, from the keyring when it was logged in to there, or else from the plaintext file.
+pub fn load(file: &Path, registry: &str) -> anyhow::Result<Option<Credentials>> {
+    if read_keyring_registries(file)?.iter().any(|stored| stored == registry) {
+        match keyring_get(registry) {
+            Ok(Some(credentials)) => return Ok(Some(credentials)),
+            Ok(None) => {},
+            // Machines without a keyring, such as containers and CI runners, rely on the file.
+            Err(error) => log::debug!("credentials::load: keyring unavailable: {error}"),
+        }
+    }
+
+    Ok(read_file(file)?.remove(registry))
+}
+
+/// Save credentials of the registry, replacing earlier ones.
+pub fn store(file: &Path, registry: &str, credentials: &Credentials, store: Store) -> anyhow::Result<()> {
+    log::debug!("credentials::store: {registry} in {store:?}");
+
+    match store {
+        Store::Keyring => {
+            keyring_set(registry, credentials).map_err(|error| {
+                error.context("Unable to use the system keyring, pass --file-store to save the credentials in a file")
+            })?;
+
+            let mut registries = read_keyring_registries(file)?;
+
+            if !registries.iter().any(|stored| stored == registry) {
+                registries.push(registry.to_string());
+
+                if let Some(parent) = file.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+
+                write_keyring_registries(file, &registries)?;
+            }
+
+            Ok(())
+        },
+        Store::File => {
+            let mut entries = read_file(file)?;
+            entries.insert(registry.to_string(), credentials.clone());
+
+            if let Some(parent) = file.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            write_file(file, &entries)
+        },
+    }
+}
+
+/// Forget the credentials of the registry wherever they are stored. Returns where they were found.
+pub fn remove(file: &Path, registry: &str) -> anyhow::Result<Vec<Store>> {
+    let mut removed = Vec::new();
+    let mut registries = read_keyring_registries(file)?;
+
+    if registries.iter().any(|stored| stored == registry) {
+        match keyring_delete(registry) {
+            Ok(true) => removed.push(Store::Keyring),
+            Ok(false) => {},
+            Err(error) => log::debug!("credentials::remove: keyring unavailable: {error}"),
+        }
+
+        registries.retain(|stored| stored != registry);
+        write_keyring_registries(file, &registries)?;
+    }
+
+    let mut entries = read_file(file)?;
+    if entries.remove(registry).is_some() {
+        write_file(file, &entries)?;
+        removed.push(Store::File);
+    }
+
+    Ok(removed)
+}
+
+/// Location of the plaintext credentials file.
+pub fn credentials_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(CREDENTIALS_FILENAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_file, read_keyring_registries, store, write_file, Credentials, Store};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn test_file_store() -> anyhow::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-test-credentials-{}", std::process::id()));
+        let file = directory.join(super::CREDENTIALS_FILENAME);
+        let credentials = Credentials {
+            username: "me".to_string(),
+            secret: "hunter2".to_string(),
+        };
+
+        store(&file, "registry.tyedev.invalid", &credentials, Store::File)?;
+
+        let mut entries = read_file(&file)?;
+        assert_eq!(
+            entries
+                .get("registry.tyedev.invalid")
+                .map(|credentials| credentials.secret.as_str()),
+            Some("hunter2")
+        );
+        assert!(!entries.contains_key("other.tyedev.invalid"));
+        assert!(read_keyring_registries(&file)?.is_empty());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&file)?.permissions().mode() & 0o777, 0o600);
+        }
+
+        entries.remove("registry.tyedev.invalid");
+        write_file(&file, &entries)?;
+        assert!(read_file(&file)?.is_empty());
+
+        fs::remove_dir_all(&directory)?;
+
+        Ok(())
+    }
+}
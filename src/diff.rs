@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+
+use crate::archive;
+use crate::init;
+use crate::oci_ref::OciReference;
+use crate::outdated;
+use crate::registry::{self, Template};
+use crate::workspace;
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// The devcontainer.json to compare, relative to the workspace, rather than the first one found.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Template to compare with, rather than the one noted by the generation header of devcontainer.json.
+    #[arg(short, long, value_name = "OCI_REF")]
+    template_id: Option<OciReference>,
+}
+
+/// The default value of every option of the template, as init substitutes them when nothing is chosen.
+fn default_context(template: &Template) -> HashMap<String, String> {
+    template
+        .options
+        .iter()
+        .flatten()
+        .map(|(name, option)| (name.clone(), option.configured_default()))
+        .collect()
+}
+
+/// The devcontainer.json of a template archive, rendered with the defaults of its options.
+fn rendered_devcontainer(archive_bytes: &[u8]) -> anyhow::Result<String> {
+    let template: Template = serde_json::from_slice(&registry::template_configuration_bytes(archive_bytes)?)?;
+    let is_devcontainer_json =
+        |path: &Path| path.ends_with(".devcontainer/devcontainer.json") || path.ends_with(".devcontainer.json");
+    let Some((_, data)) = archive::find_entry(archive_bytes, is_devcontainer_json)? else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "The template archive has no devcontainer.json",
+        ))?
+    };
+
+    Ok(init::render_template_options(
+        &String::from_utf8(data)?,
+        &default_context(&template),
+    )?)
+}
+
+/// Print a unified diff from the devcontainer.json of a template, rendered with its defaults, to the one of the
+/// workspace.
+pub async fn diff(
+    DiffArgs {
+        workspace_folder,
+        config,
+        template_id,
+    }: DiffArgs,
+) -> anyhow::Result<()> {
+    log::debug!("diff");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let file = match config {
+        Some(config) => workspace.join(config),
+        None => workspace::devcontainer_files(&workspace)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No devcontainer.json in {}", workspace.display()),
+                )
+            })?,
+    };
+    let display_name = file.strip_prefix(&workspace).unwrap_or(&file).display().to_string();
+    let contents = fs::read_to_string(&file)?;
+    let template_ref = match (template_id, init::recorded_template(&contents)) {
+        (Some(template_id), _) => template_id,
+        (None, Some((reference, digest))) => outdated::recorded_reference(&reference.parse()?, digest.as_deref())?,
+        (None, None) => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{display_name} has no generation header that notes a template, use --template-id"),
+        ))?,
+    };
+
+    log::info!("Comparing {display_name} with {template_ref}");
+
+    let rendered = rendered_devcontainer(&registry::pull_archive_bytes(&template_ref).await?)?;
+    let contents = init::without_embedded_provenance(&contents);
+    let local = init::without_generation_header(contents.as_bytes());
+
+    if rendered.as_bytes() == local {
+        println!("{display_name} matches the defaults of {template_ref}");
+    } else {
+        init::print_diff(&display_name, rendered.as_bytes(), local);
+    }
+
+    log::debug!("diff: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rendered_devcontainer;
+    use tar::{Builder, Header};
+
+    #[test]
+    fn test_rendered_devcontainer() -> anyhow::Result<()> {
+        let mut builder = Builder::new(Vec::new());
+        let mut append = |path: &str, data: &[u8]| {
+            let mut header = Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, data)
+        };
+
+        append(
+            "./devcontainer-template.json",
+            br#"{"id": "debian", "version": "1.0.0", "name": "Debian",
+                "options": {"imageVariant": {"type": "string", "default": "bookworm", "proposals": ["bookworm"]}}}"#,
+        )?;
+        append(
+            "./.devcontainer/devcontainer.json",
+            b"{\n\t\"image\": \"debian:${templateOption:imageVariant}\",\n\t\"remoteUser\": \"${templateOption:user:-vscode}\"\n}\n",
+        )?;
+
+        assert_eq!(
+            rendered_devcontainer(&builder.into_inner()?)?,
+            "{\n\t\"image\": \"debian:bookworm\",\n\t\"remoteUser\": \"vscode\"\n}\n"
+        );
+
+        Ok(())
+    }
+}
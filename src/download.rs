@@ -0,0 +1,164 @@
+use std::fmt::{self, Display};
+
+use human_format::Formatter;
+use serde::Deserialize;
+
+use crate::messages::{self, Message};
+use crate::oci_ref::OciReference;
+use crate::prompt::Prompter;
+use crate::registry;
+
+/// A number of bytes, written in the config as an integer or with a unit such as `"10 MB"`.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Deserialize)]
+#[serde(try_from = "SizeValue")]
+pub struct ByteSize(pub u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<SizeValue> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: SizeValue) -> Result<Self, Self::Error> {
+        match value {
+            SizeValue::Bytes(bytes) => Ok(ByteSize(bytes)),
+            SizeValue::Text(text) => Formatter::new()
+                .with_units("B")
+                .try_parse(text.trim())
+                .map(|bytes| ByteSize(bytes as u64))
+                .map_err(|error| format!("Invalid size \"{text}\": {error}")),
+        }
+    }
+}
+
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Whole bytes need no decimals.
+        if self.0 < 1000 {
+            return write!(f, "{} B", self.0);
+        }
+
+        write!(
+            f,
+            "{}",
+            Formatter::new().with_decimals(1).with_units("B").format(self.0 as f64)
+        )
+    }
+}
+
+/// The `[download]` section of `config.toml`.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct DownloadConfig {
+    /// Ask before pulling artifacts that add up to more than this size.
+    pub confirm_above: ByteSize,
+    /// Seconds to wait for a connection to a registry.
+    pub connect_timeout: u64,
+    /// Seconds to wait for each read from a registry, rather than for the whole pull.
+    pub read_timeout: u64,
+    /// Seconds to wait for a lookup that is only advice, such as the size of an artifact or the platforms of an image.
+    pub lookup_timeout: u64,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        DownloadConfig {
+            confirm_above: ByteSize(10_000_000),
+            connect_timeout: 30,
+            read_timeout: 60,
+            lookup_timeout: 10,
+        }
+    }
+}
+
+/// The archive size of each artifact in a registry, leaving out local artifacts and the ones whose size could not be
+/// looked up.
+pub async fn archive_sizes<'a>(artifacts: &[&'a OciReference]) -> Vec<(&'a OciReference, ByteSize)> {
+    let mut sizes = Vec::with_capacity(artifacts.len());
+
+    for &artifact in artifacts.iter().filter(|artifact| !artifact.is_local()) {
+        match tokio::time::timeout(registry::lookup_timeout(), registry::archive_size(artifact)).await {
+            Ok(Ok(size)) => sizes.push((artifact, ByteSize(size))),
+            Ok(Err(error)) => log::info!("Unable to look up the download size of {artifact}: {error:#}"),
+            Err(_) => log::info!("Unable to look up the download size of {artifact}: timed out"),
+        }
+    }
+
+    sizes
+}
+
+/// Show the download size of each artifact and their total, then ask to continue when the total is over the
+/// configured threshold. Local artifacts are not downloaded, and an unknown size is left out.
+pub async fn confirm_download(
+    artifacts: &[&OciReference],
+    config: &DownloadConfig,
+    prompter: &mut dyn Prompter,
+    quiet: bool,
+) -> anyhow::Result<()> {
+    log::debug!("confirm_download");
+
+    let sizes = archive_sizes(artifacts).await;
+
+    if sizes.is_empty() {
+        return Ok(());
+    }
+
+    let total = ByteSize(sizes.iter().map(|(_, size)| size.0).sum());
+    let over_threshold = total > config.confirm_above;
+
+    for (artifact, size) in &sizes {
+        if quiet && !over_threshold {
+            log::info!("Download size of {artifact}: {size}");
+        } else {
+            println!("Download size of {artifact}: {size}");
+        }
+    }
+
+    if sizes.len() > 1 {
+        if quiet && !over_threshold {
+            log::info!("Total download size: {total}");
+        } else {
+            println!("Total download size: {total}");
+        }
+    }
+
+    if over_threshold {
+        let message = messages::format(
+            Message::DownloadConfirm,
+            &[("total", &total), ("limit", &config.confirm_above)],
+        );
+
+        if !prompter.confirm(&message, Some(true))? {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                messages::text(Message::DownloadCancelled),
+            ))?
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteSize, DownloadConfig};
+
+    #[test]
+    fn test_byte_size() -> anyhow::Result<()> {
+        let config: DownloadConfig = toml::from_str("confirm-above = \"2.5 MB\"")?;
+        assert_eq!(config.confirm_above, ByteSize(2_500_000));
+
+        let config: DownloadConfig = toml::from_str("confirm-above = 1024")?;
+        assert_eq!(config.confirm_above, ByteSize(1024));
+        assert_eq!(config.read_timeout, 60);
+
+        assert!(toml::from_str::<DownloadConfig>("confirm-above = \"lots\"").is_err());
+        assert_eq!(ByteSize(1_500_000).to_string(), "1.5 MB");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::io;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    pub html_url: String,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    /// The release tag without the leading "v".
+    pub fn version(&self) -> Result<semver::Version> {
+        let version = self.tag_name.trim_start_matches('v');
+        semver::Version::parse(version).with_context(|| format!("Invalid release tag: {}", self.tag_name))
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Owner {
+    pub login: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Repository {
+    pub name: String,
+    pub full_name: String,
+    pub owner: Owner,
+    pub html_url: String,
+    pub description: Option<String>,
+    pub stargazers_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositorySearch {
+    items: Vec<Repository>,
+}
+
+/// The `owner/repo` path of this project, taken from the package metadata.
+fn repository_path() -> Result<&'static str> {
+    let repository = env!("CARGO_PKG_REPOSITORY");
+    let path = repository.strip_prefix("https://github.com/").ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Repository is not hosted on GitHub: {repository}"),
+        )
+    })?;
+
+    Ok(path.trim_end_matches('/'))
+}
+
+pub fn client() -> Result<reqwest::Client> {
+    let client = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+    Ok(client)
+}
+
+/// Fetch metadata of the most recent published release.
+pub async fn latest_release() -> Result<Release> {
+    log::debug!("latest_release");
+
+    let url = format!("https://api.github.com/repos/{}/releases/latest", repository_path()?);
+    let release = client()?
+        .get(&url)
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Release>()
+        .await
+        .context("Failed to read the latest release")?;
+
+    log::debug!("latest_release: {}", release.tag_name);
+
+    Ok(release)
+}
+
+/// Download the body of the given URL.
+pub async fn download(url: &str) -> Result<Vec<u8>> {
+    log::debug!("download: {url}");
+
+    let bytes = client()?.get(url).send().await?.error_for_status()?.bytes().await?;
+
+    Ok(bytes.to_vec())
+}
+
+/// Search repositories with the given topic, the most starred first. Set `GITHUB_TOKEN` to raise the rate limit of
+/// anonymous searches.
+pub async fn search_repositories(text: &str, topic: &str, count: usize) -> Result<Vec<Repository>> {
+    log::debug!("search_repositories: {topic}");
+
+    let query = format!("{text} topic:{topic}");
+    let count = count.to_string();
+    let mut request = client()?
+        .get("https://api.github.com/search/repositories")
+        .header("Accept", "application/vnd.github+json")
+        .query(&[("q", query.as_str()), ("sort", "stars"), ("per_page", count.as_str())]);
+
+    if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+        request = request.bearer_auth(token);
+    }
+
+    let search = request
+        .send()
+        .await?
+        .error_for_status()
+        .context("Failed to search GitHub, setting GITHUB_TOKEN raises the rate limit")?
+        .json::<RepositorySearch>()
+        .await
+        .context("Failed to read the repository search")?;
+
+    log::debug!("search_repositories: {} found", search.items.len());
+
+    Ok(search.items)
+}
@@ -0,0 +1,349 @@
+use std::collections::VecDeque;
+use std::env;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+
+use crate::init;
+use crate::oci_ref::OciReference;
+use crate::registry::{DevcontainerIndex, Feature};
+use crate::workspace::{self, FeatureSource};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum GraphFormat {
+    /// The install order, with what each feature waits for.
+    #[default]
+    Ascii,
+    /// Graphviz DOT, render with `dot -Tsvg`.
+    Dot,
+    /// A Mermaid flowchart, rendered by most code hosts in Markdown.
+    Mermaid,
+}
+
+#[derive(Debug, Args)]
+pub struct GraphArgs {
+    /// Features to graph instead of those of the workspace.
+    #[arg(value_name = "OCI_REF")]
+    features: Vec<OciReference>,
+
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY", conflicts_with = "features")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Document format.
+    #[arg(long, value_name = "FORMAT", default_value = "ascii")]
+    format: GraphFormat,
+}
+
+/// A feature of the graph, by id.
+#[derive(Debug)]
+struct Node {
+    id: String,
+    /// Added because another feature depends on it, rather than configured.
+    implicit: bool,
+    /// Missing when the configuration of the feature could not be read.
+    feature: Option<Feature>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EdgeKind {
+    DependsOn,
+    InstallsAfter,
+}
+
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DependsOn => write!(f, "dependsOn"),
+            Self::InstallsAfter => write!(f, "installsAfter"),
+        }
+    }
+}
+
+/// `after` is installed before `before`, indexes into the nodes.
+#[derive(Debug, PartialEq)]
+struct Edge {
+    after: usize,
+    before: usize,
+    kind: EdgeKind,
+}
+
+/// The references a feature declares to install before itself, by id.
+fn declared(feature: &Feature) -> Vec<(OciReference, EdgeKind)> {
+    let depends_on = feature
+        .depends_on
+        .iter()
+        .flatten()
+        .map(|(reference, _)| (reference, EdgeKind::DependsOn));
+    let installs_after = feature
+        .installs_after
+        .iter()
+        .flatten()
+        .map(|reference| (reference, EdgeKind::InstallsAfter));
+
+    depends_on
+        .chain(installs_after)
+        .filter_map(|(reference, kind)| match reference.parse() {
+            Ok(reference) => Some((reference, kind)),
+            Err(error) => {
+                log::warn!("{}: invalid dependency {reference}: {error}", feature.id);
+                None
+            },
+        })
+        .collect()
+}
+
+/// Read the configuration of every feature, adding the features they depend on that are not configured.
+async fn resolve(index: &DevcontainerIndex, roots: Vec<(String, FeatureSource)>) -> Vec<Node> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut pending: VecDeque<(String, FeatureSource, bool)> =
+        roots.into_iter().map(|(id, source)| (id, source, false)).collect();
+
+    while let Some((id, source, implicit)) = pending.pop_front() {
+        if nodes.iter().any(|node| node.id == id) {
+            continue;
+        }
+
+        let feature = match &source {
+            FeatureSource::Registry(reference) => init::get_feature(index, reference).await,
+            FeatureSource::Folder(folder) => fs::read_to_string(folder.join("devcontainer-feature.json"))
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| Ok(serde_jsonc::from_str::<Feature>(&contents)?)),
+            FeatureSource::Url(url) => Err(anyhow::anyhow!("Features downloaded from {url} are not supported")),
+        };
+        let feature = match feature {
+            Ok(feature) => Some(feature),
+            Err(error) => {
+                log::warn!("{id}: {error:#}, its dependencies are left out");
+                None
+            },
+        };
+
+        for (reference, kind) in feature.iter().flat_map(declared) {
+            if kind == EdgeKind::DependsOn {
+                pending.push_back((reference.id(), FeatureSource::Registry(reference), true));
+            }
+        }
+
+        nodes.push(Node { id, implicit, feature });
+    }
+
+    nodes
+}
+
+/// The edges between the nodes. An `installsAfter` of a feature that is not in the graph has no effect, so it is left
+/// out.
+fn edges(nodes: &[Node]) -> Vec<Edge> {
+    let position = |id: &str| nodes.iter().position(|node| node.id == id);
+    let mut edges = Vec::new();
+
+    for (before, node) in nodes.iter().enumerate() {
+        for (reference, kind) in node.feature.iter().flat_map(declared) {
+            if let Some(after) = position(&reference.id()) {
+                edges.push(Edge { after, before, kind });
+            }
+        }
+    }
+
+    edges
+}
+
+fn ascii(nodes: &[Node], edges: &[Edge]) -> Result<String, fmt::Error> {
+    let after: Vec<(String, Vec<String>)> = nodes
+        .iter()
+        .enumerate()
+        .map(|(position, node)| {
+            let after = edges
+                .iter()
+                .filter(|edge| edge.before == position)
+                .map(|edge| nodes[edge.after].id.clone())
+                .collect();
+            (node.id.clone(), after)
+        })
+        .collect();
+    let mut document = String::new();
+
+    for (number, id) in init::install_order(&after).iter().enumerate() {
+        let position = nodes.iter().position(|node| node.id == *id).unwrap_or_default();
+        let marker = if nodes[position].implicit { " (dependency)" } else { "" };
+
+        writeln!(document, "{}. {id}{marker}", number + 1)?;
+
+        for edge in edges.iter().filter(|edge| edge.before == position) {
+            writeln!(document, "     after {} ({})", nodes[edge.after].id, edge.kind)?;
+        }
+    }
+
+    Ok(document)
+}
+
+fn dot(nodes: &[Node], edges: &[Edge]) -> Result<String, fmt::Error> {
+    let quote = |id: &str| format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut document = String::from("digraph features {\n  rankdir=LR;\n  node [shape=box];\n");
+
+    for node in nodes {
+        let style = if node.implicit { " [style=dashed]" } else { "" };
+        writeln!(document, "  {}{style};", quote(&node.id))?;
+    }
+
+    for edge in edges {
+        let style = match edge.kind {
+            EdgeKind::DependsOn => "",
+            EdgeKind::InstallsAfter => ", style=dashed",
+        };
+
+        writeln!(
+            document,
+            "  {} -> {} [label=\"{}\"{style}];",
+            quote(&nodes[edge.after].id),
+            quote(&nodes[edge.before].id),
+            edge.kind
+        )?;
+    }
+
+    document.push_str("}\n");
+
+    Ok(document)
+}
+
+fn mermaid(nodes: &[Node], edges: &[Edge]) -> Result<String, fmt::Error> {
+    let mut document = String::from("flowchart LR\n");
+
+    for (position, node) in nodes.iter().enumerate() {
+        let label = node.id.replace('"', "#quot;");
+
+        if node.implicit {
+            writeln!(document, "  n{position}([\"{label}\"])")?;
+        } else {
+            writeln!(document, "  n{position}[\"{label}\"]")?;
+        }
+    }
+
+    for edge in edges {
+        let arrow = match edge.kind {
+            EdgeKind::DependsOn => "-->",
+            EdgeKind::InstallsAfter => "-.->",
+        };
+
+        writeln!(document, "  n{} {arrow}|{}| n{}", edge.after, edge.kind, edge.before)?;
+    }
+
+    Ok(document)
+}
+
+/// Print the order the features of a workspace install in, following `dependsOn` and `installsAfter`.
+pub async fn graph(
+    index: &DevcontainerIndex,
+    GraphArgs {
+        features,
+        workspace_folder,
+        format,
+    }: GraphArgs,
+) -> anyhow::Result<()> {
+    log::debug!("graph");
+
+    let roots: Vec<(String, FeatureSource)> = if features.is_empty() {
+        let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+
+        workspace::workspace_features(&workspace)?
+            .into_iter()
+            .map(|feature| match feature.source {
+                FeatureSource::Registry(reference) => (reference.id(), FeatureSource::Registry(reference)),
+                source => (feature.key, source),
+            })
+            .collect()
+    } else {
+        features
+            .into_iter()
+            .map(|reference| (reference.id(), FeatureSource::Registry(reference)))
+            .collect()
+    };
+    let nodes = resolve(index, roots).await;
+    let edges = edges(&nodes);
+    let document = match format {
+        GraphFormat::Ascii => ascii(&nodes, &edges)?,
+        GraphFormat::Dot => dot(&nodes, &edges)?,
+        GraphFormat::Mermaid => mermaid(&nodes, &edges)?,
+    };
+
+    print!("{document}");
+
+    log::debug!("graph: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ascii, edges, mermaid, Edge, EdgeKind, Node};
+    use crate::registry::Feature;
+
+    fn nodes() -> serde_json::Result<Vec<Node>> {
+        let feature = |json: &str| serde_json::from_str::<Feature>(json).map(Some);
+
+        Ok(vec![
+            Node {
+                id: "ghcr.io/devcontainers/features/node".into(),
+                implicit: false,
+                feature: feature(
+                    r#"{"id": "node", "version": "1.0.0", "name": "Node.js",
+                        "installsAfter": ["ghcr.io/devcontainers/features/common-utils", "ghcr.io/devcontainers/features/git"],
+                        "dependsOn": {"ghcr.io/me/features/certs:1": {}}}"#,
+                )?,
+            },
+            Node {
+                id: "ghcr.io/devcontainers/features/common-utils".into(),
+                implicit: false,
+                feature: feature(r#"{"id": "common-utils", "version": "2.0.0", "name": "Common Utilities"}"#)?,
+            },
+            Node {
+                id: "ghcr.io/me/features/certs".into(),
+                implicit: true,
+                feature: None,
+            },
+        ])
+    }
+
+    #[test]
+    fn test_edges() -> serde_json::Result<()> {
+        let nodes = nodes()?;
+
+        assert_eq!(
+            edges(&nodes),
+            vec![
+                Edge {
+                    after: 2,
+                    before: 0,
+                    kind: EdgeKind::DependsOn
+                },
+                Edge {
+                    after: 1,
+                    before: 0,
+                    kind: EdgeKind::InstallsAfter
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render() -> anyhow::Result<()> {
+        let nodes = nodes()?;
+        let edges = edges(&nodes);
+
+        assert_eq!(
+            ascii(&nodes, &edges)?,
+            "1. ghcr.io/devcontainers/features/common-utils\n\
+             2. ghcr.io/me/features/certs (dependency)\n\
+             3. ghcr.io/devcontainers/features/node\n     \
+             after ghcr.io/me/features/certs (dependsOn)\n     \
+             after ghcr.io/devcontainers/features/common-utils (installsAfter)\n"
+        );
+        assert!(mermaid(&nodes, &edges)?.contains("  n1 -.->|installsAfter| n0\n"));
+
+        Ok(())
+    }
+}
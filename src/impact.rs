@@ -0,0 +1,221 @@
+use serde::Deserialize;
+
+use crate::download::{self, ByteSize};
+use crate::messages::{self, Message};
+use crate::oci_ref::OciReference;
+use crate::prompt::Prompter;
+use crate::registry::Feature;
+
+/// The `[impact]` section of `config.toml`, limits on what the features added by init bring into the dev container.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ImpactConfig {
+    /// Ask before adding more than this many features.
+    pub max_features: usize,
+    /// Ask before adding features whose archives add up to more than this size.
+    pub max_size: ByteSize,
+    /// Ask before adding features that run more than this many lifecycle hooks.
+    pub max_hooks: usize,
+    /// Ask before adding a feature that runs the container privileged.
+    pub confirm_privileged: bool,
+}
+
+impl Default for ImpactConfig {
+    fn default() -> Self {
+        ImpactConfig {
+            max_features: 8,
+            max_size: ByteSize(5_000_000),
+            max_hooks: 4,
+            confirm_privileged: true,
+        }
+    }
+}
+
+/// What the added features bring into the dev container, all together.
+#[derive(Debug, Default, PartialEq)]
+pub struct Impact {
+    pub features: usize,
+    /// Total size of the archives whose size is known, missing when none is.
+    pub size: Option<ByteSize>,
+    /// Ids of the features that run the container privileged.
+    pub privileged: Vec<String>,
+    /// Lifecycle hooks of the features, as the feature id and the name of the hook.
+    pub hooks: Vec<String>,
+}
+
+/// Names of the lifecycle hooks a feature runs.
+fn lifecycle_hooks(feature: &Feature) -> Vec<&'static str> {
+    [
+        ("onCreateCommand", &feature.on_create_command),
+        ("updateContentCommand", &feature.update_content_command),
+        ("postCreateCommand", &feature.post_create_command),
+        ("postStartCommand", &feature.post_start_command),
+        ("postAttachCommand", &feature.post_attach_command),
+    ]
+    .into_iter()
+    .filter(|(_, hook)| hook.is_some())
+    .map(|(name, _)| name)
+    .collect()
+}
+
+impl Impact {
+    pub fn new(features: &[(OciReference, Feature)], size: Option<ByteSize>) -> Self {
+        Impact {
+            features: features.len(),
+            size,
+            privileged: features
+                .iter()
+                .filter(|(_, feature)| feature.privileged == Some(true))
+                .map(|(feature_ref, _)| feature_ref.id())
+                .collect(),
+            hooks: features
+                .iter()
+                .flat_map(|(feature_ref, feature)| {
+                    lifecycle_hooks(feature)
+                        .into_iter()
+                        .map(move |hook| format!("{} {hook}", feature_ref.id()))
+                })
+                .collect(),
+        }
+    }
+
+    /// What is over the limits of `config`, along with the limit, empty when it is within all of them.
+    pub fn exceeded(&self, config: &ImpactConfig) -> Vec<String> {
+        let mut exceeded = Vec::new();
+
+        if self.features > config.max_features {
+            exceeded.push(format!("{} features (limit {})", self.features, config.max_features));
+        }
+
+        if let Some(size) = self.size.filter(|size| *size > config.max_size) {
+            exceeded.push(format!("{size} of archives (limit {})", config.max_size));
+        }
+
+        if self.hooks.len() > config.max_hooks {
+            exceeded.push(format!(
+                "{} lifecycle hooks (limit {})",
+                self.hooks.len(),
+                config.max_hooks
+            ));
+        }
+
+        if config.confirm_privileged && !self.privileged.is_empty() {
+            exceeded.push("privileged features".to_string());
+        }
+
+        exceeded
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("Features: {}", self.features)];
+
+        if let Some(size) = self.size {
+            lines.push(format!("Archive size: {size}"));
+        }
+
+        if !self.hooks.is_empty() {
+            lines.push(format!("Lifecycle hooks: {}", self.hooks.join(", ")));
+        }
+
+        if !self.privileged.is_empty() {
+            lines.push(format!("Privileged: {}", self.privileged.join(", ")));
+        }
+
+        lines
+    }
+}
+
+/// Show what the added features bring into the dev container, then ask to continue when it is over any limit of the
+/// configuration.
+pub async fn confirm_impact(
+    features: &[(OciReference, Feature)],
+    config: &ImpactConfig,
+    prompter: &mut dyn Prompter,
+    quiet: bool,
+) -> anyhow::Result<()> {
+    log::debug!("confirm_impact");
+
+    if features.is_empty() {
+        return Ok(());
+    }
+
+    let artifacts: Vec<&OciReference> = features.iter().map(|(feature_ref, _)| feature_ref).collect();
+    let sizes = download::archive_sizes(&artifacts).await;
+    let size = (!sizes.is_empty()).then(|| ByteSize(sizes.iter().map(|(_, size)| size.0).sum()));
+    let impact = Impact::new(features, size);
+    let exceeded = impact.exceeded(config);
+
+    for line in impact.lines() {
+        if quiet && exceeded.is_empty() {
+            log::info!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+
+    if !exceeded.is_empty() {
+        let message = messages::format(Message::ImpactConfirm, &[("limits", &exceeded.join(", "))]);
+
+        if !prompter.confirm(&message, Some(true))? {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                messages::text(Message::ImpactCancelled),
+            ))?
+        }
+    }
+
+    log::debug!("confirm_impact: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Impact, ImpactConfig};
+    use crate::download::ByteSize;
+    use crate::registry::{Feature, LifecycleHook};
+
+    #[test]
+    fn test_impact() -> anyhow::Result<()> {
+        let features = vec![
+            (
+                "ghcr.io/devcontainers/features/docker-in-docker:2".parse()?,
+                Feature {
+                    privileged: Some(true),
+                    post_start_command: Some(LifecycleHook::Single("/usr/local/share/docker-init.sh".to_string())),
+                    ..Default::default()
+                },
+            ),
+            (
+                "ghcr.io/devcontainers/features/node:1".parse()?,
+                Feature {
+                    on_create_command: Some(LifecycleHook::Single("npm ci".to_string())),
+                    post_create_command: Some(LifecycleHook::Multiple(vec!["node -v".to_string()])),
+                    ..Default::default()
+                },
+            ),
+        ];
+        let impact = Impact::new(&features, Some(ByteSize(20_000)));
+
+        assert_eq!(impact.features, 2);
+        assert_eq!(impact.privileged, ["ghcr.io/devcontainers/features/docker-in-docker"]);
+        assert_eq!(
+            impact.hooks,
+            [
+                "ghcr.io/devcontainers/features/docker-in-docker postStartCommand",
+                "ghcr.io/devcontainers/features/node onCreateCommand",
+                "ghcr.io/devcontainers/features/node postCreateCommand",
+            ]
+        );
+        assert_eq!(impact.exceeded(&ImpactConfig::default()), ["privileged features"]);
+
+        let config: ImpactConfig =
+            toml::from_str("max-features = 1\nmax-size = \"10 KB\"\nconfirm-privileged = false")?;
+        assert_eq!(
+            impact.exceeded(&config),
+            ["2 features (limit 1)", "20.0 KB of archives (limit 10.0 KB)"]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Subcommand};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::{self, DevcontainerIndex};
+use crate::search::CollectionCategory;
+use crate::theme;
+
+/// Collections that `--pull-index` keeps at a fixed version, so a team resolves the same features and templates.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexConfig {
+    /// Manifest digest of the collection metadata by namespace, such as `ghcr.io/devcontainers/features`.
+    pub pins: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Args)]
+pub struct IndexArgs {
+    #[command(subcommand)]
+    command: IndexCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum IndexCommands {
+    /// Report features and templates added, removed, or updated since the previous pull of the index.
+    Diff(DiffArgs),
+    /// Print the `[index.pins]` entry that keeps a collection at its current version.
+    Pin(PinArgs),
+}
+
+#[derive(Debug, Args)]
+struct PinArgs {
+    /// Namespace of the collection, such as `ghcr.io/devcontainers/features`.
+    #[arg(value_name = "OCI_REF")]
+    collection: String,
+}
+
+#[derive(Debug, Args)]
+struct DiffArgs {
+    /// The older index, defaults to the one kept by the last `--pull-index`.
+    #[arg(value_name = "OLD")]
+    old: Option<PathBuf>,
+
+    /// The newer index, defaults to the current one.
+    #[arg(value_name = "NEW", requires = "old")]
+    new: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Updated,
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added => write!(f, "+"),
+            Self::Removed => write!(f, "-"),
+            Self::Updated => write!(f, "~"),
+        }
+    }
+}
+
+/// One feature or template that differs between two indexes.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub change: ChangeKind,
+    pub collection: CollectionCategory,
+    pub id: String,
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+}
+
+/// Version of every feature and template, by id.
+fn versions(index: &DevcontainerIndex) -> BTreeMap<String, (CollectionCategory, String)> {
+    let features = index.iter_features(true).map(|feature| {
+        (
+            feature.id.clone(),
+            (CollectionCategory::Features, feature.version.clone()),
+        )
+    });
+    let templates = index.iter_templates(true).map(|template| {
+        (
+            template.id.clone(),
+            (CollectionCategory::Templates, template.version.clone()),
+        )
+    });
+
+    features.chain(templates).collect()
+}
+
+/// Changes from `old` to `new`, ordered by id.
+fn diff(
+    mut old: BTreeMap<String, (CollectionCategory, String)>,
+    new: BTreeMap<String, (CollectionCategory, String)>,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for (id, (collection, new_version)) in new {
+        match old.remove(&id) {
+            None => changes.push(Change {
+                change: ChangeKind::Added,
+                collection,
+                id,
+                old_version: None,
+                new_version: Some(new_version),
+            }),
+            Some((_, old_version)) if old_version != new_version => changes.push(Change {
+                change: ChangeKind::Updated,
+                collection,
+                id,
+                old_version: Some(old_version),
+                new_version: Some(new_version),
+            }),
+            Some(_) => {},
+        }
+    }
+
+    changes.extend(old.into_iter().map(|(id, (collection, old_version))| Change {
+        change: ChangeKind::Removed,
+        collection,
+        id,
+        old_version: Some(old_version),
+        new_version: None,
+    }));
+    changes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    changes
+}
+
+fn read_index(filename: &Path) -> anyhow::Result<DevcontainerIndex> {
+    match registry::read_devcontainer_index(filename) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No index found at {}", filename.display()),
+        ))?,
+        result => Ok(result?),
+    }
+}
+
+fn print_changes(changes: &[Change]) {
+    let theme = theme::current();
+
+    for change in changes {
+        let version = match (&change.old_version, &change.new_version) {
+            (Some(old_version), Some(new_version)) => format!("{old_version} -> {new_version}"),
+            (Some(version), None) | (None, Some(version)) => version.clone(),
+            (None, None) => String::new(),
+        };
+        let collection = change.collection.to_string();
+        let line = format!("{} {collection:<8} {} {version}", change.change, change.id);
+        let style = match change.change {
+            ChangeKind::Added => &theme.diff_added,
+            ChangeKind::Removed => &theme.diff_removed,
+            ChangeKind::Updated => &theme.diff_header,
+        };
+
+        println!("{}", style.paint(line));
+    }
+
+    let count = |kind: ChangeKind| changes.iter().filter(|change| change.change == kind).count();
+
+    println!(
+        "{} added, {} removed, {} updated",
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Updated)
+    );
+}
+
+/// The namespace as a key of a TOML table, quoted since it contains dots.
+fn toml_key(namespace: &str) -> String {
+    format!("\"{}\"", namespace.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub async fn index(
+    index: &DevcontainerIndex,
+    index_file: &Path,
+    IndexArgs { command }: IndexArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("index");
+
+    match command {
+        IndexCommands::Diff(DiffArgs { old, new }) => {
+            let old = match old {
+                Some(filename) => read_index(&filename)?,
+                None => read_index(&registry::previous_index_file(index_file)).map_err(|error| {
+                    error.context(
+                        "The previous index is kept from the next `--pull-index`, or pass the files to compare",
+                    )
+                })?,
+            };
+            let changes = match new {
+                Some(filename) => diff(versions(&old), versions(&read_index(&filename)?)),
+                None => diff(versions(&old), versions(index)),
+            };
+
+            match format {
+                OutputFormat::Json => output::print_json(Kind::IndexDiff, &changes)?,
+                OutputFormat::Text => print_changes(&changes),
+            }
+        },
+        IndexCommands::Pin(PinArgs { collection }) => {
+            if index.get_collection(&collection).is_none() {
+                log::warn!("{collection} is not in the index, pinning it adds it on the next `--pull-index`");
+            }
+
+            let image = format!("{collection}:latest").parse()?;
+            let digest = registry::manifest_digest(&image).await?;
+
+            println!("[index.pins]");
+            println!("{} = \"{digest}\"", toml_key(&collection));
+        },
+    }
+
+    log::debug!("index: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, ChangeKind};
+    use crate::search::CollectionCategory;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_diff() {
+        let entry = |id: &str, version: &str| (id.to_string(), (CollectionCategory::Features, version.to_string()));
+        let old = BTreeMap::from([entry("git", "1.3.2"), entry("node", "1.6.1"), entry("rust", "1.0.0")]);
+        let new = BTreeMap::from([entry("git", "1.3.2"), entry("node", "1.7.0"), entry("python", "1.0.0")]);
+        let changes = diff(old, new);
+        let summary: Vec<(ChangeKind, &str, Option<&str>, Option<&str>)> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.change,
+                    change.id.as_str(),
+                    change.old_version.as_deref(),
+                    change.new_version.as_deref(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (ChangeKind::Updated, "node", Some("1.6.1"), Some("1.7.0")),
+                (ChangeKind::Added, "python", None, Some("1.0.0")),
+                (ChangeKind::Removed, "rust", Some("1.0.0"), None),
+            ]
+        );
+    }
+}
@@ -1,22 +1,48 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::env;
 use std::fmt::{self, Display};
-use std::fs::{self, File};
-use std::io::{self, Read, Write};
-use std::path::{Path, PathBuf};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
 use std::result::Result;
 use std::str::FromStr;
 
 use clap::Args;
-use inquire::{autocompletion::Replacement, Autocomplete, Confirm, CustomUserError, Select, Text};
+use inquire::{autocompletion::Replacement, Autocomplete, CustomUserError};
 use regex::bytes::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use serde_json::{self, Map, Value};
-use tar::{self, Archive, Builder, EntryType, Header};
+use sha2::{Digest, Sha256};
+use tar::{self, Builder, EntryType, Header};
 
+use crate::answers::{self, Answers};
+use crate::archive::{self, Extracted};
+use crate::atomic_write;
+use crate::batch;
+use crate::config::Config;
+use crate::download::{self, DownloadConfig};
+use crate::impact::{self, ImpactConfig};
+use crate::jsonc;
+use crate::lock::{self, Lockfile};
+use crate::merge;
+use crate::messages::{self, Message};
 use crate::oci_ref::OciReference;
-use crate::registry::{self, DevOption, StringDevOption};
+use crate::output::{self, Kind, OutputFormat};
+use crate::platform;
+use crate::policy::{self, Policy};
+use crate::prompt::{self, DefaultPrompter, InteractivePrompter, Prompter, RecordingPrompter, ScriptedPrompter};
+use crate::provenance::{self, Provenance};
+use crate::registry::{self, DevOption, LifecycleHook, StringDevOption};
+use crate::suggest;
+use crate::theme;
+use crate::timestamp;
+use crate::validate::{self, Severity};
+use crate::vscode::{self, OpenCode};
+use crate::workspace;
 
-#[derive(Debug, Args)]
+#[derive(Debug, Default, Args)]
 pub struct InitArgs {
     /// Avoid interactive prompts.
     #[arg(short = 'z', long)]
@@ -26,820 +52,4875 @@ pub struct InitArgs {
     #[arg(short = 's', long)]
     attempt_single_file: bool,
 
-    /// Strip comments from the generated devcontainer.json.
+    /// Write the generated devcontainer.json to the given path, relative to the workspace folder.
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["attempt_single_file", "stdout"])]
+    output: Option<PathBuf>,
+
+    /// Print the generated files instead of writing them to the workspace.
+    #[arg(long, conflicts_with = "attempt_single_file")]
+    stdout: bool,
+
+    /// Strip comments from the generated devcontainer.json, including the generation header.
     #[arg(short, long)]
     remove_comments: bool,
 
+    /// Print a diff of the files init would create or overwrite, without writing any.
+    #[arg(long, conflicts_with_all = ["stdout", "open_code"])]
+    dry_run: bool,
+
+    /// Overwrite existing files without first copying each to `<name>.bak`.
+    #[arg(long)]
+    no_backup: bool,
+
+    /// Write features pinned to the digest they resolve to, such as `<id>@sha256:…`, rather than to a version tag.
+    #[arg(long)]
+    pin_digests: bool,
+
+    /// Write the versions and digests of the template and features pulled to `tyedev.lock`.
+    #[arg(long, conflicts_with_all = ["stdout", "dry_run"])]
+    lock: bool,
+
+    /// Refuse templates and features that `tyedev.lock` does not have, or that resolve to another digest.
+    #[arg(long)]
+    locked: bool,
+
     /// Reference to a Template in a supported OCI registry.
     #[arg(short, long, value_name = "OCI_REF")]
     template_id: Option<OciReference>,
 
+    /// Display name of the devcontainer, prompted for when omitted.
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// User that tools connecting to the container run as, written to `remoteUser`.
+    #[arg(long, value_name = "USER")]
+    remote_user: Option<String>,
+
+    /// User that the container runs as, written to `containerUser`.
+    #[arg(long, value_name = "USER")]
+    container_user: Option<String>,
+
+    /// Path of the workspace inside the container, written to `workspaceFolder`.
+    #[arg(long, value_name = "PATH")]
+    container_workspace_folder: Option<String>,
+
+    /// Mount of the workspace in the Docker `--mount` format, written to `workspaceMount`.
+    #[arg(long, value_name = "MOUNT", requires = "container_workspace_folder")]
+    workspace_mount: Option<String>,
+
+    /// Start from a bare image instead of a template, then go straight to adding features.
+    #[arg(long, conflicts_with = "template_id")]
+    no_template: bool,
+
     /// Add the given features, may specify more than once.
     #[arg(short = 'f', long, value_name = "OCI_REF")]
     include_features: Option<Vec<OciReference>>,
 
+    /// Add the features of the given preset of the configuration, without prompting for their options. May be
+    /// repeated.
+    #[arg(long, value_name = "NAME")]
+    preset: Vec<String>,
+
+    /// Add the features of a JSON or YAML file shaped as the `features` object of devcontainer.json, with their
+    /// options, without prompting for the others.
+    #[arg(long, value_name = "FILE")]
+    features_file: Option<PathBuf>,
+
+    /// Ids of the included features whose options take their default when not given, instead of prompting.
+    #[arg(skip)]
+    defaulted_features: HashSet<String>,
+
+    /// Ids of the included features whose given options must all be declared and valid, rather than warned about.
+    #[arg(skip)]
+    checked_features: HashSet<String>,
+
+    /// Add the given VS Code extension to `customizations.vscode.extensions`, may specify more than once.
+    #[arg(long = "extension", value_name = "EXTENSION_ID")]
+    extensions: Vec<String>,
+
+    /// Command run when the content of the workspace is first available and whenever it is updated. A JSON array runs
+    /// without a shell.
+    #[arg(long, value_name = "COMMAND")]
+    update_content_command: Option<LifecycleHook>,
+
+    /// Command run once after the container is created. A JSON array runs without a shell.
+    #[arg(long, value_name = "COMMAND")]
+    post_create_command: Option<LifecycleHook>,
+
+    /// Command run every time the container starts. A JSON array runs without a shell.
+    #[arg(long, value_name = "COMMAND")]
+    post_start_command: Option<LifecycleHook>,
+
+    /// Forward the given port of the container, as `3000`, `db:5432`, or labeled as `3000=Frontend`. May be repeated.
+    #[arg(long = "forward-port", value_name = "PORT[=LABEL]")]
+    forward_ports: Vec<ForwardPort>,
+
+    /// Set an environment variable of the whole container in `containerEnv`. May be repeated.
+    #[arg(long = "env", value_name = "NAME=VALUE")]
+    container_env: Vec<OptionArg>,
+
+    /// Set an environment variable of the tools connecting to the container in `remoteEnv`. May be repeated.
+    #[arg(long = "remote-env", value_name = "NAME=VALUE")]
+    remote_env: Vec<OptionArg>,
+
     /// Include deprecated results when searching.
     #[arg(long)]
     include_deprecated: bool,
 
+    /// Exit with an error when the template or any feature is deprecated.
+    #[arg(long)]
+    fail_on_deprecated: bool,
+
     /// Target workspace for the devcontainer configuration.
     #[arg(short, long, value_name = "DIRECTORY")]
     workspace_folder: Option<PathBuf>,
-}
 
-async fn get_feature(
-    index: &registry::DevcontainerIndex,
-    feature_ref: &OciReference,
-) -> anyhow::Result<registry::Feature> {
-    log::debug!("get_feature");
+    /// Save every prompt answer to the given file.
+    #[arg(long, value_name = "FILE")]
+    record: Option<PathBuf>,
 
-    match index.get_feature(&feature_ref.id()) {
-        Some(feature) => Ok(feature.clone()),
-        None => pull_feature_configuration(feature_ref).await,
-    }
-}
+    /// Answer prompts from a file previously saved with `--record`.
+    #[arg(long, value_name = "FILE", conflicts_with = "non_interactive")]
+    replay: Option<PathBuf>,
+
+    /// Keep files that already exist in the workspace.
+    #[arg(long, conflicts_with = "overwrite_all")]
+    skip_existing: bool,
+
+    /// Replace files that already exist in the workspace without asking.
+    #[arg(long)]
+    overwrite_all: bool,
 
-async fn pull_feature_configuration(feature_ref: &OciReference) -> anyhow::Result<registry::Feature> {
-    log::debug!("pull_feature_configuration");
-    let bytes = registry::pull_archive_bytes(feature_ref).await?;
-    let mut archive = Archive::new(bytes.as_slice());
-    let entries = archive.entries()?;
+    /// Continue without asking when the workspace already has a devcontainer configuration, merging into it.
+    #[arg(long)]
+    force: bool,
 
-    for entry in entries {
-        let mut entry = entry?;
-        let filename = entry.path()?;
-        let filename = filename.to_str();
+    /// Skip comparing the architectures of the base image and features with this machine.
+    #[arg(long)]
+    no_platform_check: bool,
 
-        if filename.is_some_and(|p| p.ends_with("devcontainer-feature.json")) {
-            let size = entry.size() as usize;
-            let mut data: Vec<u8> = Vec::with_capacity(size);
-            entry.read_to_end(&mut data)?;
-            let feature: registry::Feature = serde_json::from_slice(data.as_slice())?;
+    /// Open the workspace in VS Code when done, either the folder or directly in the container.
+    #[arg(long, value_name = "MODE", num_args = 0..=1, default_missing_value = "folder")]
+    open_code: Option<OpenCode>,
 
-            log::debug!("pull_feature_configuration: read {} bytes", size);
+    /// Leave out the comment noting how the devcontainer.json was generated.
+    #[arg(long)]
+    no_header: bool,
 
-            return Ok(feature);
-        }
-    }
+    /// Leave out `.tyedev.toml`, which records the choices made for `tyedev regenerate`.
+    #[arg(long)]
+    no_provenance: bool,
+
+    /// Leave out the `customizations.tyedev` block noting the template, its version, and the option values.
+    #[arg(long)]
+    no_embedded_provenance: bool,
 
-    Err(io::Error::new(
-        io::ErrorKind::NotFound,
-        "No devcontainer-feature.json found in archive",
-    ))?
+    /// Replace a devcontainer.json generated from the same template, rather than merging the new choices into it.
+    #[arg(long)]
+    no_merge: bool,
+
+    /// Print a summary of the files written and the choices made, as text or JSON. Text is the default unless
+    /// non-interactive.
+    #[arg(long, value_name = "FORMAT", conflicts_with = "stdout")]
+    report: Option<OutputFormat>,
+
+    /// Initialize every project of a TOML manifest, one after another, without prompts.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "template_id", "no_template", "include_features", "name", "workspace_folder", "output", "stdout",
+            "record", "replay", "open_code", "answers_file", "save_answers", "option", "feature_option", "preset",
+            "features_file",
+        ],
+    )]
+    batch: Option<PathBuf>,
+
+    /// Take the template, options, and features from a YAML answers file instead of prompting.
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["template_id", "no_template", "include_features", "replay"],
+    )]
+    answers_file: Option<PathBuf>,
+
+    /// Save the template, options, and features chosen to a YAML answers file, for `--answers-file` to repeat.
+    #[arg(long, value_name = "FILE")]
+    save_answers: Option<PathBuf>,
+
+    /// Value of a template option, used instead of prompting. May be repeated.
+    #[arg(short = 'o', long = "option", value_name = "NAME=VALUE")]
+    option: Vec<OptionArg>,
+
+    /// Value of an option of an included feature, used instead of prompting. May be repeated.
+    #[arg(long, num_args = 2, value_names = ["OCI_REF", "NAME=VALUE"])]
+    feature_option: Vec<String>,
+
+    /// Values of template options by name, used instead of prompting.
+    #[arg(skip)]
+    template_options: BTreeMap<String, String>,
+
+    /// Values of feature options by feature id, used instead of prompting.
+    #[arg(skip)]
+    feature_options: FeatureOptions,
 }
 
-#[derive(Clone, Debug, PartialEq, Default)]
-struct DevOptionProposalsAutocomplete(Vec<String>);
+/// Values of feature options by feature id.
+type FeatureOptions = HashMap<String, Map<String, Value>>;
 
-impl DevOptionProposalsAutocomplete {
-    fn new(default_value: &String, values: &[String]) -> DevOptionProposalsAutocomplete {
-        if default_value.is_empty() || values.contains(default_value) {
-            DevOptionProposalsAutocomplete(values.into())
-        } else {
-            let mut all_values: Vec<String> = values.into();
-            all_values.insert(0, default_value.clone());
-            DevOptionProposalsAutocomplete(all_values)
-        }
-    }
+/// A `[preset.<name>]` section of `config.toml`, features added together by `tyedev init --preset <name>`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preset {
+    /// Options of the features by reference, as in the `features` object of devcontainer.json.
+    pub features: Map<String, Value>,
 }
 
-impl Autocomplete for DevOptionProposalsAutocomplete {
-    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
-        let DevOptionProposalsAutocomplete(proposals) = self;
-        let input_lower = input.to_lowercase();
-        let suggestions = proposals
-            .iter()
-            .filter_map(|s| {
-                if s.to_lowercase().starts_with(&input_lower) {
-                    Some(s.clone())
-                } else {
-                    None
-                }
-            })
-            .collect();
-        Ok(suggestions)
-    }
+/// The `[init]` section of `config.toml`.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct InitConfig {
+    /// What `--open-code` does when not given.
+    pub open_code: OpenCode,
+    /// Whether the generated devcontainer.json starts with a comment noting how it was generated.
+    pub header: bool,
+}
 
-    fn get_completion(
-        &mut self,
-        input: &str,
-        highlighted_suggestion: Option<String>,
-    ) -> Result<Replacement, CustomUserError> {
-        Ok(highlighted_suggestion.or_else(|| {
-            let suggestions = self.get_suggestions(input).ok()?;
-            if let [suggestion] = suggestions.as_slice() {
-                Some(suggestion.clone())
-            } else {
-                None
-            }
-        }))
+impl Default for InitConfig {
+    fn default() -> Self {
+        InitConfig {
+            open_code: OpenCode::default(),
+            header: true,
+        }
     }
 }
 
+/// A port to forward given on the command line as `port` or `host:port`, optionally labeled as `port=label`.
 #[derive(Clone, Debug, PartialEq)]
-pub enum DevOptionPromptValue {
-    String(String),
-    Boolean(bool),
+struct ForwardPort {
+    /// A number, or a string for a port of another host.
+    port: Value,
+    label: Option<String>,
 }
 
-impl Display for DevOptionPromptValue {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            DevOptionPromptValue::Boolean(value) => write!(f, "{value}"),
-            DevOptionPromptValue::String(value) => write!(f, "{value}"),
+impl FromStr for ForwardPort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (port, label) = match s.split_once('=') {
+            Some((port, label)) => (port.trim(), Some(label.trim()).filter(|label| !label.is_empty())),
+            None => (s.trim(), None),
+        };
+        let port = match port.rsplit_once(':') {
+            None => port.parse::<u16>().ok().map(Value::from),
+            Some((host, number)) if !host.is_empty() => number.parse::<u16>().ok().map(|_| Value::from(port)),
+            Some(_) => None,
+        };
+
+        match port {
+            Some(port) => Ok(ForwardPort {
+                port,
+                label: label.map(String::from),
+            }),
+            None => Err(format!("expected PORT, HOST:PORT, or either with =LABEL, found {s:?}")),
         }
     }
 }
 
+/// The value of an option given on the command line as `name=value`.
 #[derive(Clone, Debug, PartialEq)]
-pub struct DevOptionPrompt<'t> {
-    inner: &'t DevOption,
-    name: &'t str,
+struct OptionArg {
+    name: String,
+    value: String,
 }
 
-impl<'t> DevOptionPrompt<'t> {
-    pub fn new(name: &'t str, dev_option: &'t DevOption) -> DevOptionPrompt<'t> {
-        DevOptionPrompt {
-            inner: dev_option,
-            name,
+impl FromStr for OptionArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => Ok(OptionArg {
+                name: name.trim().to_string(),
+                value: value.to_string(),
+            }),
+            _ => Err(format!("expected NAME=VALUE, found {s:?}")),
         }
     }
+}
 
-    fn display_prompt(&self) -> anyhow::Result<DevOptionPromptValue> {
-        let dev_option = self.inner;
-        let default = dev_option.configured_default();
+/// Feature option values of `--feature-option`, by feature id. Clap collects the values of every occurrence into one
+/// list, a reference followed by `name=value` each time.
+fn feature_option_args(feature_option: &[String]) -> anyhow::Result<FeatureOptions> {
+    let mut feature_options = FeatureOptions::new();
 
-        match dev_option {
-            DevOption::Boolean { description, .. } => {
-                let message = description
-                    .as_ref()
-                    .map_or_else(|| format!("Include {}?", self.name), |s| s.clone());
-                let default_value = bool::from_str(&default)?;
-                let result = Confirm::new(&message).with_default(default_value).prompt()?;
-                let value = DevOptionPromptValue::Boolean(result);
+    for pair in feature_option.chunks(2) {
+        let [reference, option] = pair else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("--feature-option takes a reference and NAME=VALUE, found {pair:?}"),
+            ))?
+        };
+        let feature_ref: OciReference = reference.parse()?;
+        let OptionArg { name, value } = option
+            .parse()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, format!("--feature-option {error}")))?;
 
-                Ok(value)
-            },
-            DevOption::String(StringDevOption::EnumValues {
-                description, r#enum, ..
-            }) => {
-                let message = description
-                    .as_ref()
-                    .map_or_else(|| format!("Choose value for {}:", self.name), |s| s.clone());
-                let options = r#enum.iter().collect();
-                let start = r#enum.iter().position(|s| *s == default).unwrap_or_default();
-                let result = Select::new(&message, options).with_starting_cursor(start).prompt()?;
-                let value = DevOptionPromptValue::String(result.clone());
+        feature_options
+            .entry(feature_ref.id())
+            .or_default()
+            .insert(name, Value::String(value));
+    }
 
-                Ok(value)
-            },
-            DevOption::String(StringDevOption::Proposals {
-                description, proposals, ..
-            }) => {
-                let message = description
-                    .as_ref()
-                    .map_or_else(|| format!("What value for {}?", self.name), |s| s.clone());
-                let text_prompt = if let Some(values) = proposals.as_ref().filter(|&p| !p.is_empty()) {
-                    let autocomplete = DevOptionProposalsAutocomplete::new(&default, values);
+    Ok(feature_options)
+}
 
-                    Text::new(&message)
-                        .with_default(&default)
-                        .with_autocomplete(autocomplete)
-                } else {
-                    Text::new(&message).with_default(&default)
-                };
-                let result = text_prompt.prompt()?;
-                let value = DevOptionPromptValue::String(result);
+/// What init did to a file of the template.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum FileStatus {
+    Created,
+    Overwritten,
+    /// Already present with the same content.
+    Unchanged,
+    /// Already present and kept as it was.
+    Skipped,
+}
 
-                Ok(value)
-            },
-        }
-    }
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct WrittenFile {
+    /// Path relative to the workspace.
+    pub path: String,
+    pub status: FileStatus,
+    /// On a dry run, the diff from the existing file, or from nothing, to what init would have written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+    /// Where the file was copied to before it was overwritten, relative to the workspace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Default)]
-struct FeaturesAutocomplete(Vec<String>);
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct AddedFeature {
+    /// Key of the feature in the `features` object of devcontainer.json.
+    pub reference: String,
+    /// The `oci-layout:` or `file://` reference of a feature copied into the workspace from disk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub options: Value,
+}
 
-impl FeaturesAutocomplete {
-    fn new(index: &registry::DevcontainerIndex, include_deprecated: bool) -> Self {
-        let inner = index
-            .iter_features(include_deprecated)
-            .map(|feature| feature.id.clone())
-            .collect();
-        FeaturesAutocomplete(inner)
-    }
+/// Summary of an init, printed once the files are written.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InitReport {
+    pub workspace: PathBuf,
+    /// The template reference, absent for the built in start points.
+    pub template: Option<String>,
+    /// Version of the template.
+    pub template_version: Option<String>,
+    /// Digest of the template, absent when the registry did not give one.
+    pub template_digest: Option<String>,
+    /// Display name of the devcontainer, absent when left empty.
+    pub name: Option<String>,
+    pub template_options: BTreeMap<String, String>,
+    pub features: Vec<AddedFeature>,
+    pub files: Vec<WrittenFile>,
+    pub warnings: Vec<String>,
+    /// Follow up steps of the template, its NOTES.md with the template options substituted.
+    pub notes: Option<String>,
+    /// Nothing was written, the files are what init would have done.
+    pub dry_run: bool,
 }
 
-impl inquire::Autocomplete for FeaturesAutocomplete {
-    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, inquire::CustomUserError> {
-        let FeaturesAutocomplete(proposals) = self;
-        let suggestions = proposals
-            .iter()
-            .filter_map(|feature_id| {
-                if feature_id.contains(input) {
-                    Some(feature_id.clone())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<String>>();
-        Ok(suggestions)
+impl InitArgs {
+    /// Interactive init starting from an already chosen template and features.
+    #[cfg(feature = "browse")]
+    pub fn with_selection(template_id: Option<OciReference>, include_features: Vec<OciReference>) -> Self {
+        InitArgs {
+            template_id,
+            include_features: Some(include_features).filter(|features| !features.is_empty()),
+            ..Default::default()
+        }
     }
 
-    fn get_completion(
-        &mut self,
-        input: &str,
-        highlighted_suggestion: Option<String>,
-    ) -> Result<inquire::autocompletion::Replacement, inquire::CustomUserError> {
-        Ok(highlighted_suggestion.or_else(|| {
-            let suggestions = self.get_suggestions(input).ok()?;
-            if let [suggestion] = suggestions.as_slice() {
-                Some(suggestion.clone())
-            } else {
-                None
-            }
-        }))
+    /// Non-interactive init of one project of a batch manifest, sharing the other flags of the batch.
+    pub fn for_project(&self, project: &batch::Project, workspace: PathBuf) -> anyhow::Result<Self> {
+        log::debug!("InitArgs::for_project");
+        let template_id = match &project.template {
+            Some(template) => Some(template.parse()?),
+            None => None,
+        };
+        let (include_features, feature_options) = feature_choices(&project.features)?;
+
+        Ok(InitArgs {
+            non_interactive: true,
+            attempt_single_file: self.attempt_single_file,
+            remove_comments: self.remove_comments,
+            dry_run: self.dry_run,
+            no_backup: self.no_backup,
+            pin_digests: self.pin_digests,
+            lock: self.lock,
+            locked: self.locked,
+            no_template: template_id.is_none(),
+            template_id,
+            name: project.name.clone(),
+            remote_user: self.remote_user.clone(),
+            container_user: self.container_user.clone(),
+            container_workspace_folder: self.container_workspace_folder.clone(),
+            workspace_mount: self.workspace_mount.clone(),
+            include_features: Some(include_features).filter(|features| !features.is_empty()),
+            extensions: self.extensions.clone(),
+            update_content_command: self.update_content_command.clone(),
+            post_create_command: self.post_create_command.clone(),
+            post_start_command: self.post_start_command.clone(),
+            forward_ports: self.forward_ports.clone(),
+            container_env: self.container_env.clone(),
+            remote_env: self.remote_env.clone(),
+            fail_on_deprecated: self.fail_on_deprecated,
+            workspace_folder: Some(workspace),
+            skip_existing: self.skip_existing,
+            overwrite_all: self.overwrite_all,
+            force: self.force,
+            no_platform_check: self.no_platform_check,
+            no_header: self.no_header,
+            no_provenance: self.no_provenance,
+            no_embedded_provenance: self.no_embedded_provenance,
+            no_merge: self.no_merge,
+            template_options: project
+                .options
+                .iter()
+                .map(|(name, value)| (name.clone(), option_text(value)))
+                .collect(),
+            feature_options,
+            ..Default::default()
+        })
     }
-}
 
-#[derive(Clone, Debug, Default)]
-struct FeatureEntryBuilder {
-    features: HashMap<String, Value>,
-}
+    /// Non-interactive init that renders the choices recorded for a workspace again, over the files it has.
+    pub fn for_regenerate(
+        answers: Answers,
+        template_id: Option<OciReference>,
+        workspace: PathBuf,
+        dry_run: bool,
+        no_backup: bool,
+        no_header: bool,
+    ) -> anyhow::Result<Self> {
+        log::debug!("InitArgs::for_regenerate");
+        let mut args = InitArgs {
+            workspace_folder: Some(workspace),
+            overwrite_all: true,
+            dry_run,
+            no_backup,
+            no_header,
+            // The caller records the template as it was given, rather than pinned.
+            no_provenance: true,
+            ..Default::default()
+        };
 
-impl FeatureEntryBuilder {
-    fn new() -> Self {
-        log::debug!("FeatureEntryBuilder::new");
-        FeatureEntryBuilder {
-            features: HashMap::new(),
-        }
+        args.use_answers(answers)?;
+        args.no_template = template_id.is_none();
+        args.template_id = template_id;
+
+        Ok(args)
     }
 
-    fn use_prompt_values(&mut self, feature: &registry::Feature) -> anyhow::Result<()> {
-        log::debug!("FeatureEntryBuilder::use_prompt_values");
-        let key = format!("{}:{}", feature.id, feature.major_version);
-        let value = {
-            let mut inner = Map::new();
+    /// Include the features of the named preset. Options given on the command line win over those of the preset, and
+    /// the rest take their default.
+    fn use_preset(&mut self, name: &str, presets: &BTreeMap<String, Preset>) -> anyhow::Result<()> {
+        log::debug!("InitArgs::use_preset");
+        let preset = presets.get(name).ok_or_else(|| {
+            let error = io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No preset {name} in the configuration, define it in a [preset.{name}] section"),
+            );
+            suggest::with_suggestions(error.into(), name, presets.keys().map(String::as_str))
+        })?;
 
-            if let Some(options) = &feature.options {
-                for (name, dev_option) in options {
-                    let prompt = DevOptionPrompt::new(name, dev_option);
-                    let prompt_value = prompt.display_prompt()?;
+        self.include_defaulted(&preset.features)
+    }
 
-                    // TODO consider using inquire::{PromptType}::prompt_skippable instead.
-                    if prompt_value.to_string() == dev_option.configured_default() {
-                        continue;
-                    }
+    /// Include the features of a features file, whose options are checked against those the features declare.
+    fn use_features_file(&mut self, filename: &Path) -> anyhow::Result<()> {
+        log::debug!("InitArgs::use_features_file");
+        let features = read_features_file(filename)?;
 
-                    let value = match prompt_value {
-                        DevOptionPromptValue::Boolean(b) => serde_json::to_value(b),
-                        DevOptionPromptValue::String(s) => serde_json::to_value(s),
-                    }?;
+        for key in features.keys() {
+            let feature_ref: OciReference = key.parse()?;
+            self.checked_features.insert(feature_ref.id());
+        }
 
-                    inner.insert(name.clone(), value);
-                }
+        self.include_defaulted(&features)
+    }
+
+    /// Include the features of a `features` object, keeping the options given on the command line. Options of the
+    /// features that are in neither take their default.
+    fn include_defaulted(&mut self, features: &Map<String, Value>) -> anyhow::Result<()> {
+        let (feature_refs, feature_options) = feature_choices(features)?;
+        let include_features = self.include_features.get_or_insert_with(Vec::new);
+
+        for feature_ref in feature_refs {
+            let id = feature_ref.id();
+
+            if !include_features.iter().any(|included| included.id() == id) {
+                include_features.push(feature_ref);
             }
 
-            Value::Object(inner)
-        };
+            self.defaulted_features.insert(id);
+        }
 
-        self.features.insert(key, value);
+        for (id, options) in feature_options {
+            let given = self.feature_options.entry(id).or_default();
+
+            for (option, value) in options {
+                given.entry(option).or_insert(value);
+            }
+        }
 
         Ok(())
     }
 
-    fn use_default_values(&mut self, feature: &registry::Feature) {
-        log::debug!("FeatureEntryBuilder::use_default_values");
-        let key = format!("{}:{}", feature.id, feature.major_version);
-        let value = Value::Object(Map::default());
-
-        self.features.insert(key, value);
-    }
+    /// Take the choices of an answers file instead of prompting for them. A name given as a flag is kept.
+    fn use_answers(&mut self, answers: Answers) -> anyhow::Result<()> {
+        log::debug!("InitArgs::use_answers");
+        let (include_features, feature_options) = feature_choices(&answers.features)?;
 
-    fn as_value(&self) -> Result<Value, serde_json::Error> {
-        serde_json::to_value(self.features.clone())
-    }
+        self.non_interactive = true;
+        self.template_id = match answers.template {
+            Some(template) => Some(template.parse()?),
+            None => None,
+        };
+        self.no_template = self.template_id.is_none();
+        self.name = self.name.take().or(answers.name);
+        self.include_features = Some(include_features).filter(|features| !features.is_empty());
+        self.template_options = (answers.options.iter())
+            .map(|(name, value)| (name.clone(), option_text(value)))
+            .collect();
+        self.feature_options = feature_options;
 
-    fn len(&self) -> usize {
-        self.features.len()
+        Ok(())
     }
 }
 
-#[derive(Debug, Default)]
-struct TemplateBuilder {
-    config: Option<registry::Template>,
-    context: HashMap<String, String>,
-    features: FeatureEntryBuilder,
-    archive_bytes: Vec<u8>,
+/// Read a features file, JSON with comments unless its extension says YAML.
+fn read_features_file(filename: &Path) -> anyhow::Result<Map<String, Value>> {
+    log::debug!("read_features_file: {}", filename.display());
+
+    let contents = fs::read_to_string(filename)?;
+    let is_yaml = filename
+        .extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml");
+    let features = match is_yaml {
+        true => serde_yaml_ng::from_str(&contents).map_err(anyhow::Error::from),
+        false => serde_jsonc::from_str(&contents).map_err(anyhow::Error::from),
+    };
+
+    features.map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid features file {}: {error}", filename.display()),
+        )
+        .into()
+    })
 }
 
-impl TemplateBuilder {
-    async fn new(template_ref: &OciReference, config: Option<registry::Template>) -> anyhow::Result<Self> {
-        log::debug!("TemplateBuilder::new");
-        let archive_bytes = registry::pull_archive_bytes(template_ref).await?;
+/// What is wrong with the options given to a feature: options it does not declare, and values its options do not
+/// take.
+fn invalid_feature_options(
+    feature_ref: &OciReference,
+    feature: &registry::Feature,
+    given: &Map<String, Value>,
+) -> Vec<String> {
+    let options = feature.options.as_ref();
+
+    given
+        .iter()
+        .filter_map(|(name, value)| {
+            let Some(dev_option) = options.and_then(|options| options.get(name)) else {
+                let declared: Vec<&str> = options
+                    .iter()
+                    .flat_map(|options| options.keys())
+                    .map(String::as_str)
+                    .collect();
+                return Some(format!(
+                    "{feature_ref} has no option {name}, it has {}",
+                    match declared.is_empty() {
+                        true => "none".to_string(),
+                        false => declared.join(", "),
+                    }
+                ));
+            };
+            let text = option_text(value);
+
+            match dev_option {
+                _ if value.is_array() || value.is_object() || value.is_null() => Some(format!(
+                    "Option {name} of {feature_ref} takes a single value, not {value}"
+                )),
+                DevOption::Boolean { .. } if text != "true" && text != "false" => {
+                    Some(format!("Option {name} of {feature_ref} is a boolean, not {value}"))
+                },
+                DevOption::String(StringDevOption::EnumValues { r#enum, .. }) if !r#enum.contains(&text) => {
+                    Some(format!(
+                        "Option {name} of {feature_ref} is one of {}, not {value}",
+                        r#enum.join(", ")
+                    ))
+                },
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The flags that give a value to each required option, by `flag`, which skips the options already given.
+fn required_option_flags<'o>(
+    options: impl Iterator<Item = (&'o String, &'o DevOption)>,
+    flag: impl Fn(&str) -> Option<String>,
+) -> Vec<String> {
+    options
+        .filter(|(_, option)| option.is_required())
+        .filter_map(|(name, _)| flag(name))
+        .collect()
+}
+
+/// Features to include along with the values of their options by feature id, from an object shaped like the
+/// `features` of devcontainer.json.
+fn feature_choices(features: &Map<String, Value>) -> anyhow::Result<(Vec<OciReference>, FeatureOptions)> {
+    let mut include_features = Vec::new();
+    let mut feature_options = HashMap::new();
+
+    for (key, options) in features {
+        let feature_ref: OciReference = key.parse()?;
+        let options = match options {
+            Value::Object(options) => options.clone(),
+            Value::Null => Map::new(),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The options of {key} are not a table"),
+            ))?,
+        };
+
+        feature_options.insert(feature_ref.id(), options);
+        include_features.push(feature_ref);
+    }
+
+    Ok((include_features, feature_options))
+}
+
+pub async fn get_feature(
+    index: &registry::DevcontainerIndex,
+    feature_ref: &OciReference,
+) -> anyhow::Result<registry::Feature> {
+    log::debug!("get_feature");
+
+    let id = feature_ref.id();
+
+    match index.get_feature(&id) {
+        Some(feature) if is_published_tag(&feature_ref.tag_name(), &feature.version) => Ok(feature.clone()),
+        Some(_) => registry::pull_feature_configuration(feature_ref).await,
+        None => registry::pull_feature_configuration(feature_ref)
+            .await
+            .map_err(|error| suggest::with_suggestions(error, &id, feature_ids(index))),
+    }
+}
+
+/// Whether [`get_feature`] has to pull the feature, rather than reading its configuration from the index.
+fn needs_pull(index: &registry::DevcontainerIndex, feature_ref: &OciReference) -> bool {
+    !index
+        .get_feature(&feature_ref.id())
+        .is_some_and(|feature| is_published_tag(&feature_ref.tag_name(), &feature.version))
+}
+
+fn feature_ids(index: &registry::DevcontainerIndex) -> impl Iterator<Item = &str> {
+    index.iter_features(true).map(|feature| feature.id.as_str())
+}
+
+fn template_ids(index: &registry::DevcontainerIndex) -> impl Iterator<Item = &str> {
+    index.iter_templates(true).map(|template| template.id.as_str())
+}
+
+/// Pull a template that may be missing from the index, suggesting near misses from the index when the pull fails.
+async fn pull_template(
+    index: &registry::DevcontainerIndex,
+    template_ref: &OciReference,
+    template: Option<&registry::Template>,
+) -> anyhow::Result<TemplateBuilder> {
+    let builder = TemplateBuilder::new(template_ref, template.cloned()).await;
+
+    match template {
+        Some(_) => builder,
+        None => builder.map_err(|error| suggest::with_suggestions(error, &template_ref.id(), template_ids(index))),
+    }
+}
+
+/// Whether `tag` refers to `version`, following the major and minor tags published alongside each release.
+fn is_published_tag(tag: &str, version: &str) -> bool {
+    tag == "latest" || tag == version || version.starts_with(&format!("{tag}."))
+}
+
+/// Warn about a deprecated feature or template, or refuse it when `fail_on_deprecated` is set.
+fn check_deprecated(
+    index: &registry::DevcontainerIndex,
+    id: &str,
+    deprecated: bool,
+    fail_on_deprecated: bool,
+) -> io::Result<()> {
+    if !deprecated && !index.is_deprecated(id) {
+        return Ok(());
+    }
+
+    if fail_on_deprecated {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{id} is deprecated"),
+        ))
+    } else {
+        log::warn!("{}", theme::current().deprecated.paint(format!("{id} is deprecated")));
+        Ok(())
+    }
+}
+
+/// A feature required by another one that is not part of the configuration yet.
+struct Dependency {
+    dependent: String,
+    feature_ref: OciReference,
+    options: Value,
+    /// From `dependsOn`, otherwise only ordered by `installsAfter`.
+    required: bool,
+}
+
+/// The features that `feature` declares to install before itself. References that do not parse are skipped with a
+/// warning, the feature fails to install either way.
+fn dependencies(feature_ref: &OciReference, feature: &registry::Feature) -> Vec<Dependency> {
+    let depends_on = feature
+        .depends_on
+        .iter()
+        .flatten()
+        .map(|(reference, options)| (reference, options.clone(), true));
+    let installs_after = feature
+        .installs_after
+        .iter()
+        .flatten()
+        .map(|reference| (reference, Value::Object(Map::new()), false));
+
+    depends_on
+        .chain(installs_after)
+        .filter_map(
+            |(reference, options, required)| match reference.parse::<OciReference>() {
+                Ok(dependency_ref) => Some(Dependency {
+                    dependent: feature_ref.id(),
+                    feature_ref: dependency_ref,
+                    options,
+                    required,
+                }),
+                Err(error) => {
+                    log::warn!(
+                        "{} declares an invalid dependency {reference}: {error}",
+                        feature_ref.id()
+                    );
+                    None
+                },
+            },
+        )
+        .collect()
+}
+
+/// Offer to add the features that the added features depend on, and those in turn, with their default options.
+/// Features of `dependsOn` are added unless declined, features of `installsAfter` only when accepted.
+#[allow(clippy::too_many_arguments)]
+async fn add_dependencies(
+    index: &registry::DevcontainerIndex,
+    features: &mut FeatureEntryBuilder,
+    added: &mut Vec<(OciReference, registry::Feature)>,
+    present: &[String],
+    download: &DownloadConfig,
+    policy: &Policy,
+    fail_on_deprecated: bool,
+    quiet: bool,
+    prompter: &mut dyn Prompter,
+) -> anyhow::Result<()> {
+    log::debug!("add_dependencies");
+    let mut present: Vec<String> = present
+        .iter()
+        .cloned()
+        .chain(added.iter().map(|(feature_ref, _)| feature_ref.id()))
+        .collect();
+    let mut pending: VecDeque<Dependency> = added
+        .iter()
+        .flat_map(|(feature_ref, feature)| dependencies(feature_ref, feature))
+        .collect();
+
+    while let Some(dependency) = pending.pop_front() {
+        let id = dependency.feature_ref.id();
+
+        if present.contains(&id) {
+            continue;
+        }
+
+        let message = messages::format(
+            if dependency.required {
+                Message::AddDependency
+            } else {
+                Message::AddInstallsAfter
+            },
+            &[("feature", &dependency.dependent), ("id", &id)],
+        );
+
+        // Answered either way, so that the same dependency is only offered once.
+        present.push(id.clone());
+
+        if !prompter.confirm(&message, Some(dependency.required))? {
+            if dependency.required {
+                log::warn!("{} may fail to install without {id}", dependency.dependent);
+            }
+            continue;
+        }
+
+        Policy::enforce(policy.check_reference(&dependency.feature_ref))?;
+
+        if needs_pull(index, &dependency.feature_ref) {
+            download::confirm_download(&[&dependency.feature_ref], download, prompter, quiet).await?;
+        }
+
+        let feature = get_feature(index, &dependency.feature_ref).await?;
+        check_deprecated(index, &id, feature.is_deprecated(), fail_on_deprecated)?;
+        Policy::enforce(policy.check_feature(&dependency.feature_ref, &feature))?;
+
+        if quiet {
+            log::info!("Adding dependency: {}", dependency.feature_ref);
+        } else {
+            println!(
+                "{}",
+                messages::format(Message::AddingDependency, &[("reference", &dependency.feature_ref)])
+            );
+        }
+
+        features.use_options(&dependency.feature_ref, &feature, dependency.options);
+        pending.extend(dependencies(&dependency.feature_ref, &feature));
+        added.push((dependency.feature_ref, feature));
+    }
+
+    Ok(())
+}
+
+/// Order of installation, given each feature id with the ids it installs after. Every round installs the features
+/// whose dependencies are installed, in the order they were added. Features left over by a cycle go last.
+pub fn install_order(features: &[(String, Vec<String>)]) -> Vec<String> {
+    let ids: Vec<&String> = features.iter().map(|(id, _)| id).collect();
+    let mut order: Vec<String> = Vec::with_capacity(features.len());
+
+    loop {
+        let round: Vec<String> = features
+            .iter()
+            .filter(|(id, _)| !order.contains(id))
+            .filter(|(_, after)| {
+                after
+                    .iter()
+                    .all(|dependency| !ids.contains(&dependency) || order.contains(dependency))
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        if round.is_empty() {
+            break;
+        }
+
+        order.extend(round);
+    }
+
+    if order.len() < features.len() {
+        let cycle: Vec<String> = ids.into_iter().filter(|id| !order.contains(id)).cloned().collect();
+        log::warn!("Features depend on each other in a cycle: {}", cycle.join(", "));
+        order.extend(cycle);
+    }
+
+    order
+}
+
+/// Look up every feature before any prompt is shown, reporting all bad references at once.
+async fn resolve_features(
+    index: &registry::DevcontainerIndex,
+    feature_refs: Vec<OciReference>,
+) -> anyhow::Result<Vec<(OciReference, registry::Feature)>> {
+    log::debug!("resolve_features");
+    let mut features = Vec::with_capacity(feature_refs.len());
+    let mut errors = Vec::new();
+
+    for feature_ref in feature_refs {
+        match get_feature(index, &feature_ref).await {
+            Ok(feature) => features.push((feature_ref, feature)),
+            Err(error) => errors.push(format!("  {feature_ref}: {error}")),
+        }
+    }
+
+    if !errors.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid --include-features references:\n{}", errors.join("\n")),
+        ))?
+    }
+
+    Ok(features)
+}
+
+/// An option value as the text compared with defaults and substituted in templates, `true` rather than `"true"`.
+pub fn option_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Placeholder for the value of a template option, such as `${templateOption:imageVariant}`. The fallback of
+/// `${templateOption:imageVariant:-bookworm}` is used when the option has no value or an empty one.
+const TEMPLATE_OPTION_PATTERN: &str = r"\$\{templateOption:\s*(?<name>\w+)\s*(?::-(?<fallback>[^}]*))?\}";
+
+/// The value substituted for a placeholder: the option value unless it is empty, then the fallback if declared.
+fn template_option_value<'c>(
+    context: &'c HashMap<String, String>,
+    name: &str,
+    fallback: Option<&'c str>,
+) -> Option<&'c str> {
+    let value = context.get(name).map(String::as_str);
+
+    match (value, fallback) {
+        (Some(value), _) if !value.is_empty() => Some(value),
+        (_, Some(fallback)) => Some(fallback),
+        (value, None) => value,
+    }
+}
+
+/// Substitute the placeholders of template options in `contents`, leaving those without a value empty.
+pub fn render_template_options(contents: &str, context: &HashMap<String, String>) -> Result<String, regex::Error> {
+    let template_option_re = regex::Regex::new(TEMPLATE_OPTION_PATTERN)?;
+    let rendered = template_option_re.replace_all(contents, |captures: &regex::Captures| {
+        let fallback = captures.name("fallback").map(|fallback| fallback.as_str());
+        template_option_value(context, &captures["name"], fallback)
+            .unwrap_or_default()
+            .to_string()
+    });
+
+    Ok(rendered.into_owned())
+}
+
+/// Key of a feature read from disk: the folder next to devcontainer.json that it is copied to.
+fn local_feature_key(id: &str) -> String {
+    format!("./{id}")
+}
+
+/// Keep the read and execute bits of an archive entry. Setuid, setgid, sticky, and write access for anyone but the
+/// owner are dropped, while the owner always keeps read and write access.
+fn sanitize_mode(mode: u32) -> u32 {
+    (mode & 0o755) | 0o600
+}
+
+#[cfg(unix)]
+fn write_with_mode(filename: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match mode {
+        Some(mode) => {
+            let permissions = fs::Permissions::from_mode(sanitize_mode(mode));
+            atomic_write::write_with_permissions(filename, contents, permissions)
+        },
+        None => atomic_write::write(filename, contents),
+    }
+}
+
+/// Mode bits have no meaning on this platform, the default permissions apply.
+#[cfg(not(unix))]
+fn write_with_mode(filename: &Path, contents: &[u8], _mode: Option<u32>) -> io::Result<()> {
+    atomic_write::write(filename, contents)
+}
+
+#[cfg(unix)]
+fn set_directory_mode(directory: &Path, mode: Option<u32>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    match mode {
+        // Directories must stay traversable by the owner.
+        Some(mode) => fs::set_permissions(directory, fs::Permissions::from_mode(sanitize_mode(mode) | 0o700)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_directory_mode(_directory: &Path, _mode: Option<u32>) -> io::Result<()> {
+    Ok(())
+}
+
+/// Files of a template archive that describe the template rather than being written to the workspace.
+const TEMPLATE_METADATA_FILES: [&str; 3] = ["NOTES.md", "README.md", "devcontainer-template.json"];
+
+/// Destination of the generated devcontainer.json, when not the template's own location. Printing to stdout prints
+/// the other template files as well, each after a `--- <path>` line when there is more than one.
+#[derive(Clone, Debug, PartialEq)]
+enum OutputTarget {
+    File(PathBuf),
+    Stdout,
+}
+
+/// Replace the value of the first `"name"` property, which is the top level name in every published template. Leaves
+/// the rest of the text, including comments, untouched.
+fn replace_name(contents: &[u8], name: &str) -> Option<Vec<u8>> {
+    let name_re = Regex::new(r#""name"\s*:\s*"(?:[^"\\]|\\.)*""#).ok()?;
+    let found = name_re.find(contents)?;
+    let replacement = format!("\"name\": {}", serde_json::to_string(name).ok()?);
+    let mut replaced = Vec::with_capacity(contents.len() + replacement.len());
+    replaced.extend_from_slice(&contents[..found.start()]);
+    replaced.extend_from_slice(replacement.as_bytes());
+    replaced.extend_from_slice(&contents[found.end()..]);
+
+    Some(replaced)
+}
+
+/// What to do when a template file is already present in the workspace.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum ConflictPolicy {
+    #[default]
+    Prompt,
+    Skip,
+    Overwrite,
+}
+
+/// Copy a file about to be overwritten to `<name>.bak` beside it, replacing an older backup.
+fn backup_file(filename: &Path) -> io::Result<PathBuf> {
+    let mut backup = filename.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+
+    fs::copy(filename, &backup)?;
+
+    Ok(backup)
+}
+
+/// Write a template file, asking before an existing file with different content is replaced. The replaced file is
+/// first copied aside when `backup` is set.
+fn write_template_file(
+    workspace: &Path,
+    filename: &Path,
+    contents: &[u8],
+    mode: Option<u32>,
+    conflicts: ConflictPolicy,
+    backup: bool,
+    prompter: &mut dyn Prompter,
+) -> anyhow::Result<FileStatus> {
+    let display_name = filename
+        .strip_prefix(workspace)
+        .unwrap_or(filename)
+        .display()
+        .to_string();
+    let existing = match fs::read(filename) {
+        Ok(existing) => Some(existing),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => Err(error)?,
+    };
+
+    if let Some(existing) = existing {
+        // Only the timestamp of the generation header differs when the same template is applied again.
+        if without_generation_header(&existing) == without_generation_header(contents) {
+            log::info!("Unchanged: {display_name}");
+            return Ok(FileStatus::Unchanged);
+        }
+
+        let overwrite = match conflicts {
+            ConflictPolicy::Skip => false,
+            ConflictPolicy::Overwrite => true,
+            ConflictPolicy::Prompt => {
+                let choices = [
+                    messages::text(Message::FileOverwrite),
+                    messages::text(Message::FileSkip),
+                    messages::text(Message::FileShowDiff),
+                ];
+                let message = messages::format(Message::FileExists, &[("file", &display_name)]);
+                loop {
+                    match prompter.select(&message, &choices, 1)? {
+                        0 => break true,
+                        1 => break false,
+                        _ => print_diff(&display_name, &existing, contents),
+                    }
+                }
+            },
+        };
+
+        if !overwrite {
+            log::warn!("Skipping existing file: {display_name}");
+            return Ok(FileStatus::Skipped);
+        }
+
+        if backup {
+            let backup = backup_file(filename)?;
+            log::info!("Backed up {display_name} to {}", backup.display());
+        }
+
+        log::info!("Writing to {}", filename.display());
+        write_with_mode(filename, contents, mode)?;
+
+        return Ok(FileStatus::Overwritten);
+    }
+
+    log::info!("Writing to {}", filename.display());
+    write_with_mode(filename, contents, mode)?;
+
+    Ok(FileStatus::Created)
+}
+
+/// What writing a template file would do, along with a diff of what it would write when it creates or overwrites the
+/// file. Nothing is written.
+fn plan_template_file(
+    workspace: &Path,
+    filename: &Path,
+    contents: &[u8],
+    conflicts: ConflictPolicy,
+) -> anyhow::Result<(FileStatus, Option<String>)> {
+    let display_name = filename
+        .strip_prefix(workspace)
+        .unwrap_or(filename)
+        .display()
+        .to_string();
+    let existing = match fs::read(filename) {
+        Ok(existing) => Some(existing),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => Err(error)?,
+    };
+    let status = match &existing {
+        None => FileStatus::Created,
+        Some(existing) if without_generation_header(existing) == without_generation_header(contents) => {
+            FileStatus::Unchanged
+        },
+        Some(_) if conflicts == ConflictPolicy::Skip => FileStatus::Skipped,
+        Some(_) => FileStatus::Overwritten,
+    };
+
+    let diff = matches!(status, FileStatus::Created | FileStatus::Overwritten)
+        .then(|| diff_text(&display_name, existing.as_deref().unwrap_or_default(), contents));
+
+    Ok((status, diff))
+}
+
+/// Offer to edit the rendered devcontainer.json before it is written. Every save is validated again, and errors are
+/// reported before asking whether to edit again, to write the edited text anyway, or to discard the edits.
+fn edit_devcontainer<'c>(
+    contents: Cow<'c, [u8]>,
+    index: &registry::DevcontainerIndex,
+    prompter: &mut dyn Prompter,
+) -> anyhow::Result<Cow<'c, [u8]>> {
+    log::debug!("edit_devcontainer");
+
+    if !prompter.confirm(&messages::text(Message::EditBefore), Some(false))? {
+        return Ok(contents);
+    }
+
+    let mut text = String::from_utf8_lossy(&contents).into_owned();
+    let choices = [
+        messages::text(Message::EditAgain),
+        messages::text(Message::EditWriteAnyway),
+        messages::text(Message::EditDiscard),
+    ];
+
+    loop {
+        text = prompter.editor(&messages::text(Message::Edit), &text, ".json")?;
+
+        let diagnostics = validate::check_text(&text, index);
+
+        for diagnostic in &diagnostics {
+            log::warn!("{diagnostic}");
+        }
+
+        if diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity != Severity::Error)
+        {
+            return Ok(Cow::Owned(text.into_bytes()));
+        }
+
+        match prompter.select(&messages::text(Message::EditErrors), &choices, 0)? {
+            0 => continue,
+            1 => return Ok(Cow::Owned(text.into_bytes())),
+            _ => return Ok(contents),
+        }
+    }
+}
+
+/// Start of the comment that notes how a devcontainer.json was generated.
+const GENERATION_HEADER_PREFIX: &str = "// Generated by tyedev";
+
+/// Start of the generation header line that notes the template.
+const TEMPLATE_HEADER_PREFIX: &str = "// Template: ";
+
+/// The generation header: the version of tyedev, the time, and the template with its digest when there is one.
+fn generation_header(unix_time: u64, template: Option<(&str, Option<&str>)>) -> String {
+    let mut header = format!(
+        "{GENERATION_HEADER_PREFIX} {} on {}.\n",
+        env!("CARGO_PKG_VERSION"),
+        timestamp::rfc3339(unix_time)
+    );
+
+    match template {
+        Some((reference, Some(digest))) => {
+            header.push_str(&format!("{TEMPLATE_HEADER_PREFIX}{reference} ({digest})\n"))
+        },
+        Some((reference, None)) => header.push_str(&format!("{TEMPLATE_HEADER_PREFIX}{reference}\n")),
+        None => {},
+    }
+
+    header
+}
+
+/// The template reference and digest noted by the generation header of a devcontainer.json, or else by its
+/// `customizations.tyedev` block.
+pub fn recorded_template(contents: &str) -> Option<(String, Option<String>)> {
+    header_template(contents).or_else(|| {
+        let provenance = embedded_provenance(contents)?;
+
+        Some((provenance.template?, provenance.digest))
+    })
+}
+
+/// The template reference and digest noted by a leading generation header, the reverse of [`generation_header`].
+fn header_template(contents: &str) -> Option<(String, Option<String>)> {
+    if !contents.starts_with(GENERATION_HEADER_PREFIX) {
+        return None;
+    }
+
+    let template = contents.lines().nth(1)?.strip_prefix(TEMPLATE_HEADER_PREFIX)?;
+
+    match template.rsplit_once(" (") {
+        Some((reference, digest)) if digest.ends_with(')') => {
+            Some((reference.to_string(), Some(digest.trim_end_matches(')').to_string())))
+        },
+        _ => Some((template.to_string(), None)),
+    }
+}
+
+/// The template, its version and digest, and the option values noted by the `customizations.tyedev` block, the
+/// reverse of [`TemplateBuilder::embedded_provenance`].
+fn embedded_provenance(contents: &str) -> Option<Provenance> {
+    let config: Value = serde_jsonc::from_str(contents).ok()?;
+    let provenance = config.get("customizations")?.get(env!("CARGO_PKG_NAME"))?;
+    let text = |name: &str| provenance.get(name).and_then(Value::as_str).map(str::to_string);
+
+    Some(Provenance {
+        template: Some(text("template")?),
+        version: text("version"),
+        digest: text("digest"),
+        options: (provenance
+            .get("options")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten())
+        .map(|(name, value)| (name.clone(), option_text(value)))
+        .collect(),
+        ..Default::default()
+    })
+}
+
+/// Remove the `customizations.tyedev` block of a devcontainer.json, along with `customizations` when nothing else
+/// is left in it. Whether there was a block is returned.
+fn remove_embedded_provenance(config: &mut Value) -> bool {
+    let Some(devcontainer) = config.as_object_mut() else {
+        return false;
+    };
+    let Some(customizations) = devcontainer.get_mut("customizations").and_then(Value::as_object_mut) else {
+        return false;
+    };
+
+    if customizations.shift_remove(env!("CARGO_PKG_NAME")).is_none() {
+        return false;
+    }
+
+    if customizations.is_empty() {
+        devcontainer.shift_remove("customizations");
+    }
+
+    true
+}
+
+/// The text of a devcontainer.json without its `customizations.tyedev` block, which no template has.
+pub fn without_embedded_provenance(contents: &str) -> Cow<'_, str> {
+    let Ok(mut config) = serde_jsonc::from_str::<Value>(contents) else {
+        return Cow::Borrowed(contents);
+    };
+
+    if !remove_embedded_provenance(&mut config) {
+        return Cow::Borrowed(contents);
+    }
+
+    jsonc::update(contents, &config).map_or(Cow::Borrowed(contents), Cow::Owned)
+}
+
+/// The contents after a leading generation header, which is its first line and the template line that may follow
+/// it. Comments of the user after the header are kept.
+pub fn without_generation_header(contents: &[u8]) -> &[u8] {
+    fn after_line(contents: &[u8]) -> &[u8] {
+        contents
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(&[], |end| &contents[end + 1..])
+    }
+
+    if !contents.starts_with(GENERATION_HEADER_PREFIX.as_bytes()) {
+        return contents;
+    }
+
+    let rest = after_line(contents);
+
+    if rest.starts_with(TEMPLATE_HEADER_PREFIX.as_bytes()) {
+        after_line(rest)
+    } else {
+        rest
+    }
+}
+
+/// Unified diff from the existing file to the rendered template file, or `None` when either is not text.
+fn unified_diff(display_name: &str, existing: &[u8], contents: &[u8]) -> Option<String> {
+    let is_text = |bytes: &[u8]| !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok();
+
+    if !is_text(existing) || !is_text(contents) {
+        return None;
+    }
+
+    let existing = String::from_utf8_lossy(existing);
+    let contents = String::from_utf8_lossy(contents);
+    let diff = similar::TextDiff::from_lines(existing.as_ref(), contents.as_ref());
+
+    Some(
+        diff.unified_diff()
+            .header(&format!("a/{display_name}"), &format!("b/{display_name}"))
+            .to_string(),
+    )
+}
+
+/// The unified diff of a file, or the line git prints in its place when either side is not text.
+fn diff_text(display_name: &str, existing: &[u8], contents: &[u8]) -> String {
+    unified_diff(display_name, existing, contents)
+        .unwrap_or_else(|| format!("Binary files a/{display_name} and b/{display_name} differ\n"))
+}
+
+pub fn print_diff(display_name: &str, existing: &[u8], contents: &[u8]) {
+    print_diff_text(&diff_text(display_name, existing, contents));
+}
+
+fn print_diff_text(diff: &str) {
+    let theme = theme::current();
+
+    for line in diff.lines() {
+        let style = if ["+++", "---", "@@", "Binary files "]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        {
+            &theme.diff_header
+        } else if line.starts_with('+') {
+            &theme.diff_added
+        } else if line.starts_with('-') {
+            &theme.diff_removed
+        } else {
+            println!("{line}");
+            continue;
+        };
+
+        println!("{}", style.paint(line));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+struct DevOptionProposalsAutocomplete(Vec<String>);
+
+impl DevOptionProposalsAutocomplete {
+    fn new(default_value: &String, values: &[String]) -> DevOptionProposalsAutocomplete {
+        if default_value.is_empty() || values.contains(default_value) {
+            DevOptionProposalsAutocomplete(values.into())
+        } else {
+            let mut all_values: Vec<String> = values.into();
+            all_values.insert(0, default_value.clone());
+            DevOptionProposalsAutocomplete(all_values)
+        }
+    }
+}
+
+impl Autocomplete for DevOptionProposalsAutocomplete {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        let DevOptionProposalsAutocomplete(proposals) = self;
+        let input_lower = input.to_lowercase();
+        let suggestions = proposals
+            .iter()
+            .filter_map(|s| {
+                if s.to_lowercase().starts_with(&input_lower) {
+                    Some(s.clone())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Ok(suggestions)
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        Ok(highlighted_suggestion.or_else(|| {
+            let suggestions = self.get_suggestions(input).ok()?;
+            if let [suggestion] = suggestions.as_slice() {
+                Some(suggestion.clone())
+            } else {
+                None
+            }
+        }))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DevOptionPromptValue {
+    String(String),
+    Boolean(bool),
+}
+
+impl Display for DevOptionPromptValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DevOptionPromptValue::Boolean(value) => write!(f, "{value}"),
+            DevOptionPromptValue::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DevOptionPrompt<'t> {
+    inner: &'t DevOption,
+    name: &'t str,
+}
+
+impl<'t> DevOptionPrompt<'t> {
+    pub fn new(name: &'t str, dev_option: &'t DevOption) -> DevOptionPrompt<'t> {
+        DevOptionPrompt {
+            inner: dev_option,
+            name,
+        }
+    }
+
+    fn display_prompt(&self, prompter: &mut dyn Prompter) -> anyhow::Result<DevOptionPromptValue> {
+        let dev_option = self.inner;
+        let default = dev_option.configured_default();
+
+        match dev_option {
+            DevOption::Boolean { description, .. } => {
+                let message = description.as_ref().map_or_else(
+                    || messages::format(Message::OptionInclude, &[("name", &self.name)]),
+                    |s| s.clone(),
+                );
+                let default_value = bool::from_str(&default)?;
+                let result = prompter.confirm(&message, Some(default_value))?;
+                let value = DevOptionPromptValue::Boolean(result);
+
+                Ok(value)
+            },
+            DevOption::String(StringDevOption::EnumValues {
+                description, r#enum, ..
+            }) => {
+                let message = description.as_ref().map_or_else(
+                    || messages::format(Message::OptionChoose, &[("name", &self.name)]),
+                    |s| s.clone(),
+                );
+                let start = r#enum.iter().position(|s| *s == default).unwrap_or_default();
+                let result = prompter.select(&message, r#enum, start)?;
+                let value = DevOptionPromptValue::String(r#enum[result].clone());
+
+                Ok(value)
+            },
+            DevOption::String(StringDevOption::Proposals {
+                description, proposals, ..
+            }) => {
+                let message = description.as_ref().map_or_else(
+                    || messages::format(Message::OptionValue, &[("name", &self.name)]),
+                    |s| s.clone(),
+                );
+                let autocomplete: Option<Box<dyn Autocomplete>> = proposals
+                    .as_ref()
+                    .filter(|&p| !p.is_empty())
+                    .map(|values| Box::new(DevOptionProposalsAutocomplete::new(&default, values)) as _);
+                let result = prompter.text(&message, Some(&default), autocomplete)?;
+                let value = DevOptionPromptValue::String(result);
+
+                Ok(value)
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Default)]
+struct FeaturesAutocomplete(Vec<String>);
+
+impl FeaturesAutocomplete {
+    fn new(index: &registry::DevcontainerIndex, include_deprecated: bool) -> Self {
+        let inner = index
+            .iter_features(include_deprecated)
+            .map(|feature| feature.id.clone())
+            .collect();
+        FeaturesAutocomplete(inner)
+    }
+}
+
+impl inquire::Autocomplete for FeaturesAutocomplete {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, inquire::CustomUserError> {
+        let FeaturesAutocomplete(proposals) = self;
+        let suggestions = proposals
+            .iter()
+            .filter_map(|feature_id| {
+                if feature_id.contains(input) {
+                    Some(feature_id.clone())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<String>>();
+        Ok(suggestions)
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<inquire::autocompletion::Replacement, inquire::CustomUserError> {
+        Ok(highlighted_suggestion.or_else(|| {
+            let suggestions = self.get_suggestions(input).ok()?;
+            if let [suggestion] = suggestions.as_slice() {
+                Some(suggestion.clone())
+            } else {
+                None
+            }
+        }))
+    }
+}
+
+/// The `features` object of the generated devcontainer.json, in the order the features were added so that
+/// regenerating a configuration gives an identical file.
+#[derive(Clone, Debug, Default)]
+struct FeatureEntryBuilder {
+    features: Map<String, Value>,
+}
+
+impl FeatureEntryBuilder {
+    fn new() -> Self {
+        log::debug!("FeatureEntryBuilder::new");
+        FeatureEntryBuilder { features: Map::new() }
+    }
+
+    /// Prompt for every option of the feature, except those with a value in `given`.
+    fn use_prompt_values(
+        &mut self,
+        feature_ref: &OciReference,
+        feature: &registry::Feature,
+        given: &Map<String, Value>,
+        prompter: &mut dyn Prompter,
+    ) -> anyhow::Result<()> {
+        log::debug!("FeatureEntryBuilder::use_prompt_values");
+        let key = Self::entry_key(feature_ref, feature);
+        let options = feature.options.as_ref();
+
+        for name in given.keys() {
+            if !options.is_some_and(|options| options.contains_key(name)) {
+                log::warn!("{feature_ref} has no option {name}");
+            }
+        }
+
+        let value = {
+            let mut inner = Map::new();
+
+            if let Some(options) = options {
+                for (name, dev_option) in options {
+                    if let Some(value) = given.get(name) {
+                        // Values given on the command line are text, whatever the type of the option.
+                        let value = match (dev_option, value) {
+                            (DevOption::Boolean { .. }, Value::String(text)) => {
+                                Value::Bool(text.parse().map_err(|_| {
+                                    io::Error::new(
+                                        io::ErrorKind::InvalidInput,
+                                        format!("Option {name} of {feature_ref} is a boolean, not {text:?}"),
+                                    )
+                                })?)
+                            },
+                            (_, value) => value.clone(),
+                        };
+
+                        if option_text(&value) != dev_option.configured_default() {
+                            inner.insert(name.clone(), value);
+                        }
+                        continue;
+                    }
+
+                    let prompt = DevOptionPrompt::new(name, dev_option);
+                    let prompt_value = prompt.display_prompt(prompter)?;
+
+                    // TODO consider using inquire::{PromptType}::prompt_skippable instead.
+                    if prompt_value.to_string() == dev_option.configured_default() {
+                        continue;
+                    }
+
+                    let value = match prompt_value {
+                        DevOptionPromptValue::Boolean(b) => serde_json::to_value(b),
+                        DevOptionPromptValue::String(s) => serde_json::to_value(s),
+                    }?;
+
+                    inner.insert(name.clone(), value);
+                }
+            }
+
+            Value::Object(inner)
+        };
+
+        self.features.insert(key, value);
+
+        Ok(())
+    }
+
+    /// Key of the feature in the `features` object.
+    fn entry_key(feature_ref: &OciReference, feature: &registry::Feature) -> String {
+        let major_version = match feature.major_version.as_str() {
+            "" => feature.version.split('.').next().unwrap_or_default(),
+            major_version => major_version,
+        };
+
+        // A digest stays pinned in the generated configuration. A local artifact is copied into a folder next to
+        // devcontainer.json, which is how the dev container CLI refers to a feature that is not published.
+        match feature_ref.digest() {
+            Some(digest) => format!("{}@{digest}", feature_ref.id()),
+            None if feature_ref.is_local() => local_feature_key(&feature.id),
+            None => format!("{}:{major_version}", feature_ref.id()),
+        }
+    }
+
+    /// Add a feature with the given options, without prompting.
+    fn use_options(&mut self, feature_ref: &OciReference, feature: &registry::Feature, options: Value) {
+        log::debug!("FeatureEntryBuilder::use_options");
+        let options = match options {
+            Value::Object(options) => Value::Object(options),
+            _ => Value::Object(Map::new()),
+        };
+
+        self.features.insert(Self::entry_key(feature_ref, feature), options);
+    }
+
+    /// Reorder the added features to install in `order`, given as feature ids, so that the `features` object reads in
+    /// the order the features are installed. Keys of other features stay after them.
+    fn sort_by_install_order(&mut self, added: &[(OciReference, registry::Feature)], order: &[String]) {
+        let position = |key: &String| {
+            added
+                .iter()
+                .find(|(feature_ref, feature)| Self::entry_key(feature_ref, feature) == *key)
+                .and_then(|(feature_ref, _)| order.iter().position(|id| *id == feature_ref.id()))
+                .unwrap_or(order.len())
+        };
+
+        let mut features: Vec<(String, Value)> = std::mem::take(&mut self.features).into_iter().collect();
+
+        // A stable sort keeps the order of addition among features of the same position.
+        features.sort_by_key(|(key, _)| position(key));
+        self.features = features.into_iter().collect();
+    }
+
+    /// Pin the added features from a registry to the manifest digest their tag currently resolves to, such as
+    /// `ghcr.io/devcontainers/features/node@sha256:…`, keeping their order.
+    async fn pin_digests(&mut self, added: &[(OciReference, registry::Feature)]) -> anyhow::Result<()> {
+        log::debug!("FeatureEntryBuilder::pin_digests");
+        let mut pinned = HashMap::new();
+
+        for (feature_ref, feature) in added {
+            if let OciReference::Registry(image) = feature_ref {
+                if image.digest().is_none() {
+                    let digest = registry::manifest_digest(image).await?;
+                    pinned.insert(
+                        Self::entry_key(feature_ref, feature),
+                        format!("{}@{digest}", feature_ref.id()),
+                    );
+                }
+            }
+        }
+
+        self.features = std::mem::take(&mut self.features)
+            .into_iter()
+            .map(|(key, options)| (pinned.remove(&key).unwrap_or(key), options))
+            .collect();
+
+        Ok(())
+    }
+
+    fn as_value(&self) -> Result<Value, serde_json::Error> {
+        Ok(Value::Object(self.features.clone()))
+    }
+
+    fn len(&self) -> usize {
+        self.features.len()
+    }
+}
+
+#[derive(Debug, Default)]
+struct TemplateBuilder {
+    config: Option<registry::Template>,
+    name: Option<String>,
+    context: HashMap<String, String>,
+    features: FeatureEntryBuilder,
+    archive_bytes: Vec<u8>,
+    /// The reference the template was pulled from and its digest, absent for the built in start points.
+    source: Option<(String, Option<String>)>,
+    /// Comment written at the top of devcontainer.json.
+    header: Option<String>,
+    /// VS Code extensions to add to `customizations.vscode.extensions`.
+    extensions: Vec<String>,
+    /// Top level properties of devcontainer.json chosen during init, replacing those of the template.
+    properties: Map<String, Value>,
+    /// Note the template and the option values in the `customizations.tyedev` block of devcontainer.json.
+    embed_provenance: bool,
+    /// Merge the choices into a devcontainer.json generated from the same template, rather than replacing it.
+    merge: bool,
+    /// Write devcontainer.json without the comments of the template.
+    remove_comments: bool,
+    /// Print the files instead of writing them.
+    dry_run: bool,
+    /// Copy existing files to `<name>.bak` before overwriting them.
+    backup: bool,
+    /// Archives of the features read from disk by id, extracted into a folder of that name next to devcontainer.json.
+    local_features: Vec<(String, Vec<u8>)>,
+}
+
+impl TemplateBuilder {
+    async fn new(template_ref: &OciReference, config: Option<registry::Template>) -> anyhow::Result<Self> {
+        log::debug!("TemplateBuilder::new");
+        let archive_bytes = registry::pull_archive_bytes(template_ref).await?;
+        let digest = match template_ref {
+            OciReference::Registry(reference) => registry::manifest_digest(reference)
+                .await
+                .inspect_err(|error| log::debug!("TemplateBuilder::new: no digest for {template_ref}: {error}"))
+                .ok(),
+            // The digest of a local archive identifies it just as well.
+            _ => Some(format!("sha256:{:x}", Sha256::digest(&archive_bytes))),
+        };
         let template_archive = TemplateBuilder {
             config,
+            name: None,
             context: HashMap::new(),
             features: FeatureEntryBuilder::new(),
             archive_bytes,
+            source: Some((template_ref.to_string(), digest)),
+            header: None,
+            extensions: Vec::new(),
+            properties: Map::new(),
+            embed_provenance: false,
+            merge: false,
+            remove_comments: false,
+            dry_run: false,
+            backup: false,
+            local_features: Vec::new(),
+        };
+
+        Ok(template_archive)
+    }
+
+    /// Note how devcontainer.json was generated in a comment at its top.
+    fn use_generation_header(&mut self) -> anyhow::Result<()> {
+        let unix_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let template = self
+            .source
+            .as_ref()
+            .map(|(reference, digest)| (reference.as_str(), digest.as_deref()));
+
+        self.header = Some(generation_header(unix_time, template));
+
+        Ok(())
+    }
+
+    /// The devcontainer.json of the template as the recorded choices rendered it.
+    fn recorded_devcontainer(&self, template_text: &str, recorded: &Provenance) -> anyhow::Result<Value> {
+        let options: HashMap<String, String> = recorded.options.clone().into_iter().collect();
+        let mut config: Value = serde_jsonc::from_str(&render_template_options(template_text, &options)?)?;
+
+        if let Some(devcontainer) = config.as_object_mut() {
+            if let Some(name) = &recorded.name {
+                devcontainer.insert("name".into(), Value::String(name.clone()));
+            }
+            if !recorded.features.is_empty() {
+                match devcontainer.get_mut("features").and_then(Value::as_object_mut) {
+                    Some(features) => features.extend(recorded.features.clone()),
+                    None => {
+                        devcontainer.insert("features".into(), Value::Object(recorded.features.clone()));
+                    },
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The devcontainer.json at `filename` with `contents` merged into it, when it records being generated from the
+    /// same template, either in `.tyedev.toml` or in its `customizations.tyedev` block. The template rendered with the
+    /// recorded choices is the common base, so that edits made to the file since are kept. Nothing is returned for any
+    /// other file, which is written as usual.
+    fn merged_devcontainer(
+        &self,
+        workspace: &Path,
+        filename: &Path,
+        template_text: &str,
+        contents: &[u8],
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        log::debug!("TemplateBuilder::merged_devcontainer");
+        let Some((reference, digest)) = self.source.as_ref().filter(|_| self.merge) else {
+            return Ok(None);
+        };
+        let existing = match fs::read_to_string(filename) {
+            Ok(existing) => existing,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => Err(error)?,
+        };
+        let template_id = reference.parse::<OciReference>()?.id();
+        let is_same_template = |recorded: &Provenance| {
+            (recorded.template.as_deref())
+                .and_then(|template| template.parse::<OciReference>().ok())
+                .is_some_and(|template| template.id() == template_id)
+        };
+        let Some(recorded) = (provenance::read_provenance(workspace)?.filter(is_same_template))
+            .or_else(|| embedded_provenance(&existing).filter(is_same_template))
+        else {
+            return Ok(None);
+        };
+        let body = String::from_utf8_lossy(without_generation_header(existing.as_bytes())).into_owned();
+        let Ok(mut current) = serde_jsonc::from_str::<Value>(&body) else {
+            return Ok(None);
+        };
+        let new: Value = serde_jsonc::from_slice(contents)?;
+        // Another version of the template is no common base, the new choices win wherever the file differs.
+        let mut base = match recorded.digest == *digest {
+            true => Some(self.recorded_devcontainer(template_text, &recorded)?),
+            false => None,
+        };
+
+        for config in base.iter_mut().chain([&mut current]) {
+            remove_embedded_provenance(config);
+            merge::align_feature_keys(config, &new);
+        }
+
+        let (Some(merged), conflicts) = merge::merge(base.as_ref(), Some(&current), Some(&new)) else {
+            return Ok(None);
+        };
+        let display_name = filename.strip_prefix(workspace).unwrap_or(filename).display();
+
+        log::info!("Merging the choices into {display_name}");
+
+        for path in conflicts {
+            warnings.push(format!(
+                "{display_name}: {path} was edited, and changed by the template as well, the value of the template was used"
+            ));
+        }
+
+        let text = match jsonc::update(&body, &merged) {
+            Some(text) => text,
+            None => {
+                warnings.push(format!("Comments have been stripped from {display_name}"));
+                let mut bytes: Vec<u8> = Vec::new();
+                serde_json_pretty::to_writer_with_tabs(&mut bytes, &merged)?;
+                String::from_utf8(bytes)?
+            },
+        };
+
+        Ok(Some(text.into_bytes()))
+    }
+
+    /// The `customizations.tyedev` block of devcontainer.json, nothing for the built in start points.
+    fn embedded_provenance(&self) -> Option<Value> {
+        let (reference, digest) = self.source.as_ref().filter(|_| self.embed_provenance)?;
+        let options: BTreeMap<&String, &String> = self.context.iter().collect();
+        let mut provenance = serde_json::json!({
+            "template": reference,
+            "version": self.config.as_ref().map(|config| &config.version),
+            "digest": digest,
+            "options": options,
+            "tyedevVersion": env!("CARGO_PKG_VERSION"),
+        });
+
+        if let Some(provenance) = provenance.as_object_mut() {
+            provenance.retain(|_, value| !value.is_null());
+        }
+
+        Some(provenance)
+    }
+
+    /// Number of files of the template that are written to the workspace.
+    fn template_file_count(&self) -> io::Result<usize> {
+        let entries = archive::list_entries(self.archive_bytes.as_slice())?;
+
+        Ok((entries.iter())
+            .filter(|entry| matches!(entry.entry_type, EntryType::Regular | EntryType::Continuous))
+            .filter(|entry| !TEMPLATE_METADATA_FILES.iter().any(|&name| entry.path.ends_with(name)))
+            .count())
+    }
+
+    fn replace_config(&mut self) -> std::io::Result<()> {
+        log::debug!("TemplateBuilder::replace_config");
+        let found = archive::find_entry(self.archive_bytes.as_slice(), |path| {
+            path.to_str().is_some_and(|p| p.ends_with("devcontainer-template.json"))
+        })?;
+
+        if let Some((_, data)) = found {
+            let config = serde_json::from_slice(data.as_slice())?;
+
+            self.config.replace(config);
+            log::debug!("TemplateBuilder::replace_config: read {} bytes", data.len());
+
+            return Ok(());
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "The devcontainer-template.json file was not found in the archive",
+        ))?
+    }
+
+    /// Prompt for every option of the template, except those with a value in `given`.
+    fn use_prompt_values(
+        &mut self,
+        given: &BTreeMap<String, String>,
+        prompter: &mut dyn Prompter,
+    ) -> anyhow::Result<()> {
+        log::debug!("TemplateBuilder::use_prompt_values");
+        let config = self
+            .config
+            .as_ref()
+            .ok_or_else(|| io::Error::other("Missing configuration"))?;
+
+        for name in given.keys() {
+            if !config
+                .options
+                .as_ref()
+                .is_some_and(|options| options.contains_key(name))
+            {
+                log::warn!("The template has no option {name}");
+            }
+        }
+
+        if let Some(options) = &config.options {
+            self.context.clear();
+
+            for (name, template_option) in options {
+                if let Some(value) = given.get(name) {
+                    self.context.insert(name.clone(), value.clone());
+                    continue;
+                }
+
+                let dev_prompt = DevOptionPrompt::new(name, template_option);
+                let value = dev_prompt.display_prompt(prompter)?;
+                self.context.insert(name.clone(), value.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Placeholders of the template files that may not render as intended: fallbacks, which other devcontainer tools
+    /// leave in place, and options the template does not declare.
+    fn lint_placeholders(&self) -> anyhow::Result<Vec<String>> {
+        log::debug!("TemplateBuilder::lint_placeholders");
+        let template_option_re = regex::Regex::new(TEMPLATE_OPTION_PATTERN)?;
+        let options = self.config.as_ref().and_then(|config| config.options.as_ref());
+        let mut warnings = Vec::new();
+
+        let files = archive::read_files(self.archive_bytes.as_slice(), |path, size| {
+            size <= archive::MAX_ENTRY_SIZE && !path.ends_with("devcontainer-template.json")
+        })?;
+
+        for (path, bytes) in files {
+            let contents = String::from_utf8_lossy(&bytes);
+
+            for captures in template_option_re.captures_iter(&contents) {
+                let placeholder = &captures[0];
+                let name = &captures["name"];
+
+                if !options.is_some_and(|options| options.contains_key(name)) {
+                    warnings.push(format!(
+                        "{path}: {placeholder} refers to an option the template does not declare"
+                    ));
+                }
+
+                if captures.name("fallback").is_some() {
+                    warnings.push(format!(
+                        "{path}: {placeholder} relies on a fallback value, which other devcontainer tools leave unreplaced"
+                    ));
+                }
+            }
+        }
+
+        warnings.dedup();
+
+        Ok(warnings)
+    }
+
+    /// Use the given display name, otherwise prompt for one based on the template name and workspace directory.
+    fn use_name(&mut self, name: Option<String>, workspace: &Path, prompter: &mut dyn Prompter) -> anyhow::Result<()> {
+        log::debug!("TemplateBuilder::use_name");
+        let name = match name {
+            Some(name) => name,
+            None => {
+                // The built-in start points have no meaningful name of their own.
+                let template_name = self
+                    .config
+                    .as_ref()
+                    .filter(|config| !config.id.starts_with("tyedev-"))
+                    .map(|config| config.name.as_str());
+                let directory_name = workspace.file_name().map(|name| name.to_string_lossy());
+                let default = match (template_name, directory_name) {
+                    (Some(template_name), Some(directory_name)) => format!("{template_name} ({directory_name})"),
+                    (Some(template_name), None) => template_name.to_string(),
+                    (None, Some(directory_name)) => directory_name.to_string(),
+                    (None, None) => String::new(),
+                };
+                prompter.text(&messages::text(Message::DevcontainerName), Some(&default), None)?
+            },
+        };
+
+        self.name = Some(name).filter(|name| !name.is_empty());
+
+        Ok(())
+    }
+
+    /// Use the given VS Code extensions, along with those picked from the extensions the added features recommend.
+    fn use_extensions(
+        &mut self,
+        given: &[String],
+        added: &[(OciReference, registry::Feature)],
+        prompter: &mut dyn Prompter,
+    ) -> anyhow::Result<()> {
+        log::debug!("TemplateBuilder::use_extensions");
+        let mut recommended: Vec<String> = Vec::new();
+
+        for (_, feature) in added {
+            let extensions = feature.customizations.as_ref().and_then(|c| c.vscode_extensions());
+
+            for extension in extensions.into_iter().flatten() {
+                if !given.contains(&extension) && !recommended.contains(&extension) {
+                    recommended.push(extension);
+                }
+            }
+        }
+
+        self.extensions = given.to_vec();
+
+        if !recommended.is_empty() {
+            let chosen = prompter.multi_select(&messages::text(Message::Extensions), &recommended, &[])?;
+            self.extensions
+                .extend(chosen.into_iter().map(|index| recommended[index].clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Use the given lifecycle commands, otherwise offer to prompt for each of them when none are given.
+    fn use_lifecycle_hooks(
+        &mut self,
+        given: [(&str, Option<&LifecycleHook>); 3],
+        prompter: &mut dyn Prompter,
+    ) -> anyhow::Result<()> {
+        log::debug!("TemplateBuilder::use_lifecycle_hooks");
+        let prompt = given.iter().all(|(_, hook)| hook.is_none())
+            && prompter.confirm(&messages::text(Message::LifecycleCommands), Some(false))?;
+
+        for (property, hook) in given {
+            let hook = match hook {
+                Some(hook) => Some(hook.clone()),
+                None if prompt => {
+                    let message = messages::format(Message::LifecycleCommand, &[("property", &property)]);
+                    let current = self.properties.get(property).map(option_text);
+                    let input = prompter.text(&message, current.as_deref(), None)?;
+
+                    match input.trim() {
+                        "" => None,
+                        input => Some(
+                            input
+                                .parse::<LifecycleHook>()
+                                .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?,
+                        ),
+                    }
+                },
+                None => continue,
+            };
+
+            match hook {
+                Some(hook) => self.properties.insert(property.into(), serde_json::to_value(hook)?),
+                None => self.properties.shift_remove(property),
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Use the given ports to forward, otherwise prompt for them.
+    fn use_forward_ports(&mut self, given: &[ForwardPort], prompter: &mut dyn Prompter) -> anyhow::Result<()> {
+        log::debug!("TemplateBuilder::use_forward_ports");
+        let ports = match given {
+            [] => {
+                let current = (self.properties.get("forwardPorts").and_then(Value::as_array))
+                    .map(|ports| {
+                        (ports.iter())
+                            .map(|port| {
+                                let port = option_text(port);
+                                let label = self
+                                    .properties
+                                    .get("portsAttributes")
+                                    .and_then(|attributes| attributes.get(&port))
+                                    .and_then(|attributes| attributes.get("label"))
+                                    .and_then(Value::as_str);
+                                match label {
+                                    Some(label) => format!("{port}={label}"),
+                                    None => port,
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .unwrap_or_default();
+                let input = prompter.text(&messages::text(Message::ForwardPorts), Some(&current), None)?;
+
+                (input.split_whitespace())
+                    .map(|port| port.parse::<ForwardPort>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?
+            },
+            given => given.to_vec(),
+        };
+
+        self.properties.shift_remove("forwardPorts");
+        self.properties.shift_remove("portsAttributes");
+
+        if ports.is_empty() {
+            return Ok(());
+        }
+
+        let attributes: Map<String, Value> = (ports.iter())
+            .filter_map(|ForwardPort { port, label }| {
+                Some((option_text(port), serde_json::json!({ "label": label.as_ref()? })))
+            })
+            .collect();
+
+        self.properties.insert(
+            "forwardPorts".into(),
+            ports.into_iter().map(|ForwardPort { port, .. }| port).collect(),
+        );
+
+        if !attributes.is_empty() {
+            self.properties
+                .insert("portsAttributes".into(), Value::Object(attributes));
+        }
+
+        Ok(())
+    }
+
+    /// Use the given environment variables, otherwise prompt for them after showing those the added features set
+    /// already.
+    fn use_environment(
+        &mut self,
+        container_env: &[OptionArg],
+        remote_env: &[OptionArg],
+        added: &[(OciReference, registry::Feature)],
+        quiet: bool,
+        prompter: &mut dyn Prompter,
+    ) -> anyhow::Result<()> {
+        log::debug!("TemplateBuilder::use_environment");
+        let feature_env: Vec<(&str, &str, &str)> = (added.iter())
+            .flat_map(|(_, feature)| {
+                (feature.container_env.iter().flatten())
+                    .map(|(name, value)| (name.as_str(), value.as_str(), feature.id.as_str()))
+            })
+            .collect();
+        let mut variables: Vec<(&str, OptionArg)> = (container_env.iter())
+            .map(|variable| ("containerEnv", variable.clone()))
+            .chain(remote_env.iter().map(|variable| ("remoteEnv", variable.clone())))
+            .collect();
+
+        if variables.is_empty() {
+            if !quiet && !feature_env.is_empty() {
+                println!("Environment set by the features:");
+
+                for (name, value, feature) in &feature_env {
+                    println!("  {name}={value} ({feature})");
+                }
+            }
+
+            let targets = [
+                ("containerEnv", Message::EnvironmentContainer),
+                ("remoteEnv", Message::EnvironmentRemote),
+            ];
+            let choices: Vec<String> = targets.iter().map(|(_, message)| messages::text(*message)).collect();
+
+            while prompter.confirm(&messages::text(Message::AddEnvironment), Some(false))? {
+                let input = prompter.text(&messages::text(Message::Environment), None, None)?;
+                let variable: OptionArg = input
+                    .parse()
+                    .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+                let message = messages::format(Message::EnvironmentTarget, &[("name", &variable.name)]);
+                let (property, _) = targets[prompter.select(&message, &choices, 0)?];
+
+                variables.push((property, variable));
+            }
+        }
+
+        self.properties.shift_remove("containerEnv");
+        self.properties.shift_remove("remoteEnv");
+
+        for (property, OptionArg { name, value }) in variables {
+            if let Some((_, _, feature)) = feature_env.iter().find(|(feature_name, ..)| *feature_name == name) {
+                log::warn!("{name} is set by the feature {feature} as well");
+            }
+
+            let environment = self
+                .properties
+                .entry(property)
+                .or_insert_with(|| Value::Object(Map::new()));
+
+            if let Some(environment) = environment.as_object_mut() {
+                environment.insert(name, Value::String(value));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The base image of the template, from the `image` of devcontainer.json or else the `FROM` of its Dockerfile.
+    /// Images that still depend on a variable cannot be known ahead of the build.
+    fn base_image(&self) -> anyhow::Result<Option<String>> {
+        log::debug!("TemplateBuilder::base_image");
+        let mut image = None;
+        let mut from = None;
+
+        let is_devcontainer_json =
+            |path: &Path| path.ends_with(".devcontainer/devcontainer.json") || path.ends_with(".devcontainer.json");
+        let is_dockerfile = |path: &Path| path.ends_with(".devcontainer/Dockerfile");
+        let files = archive::read_files(self.archive_bytes.as_slice(), |path, size| {
+            size <= archive::MAX_ENTRY_SIZE && (is_devcontainer_json(path) || is_dockerfile(path))
+        })?;
+
+        for (path, data) in files {
+            let contents = render_template_options(&String::from_utf8(data)?, &self.context)?;
+
+            if is_devcontainer_json(Path::new(&path)) {
+                let value: Value = serde_jsonc::from_str(&contents)?;
+                image = value.get("image").and_then(Value::as_str).map(String::from);
+            } else {
+                from = workspace::dockerfile_base_image(&contents);
+            }
+        }
+
+        Ok(image.or(from).filter(|image| !image.contains('$')))
+    }
+
+    fn is_single_file_eligible(&self) -> bool {
+        if !self.local_features.is_empty() {
+            log::warn!("Skipping --attempt-single-file as local features are copied into the .devcontainer folder");
+            return false;
+        }
+
+        if let Some(template) = self.config.as_ref() {
+            if let Some(template_type) = template.r#type.as_ref() {
+                return match template_type {
+                    registry::TemplateType::DockerCompose => {
+                        log::warn!(
+                            "Skipping --attempt-single-file as the selected template includes a docker-compose.yml"
+                        );
+                        false
+                    },
+                    registry::TemplateType::Dockerfile => {
+                        log::warn!("Skipping --attempt-single-file as the selected template includes a Dockerfile");
+                        false
+                    },
+                    // Most image templates have exactly four files: .devcontainer/devcontainer.json, devcontainer-feature.json, NOTES.md, README.md
+                    registry::TemplateType::Image if template.file_count.is_some_and(|count| count > 4) => {
+                        log::warn!("Skipping --attempt-single-file as the template likely contains more than just a devcontainer.json file");
+                        false
+                    },
+                    registry::TemplateType::Image => true,
+                };
+            }
+        }
+
+        false
+    }
+
+    /// The NOTES.md at the top of the template with the template options substituted, nothing when it has none.
+    fn notes(&self) -> anyhow::Result<Option<String>> {
+        log::debug!("TemplateBuilder::notes");
+        let found = archive::find_entry(self.archive_bytes.as_slice(), |path| {
+            path.strip_prefix(".").unwrap_or(path) == Path::new("NOTES.md")
+        })?;
+        let Some((_, data)) = found else {
+            return Ok(None);
+        };
+        let notes = render_template_options(&String::from_utf8(data)?, &self.context)?;
+
+        Ok(Some(notes.trim().to_string()).filter(|notes| !notes.is_empty()))
+    }
+
+    /// The devcontainer.json as it would be written, before it is edited, for review before any file is written.
+    /// Nothing is returned when the template has none.
+    fn preview_devcontainer(&self) -> anyhow::Result<Option<Vec<u8>>> {
+        log::debug!("TemplateBuilder::preview_devcontainer");
+        let found = archive::find_entry(self.archive_bytes.as_slice(), |path| {
+            path.ends_with(".devcontainer/devcontainer.json") || path.ends_with(".devcontainer.json")
+        })?;
+        let Some((_, data)) = found else {
+            return Ok(None);
+        };
+        let with_context = render_template_options(&String::from_utf8(data)?, &self.context)?;
+        let contents = self.devcontainer_contents(Cow::Owned(with_context.into_bytes()), &mut Vec::new())?;
+
+        Ok(Some(contents.into_owned()))
+    }
+
+    /// The devcontainer.json of the template, with the template options already substituted, given the name and the
+    /// features chosen. The name and features are edited into the text of the template, keeping its comments unless
+    /// they were asked to be removed. When the text has to be written again instead, that is added to `warnings`.
+    fn devcontainer_contents<'c>(
+        &self,
+        with_context: Cow<'c, [u8]>,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<Cow<'c, [u8]>> {
+        let renamed = self.name.as_deref().and_then(|name| replace_name(&with_context, name));
+        let needs_name = self.name.is_some() && renamed.is_none();
+        let with_context = renamed.map_or(with_context, Cow::Owned);
+
+        let provenance = self.embedded_provenance();
+
+        let contents = if self.features.len() > 0
+            || needs_name
+            || !self.extensions.is_empty()
+            || !self.properties.is_empty()
+            || provenance.is_some()
+            || self.remove_comments
+        {
+            let mut bytes: Vec<u8> = Vec::new();
+            bytes.write_all(&with_context)?;
+            let mut value: Value = serde_jsonc::from_slice(bytes.as_slice())?;
+            let devcontainer = value
+                .as_object_mut()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Format of devcontainer.json is invalid"))?;
+            if let Some(name) = self.name.as_ref().filter(|_| needs_name) {
+                devcontainer.shift_insert(0, "name".into(), Value::String(name.clone()));
+            }
+            if self.features.len() > 0 {
+                match devcontainer.get_mut("features").and_then(|f| f.as_object_mut()) {
+                    Some(features) => features.extend(self.features.features.clone()),
+                    None => {
+                        let features_value = self.features.as_value()?;
+                        devcontainer.insert("features".into(), features_value);
+                    },
+                }
+            }
+            for (property, value) in &self.properties {
+                devcontainer.insert(property.clone(), value.clone());
+            }
+            if !self.extensions.is_empty() {
+                let vscode = devcontainer
+                    .entry("customizations")
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                    .map(|customizations| {
+                        customizations
+                            .entry("vscode")
+                            .or_insert_with(|| Value::Object(Map::new()))
+                    })
+                    .and_then(Value::as_object_mut);
+                let extensions = vscode
+                    .map(|vscode| vscode.entry("extensions").or_insert_with(|| Value::Array(Vec::new())))
+                    .and_then(Value::as_array_mut);
+                if let Some(extensions) = extensions {
+                    for extension in &self.extensions {
+                        let extension = Value::String(extension.clone());
+                        if !extensions.contains(&extension) {
+                            extensions.push(extension);
+                        }
+                    }
+                }
+            }
+            if let Some(provenance) = provenance {
+                let customizations = devcontainer
+                    .entry("customizations")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let Some(customizations) = customizations.as_object_mut() {
+                    customizations.insert(env!("CARGO_PKG_NAME").into(), provenance);
+                }
+            }
+            let edited = (!self.remove_comments)
+                .then(|| jsonc::update(std::str::from_utf8(&with_context).ok()?, &value))
+                .flatten();
+            match edited {
+                Some(edited) => Cow::Owned(edited.into_bytes()),
+                None => {
+                    if !self.remove_comments {
+                        warnings.push("Comments have been stripped from devcontainer.json".to_string());
+                    }
+                    let mut bytes: Vec<u8> = Vec::new();
+                    serde_json_pretty::to_writer_with_tabs(&mut bytes, &value)?;
+                    Cow::Owned(bytes)
+                },
+            }
+        } else {
+            with_context
+        };
+
+        Ok(contents)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn apply_context_and_features(
+        &mut self,
+        index: &registry::DevcontainerIndex,
+        attempt_single_file: bool,
+        output: Option<&OutputTarget>,
+        workspace: &Path,
+        conflicts: ConflictPolicy,
+        prompter: &mut dyn Prompter,
+        warnings: &mut Vec<String>,
+    ) -> anyhow::Result<Vec<WrittenFile>> {
+        log::debug!("TemplateBuilder::apply_context_and_features");
+        let template_option_re = Regex::new(TEMPLATE_OPTION_PATTERN)?;
+        let apply_context = |captures: &Captures| -> Vec<u8> {
+            let name = &captures["name"];
+            let name = std::str::from_utf8(name).ok();
+            let fallback = captures
+                .name("fallback")
+                .and_then(|fallback| std::str::from_utf8(fallback.as_bytes()).ok());
+            match name.and_then(|key| template_option_value(&self.context, key, fallback)) {
+                Some(value) => {
+                    log::debug!(
+                        "TemplateBuilder::apply_context_and_features: Replacing ${{templateOption:{}}} with \"{}\"",
+                        name.unwrap_or_default(),
+                        value
+                    );
+                    value.as_bytes().to_vec()
+                },
+                None => {
+                    log::warn!("No value provided for ${{templateOption:{}}}", name.unwrap_or_default());
+                    Vec::new()
+                },
+            }
+        };
+        let stdout = output == Some(&OutputTarget::Stdout);
+        let delimited = stdout && (self.template_file_count()? > 1 || !self.local_features.is_empty());
+        let print_file = |filename: &Path, contents: &[u8]| -> io::Result<()> {
+            let mut out = io::stdout().lock();
+
+            if delimited {
+                writeln!(
+                    out,
+                    "--- {}",
+                    filename.strip_prefix(workspace).unwrap_or(filename).display()
+                )?;
+            }
+
+            out.write_all(contents)?;
+
+            if delimited && !contents.ends_with(b"\n") {
+                writeln!(out)?;
+            }
+
+            Ok(())
+        };
+        let mut written: Vec<WrittenFile> = Vec::new();
+        let mut devcontainer_folder = workspace.join(".devcontainer");
+        let backup = self.backup;
+        let mut record =
+            |filename: &Path, (status, diff): (FileStatus, Option<String>), warnings: &mut Vec<String>| {
+                let path = filename
+                    .strip_prefix(workspace)
+                    .unwrap_or(filename)
+                    .display()
+                    .to_string();
+
+                if status == FileStatus::Skipped {
+                    warnings.push(format!("Skipped existing file: {path}"));
+                }
+
+                let backup = (backup && status == FileStatus::Overwritten).then(|| format!("{path}.bak"));
+
+                written.push(WrittenFile {
+                    path,
+                    status,
+                    diff,
+                    backup,
+                });
+            };
+
+        archive::extract_to(self.archive_bytes.as_slice(), workspace, |entry| {
+            let mut filename = entry.path;
+
+            if TEMPLATE_METADATA_FILES.iter().any(|&name| filename.ends_with(name)) {
+                log::debug!(
+                    "TemplateBuilder::apply_context_and_features: Skipping template file: {}",
+                    filename.display()
+                );
+                return Ok(());
+            }
+
+            match entry.contents {
+                Extracted::Directory => {
+                    if !self.dry_run && !stdout {
+                        log::info!("Creating directory: {}", filename.display());
+                        fs::create_dir_all(&filename)?;
+                        set_directory_mode(&filename, entry.mode)?;
+                    }
+                },
+                Extracted::File(bytes) => {
+                    log::info!("Reading file from template archive: {}", filename.display());
+
+                    let mode = entry.mode;
+                    let with_context = template_option_re.replace_all(bytes.as_slice(), apply_context);
+                    let dc_filename1 = ".devcontainer/devcontainer.json";
+                    let dc_filename2 = ".devcontainer.json";
+
+                    if filename.ends_with(dc_filename1) || filename.ends_with(dc_filename2) {
+                        match output {
+                            Some(OutputTarget::File(output)) => filename = workspace.join(output),
+                            _ if attempt_single_file && self.is_single_file_eligible() => {
+                                filename = workspace.join(".devcontainer.json");
+                            },
+                            _ => {},
+                        }
+
+                        if let Some(parent) = filename.parent() {
+                            devcontainer_folder = parent.to_path_buf();
+                        }
+
+                        let known_warnings = warnings.len();
+                        let contents = self.devcontainer_contents(with_context, warnings)?;
+                        let merged = match output {
+                            Some(OutputTarget::Stdout) => None,
+                            _ => self.merged_devcontainer(
+                                workspace,
+                                &filename,
+                                &String::from_utf8_lossy(&bytes),
+                                &contents,
+                                warnings,
+                            )?,
+                        };
+                        // A merge keeps the edits of the file, so it is written without asking.
+                        let (contents, conflicts) = match merged {
+                            Some(merged) if conflicts != ConflictPolicy::Skip => {
+                                (Cow::Owned(merged), ConflictPolicy::Overwrite)
+                            },
+                            _ => (contents, conflicts),
+                        };
+
+                        for warning in &warnings[known_warnings..] {
+                            log::warn!("{warning}");
+                        }
+
+                        let contents = edit_devcontainer(contents, index, prompter)?;
+                        // Comments typed in the editor are removed as well, unless the edits are no longer JSON.
+                        let contents = match serde_jsonc::from_slice::<Value>(&contents) {
+                            Ok(value) if self.remove_comments => {
+                                let mut bytes: Vec<u8> = Vec::new();
+                                serde_json_pretty::to_writer_with_tabs(&mut bytes, &value)?;
+                                bytes.push(b'\n');
+                                Cow::Owned(bytes)
+                            },
+                            _ => contents,
+                        };
+                        let contents = match &self.header {
+                            Some(header) => Cow::Owned([header.as_bytes(), &contents].concat()),
+                            None => contents,
+                        };
+
+                        if stdout {
+                            print_file(&filename, &contents)?;
+                        } else if self.dry_run {
+                            record(
+                                &filename,
+                                plan_template_file(workspace, &filename, &contents, conflicts)?,
+                                warnings,
+                            );
+                        } else {
+                            if let Some(parent) = filename.parent() {
+                                fs::create_dir_all(parent)?;
+                            }
+                            let status = write_template_file(
+                                workspace, &filename, &contents, mode, conflicts, backup, prompter,
+                            )?;
+                            record(&filename, (status, None), warnings);
+                        }
+                    } else if stdout {
+                        print_file(&filename, &with_context)?;
+                    } else if self.dry_run {
+                        let planned = plan_template_file(workspace, &filename, &with_context, conflicts)?;
+                        record(&filename, planned, warnings);
+                    } else {
+                        let status = write_template_file(
+                            workspace,
+                            &filename,
+                            &with_context,
+                            mode,
+                            conflicts,
+                            backup,
+                            prompter,
+                        )?;
+                        record(&filename, (status, None), warnings);
+                    }
+                },
+                Extracted::Link => {
+                    log::warn!("Skipping link in template archive: {}", filename.display());
+                    warnings.push(format!("Skipped link in template archive: {}", filename.display()));
+                },
+                Extracted::Other(entry_type) => {
+                    log::debug!(
+                        "TemplateBuilder::apply_context_and_features: Skipping {:?} entry: {}",
+                        entry_type,
+                        filename.display()
+                    );
+                },
+            }
+
+            Ok(())
+        })?;
+
+        for (id, archive_bytes) in &self.local_features {
+            let folder = devcontainer_folder.join(id);
+
+            archive::extract_to(archive_bytes.as_slice(), &folder, |entry| {
+                let filename = entry.path;
+
+                match entry.contents {
+                    Extracted::Directory => {
+                        if !self.dry_run && !stdout {
+                            fs::create_dir_all(&filename)?;
+                            set_directory_mode(&filename, entry.mode)?;
+                        }
+                    },
+                    Extracted::File(bytes) if stdout => print_file(&filename, &bytes)?,
+                    Extracted::File(bytes) if self.dry_run => {
+                        record(
+                            &filename,
+                            plan_template_file(workspace, &filename, &bytes, conflicts)?,
+                            warnings,
+                        );
+                    },
+                    Extracted::File(bytes) => {
+                        if let Some(parent) = filename.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        let status = write_template_file(
+                            workspace, &filename, &bytes, entry.mode, conflicts, backup, prompter,
+                        )?;
+                        record(&filename, (status, None), warnings);
+                    },
+                    Extracted::Link => {
+                        log::warn!("Skipping link in feature archive: {}", filename.display());
+                        warnings.push(format!("Skipped link in feature archive: {}", filename.display()));
+                    },
+                    Extracted::Other(entry_type) => {
+                        log::debug!(
+                            "TemplateBuilder::apply_context_and_features: Skipping {:?} entry: {}",
+                            entry_type,
+                            filename.display()
+                        );
+                    },
+                }
+
+                Ok(())
+            })?;
+        }
+
+        log::debug!("TemplateBuilder::apply_context_and_features: done");
+
+        Ok(written)
+    }
+
+    fn create_empty_start_point() -> anyhow::Result<Self> {
+        let template_value = serde_json::json!({
+            "id": "tyedev-base-template",
+            "version": "1.0.0",
+            "name": "Base Template (tyedev)",
+            "options": {
+                "imageVariant": {
+                    "type": "string",
+                    "default": "jammy",
+                    "proposals": [
+                        "bookworm",
+                        "bullseye",
+                        "jammy",
+                        "focal"
+                    ]
+                }
+            },
+            "type": "image",
+            "fileCount": 2,
+            "owner": "CodeMan99"
+        });
+        let dot_devcontainer_json: &[u8] = b"{\n\t\"name\": \"tyedev default\",\n\t\"image\": \"mcr.microsoft.com/devcontainers/base:${templateOption:imageVariant}\"\n}\n";
+        let template_builder = Self::create_start_point(template_value, dot_devcontainer_json)?;
+
+        #[cfg(test)]
+        {
+            let tmp = env::temp_dir();
+            fs::write(
+                tmp.join("devcontainer-template-tyedev-default.tar"),
+                &template_builder.archive_bytes,
+            )?;
+        }
+
+        Ok(template_builder)
+    }
+
+    /// A bare `{ "image": … }` configuration, for adding features to an image the user already has.
+    fn create_image_start_point() -> anyhow::Result<Self> {
+        let template_value = serde_json::json!({
+            "id": "tyedev-image-template",
+            "version": "1.0.0",
+            "name": "Image Only (tyedev)",
+            "options": {
+                "image": {
+                    "type": "string",
+                    "description": "Base image",
+                    "default": "mcr.microsoft.com/devcontainers/base:bookworm",
+                    "proposals": [
+                        "mcr.microsoft.com/devcontainers/base:bookworm",
+                        "mcr.microsoft.com/devcontainers/base:jammy",
+                        "debian:bookworm",
+                        "ubuntu:noble",
+                        "alpine:latest"
+                    ]
+                }
+            },
+            "type": "image",
+            "fileCount": 2,
+            "owner": "CodeMan99"
+        });
+        let dot_devcontainer_json: &[u8] = b"{\n\t\"image\": \"${templateOption:image}\"\n}\n";
+
+        Self::create_start_point(template_value, dot_devcontainer_json)
+    }
+
+    /// Build an in-memory template archive holding the given configuration and devcontainer.json.
+    fn create_start_point(template_value: Value, dot_devcontainer_json: &[u8]) -> anyhow::Result<Self> {
+        let tar_blocksize = 512;
+        // 3 header blocks, 2 content blocks, 2 zero blocks
+        let tar_chunks = 7;
+        let mut builder = Builder::new(Vec::with_capacity(tar_blocksize * tar_chunks));
+        let mtime = {
+            let unix_time = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+            unix_time.as_secs()
+        };
+
+        let create_directory_header = |path: &str| -> io::Result<Header> {
+            let mut header = Header::new_gnu();
+            header.set_entry_type(EntryType::Directory);
+            header.set_path(path)?;
+            header.set_size(0);
+            header.set_mtime(mtime);
+            header.set_mode(0o775);
+            header.set_uid(1000);
+            header.set_gid(1000);
+            header.set_cksum();
+            Ok(header)
+        };
+
+        let create_file_header = |size| {
+            let mut header = Header::new_gnu();
+            header.set_entry_type(EntryType::Regular);
+            header.set_size(size);
+            header.set_mtime(mtime);
+            header.set_mode(0o664);
+            header.set_uid(1000);
+            header.set_gid(1000);
+            header
+        };
+
+        let dot_devcontainer_dir = create_directory_header(".devcontainer/")?;
+        builder.get_mut().write_all(dot_devcontainer_dir.as_bytes())?;
+
+        let mut header_devcontainer_json = create_file_header(dot_devcontainer_json.len() as u64);
+        builder.append_data(
+            &mut header_devcontainer_json,
+            ".devcontainer/devcontainer.json",
+            dot_devcontainer_json,
+        )?;
+
+        let devcontainer_template_json = serde_json::to_string_pretty(&template_value)?;
+        let mut header_template_json = create_file_header(devcontainer_template_json.len() as u64);
+        builder.append_data(
+            &mut header_template_json,
+            "devcontainer-template.json",
+            devcontainer_template_json.as_bytes(),
+        )?;
+
+        let archive_bytes = builder.into_inner()?;
+
+        let tb = TemplateBuilder {
+            config: serde_json::from_value(template_value).ok(),
+            name: None,
+            context: HashMap::default(),
+            features: FeatureEntryBuilder::default(),
+            archive_bytes,
+            source: None,
+            header: None,
+            extensions: Vec::new(),
+            properties: Map::new(),
+            embed_provenance: false,
+            merge: false,
+            remove_comments: false,
+            dry_run: false,
+            backup: false,
+            local_features: Vec::new(),
+        };
+
+        Ok(tb)
+    }
+}
+
+pub mod serde_json_pretty {
+    use serde::Serialize;
+    use serde_json::{error::Result, ser::PrettyFormatter, Serializer};
+    use std::io::Write;
+
+    /// This is the same as `serde_json::to_writer_pretty` except with use of tabs for indentation.
+    pub fn to_writer_with_tabs<W: Write, V: ?Sized + Serialize>(writer: W, value: &V) -> Result<()> {
+        let formatter = PrettyFormatter::with_indent(b"\t");
+        let mut serializer = Serializer::with_formatter(writer, formatter);
+        value.serialize(&mut serializer)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        #[test]
+        fn test_to_writer_with_tabs() -> Result<()> {
+            let value = serde_json::json!({"test": {"deep": 1}});
+            let mut vec: Vec<u8> = Vec::new();
+            to_writer_with_tabs(&mut vec, &value)?;
+            let bytes = vec.as_slice();
+            assert_eq!(bytes, b"{\n\t\"test\": {\n\t\t\"deep\": 1\n\t}\n}");
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum PromptEntryAction {
+    Existing,
+    Enter,
+    Empty,
+}
+
+impl Display for PromptEntryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Existing => write!(f, "{}", messages::text(Message::StartPointExisting)),
+            Self::Enter => write!(f, "{}", messages::text(Message::StartPointEnter)),
+            Self::Empty => write!(f, "{}", messages::text(Message::StartPointEmpty)),
+        }
+    }
+}
+
+/// What to do when the workspace already has a devcontainer configuration.
+#[derive(Debug, PartialEq)]
+enum ExistingAction {
+    Overwrite,
+    Merge,
+    Alongside,
+    Abort,
+}
+
+impl Display for ExistingAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overwrite => write!(f, "{}", messages::text(Message::ExistingOverwrite)),
+            Self::Merge => write!(f, "{}", messages::text(Message::ExistingMerge)),
+            Self::Alongside => write!(f, "{}", messages::text(Message::ExistingAlongside)),
+            Self::Abort => write!(f, "{}", messages::text(Message::ExistingAbort)),
+        }
+    }
+}
+
+/// The devcontainer configuration the workspace already has, either `.devcontainer` or `.devcontainer.json`.
+fn existing_configuration(workspace: &Path) -> Option<&'static str> {
+    [".devcontainer", ".devcontainer.json"]
+        .into_iter()
+        .find(|name| workspace.join(name).exists())
+}
+
+/// What to do with the devcontainer.json shown for review.
+#[derive(Debug, PartialEq)]
+enum ReviewAction {
+    Accept,
+    Edit,
+    Restart,
+    Quit,
+}
+
+impl Display for ReviewAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Accept => write!(f, "{}", messages::text(Message::ReviewAccept)),
+            Self::Edit => write!(f, "{}", messages::text(Message::ReviewEdit)),
+            Self::Restart => write!(f, "{}", messages::text(Message::ReviewRestart)),
+            Self::Quit => write!(f, "{}", messages::text(Message::ReviewQuit)),
+        }
+    }
+}
+
+/// The lockfile entries of the template and the features pulled from a registry.
+async fn pulled_artifacts(
+    template_builder: &TemplateBuilder,
+    added: &[(OciReference, registry::Feature)],
+) -> anyhow::Result<Lockfile> {
+    let mut pulled = Lockfile::default();
+
+    if let (Some((reference, digest)), Some(config)) = (&template_builder.source, &template_builder.config) {
+        let template_ref: OciReference = reference.parse()?;
+
+        if let Some(artifact) = lock::locked_artifact(&template_ref, &config.version, digest.as_deref()).await? {
+            pulled.templates.insert(reference.clone(), artifact);
+        }
+    }
+
+    for (feature_ref, feature) in added {
+        if let Some(artifact) = lock::locked_artifact(feature_ref, &feature.version, feature_ref.digest()).await? {
+            pulled.features.insert(feature_ref.to_string(), artifact);
+        }
+    }
+
+    Ok(pulled)
+}
+
+/// Templates labeled with their collection name, grouped in index order. Typing a collection name in the prompt
+/// filters down to that collection. Templates named in `suggested`, or variants of them such as `rust-postgres`, are
+/// listed first and labeled as suggested.
+/// Longest description shown next to a choice, in characters.
+const MAX_CHOICE_DESCRIPTION: usize = 80;
+
+/// The description of a template or feature, cut short, with its documentation URL, to show next to its choice.
+fn choice_details(description: Option<&str>, documentation_url: Option<&str>) -> String {
+    let description = description
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+        .map(
+            |description| match description.char_indices().nth(MAX_CHOICE_DESCRIPTION) {
+                Some((end, _)) => format!("{}…", description[..end].trim_end()),
+                None => description.to_string(),
+            },
+        );
+
+    match (description, documentation_url) {
+        (Some(description), Some(url)) => format!("{description} <{url}>"),
+        (Some(description), None) => description,
+        (None, Some(url)) => format!("<{url}>"),
+        (None, None) => String::new(),
+    }
+}
+
+fn template_choices<'i>(
+    index: &'i registry::DevcontainerIndex,
+    include_deprecated: bool,
+    suggested: &[&str],
+) -> Vec<(String, &'i registry::Template)> {
+    let is_suggested = |template: &registry::Template| {
+        let name = template.id.rsplit('/').next().unwrap_or_default();
+        suggested.iter().any(|suggested| {
+            name.strip_prefix(suggested)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+        })
+    };
+    let mut choices: Vec<(bool, String, &registry::Template)> = index
+        .collections()
+        .iter()
+        .filter(|collection| include_deprecated || !collection.is_deprecated())
+        .flat_map(|collection| {
+            collection.templates.iter().map(move |template| {
+                let deprecated = if collection.is_deprecated() {
+                    " [deprecated]"
+                } else {
+                    ""
+                };
+                let suggested = is_suggested(template);
+                let suggestion = if suggested { " [suggested]" } else { "" };
+                let label = format!(
+                    "{} / {} ({}){deprecated}{suggestion}",
+                    collection.source_information.name, template.name, template.id
+                );
+                (suggested, label, template)
+            })
+        })
+        .collect();
+
+    // A stable sort keeps the index order within the suggested and the other templates.
+    choices.sort_by_key(|(suggested, _, _)| !suggested);
+    choices
+        .into_iter()
+        .map(|(_, label, template)| (label, template))
+        .collect()
+}
+
+pub async fn init(
+    index: &registry::DevcontainerIndex,
+    mut args: InitArgs,
+    format: OutputFormat,
+    config: &Config,
+) -> anyhow::Result<()> {
+    log::debug!("init");
+
+    args.no_header |= !config.init.header;
+
+    if let Some(manifest) = args.batch.take() {
+        let batch = batch::init_batch(index, &manifest, &args, config).await?;
+
+        let format = args.report.unwrap_or(format);
+
+        match format {
+            OutputFormat::Json => output::print_json(Kind::InitBatchReport, &batch)?,
+            OutputFormat::Text => batch::print_batch_report(&batch),
+        }
+
+        return batch.result().map_err(|error| output::failed(format, error));
+    }
+
+    if let Some(filename) = args.answers_file.take() {
+        args.use_answers(answers::read_answers(&filename)?)?;
+    }
+
+    for name in std::mem::take(&mut args.preset) {
+        args.use_preset(&name, &config.preset)?;
+    }
+
+    if let Some(filename) = args.features_file.take() {
+        args.use_features_file(&filename)?;
+    }
+
+    if !args.non_interactive && args.replay.is_none() && !prompt::is_interactive() {
+        log::warn!("Not running in a terminal, continuing with default answers as if --non-interactive was given");
+        args.non_interactive = true;
+    }
+
+    let policy = policy::read_policy()?;
+    let mut prompter: Box<dyn Prompter> = match &args.replay {
+        Some(filename) => Box::new(ScriptedPrompter::from_file(filename)?),
+        None if args.non_interactive => Box::new(DefaultPrompter),
+        None => Box::new(InteractivePrompter),
+    };
+
+    // Nothing is written to the workspace when printing to stdout or on a dry run, so there is nothing to open.
+    let open_code = match args.open_code {
+        _ if args.stdout || args.dry_run => OpenCode::Never,
+        Some(open_code) => open_code,
+        None => config.init.open_code,
+    };
+    let workspace = args.workspace_folder.clone().map_or_else(env::current_dir, Ok)?;
+    let report_format = match args.report {
+        None if format == OutputFormat::Json && !args.stdout => Some(OutputFormat::Json),
+        None if (args.non_interactive && !args.dry_run) || args.stdout => None,
+        report => Some(report.unwrap_or_default()),
+    };
+    let save_answers = args.save_answers.clone();
+
+    let report = match args.record.clone() {
+        Some(filename) => {
+            let mut recorder = RecordingPrompter::new(prompter);
+            // Save even when init fails, a partial session is still useful for reproducing the failure.
+            let result =
+                init_with_prompter(index, args, &config.download, &config.impact, &policy, &mut recorder).await;
+            recorder.save(&filename)?;
+            log::info!("Recorded session to {}", filename.display());
+            result?
+        },
+        None => {
+            init_with_prompter(
+                index,
+                args,
+                &config.download,
+                &config.impact,
+                &policy,
+                prompter.as_mut(),
+            )
+            .await?
+        },
+    };
+
+    if let Some(filename) = save_answers {
+        answers::save_answers(&filename, &Answers::from_report(&report))?;
+        log::info!("Saved answers to {}", filename.display());
+    }
+
+    match report_format {
+        Some(OutputFormat::Json) => output::print_json(Kind::InitReport, &report)?,
+        Some(OutputFormat::Text) => print_report(&report),
+        None => {},
+    }
+
+    vscode::open(&workspace, open_code)
+}
+
+pub async fn init_with_prompter(
+    index: &registry::DevcontainerIndex,
+    InitArgs {
+        non_interactive,
+        attempt_single_file,
+        mut output,
+        stdout,
+        remove_comments,
+        dry_run,
+        template_id,
+        name,
+        remote_user,
+        container_user,
+        container_workspace_folder,
+        workspace_mount,
+        no_template,
+        include_features,
+        preset: _,
+        features_file: _,
+        defaulted_features,
+        checked_features,
+        extensions,
+        update_content_command,
+        post_create_command,
+        post_start_command,
+        forward_ports,
+        container_env,
+        remote_env,
+        include_deprecated,
+        fail_on_deprecated,
+        workspace_folder,
+        record: _,
+        replay: _,
+        skip_existing,
+        mut overwrite_all,
+        force,
+        no_platform_check,
+        open_code: _,
+        no_backup,
+        pin_digests,
+        lock,
+        locked,
+        no_header,
+        no_provenance,
+        no_embedded_provenance,
+        mut no_merge,
+        report: _,
+        batch: _,
+        answers_file: _,
+        save_answers: _,
+        option,
+        feature_option,
+        mut template_options,
+        mut feature_options,
+    }: InitArgs,
+    download: &DownloadConfig,
+    impact: &ImpactConfig,
+    policy: &Policy,
+    prompter: &mut dyn Prompter,
+) -> anyhow::Result<InitReport> {
+    log::debug!("init_with_prompter");
+    // Do this evaluation of the `env` first so that it can error early.
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let quiet = non_interactive || stdout;
+    let properties: Vec<(&str, &str)> = [
+        ("remoteUser", remote_user.as_deref()),
+        ("containerUser", container_user.as_deref()),
+        ("workspaceFolder", container_workspace_folder.as_deref()),
+        ("workspaceMount", workspace_mount.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(property, value)| Some((property, value?)))
+    .collect();
+    let lifecycle_hooks = [
+        ("updateContentCommand", update_content_command.as_ref()),
+        ("postCreateCommand", post_create_command.as_ref()),
+        ("postStartCommand", post_start_command.as_ref()),
+    ];
+    // Nothing is asked when the flags already say what to do with existing files, or when nothing is written to them.
+    let existing = existing_configuration(&workspace)
+        .filter(|_| !(skip_existing || overwrite_all || force || stdout || dry_run || output.is_some()));
+
+    if let Some(existing) = existing {
+        if non_interactive {
+            Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{existing} already exists, pass --force to merge into it or --overwrite-all to replace it"),
+            ))?;
+        }
+
+        let actions = [
+            ExistingAction::Overwrite,
+            ExistingAction::Merge,
+            ExistingAction::Alongside,
+            ExistingAction::Abort,
+        ];
+        let choices: Vec<String> = actions.iter().map(|action| action.to_string()).collect();
+        let message = messages::format(Message::Existing, &[("file", &existing)]);
+
+        match actions[prompter.select(&message, &choices, 1)?] {
+            ExistingAction::Overwrite => {
+                overwrite_all = true;
+                no_merge = true;
+            },
+            ExistingAction::Merge => {},
+            ExistingAction::Alongside => {
+                let config_name = prompter.text(&messages::text(Message::ExistingName), None, None)?;
+                let config_name = config_name.trim();
+
+                if config_name.is_empty() || config_name.starts_with('.') || config_name.contains(['/', '\\']) {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{config_name:?} is not a valid name for a configuration folder"),
+                    ))?;
+                }
+
+                output = Some(Path::new(".devcontainer").join(config_name).join("devcontainer.json"));
+            },
+            ExistingAction::Abort => Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                messages::text(Message::ExistingAborted),
+            ))?,
+        }
+    }
+
+    template_options.extend(option.into_iter().map(|OptionArg { name, value }| (name, value)));
+
+    for (id, options) in feature_option_args(&feature_option)? {
+        feature_options.entry(id).or_default().extend(options);
+    }
+
+    let lockfile = match locked {
+        true => Some(lock::require_lockfile(&workspace)?),
+        false => None,
+    };
+    // Nor anything that the lock does not have.
+    let require_locked = |reference: &OciReference| match &lockfile {
+        Some(lockfile) => lockfile.require(reference),
+        None => Ok(()),
+    };
+
+    for reference in template_id.iter().chain(include_features.iter().flatten()) {
+        require_locked(reference)?;
+    }
+
+    // Nothing is pulled that the policy refuses.
+    Policy::enforce(
+        template_id
+            .iter()
+            .chain(include_features.iter().flatten())
+            .flat_map(|reference| policy.check_reference(reference))
+            .collect(),
+    )?;
+    // Everything known to be pulled up front is confirmed at once.
+    let artifacts: Vec<&OciReference> = template_id
+        .iter()
+        .chain(
+            include_features
+                .iter()
+                .flatten()
+                .filter(|feature_ref| needs_pull(index, feature_ref)),
+        )
+        .collect();
+    download::confirm_download(&artifacts, download, prompter, quiet).await?;
+    // Likewise a typo in a feature reference should not wait until after the template prompts.
+    let included_features = match include_features {
+        Some(feature_refs) => Some(resolve_features(index, feature_refs).await?),
+        None => None,
+    };
+
+    for (feature_ref, feature) in included_features.iter().flatten() {
+        check_deprecated(index, &feature_ref.id(), feature.is_deprecated(), fail_on_deprecated)?;
+    }
+
+    let invalid: Vec<String> = (included_features.iter().flatten())
+        .filter(|(feature_ref, _)| checked_features.contains(&feature_ref.id()))
+        .flat_map(|(feature_ref, feature)| {
+            let given = feature_options.get(&feature_ref.id()).cloned().unwrap_or_default();
+            invalid_feature_options(feature_ref, feature, &given)
+        })
+        .collect();
+
+    if !invalid.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid feature options:\n  {}", invalid.join("\n  ")),
+        ))?;
+    }
+
+    for id in feature_options.keys() {
+        if !included_features
+            .iter()
+            .flatten()
+            .any(|(feature_ref, _)| feature_ref.id() == *id)
+        {
+            log::warn!("Options are given for {id}, which is not included with --include-features");
+        }
+    }
+
+    Policy::enforce(
+        included_features
+            .iter()
+            .flatten()
+            .flat_map(|(feature_ref, feature)| policy.check_feature(feature_ref, feature))
+            .collect(),
+    )?;
+
+    /*
+     * Done        1(a). What template are we starting with?
+     * Done        1(b). Start with an empty, image-based devcontainer.json.
+     * Done        2(a). Pick values for any temapltes options.
+     * Done        2(b). Replace `${templateOption:name}` placeholders. Reminder that this is *not* limited to
+     *                   devcontainer.json and may appear in any of the template's files from the tar archive.
+     *
+     *                       const pattern = /\${templateOption:\s*(\w+?)\s*}/g; // ${templateOption:XXXX}
+     *
+     * Done        3(a). Prompt for features loop?
+     * Done        3(b). Search for feature.
+     * Done        3(c). Pick values for any feature options.
+     * Done        3(d). Edit devcontainer.json.
+     * Done        4(a). Display the resulting devcontainer.json.
+     * Done        4(b). Prompt loop to (A)ccept, (E)dit, (R)estart, or (Q)uit
+     * Done           5. Write files to disk.
+     */
+    let mut template_id = template_id;
+    let mut no_template = no_template;
+    let (mut template_builder, mut warnings, added) = 'review: loop {
+        let mut template_builder: TemplateBuilder = match &template_id {
+            Some(template_ref) => {
+                let id = template_ref.id();
+                let template = index.get_template(&id);
+                check_deprecated(index, &id, false, fail_on_deprecated)?;
+
+                pull_template(index, template_ref, template).await?
+            },
+            None if no_template => TemplateBuilder::create_image_start_point()?,
+            None if non_interactive => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Must provide --template-id or --no-template in non-interactive mode",
+            ))?,
+            None => {
+                let start_points = [
+                    PromptEntryAction::Existing,
+                    PromptEntryAction::Enter,
+                    PromptEntryAction::Empty,
+                ];
+                let choices: Vec<String> = start_points.iter().map(|action| action.to_string()).collect();
+                let start_point = &start_points[prompter.select(&messages::text(Message::StartPoint), &choices, 0)?];
+
+                match start_point {
+                    PromptEntryAction::Existing => {
+                        let suggested = workspace::suggested_templates(&workspace);
+
+                        if !suggested.is_empty() {
+                            log::info!("Suggesting templates for this workspace: {}", suggested.join(", "));
+                        }
+
+                        let templates = template_choices(index, include_deprecated, &suggested);
+                        let labels: Vec<String> = templates.iter().map(|(label, _)| label.clone()).collect();
+                        let details: Vec<String> = (templates.iter())
+                            .map(|(_, template)| {
+                                choice_details(template.description.as_deref(), template.documentation_url.as_deref())
+                            })
+                            .collect();
+                        let choice = prompter.select_with_details(
+                            &messages::text(Message::PickTemplate),
+                            &labels,
+                            &details,
+                            0,
+                        )?;
+                        let template = templates[choice].1;
+                        check_deprecated(index, &template.id, false, fail_on_deprecated)?;
+                        let template_ref = template.id.parse()?;
+                        require_locked(&template_ref)?;
+                        Policy::enforce(policy.check_reference(&template_ref))?;
+                        download::confirm_download(&[&template_ref], download, prompter, quiet).await?;
+                        TemplateBuilder::new(&template_ref, Some(template.clone())).await?
+                    },
+                    PromptEntryAction::Enter => {
+                        let template_id = prompter.text(&messages::text(Message::EnterTemplate), None, None)?;
+                        let template_ref: OciReference = template_id
+                            .parse()
+                            .map_err(|error| suggest::with_suggestions(error, &template_id, template_ids(index)))?;
+                        let template = index.get_template(&template_ref.id());
+                        check_deprecated(index, &template_ref.id(), false, fail_on_deprecated)?;
+                        require_locked(&template_ref)?;
+                        Policy::enforce(policy.check_reference(&template_ref))?;
+                        download::confirm_download(&[&template_ref], download, prompter, quiet).await?;
+                        pull_template(index, &template_ref, template).await?
+                    },
+                    PromptEntryAction::Empty => TemplateBuilder::create_empty_start_point()?,
+                }
+            },
+        };
+
+        let is_version_tag = template_id
+            .as_ref()
+            .is_some_and(|oci_ref| oci_ref.tag_name() != "latest");
+
+        if is_version_tag || template_builder.config.is_none() {
+            template_builder.replace_config()?;
+        }
+
+        let warnings = template_builder.lint_placeholders()?;
+
+        for warning in &warnings {
+            log::warn!("{warning}");
+        }
+
+        if non_interactive {
+            let template_option_specs =
+                (template_builder.config.iter()).flat_map(|config| config.options.iter().flatten());
+            let mut missing = required_option_flags(template_option_specs, |name| {
+                (!template_options.contains_key(name)).then(|| format!("--option {name}=<VALUE>"))
+            });
+
+            for (feature_ref, feature) in included_features.iter().flatten() {
+                let given = feature_options.get(&feature_ref.id());
+
+                missing.extend(required_option_flags(feature.options.iter().flatten(), |name| {
+                    (!given.is_some_and(|given| given.contains_key(name)))
+                        .then(|| format!("--feature-option {feature_ref} {name}=<VALUE>"))
+                }));
+            }
+
+            if !missing.is_empty() {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Options without a default need a value when non-interactive, give them with:\n  {}",
+                        missing.join("\n  ")
+                    ),
+                ))?;
+            }
+        }
+
+        template_builder.use_prompt_values(&template_options, prompter)?;
+        template_builder.use_name(name.clone(), &workspace, prompter)?;
+
+        for (property, value) in &properties {
+            template_builder
+                .properties
+                .insert(property.to_string(), Value::String(value.to_string()));
+        }
+
+        let mut added: Vec<(OciReference, registry::Feature)> = Vec::new();
+
+        for (feature_ref, feature) in included_features.iter().flatten() {
+            let message = messages::format(Message::AddingFeature, &[("reference", feature_ref)]);
+
+            if quiet {
+                log::info!("{message}");
+            } else {
+                println!("{message}");
+            }
+
+            let given = feature_options.get(&feature_ref.id()).cloned().unwrap_or_default();
+            let mut defaults = DefaultPrompter;
+            let feature_prompter: &mut dyn Prompter = match defaulted_features.contains(&feature_ref.id()) {
+                true => &mut defaults,
+                false => &mut *prompter,
+            };
+
+            template_builder
+                .features
+                .use_prompt_values(feature_ref, feature, &given, feature_prompter)?;
+            added.push((feature_ref.clone(), feature.clone()));
+        }
+
+        // Features of the index are picked all at once, anything else is entered one at a time after.
+        let listed: Vec<&registry::Feature> = index
+            .iter_features(include_deprecated)
+            .filter(|feature| {
+                !(included_features.iter().flatten()).any(|(feature_ref, _)| feature_ref.id() == feature.id)
+            })
+            .collect();
+        let mut picked: VecDeque<String> = VecDeque::new();
+        let add_feature = match listed.is_empty() {
+            true => Message::AddFeature,
+            false => {
+                let choices: Vec<String> = listed.iter().map(|feature| feature.id.clone()).collect();
+                let details: Vec<String> = (listed.iter())
+                    .map(|feature| {
+                        choice_details(feature.description.as_deref(), feature.documentation_url.as_deref())
+                    })
+                    .collect();
+                let message = messages::text(Message::PickFeatures);
+
+                for choice in prompter.multi_select_with_details(&message, &choices, &details, &[])? {
+                    let feature = listed[choice];
+                    picked.push_back(match feature.major_version.as_str() {
+                        "" => feature.id.clone(),
+                        major_version => format!("{}:{major_version}", feature.id),
+                    });
+                }
+
+                Message::AddOtherFeature
+            },
         };
 
-        Ok(template_archive)
+        loop {
+            let input = match picked.pop_front() {
+                Some(reference) => reference,
+                None if prompter.confirm(&messages::text(add_feature), None)? => {
+                    let features_autocomplete = FeaturesAutocomplete::new(index, include_deprecated);
+                    prompter.text(
+                        &messages::text(Message::ChooseFeature),
+                        None,
+                        Some(Box::new(features_autocomplete)),
+                    )?
+                },
+                None => break,
+            };
+            let feature_ref: OciReference = input
+                .parse()
+                .map_err(|error| suggest::with_suggestions(error, &input, feature_ids(index)))?;
+
+            require_locked(&feature_ref)?;
+            Policy::enforce(policy.check_reference(&feature_ref))?;
+
+            if needs_pull(index, &feature_ref) {
+                download::confirm_download(&[&feature_ref], download, prompter, quiet).await?;
+            }
+
+            let feature = get_feature(index, &feature_ref).await?;
+            check_deprecated(index, &feature_ref.id(), feature.is_deprecated(), fail_on_deprecated)?;
+            Policy::enforce(policy.check_feature(&feature_ref, &feature))?;
+
+            template_builder
+                .features
+                .use_prompt_values(&feature_ref, &feature, &Map::new(), prompter)?;
+            added.push((feature_ref, feature));
+        }
+
+        // Features that come with the template are already part of its configuration.
+        let template_features: Vec<String> = (template_builder.config.iter())
+            .flat_map(|config| config.feature_ids.iter().flatten())
+            .filter_map(|id| id.parse::<OciReference>().ok())
+            .map(|feature_ref| feature_ref.id())
+            .collect();
+
+        add_dependencies(
+            index,
+            &mut template_builder.features,
+            &mut added,
+            &template_features,
+            download,
+            policy,
+            fail_on_deprecated,
+            quiet,
+            prompter,
+        )
+        .await?;
+
+        if added.len() > 1 {
+            let features: Vec<(String, Vec<String>)> = added
+                .iter()
+                .map(|(feature_ref, feature)| {
+                    let after = dependencies(feature_ref, feature)
+                        .into_iter()
+                        .map(|dependency| dependency.feature_ref.id())
+                        .collect();
+                    (feature_ref.id(), after)
+                })
+                .collect();
+            let order = install_order(&features);
+
+            template_builder.features.sort_by_install_order(&added, &order);
+
+            let order = order.join(", ");
+
+            if quiet {
+                log::info!("Install order: {order}");
+            } else {
+                println!("Install order: {order}");
+            }
+        }
+
+        if !no_platform_check {
+            let image = template_builder.base_image()?;
+            let feature_ids: Vec<String> = template_builder
+                .features
+                .features
+                .keys()
+                .filter_map(|key| key.parse::<OciReference>().ok())
+                .map(|feature_ref| feature_ref.id())
+                .collect();
+
+            platform::check_compatibility(image.as_deref(), feature_ids.iter().map(String::as_str)).await;
+        }
+
+        template_builder.use_extensions(&extensions, &added, prompter)?;
+        template_builder.use_lifecycle_hooks(lifecycle_hooks, prompter)?;
+        template_builder.use_forward_ports(&forward_ports, prompter)?;
+        template_builder.use_environment(&container_env, &remote_env, &added, quiet, prompter)?;
+
+        impact::confirm_impact(&added, impact, prompter, quiet).await?;
+
+        if quiet {
+            break (template_builder, warnings, added);
+        }
+
+        loop {
+            if let Some(contents) = template_builder.preview_devcontainer()? {
+                println!("{}", String::from_utf8_lossy(&contents));
+            }
+
+            let actions = [
+                ReviewAction::Accept,
+                ReviewAction::Edit,
+                ReviewAction::Restart,
+                ReviewAction::Quit,
+            ];
+            let choices: Vec<String> = actions.iter().map(|action| action.to_string()).collect();
+
+            match actions[prompter.select(&messages::text(Message::Review), &choices, 0)?] {
+                ReviewAction::Accept => break 'review (template_builder, warnings, added),
+                ReviewAction::Edit => {
+                    template_builder.use_prompt_values(&BTreeMap::new(), prompter)?;
+                    template_builder.use_name(None, &workspace, prompter)?;
+
+                    for (feature_ref, feature) in &added {
+                        template_builder
+                            .features
+                            .use_prompt_values(feature_ref, feature, &Map::new(), prompter)?;
+                    }
+
+                    template_builder.use_extensions(&extensions, &added, prompter)?;
+                    template_builder.use_lifecycle_hooks(lifecycle_hooks, prompter)?;
+                    template_builder.use_forward_ports(&forward_ports, prompter)?;
+                    template_builder.use_environment(&container_env, &remote_env, &added, quiet, prompter)?;
+                },
+                ReviewAction::Restart => {
+                    template_id = None;
+                    no_template = false;
+                    continue 'review;
+                },
+                ReviewAction::Quit => Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    messages::text(Message::ReviewCancelled),
+                ))?,
+            }
+        }
+    };
+
+    if pin_digests {
+        template_builder.features.pin_digests(&added).await?;
     }
 
-    fn as_archive(&self) -> Archive<&[u8]> {
-        Archive::new(self.archive_bytes.as_slice())
+    for (feature_ref, feature) in added.iter().filter(|(feature_ref, _)| feature_ref.is_local()) {
+        // The id names the folder the feature is copied to, so it must not reach outside of it.
+        if !matches!(
+            Path::new(&feature.id).components().collect::<Vec<_>>()[..],
+            [Component::Normal(_)]
+        ) {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The id \"{}\" of {feature_ref} is not usable as a folder name",
+                    feature.id
+                ),
+            ))?
+        }
+
+        let archive_bytes = registry::pull_archive_bytes(feature_ref).await?;
+        template_builder
+            .local_features
+            .push((feature.id.clone(), archive_bytes));
     }
 
-    fn replace_config(&mut self) -> std::io::Result<()> {
-        log::debug!("TemplateBuilder::replace_config");
-        let mut tar = self.as_archive();
-        let entries = tar.entries()?;
+    // Dependencies are added without asking the lock first, they are only checked along with everything else here.
+    let pulled = match lock || locked {
+        true => Some(pulled_artifacts(&template_builder, &added).await?),
+        false => None,
+    };
+
+    if let (Some(lockfile), Some(pulled)) = (&lockfile, &pulled) {
+        lockfile.verify(pulled)?;
+    }
 
-        for entry in entries {
-            let mut entry = entry?;
-            let path = entry.path()?;
+    let conflicts = match (skip_existing, overwrite_all) {
+        (true, _) => ConflictPolicy::Skip,
+        (_, true) => ConflictPolicy::Overwrite,
+        _ => ConflictPolicy::Prompt,
+    };
 
-            if path.to_str().is_some_and(|p| p.ends_with("devcontainer-template.json")) {
-                let size = entry.size() as usize;
-                let mut data: Vec<u8> = Vec::with_capacity(size);
-                entry.read_to_end(&mut data)?;
-                let config = serde_json::from_slice(data.as_slice())?;
+    let output = match output {
+        Some(filename) => Some(OutputTarget::File(filename)),
+        None if stdout => Some(OutputTarget::Stdout),
+        None => None,
+    };
 
-                self.config.replace(config);
-                log::debug!("TemplateBuilder::replace_config: read {} bytes", size);
+    if !no_header && !remove_comments {
+        template_builder.use_generation_header()?;
+    }
 
-                return Ok(());
-            }
+    template_builder.embed_provenance = !no_embedded_provenance;
+    template_builder.merge = !no_merge;
+    template_builder.remove_comments = remove_comments;
+    template_builder.dry_run = dry_run;
+    template_builder.backup = !no_backup;
+
+    let files = template_builder.apply_context_and_features(
+        index,
+        attempt_single_file,
+        output.as_ref(),
+        &workspace,
+        conflicts,
+        prompter,
+        &mut warnings,
+    )?;
+
+    if let Some(pulled) = pulled.filter(|_| lock) {
+        lock::write_lockfile(&workspace, &pulled)?;
+    }
+
+    let notes = template_builder.notes()?;
+    let (template, template_digest) = template_builder.source.unzip();
+    let report = InitReport {
+        workspace,
+        template,
+        template_version: template_builder.config.map(|config| config.version),
+        template_digest: template_digest.flatten(),
+        name: template_builder.name,
+        template_options: template_builder.context.into_iter().collect(),
+        features: (template_builder.features.features.into_iter())
+            .map(|(reference, options)| AddedFeature {
+                source: (added.iter())
+                    .find(|(feature_ref, feature)| {
+                        feature_ref.is_local() && local_feature_key(&feature.id) == reference
+                    })
+                    .map(|(feature_ref, _)| feature_ref.to_string()),
+                reference,
+                options,
+            })
+            .collect(),
+        files,
+        warnings,
+        notes,
+        dry_run,
+    };
+
+    // A devcontainer.json written elsewhere is not the one of the workspace.
+    if !no_provenance && !dry_run && output.is_none() {
+        provenance::write_provenance(&report.workspace, &Provenance::from_report(&report))?;
+    }
+
+    log::debug!("init: done");
+
+    Ok(report)
+}
+
+pub fn print_report(report: &InitReport) {
+    let header = &theme::current().header;
+
+    println!();
+
+    for diff in report.files.iter().filter_map(|file| file.diff.as_deref()) {
+        print_diff_text(diff);
+        println!();
+    }
+
+    for file in &report.files {
+        let status = match file.status {
+            FileStatus::Created => "Created",
+            FileStatus::Overwritten => "Overwritten",
+            FileStatus::Unchanged => "Unchanged",
+            FileStatus::Skipped => "Skipped",
+        };
+        match &file.backup {
+            Some(backup) => println!("{:<12}{} (backup {backup})", status, file.path),
+            None => println!("{:<12}{}", status, file.path),
         }
+    }
 
-        Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            "The devcontainer-template.json file was not found in the archive",
-        ))?
+    println!(
+        "\n{} {}",
+        header.paint("Template:"),
+        report.template.as_deref().unwrap_or("none")
+    );
+
+    for (name, value) in &report.template_options {
+        println!("  {name} = {value}");
     }
 
-    fn use_prompt_values(&mut self) -> anyhow::Result<()> {
-        log::debug!("TemplateBuilder::use_prompt_values");
-        let config = self
-            .config
-            .as_ref()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Missing configuration"))?;
+    if !report.features.is_empty() {
+        println!("{}", header.paint("Features:"));
 
-        if let Some(options) = &config.options {
-            self.context.clear();
+        for feature in &report.features {
+            println!("  {} {}", feature.reference, feature.options);
+        }
+    }
 
-            for (name, template_option) in options {
-                let dev_prompt = DevOptionPrompt::new(name, template_option);
-                let value = dev_prompt.display_prompt()?;
-                self.context.insert(name.clone(), value.to_string());
+    if !report.warnings.is_empty() {
+        println!("{}", header.paint("Warnings:"));
+
+        for warning in &report.warnings {
+            println!("  {warning}");
+        }
+    }
+
+    if let Some(notes) = &report.notes {
+        println!("{}", header.paint("Notes:"));
+
+        for line in notes.lines() {
+            match line {
+                "" => println!(),
+                line => println!("  {line}"),
             }
         }
+    }
+
+    if report.dry_run {
+        println!("\nDry run, nothing was written");
+    }
+}
+
+// TODO these are more *proof of concept* than actual tests...
+#[cfg(test)]
+mod tests {
+    use super::{
+        backup_file, choice_details, feature_option_args, generation_header, init_with_prompter, install_order,
+        invalid_feature_options, is_published_tag, recorded_template, required_option_flags, sanitize_mode,
+        template_choices, template_option_value, unified_diff, without_embedded_provenance, without_generation_header,
+        ConflictPolicy, DownloadConfig, FeatureEntryBuilder, FileStatus, ForwardPort, ImpactConfig, InitArgs,
+        OptionArg, OutputTarget, Policy, Preset, TemplateBuilder, WrittenFile,
+    };
+    use crate::prompt::{Answer, DefaultPrompter, ScriptedAnswer, ScriptedPrompter};
+    use crate::registry::{DevOption, DevcontainerIndex};
+    use serde_json::{self, Map, Value};
+    use std::borrow::Cow;
+    use std::collections::{BTreeMap, HashMap};
+    use std::{env, fs};
+
+    #[test]
+    fn test_feature_entry_builder_as_value() -> serde_json::error::Result<()> {
+        let mut feature_entry_builder = FeatureEntryBuilder::default();
+
+        let git_id = "ghcr.io/devcontainers/git:1";
+        let git_options = Value::Object(Map::default());
+        feature_entry_builder.features.insert(git_id.to_owned(), git_options);
+
+        let dind_id = "ghcr.io/devcontainers/docker-in-docker:2";
+        let dind_options = serde_json::json!({"moby": false});
+        feature_entry_builder.features.insert(dind_id.to_owned(), dind_options);
+
+        let value = feature_entry_builder.as_value()?;
+        let json_str = serde_json::to_string_pretty(&value)?;
+        let keys: Vec<&String> = value
+            .as_object()
+            .map(|features| features.keys().collect())
+            .unwrap_or_default();
+
+        // Features keep the order they were added in.
+        assert_eq!(keys, [git_id, dind_id]);
+
+        println!("{{\"features\": {json_str}\n}}");
 
         Ok(())
     }
 
-    fn use_default_values(&mut self) -> std::io::Result<()> {
-        log::debug!("TemplateBuilder::use_default_values");
-        let config = self
-            .config
-            .as_ref()
-            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Missing configuration"))?;
+    #[test]
+    fn test_entry_key_round_trip() -> anyhow::Result<()> {
+        use crate::oci_ref::OciReference;
+        use crate::registry::Feature;
+        use crate::workspace::FeatureSource;
+        use std::path::Path;
 
-        if let Some(options) = &config.options {
-            let all_defaults = options
-                .iter()
-                .map(|(name, template_option)| (name.clone(), template_option.configured_default()))
-                .collect::<HashMap<String, String>>();
+        let feature = Feature {
+            id: "myfeat".into(),
+            version: "1.2.0".into(),
+            ..Default::default()
+        };
+        let directory = Path::new("/workspace/.devcontainer");
 
-            self.context.clear();
-            self.context.clone_from(&all_defaults);
+        let published: OciReference = "ghcr.io/me/features/myfeat:1.2".parse()?;
+        let key = FeatureEntryBuilder::entry_key(&published, &feature);
+        assert_eq!(key, "ghcr.io/me/features/myfeat:1");
+        assert_eq!(key.parse::<OciReference>()?.id(), published.id());
+
+        // A local artifact is written as the folder it is copied to, never as a key that resolves to a registry.
+        for local in ["file:///tmp/myfeat.tar", "oci-layout:/tmp/layout:1"] {
+            let key = FeatureEntryBuilder::entry_key(&local.parse()?, &feature);
+            assert_eq!(key, "./myfeat");
+            assert!(key.parse::<OciReference>().is_err());
+            assert!(matches!(
+                FeatureSource::parse(&key, directory)?,
+                FeatureSource::Folder(folder) if folder == directory.join("./myfeat")
+            ));
         }
 
         Ok(())
     }
 
-    fn is_single_file_eligible(&self) -> bool {
-        if let Some(template) = self.config.as_ref() {
-            if let Some(template_type) = template.r#type.as_ref() {
-                return match template_type {
-                    registry::TemplateType::DockerCompose => {
-                        log::warn!(
-                            "Skipping --attempt-single-file as the selected template includes a docker-compose.yml"
-                        );
-                        false
-                    },
-                    registry::TemplateType::Dockerfile => {
-                        log::warn!("Skipping --attempt-single-file as the selected template includes a Dockerfile");
-                        false
-                    },
-                    // Most image templates have exactly four files: .devcontainer/devcontainer.json, devcontainer-feature.json, NOTES.md, README.md
-                    registry::TemplateType::Image if template.file_count.is_some_and(|count| count > 4) => {
-                        log::warn!("Skipping --attempt-single-file as the template likely contains more than just a devcontainer.json file");
-                        false
-                    },
-                    registry::TemplateType::Image => true,
-                };
-            }
+    #[test]
+    fn test_is_published_tag() {
+        assert!(is_published_tag("latest", "1.5.0"));
+        assert!(is_published_tag("1", "1.5.0"));
+        assert!(is_published_tag("1.5", "1.5.0"));
+        assert!(is_published_tag("1.5.0", "1.5.0"));
+        assert!(!is_published_tag("1.4", "1.5.0"));
+        assert!(!is_published_tag("15", "1.5.0"));
+    }
+
+    #[test]
+    fn test_option_args() -> anyhow::Result<()> {
+        assert_eq!(
+            "imageVariant=bookworm".parse::<OptionArg>(),
+            Ok(OptionArg {
+                name: "imageVariant".into(),
+                value: "bookworm".into(),
+            })
+        );
+        assert!("imageVariant".parse::<OptionArg>().is_err());
+        assert!("=bookworm".parse::<OptionArg>().is_err());
+
+        let feature_options = feature_option_args(&[
+            "ghcr.io/devcontainers/features/node:1".into(),
+            "version=lts".into(),
+            "ghcr.io/devcontainers/features/node".into(),
+            "installYarn=false".into(),
+        ])?;
+
+        assert_eq!(
+            Value::Object(feature_options["ghcr.io/devcontainers/features/node"].clone()),
+            serde_json::json!({ "version": "lts", "installYarn": "false" })
+        );
+        assert!(feature_option_args(&["ghcr.io/devcontainers/features/node:1".into(), "version".into()]).is_err());
+
+        assert_eq!(
+            "3000=Frontend".parse::<ForwardPort>(),
+            Ok(ForwardPort {
+                port: 3000.into(),
+                label: Some("Frontend".into()),
+            })
+        );
+        assert_eq!(
+            "db:5432".parse::<ForwardPort>(),
+            Ok(ForwardPort {
+                port: "db:5432".into(),
+                label: None,
+            })
+        );
+        assert!("70000".parse::<ForwardPort>().is_err());
+        assert!(":5432".parse::<ForwardPort>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_required_option_flags() -> anyhow::Result<()> {
+        let options: HashMap<String, DevOption> = serde_json::from_value(serde_json::json!({
+            "project": { "type": "string", "description": "Name of the project" },
+            "owner": { "type": "string", "proposals": [] },
+            "imageVariant": { "type": "string", "default": "bookworm", "proposals": ["bookworm"] },
+            "suffix": { "type": "string", "default": "" },
+            "installTools": { "type": "boolean", "default": true },
+        }))?;
+        let mut flags = required_option_flags(options.iter(), |name| {
+            (name != "owner").then(|| format!("--option {name}=<VALUE>"))
+        });
+        flags.sort();
+
+        assert_eq!(flags, ["--option project=<VALUE>"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_file() -> anyhow::Result<()> {
+        let directory = env::temp_dir().join(format!("tyedev-backup-file-{}", std::process::id()));
+        let filename = directory.join("devcontainer.json");
+        fs::create_dir_all(&directory)?;
+        fs::write(&filename, "{}")?;
+
+        assert_eq!(backup_file(&filename)?, directory.join("devcontainer.json.bak"));
+        assert_eq!(fs::read_to_string(directory.join("devcontainer.json.bak"))?, "{}");
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_choices() -> anyhow::Result<()> {
+        let index: DevcontainerIndex = serde_json::from_str(
+            r#"{"collections": [{
+                "sourceInformation": {"name": "templates", "maintainer": "Dev Container Spec Maintainers", "contact": "",
+                    "repository": "", "ociReference": "ghcr.io/devcontainers/templates"},
+                "features": [],
+                "templates": [
+                    {"id": "ghcr.io/devcontainers/templates/go", "version": "1.0.0", "name": "Go"},
+                    {"id": "ghcr.io/devcontainers/templates/rustic", "version": "1.0.0", "name": "Rustic"},
+                    {"id": "ghcr.io/devcontainers/templates/rust-postgres", "version": "1.0.0", "name": "Rust Postgres"},
+                    {"id": "ghcr.io/devcontainers/templates/rust", "version": "1.0.0", "name": "Rust"}
+                ]
+            }]}"#,
+        )?;
+        let names = |suggested: &[&str]| -> Vec<String> {
+            template_choices(&index, false, suggested)
+                .into_iter()
+                .map(|(_, template)| template.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(&[]), ["Go", "Rustic", "Rust Postgres", "Rust"]);
+        assert_eq!(names(&["rust"]), ["Rust Postgres", "Rust", "Go", "Rustic"]);
+        assert!(template_choices(&index, false, &["go"])[0]
+            .0
+            .ends_with("(ghcr.io/devcontainers/templates/go) [suggested]"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_choice_details() {
+        let url = Some("https://github.com/devcontainers/features/tree/main/src/node");
+
+        assert_eq!(
+            choice_details(Some("Installs Node.js, nvm, yarn, pnpm, and needed dependencies."), url),
+            "Installs Node.js, nvm, yarn, pnpm, and needed dependencies. <https://github.com/devcontainers/features/tree/main/src/node>"
+        );
+        assert_eq!(
+            choice_details(Some(&"a".repeat(100)), None),
+            format!("{}…", "a".repeat(80))
+        );
+        assert_eq!(choice_details(Some("  "), None), "");
+        assert_eq!(
+            choice_details(None, url),
+            "<https://github.com/devcontainers/features/tree/main/src/node>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_mode() {
+        assert_eq!(sanitize_mode(0o755), 0o755);
+        assert_eq!(sanitize_mode(0o664), 0o644);
+        assert_eq!(sanitize_mode(0o4777), 0o755);
+        assert_eq!(sanitize_mode(0o400), 0o600);
+    }
+
+    #[test]
+    fn test_template_option_value() {
+        let context = HashMap::from([
+            ("variant".to_string(), "bookworm".to_string()),
+            ("empty".to_string(), String::new()),
+        ]);
+
+        assert_eq!(
+            template_option_value(&context, "variant", Some("jammy")),
+            Some("bookworm")
+        );
+        assert_eq!(template_option_value(&context, "empty", Some("jammy")), Some("jammy"));
+        assert_eq!(template_option_value(&context, "empty", None), Some(""));
+        assert_eq!(template_option_value(&context, "missing", Some("")), Some(""));
+        assert_eq!(template_option_value(&context, "missing", None), None);
+    }
+
+    #[test]
+    fn test_lint_placeholders() -> anyhow::Result<()> {
+        let template_value = serde_json::json!({
+            "id": "lint",
+            "version": "1.0.0",
+            "name": "Lint",
+            "options": {"variant": {"type": "string", "default": ""}},
+        });
+        let dot_devcontainer_json: &[u8] =
+            b"{\"image\": \"debian:${templateOption:variant:-bookworm}\", \"name\": \"${templateOption:title}\"}\n";
+        let template_builder = TemplateBuilder::create_start_point(template_value, dot_devcontainer_json)?;
+        let warnings = template_builder.lint_placeholders()?;
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("${templateOption:variant:-bookworm} relies on a fallback"));
+        assert!(warnings[1].contains("${templateOption:title} refers to an option"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("Dockerfile", b"FROM debian\nRUN true\n", b"FROM ubuntu\nRUN true\n");
+
+        assert_eq!(
+            diff.as_deref(),
+            Some("--- a/Dockerfile\n+++ b/Dockerfile\n@@ -1,2 +1,2 @@\n-FROM debian\n+FROM ubuntu\n RUN true\n")
+        );
+        assert_eq!(unified_diff("icon.png", b"\x89PNG\0", b"\x89PNG\0\x01"), None);
+    }
+
+    #[test]
+    fn test_generation_header() {
+        let header = generation_header(0, Some(("ghcr.io/devcontainers/templates/rust:4", Some("sha256:abc"))));
+        let contents = format!("{header}{{\n\t\"image\": \"rust\"\n}}\n");
+
+        assert!(header.ends_with(
+            " on 1970-01-01T00:00:00Z.\n// Template: ghcr.io/devcontainers/templates/rust:4 (sha256:abc)\n"
+        ));
+        assert_eq!(
+            without_generation_header(contents.as_bytes()),
+            b"{\n\t\"image\": \"rust\"\n}\n"
+        );
+        assert_eq!(without_generation_header(b"// My notes\n{}"), b"// My notes\n{}");
+        assert_eq!(
+            without_generation_header(format!("{header}// My notes\n{{}}").as_bytes()),
+            b"// My notes\n{}"
+        );
+        assert_eq!(
+            without_generation_header(format!("{}// My notes\n{{}}", generation_header(0, None)).as_bytes()),
+            b"// My notes\n{}"
+        );
+        assert_eq!(
+            recorded_template(&contents),
+            Some((
+                "ghcr.io/devcontainers/templates/rust:4".to_string(),
+                Some("sha256:abc".to_string())
+            ))
+        );
+        assert_eq!(recorded_template(&generation_header(0, None)), None);
+        assert_eq!(recorded_template("// Template: x\n{}"), None);
+
+        let embedded = "{\n\t\"image\": \"rust\",\n\t\"customizations\": {\n\t\t\"tyedev\": {\n\t\t\t\"template\": \"ghcr.io/devcontainers/templates/rust:4\",\n\t\t\t\"version\": \"4.0.1\"\n\t\t}\n\t}\n}\n";
+        assert_eq!(
+            recorded_template(embedded),
+            Some(("ghcr.io/devcontainers/templates/rust:4".to_string(), None))
+        );
+        assert_eq!(without_embedded_provenance(embedded), "{\n\t\"image\": \"rust\"\n}\n");
+    }
+
+    #[test]
+    fn test_install_order() {
+        let feature = |id: &str, after: &[&str]| (id.to_string(), after.iter().map(|id| id.to_string()).collect());
+        let features = vec![
+            feature("node", &["common-utils"]),
+            feature("python", &[]),
+            feature("common-utils", &["missing"]),
+            feature("a", &["b"]),
+            feature("b", &["a"]),
+        ];
+
+        assert_eq!(install_order(&features), ["python", "common-utils", "node", "a", "b"]);
+    }
+
+    #[test]
+    fn test_sort_by_install_order() -> anyhow::Result<()> {
+        let feature = |id: &str| -> anyhow::Result<_> {
+            let feature: crate::registry::Feature =
+                serde_json::from_value(serde_json::json!({ "id": id, "version": "1.0.0", "name": id }))?;
+            Ok((format!("ghcr.io/devcontainers/features/{id}:1").parse()?, feature))
+        };
+        let added = vec![feature("node")?, feature("common-utils")?];
+        let mut feature_entry_builder = FeatureEntryBuilder::default();
+
+        for (feature_ref, feature) in &added {
+            feature_entry_builder.use_options(feature_ref, feature, Value::Object(Map::new()));
         }
 
-        false
+        feature_entry_builder
+            .features
+            .insert("./local".into(), Value::Object(Map::new()));
+        feature_entry_builder.sort_by_install_order(
+            &added,
+            &[
+                "ghcr.io/devcontainers/features/common-utils".into(),
+                "ghcr.io/devcontainers/features/node".into(),
+            ],
+        );
+
+        assert_eq!(
+            feature_entry_builder.features.keys().collect::<Vec<_>>(),
+            [
+                "ghcr.io/devcontainers/features/common-utils:1",
+                "ghcr.io/devcontainers/features/node:1",
+                "./local"
+            ]
+        );
+
+        Ok(())
     }
 
-    fn apply_context_and_features(&mut self, attempt_single_file: bool, workspace: &Path) -> anyhow::Result<()> {
-        log::debug!("TemplateBuilder::apply_context_and_features");
-        let template_option_re = Regex::new(r"\$\{templateOption:\s*(?<name>\w+)\s*\}")?;
-        let apply_context = |captures: &Captures| -> &[u8] {
-            let name = &captures["name"];
-            let name = std::str::from_utf8(name).ok();
-            match name.and_then(|key| self.context.get(key)) {
-                Some(value) => {
-                    log::debug!(
-                        "TemplateBuilder::apply_context_and_features: Replacing ${{templateOption:{}}} with \"{}\"",
-                        name.unwrap_or_default(),
-                        value
-                    );
-                    value.as_bytes()
-                },
-                None => {
-                    log::warn!("No value provided for ${{templateOption:{}}}", name.unwrap_or_default());
-                    b""
-                },
-            }
+    #[test]
+    fn test_use_extensions() -> anyhow::Result<()> {
+        let feature: crate::registry::Feature = serde_json::from_value(serde_json::json!({
+            "id": "rust",
+            "version": "1.0.0",
+            "name": "Rust",
+            "customizations": {
+                "vscode": { "extensions": ["rust-lang.rust-analyzer", "tamasfe.even-better-toml", "vadimcn.vscode-lldb"] }
+            },
+        }))?;
+        let added = vec![("ghcr.io/devcontainers/features/rust:1".parse()?, feature)];
+        let mut prompter = ScriptedPrompter::new(vec![ScriptedAnswer {
+            message: "VS Code extensions recommended by the features, choose any to add to devcontainer.json:".into(),
+            answer: Answer::List(vec!["vadimcn.vscode-lldb".into()]),
+        }]);
+        let mut template_builder = TemplateBuilder::default();
+
+        template_builder.use_extensions(&["tamasfe.even-better-toml".into()], &added, &mut prompter)?;
+        assert_eq!(
+            template_builder.extensions,
+            ["tamasfe.even-better-toml", "vadimcn.vscode-lldb"]
+        );
+
+        let text = "{\n\t// Rust\n\t\"image\": \"rust\",\n\t\"customizations\": { \"vscode\": { \"extensions\": [\"tamasfe.even-better-toml\"] } }\n}";
+        let contents = template_builder.devcontainer_contents(Cow::Borrowed(text.as_bytes()), &mut Vec::new())?;
+        let contents = String::from_utf8(contents.into_owned())?;
+        assert!(contents.contains("// Rust"));
+
+        let value: Value = serde_jsonc::from_str(&contents)?;
+        assert_eq!(
+            value.pointer("/customizations/vscode/extensions"),
+            Some(&serde_json::json!(["tamasfe.even-better-toml", "vadimcn.vscode-lldb"]))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_notes() -> anyhow::Result<()> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let notes = b"## Getting started\n\nRun `cargo build` on ${templateOption:variant}.\n";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(notes.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "./NOTES.md", notes.as_slice())?;
+
+        let mut template_builder = TemplateBuilder {
+            archive_bytes: builder.into_inner()?,
+            ..Default::default()
         };
-        let mut archive = self.as_archive();
-        let entries = archive.entries()?;
-        let template_skip = ["NOTES.md", "README.md", "devcontainer-template.json"];
+        template_builder.context.insert("variant".into(), "bookworm".into());
 
-        for entry in entries {
-            let mut entry = entry?;
-            let relative_path = entry.path()?;
-            let mut filename = workspace.join(relative_path);
+        assert_eq!(
+            template_builder.notes()?.as_deref(),
+            Some("## Getting started\n\nRun `cargo build` on bookworm.")
+        );
+        assert_eq!(TemplateBuilder::create_empty_start_point()?.notes()?, None);
 
-            if template_skip.iter().any(|&name| filename.ends_with(name)) {
-                log::debug!(
-                    "TemplateBuilder::apply_context_and_features: Skipping template file: {}",
-                    filename.display()
-                );
-                continue;
-            }
+        Ok(())
+    }
 
-            match entry.header().entry_type() {
-                EntryType::Directory => {
-                    log::info!("Creating directory: {}", filename.display());
-                    fs::create_dir_all(&filename)?;
-                },
-                EntryType::Regular | EntryType::Continuous => {
-                    log::info!("Reading file from template archive: {}", filename.display());
+    #[test]
+    fn test_apply_to_stdout() -> anyhow::Result<()> {
+        let workspace = env::temp_dir().join(format!("tyedev-apply-to-stdout-{}", std::process::id()));
+        fs::create_dir_all(&workspace)?;
 
-                    let mut bytes: Vec<u8> = Vec::with_capacity(entry.size() as usize);
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in [
+            (
+                "./.devcontainer/devcontainer.json",
+                "{ \"build\": { \"dockerfile\": \"Dockerfile\" } }\n",
+            ),
+            ("./.devcontainer/Dockerfile", "FROM debian:bookworm\n"),
+            ("./NOTES.md", "Build it.\n"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, contents.as_bytes())?;
+        }
 
-                    entry.read_to_end(&mut bytes)?;
+        let mut template_builder = TemplateBuilder {
+            archive_bytes: builder.into_inner()?,
+            ..Default::default()
+        };
+        assert_eq!(template_builder.template_file_count()?, 2);
 
-                    let with_context = template_option_re.replace_all(bytes.as_mut_slice(), apply_context);
-                    let dc_filename1 = ".devcontainer/devcontainer.json";
-                    let dc_filename2 = ".devcontainer.json";
+        let files = template_builder.apply_context_and_features(
+            &DevcontainerIndex::default(),
+            false,
+            Some(&OutputTarget::Stdout),
+            &workspace,
+            ConflictPolicy::Prompt,
+            &mut DefaultPrompter,
+            &mut Vec::new(),
+        )?;
+        assert!(files.is_empty());
+        assert_eq!(fs::read_dir(&workspace)?.count(), 0);
 
-                    if filename.ends_with(dc_filename1) || filename.ends_with(dc_filename2) {
-                        if attempt_single_file && self.is_single_file_eligible() {
-                            filename = workspace.join(".devcontainer.json");
-                        }
+        fs::remove_dir_all(&workspace)?;
+        Ok(())
+    }
 
-                        if self.features.len() > 0 {
-                            let mut bytes: Vec<u8> = Vec::new();
-                            bytes.write_all(&with_context)?;
-                            let mut value: Value = serde_jsonc::from_slice(bytes.as_slice())?;
-                            let devcontainer = value.as_object_mut().ok_or_else(|| {
-                                io::Error::new(io::ErrorKind::InvalidData, "Format of devcontainer.json is invalid")
-                            })?;
-                            match devcontainer.get_mut("features").and_then(|f| f.as_object_mut()) {
-                                Some(features) => features.extend(self.features.features.clone()),
-                                None => {
-                                    let features_value = self.features.as_value()?;
-                                    devcontainer.insert("features".into(), features_value);
-                                },
-                            }
-                            log::warn!("Comments have been stripped from devcontainer.json");
-                            log::info!("Writing to {}", filename.display());
-                            let file = File::create(filename)?;
-                            serde_json_pretty::to_writer_with_tabs(file, &value)?;
-                        } else {
-                            log::info!("Writing to {}", filename.display());
-                            let mut file = File::create(filename)?;
-                            file.write_all(&with_context)?;
-                        }
-                    } else {
-                        log::info!("Writing to {}", filename.display());
-                        let mut file = File::create(filename)?;
-                        file.write_all(&with_context)?;
-                    }
-                },
-                _ => (),
-            }
-        }
+    #[test]
+    fn test_use_preset() -> anyhow::Result<()> {
+        let presets: BTreeMap<String, Preset> = toml::from_str(
+            r#"
+            [web.features."ghcr.io/devcontainers/features/node:1"]
+            version = "lts"
+            [web.features."ghcr.io/devcontainers/features/git:1"]
+            "#,
+        )?;
+        let mut args = InitArgs {
+            include_features: Some(vec!["ghcr.io/devcontainers/features/node:1".parse()?]),
+            feature_options: [(
+                "ghcr.io/devcontainers/features/node".to_string(),
+                serde_json::json!({ "version": "20" })
+                    .as_object()
+                    .cloned()
+                    .unwrap_or_default(),
+            )]
+            .into(),
+            ..Default::default()
+        };
 
-        log::debug!("TemplateBuilder::apply_context_and_features: done");
+        args.use_preset("web", &presets)?;
+        assert_eq!(args.include_features.map(|features| features.len()), Some(2));
+        assert_eq!(
+            args.feature_options["ghcr.io/devcontainers/features/node"]["version"],
+            "20"
+        );
+        assert!(args.defaulted_features.contains("ghcr.io/devcontainers/features/git"));
+        assert!(InitArgs::default()
+            .use_preset("wbe", &presets)
+            .is_err_and(|error| error.to_string().contains("Did you mean web?")));
 
         Ok(())
     }
 
-    fn create_empty_start_point() -> anyhow::Result<Self> {
-        let template_value = serde_json::json!({
-            "id": "tyedev-base-template",
-            "version": "1.0.0",
-            "name": "Base Template (tyedev)",
+    #[test]
+    fn test_features_file() -> anyhow::Result<()> {
+        let filename = env::temp_dir().join(format!("tyedev-features-{}.yaml", std::process::id()));
+        fs::write(
+            &filename,
+            "ghcr.io/devcontainers/features/node:1:\n  version: lts\n  nvmInstall: maybe\n  yarn: true\n",
+        )?;
+
+        let mut args = InitArgs::default();
+        args.use_features_file(&filename)?;
+        fs::remove_file(&filename)?;
+
+        let feature_ref: crate::oci_ref::OciReference = "ghcr.io/devcontainers/features/node:1".parse()?;
+        let feature: crate::registry::Feature = serde_json::from_value(serde_json::json!({
+            "id": "node",
+            "version": "1.6.0",
+            "name": "Node.js",
             "options": {
-                "imageVariant": {
-                    "type": "string",
-                    "default": "jammy",
-                    "proposals": [
-                        "bookworm",
-                        "bullseye",
-                        "jammy",
-                        "focal"
-                    ]
-                }
+                "version": { "type": "string", "proposals": ["lts", "latest"], "default": "lts" },
+                "nvmInstall": { "type": "boolean", "default": false },
+                "variant": { "type": "string", "enum": ["bookworm", "bullseye"], "default": "bookworm" },
             },
-            "type": "image",
-            "fileCount": 2,
-            "owner": "CodeMan99"
-        });
-        let tar_blocksize = 512;
-        // 3 header blocks, 2 content blocks, 2 zero blocks
-        let tar_chunks = 7;
-        let mut builder = Builder::new(Vec::with_capacity(tar_blocksize * tar_chunks));
-        let mtime = {
-            let unix_time = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
-            unix_time.as_secs()
-        };
+        }))?;
 
-        let create_directory_header = |path: &str| -> io::Result<Header> {
-            let mut header = Header::new_gnu();
-            header.set_entry_type(EntryType::Directory);
-            header.set_path(path)?;
-            header.set_size(0);
-            header.set_mtime(mtime);
-            header.set_mode(0o775);
-            header.set_uid(1000);
-            header.set_gid(1000);
-            header.set_cksum();
-            Ok(header)
-        };
+        assert!(args.checked_features.contains(&feature_ref.id()));
+        assert_eq!(
+            invalid_feature_options(&feature_ref, &feature, &args.feature_options[&feature_ref.id()]),
+            [
+                "Option nvmInstall of ghcr.io/devcontainers/features/node:1 is a boolean, not \"maybe\"",
+                "ghcr.io/devcontainers/features/node:1 has no option yarn, it has version, nvmInstall, variant",
+            ]
+        );
 
-        let create_file_header = |size| {
-            let mut header = Header::new_gnu();
-            header.set_entry_type(EntryType::Regular);
-            header.set_size(size);
-            header.set_mtime(mtime);
-            header.set_mode(0o664);
-            header.set_uid(1000);
-            header.set_gid(1000);
-            header
-        };
+        let given = serde_json::json!({ "nvmInstall": true, "variant": "jammy" });
+        assert_eq!(
+            invalid_feature_options(&feature_ref, &feature, given.as_object().unwrap_or(&Map::new())),
+            ["Option variant of ghcr.io/devcontainers/features/node:1 is one of bookworm, bullseye, not \"jammy\""]
+        );
 
-        let dot_devcontainer_dir = create_directory_header(".devcontainer/")?;
-        builder.get_mut().write_all(dot_devcontainer_dir.as_bytes())?;
+        Ok(())
+    }
 
-        let dot_devcontainer_json: &[u8] = b"{\n\t\"name\": \"tyedev default\",\n\t\"image\": \"mcr.microsoft.com/devcontainers/base:${templateOption:imageVariant}\"\n}\n";
-        let mut header_devcontainer_json = create_file_header(dot_devcontainer_json.len() as u64);
-        builder.append_data(
-            &mut header_devcontainer_json,
-            ".devcontainer/devcontainer.json",
-            dot_devcontainer_json,
-        )?;
+    #[test]
+    fn test_for_project() -> anyhow::Result<()> {
+        let project: crate::batch::Project = toml::from_str(
+            r#"
+            workspace = "api"
+            template = "ghcr.io/devcontainers/templates/go:3"
+            options = { imageVariant = "bookworm", installGo = true }
 
-        let devcontainer_template_json = serde_json::to_string_pretty(&template_value)?;
-        let mut header_template_json = create_file_header(devcontainer_template_json.len() as u64);
-        builder.append_data(
-            &mut header_template_json,
-            "devcontainer-template.json",
-            devcontainer_template_json.as_bytes(),
+            [features."ghcr.io/devcontainers/features/node:1"]
+            version = "20"
+            "#,
         )?;
+        let batch = InitArgs {
+            overwrite_all: true,
+            remote_user: Some("vscode".into()),
+            ..Default::default()
+        };
+        let args = batch.for_project(&project, "api".into())?;
 
-        let archive_bytes = builder.into_inner()?;
+        assert!(args.non_interactive);
+        assert!(args.overwrite_all);
+        assert_eq!(args.remote_user.as_deref(), Some("vscode"));
+        assert!(!args.no_template);
+        assert_eq!(args.template_options.get("installGo").map(String::as_str), Some("true"));
+        assert_eq!(args.include_features.map(|features| features.len()), Some(1));
+        assert_eq!(
+            args.feature_options["ghcr.io/devcontainers/features/node"]["version"],
+            "20"
+        );
 
-        #[cfg(test)]
-        {
-            let tmp = env::temp_dir();
-            let mut file = File::create(tmp.join("devcontainer-template-tyedev-default.tar"))?;
-            file.write_all(&archive_bytes)?;
-        }
+        Ok(())
+    }
 
-        let tb = TemplateBuilder {
-            config: serde_json::from_value(template_value).ok(),
-            context: HashMap::default(),
-            features: FeatureEntryBuilder::default(),
-            archive_bytes,
+    #[test]
+    fn test_create_empty_start_point() -> anyhow::Result<()> {
+        let _template_builder = TemplateBuilder::create_empty_start_point()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_init_from_scratch_with_scripted_answers() -> anyhow::Result<()> {
+        let workspace = env::temp_dir().join(format!("tyedev-init-scripted-{}", std::process::id()));
+        fs::create_dir_all(&workspace)?;
+
+        let scripted = |message: &str, answer: Answer| ScriptedAnswer {
+            message: message.into(),
+            answer,
+        };
+        let mut prompter = ScriptedPrompter::new(vec![
+            scripted("Choose a starting point:", Answer::String("Start from scratch".into())),
+            scripted("What value for imageVariant?", Answer::String("bookworm".into())),
+            scripted("Name for the devcontainer:", Answer::String("Scripted".into())),
+            scripted("Add a feature?", Answer::Boolean(false)),
+            scripted(
+                "Add commands to run as the container is created and started?",
+                Answer::Boolean(false),
+            ),
+            scripted(
+                "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                Answer::String("".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(false)),
+            scripted("Write the dev container above?", Answer::String("Accept".into())),
+            scripted("Edit devcontainer.json before writing?", Answer::Boolean(true)),
+            scripted(
+                "Edit devcontainer.json:",
+                Answer::String("{\"name\": \"Scripted\"}".into()),
+            ),
+            scripted(
+                "The edited devcontainer.json has errors:",
+                Answer::String("Edit again".into()),
+            ),
+            scripted(
+                "Edit devcontainer.json:",
+                Answer::String(
+                    "{\"name\": \"Scripted\", \"image\": \"mcr.microsoft.com/devcontainers/base:bookworm\"}".into(),
+                ),
+            ),
+        ]);
+        let args = InitArgs {
+            workspace_folder: Some(workspace.clone()),
+            no_platform_check: true,
+            ..Default::default()
         };
 
-        Ok(tb)
-    }
-}
+        let report = init_with_prompter(
+            &DevcontainerIndex::default(),
+            args,
+            &DownloadConfig::default(),
+            &ImpactConfig::default(),
+            &Policy::default(),
+            &mut prompter,
+        )
+        .await?;
+        assert_eq!(
+            report.files,
+            vec![WrittenFile {
+                path: ".devcontainer/devcontainer.json".into(),
+                status: FileStatus::Created,
+                diff: None,
+                backup: None,
+            }]
+        );
+        assert_eq!(
+            report.template_options.get("imageVariant").map(String::as_str),
+            Some("bookworm")
+        );
 
-mod serde_json_pretty {
-    use serde::Serialize;
-    use serde_json::{error::Result, ser::PrettyFormatter, Serializer};
-    use std::io::Write;
+        let filename = workspace.join(".devcontainer/devcontainer.json");
+        let devcontainer_json = fs::read_to_string(&filename)?;
+        assert!(devcontainer_json.contains("mcr.microsoft.com/devcontainers/base:bookworm"));
+        assert!(devcontainer_json.contains("\"name\": \"Scripted\""));
+        assert!(devcontainer_json.ends_with("base:bookworm\"}"));
 
-    /// This is the same as `serde_json::to_writer_pretty` except with use of tabs for indentation.
-    pub fn to_writer_with_tabs<W: Write, V: ?Sized + Serialize>(writer: W, value: &V) -> Result<()> {
-        let formatter = PrettyFormatter::with_indent(b"\t");
-        let mut serializer = Serializer::with_formatter(writer, formatter);
-        value.serialize(&mut serializer)
-    }
+        fs::write(&filename, "{}")?;
+        let mut prompter = ScriptedPrompter::new(vec![
+            scripted(
+                ".devcontainer already exists in the workspace:",
+                Answer::String("Merge into it".into()),
+            ),
+            scripted("Choose a starting point:", Answer::String("Start from scratch".into())),
+            scripted("What value for imageVariant?", Answer::String("bookworm".into())),
+            scripted("Name for the devcontainer:", Answer::String("Scripted".into())),
+            scripted("Add a feature?", Answer::Boolean(false)),
+            scripted(
+                "Add commands to run as the container is created and started?",
+                Answer::Boolean(false),
+            ),
+            scripted(
+                "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                Answer::String("".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(false)),
+            scripted("Write the dev container above?", Answer::String("Accept".into())),
+            scripted("Edit devcontainer.json before writing?", Answer::Boolean(false)),
+            scripted(
+                ".devcontainer/devcontainer.json already exists:",
+                Answer::String("Skip".into()),
+            ),
+        ]);
+        let args = InitArgs {
+            workspace_folder: Some(workspace.clone()),
+            no_platform_check: true,
+            ..Default::default()
+        };
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        #[test]
-        fn test_to_writer_with_tabs() -> Result<()> {
-            let value = serde_json::json!({"test": {"deep": 1}});
-            let mut vec: Vec<u8> = Vec::new();
-            to_writer_with_tabs(&mut vec, &value)?;
-            let bytes = vec.as_slice();
-            assert_eq!(bytes, b"{\n\t\"test\": {\n\t\t\"deep\": 1\n\t}\n}");
-            Ok(())
-        }
-    }
-}
+        let report = init_with_prompter(
+            &DevcontainerIndex::default(),
+            args,
+            &DownloadConfig::default(),
+            &ImpactConfig::default(),
+            &Policy::default(),
+            &mut prompter,
+        )
+        .await?;
+        assert_eq!(fs::read_to_string(&filename)?, "{}");
+        assert_eq!(report.files[0].status, FileStatus::Skipped);
+        assert_eq!(report.warnings.len(), 1);
 
-#[derive(Debug, PartialEq)]
-enum PromptEntryAction {
-    Existing,
-    Enter,
-    Empty,
-}
+        let args = InitArgs {
+            workspace_folder: Some(workspace.clone()),
+            non_interactive: true,
+            ..Default::default()
+        };
+        let error = init_with_prompter(
+            &DevcontainerIndex::default(),
+            args,
+            &DownloadConfig::default(),
+            &ImpactConfig::default(),
+            &Policy::default(),
+            &mut DefaultPrompter,
+        )
+        .await
+        .expect_err("an existing configuration needs --force when non-interactive");
+        assert!(error.to_string().contains("--force"));
 
-impl Display for PromptEntryAction {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Existing => write!(f, "Pick existing template"),
-            Self::Enter => write!(f, "Enter known template OCI reference"),
-            Self::Empty => write!(f, "Start from scratch"),
-        }
+        fs::remove_dir_all(&workspace)?;
+        Ok(())
     }
-}
-
-pub async fn init(
-    index: &registry::DevcontainerIndex,
-    InitArgs {
-        non_interactive,
-        attempt_single_file,
-        remove_comments: _,
-        template_id,
-        include_features,
-        include_deprecated,
-        workspace_folder,
-    }: InitArgs,
-) -> anyhow::Result<()> {
-    log::debug!("init");
-    // Do this evaluation of the `env` first so that it can error early.
-    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
 
-    /*
-     * Done        1(a). What template are we starting with?
-     * Done        1(b). Start with an empty, image-based devcontainer.json.
-     * Done        2(a). Pick values for any temapltes options.
-     * Done        2(b). Replace `${templateOption:name}` placeholders. Reminder that this is *not* limited to
-     *                   devcontainer.json and may appear in any of the template's files from the tar archive.
-     *
-     *                       const pattern = /\${templateOption:\s*(\w+?)\s*}/g; // ${templateOption:XXXX}
-     *
-     * Done        3(a). Prompt for features loop?
-     * Done        3(b). Search for feature.
-     * Done        3(c). Pick values for any feature options.
-     * Done        3(d). Edit devcontainer.json.
-     *             4(a). Display the resulting devcontainer.json.
-     *             4(b). Prompt loop to (A)ccept, (E)dit, (R)estart, or (Q)uit
-     * Done           5. Write files to disk.
-     */
-    let mut template_builder: TemplateBuilder = match &template_id {
-        Some(template_ref) => {
-            let id = template_ref.id();
-            let template = index.get_template(&id);
+    #[tokio::test]
+    async fn test_init_pick_features() -> anyhow::Result<()> {
+        let workspace = env::temp_dir().join(format!("tyedev-init-pick-{}", std::process::id()));
+        fs::create_dir_all(&workspace)?;
 
-            TemplateBuilder::new(template_ref, template.cloned()).await?
-        },
-        None if non_interactive => Err(io::Error::new(
-            io::ErrorKind::InvalidInput,
-            "Must provide --template-id in non-interactive mode",
-        ))?,
-        None => {
-            let start_point = inquire::Select::new(
-                "Choose a starting point:",
-                vec![
-                    PromptEntryAction::Existing,
-                    PromptEntryAction::Enter,
-                    PromptEntryAction::Empty,
+        let index: DevcontainerIndex = serde_json::from_str(
+            r#"{"collections": [{
+                "sourceInformation": {"name": "features", "maintainer": "Dev Container Spec Maintainers", "contact": "",
+                    "repository": "", "ociReference": "ghcr.io/devcontainers/features"},
+                "features": [
+                    {"id": "ghcr.io/devcontainers/features/node", "version": "1.6.0", "majorVersion": "1", "name": "Node.js",
+                        "options": {"version": {"type": "string", "proposals": ["lts", "20"], "default": "lts"}}},
+                    {"id": "ghcr.io/devcontainers/features/go", "version": "1.3.0", "majorVersion": "1", "name": "Go"},
+                    {"id": "ghcr.io/devcontainers/features/git", "version": "1.2.0", "majorVersion": "1", "name": "Git"}
                 ],
-            )
-            .prompt()?;
+                "templates": []
+            }]}"#,
+        )?;
+        let scripted = |message: &str, answer: Answer| ScriptedAnswer {
+            message: message.into(),
+            answer,
+        };
+        let mut prompter = ScriptedPrompter::new(vec![
+            scripted("Choose a starting point:", Answer::String("Start from scratch".into())),
+            scripted("What value for imageVariant?", Answer::String("bookworm".into())),
+            scripted("Name for the devcontainer:", Answer::String("".into())),
+            scripted(
+                "Choose the features to add, type to filter:",
+                Answer::List(vec![
+                    "ghcr.io/devcontainers/features/node".into(),
+                    "ghcr.io/devcontainers/features/git".into(),
+                ]),
+            ),
+            scripted("What value for version?", Answer::String("20".into())),
+            scripted("Add another feature by reference?", Answer::Boolean(false)),
+            scripted(
+                "Add commands to run as the container is created and started?",
+                Answer::Boolean(false),
+            ),
+            scripted(
+                "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                Answer::String("".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(false)),
+            scripted("Write the dev container above?", Answer::String("Accept".into())),
+            scripted("Edit devcontainer.json before writing?", Answer::Boolean(false)),
+        ]);
+        let args = InitArgs {
+            workspace_folder: Some(workspace.clone()),
+            no_platform_check: true,
+            ..Default::default()
+        };
 
-            match start_point {
-                PromptEntryAction::Existing => {
-                    let template_ids = index
-                        .iter_templates(include_deprecated)
-                        .map(|template| template.id.clone())
-                        .collect();
-                    let template_id =
-                        inquire::Select::new("Pick existing template from the index:", template_ids).prompt()?;
-                    let template_ref = template_id.parse()?;
-                    let template = index.get_template(&template_id);
-                    TemplateBuilder::new(&template_ref, template.cloned()).await?
-                },
-                PromptEntryAction::Enter => {
-                    let template_id = inquire::Text::new("Enter template by providing the OCI reference:").prompt()?;
-                    let template_ref = template_id.parse()?;
-                    let template = index.get_template(&template_id);
-                    TemplateBuilder::new(&template_ref, template.cloned()).await?
-                },
-                PromptEntryAction::Empty => TemplateBuilder::create_empty_start_point()?,
-            }
-        },
-    };
+        let report = init_with_prompter(
+            &index,
+            args,
+            &DownloadConfig::default(),
+            &ImpactConfig::default(),
+            &Policy::default(),
+            &mut prompter,
+        )
+        .await?;
+        let references: Vec<&str> = report
+            .features
+            .iter()
+            .map(|feature| feature.reference.as_str())
+            .collect();
 
-    let is_version_tag = template_id
-        .as_ref()
-        .is_some_and(|oci_ref| oci_ref.tag_name() != "latest");
+        assert_eq!(
+            references,
+            [
+                "ghcr.io/devcontainers/features/node:1",
+                "ghcr.io/devcontainers/features/git:1"
+            ]
+        );
+        assert_eq!(report.features[0].options, serde_json::json!({ "version": "20" }));
 
-    if is_version_tag || template_builder.config.is_none() {
-        template_builder.replace_config()?;
+        fs::remove_dir_all(&workspace)?;
+        Ok(())
     }
 
-    if non_interactive {
-        template_builder.use_default_values()?;
+    #[tokio::test]
+    async fn test_init_remove_comments() -> anyhow::Result<()> {
+        let workspace = env::temp_dir().join(format!("tyedev-init-remove-comments-{}", std::process::id()));
+        fs::create_dir_all(&workspace)?;
 
-        if let Some(feature_refs) = include_features {
-            for feature_ref in feature_refs {
-                let feature = get_feature(index, &feature_ref).await?;
-                log::info!("Adding feature: {}", feature_ref.id());
-                template_builder.features.use_default_values(&feature);
-            }
-        }
-    } else {
-        template_builder.use_prompt_values()?;
+        let scripted = |message: &str, answer: Answer| ScriptedAnswer {
+            message: message.into(),
+            answer,
+        };
+        let mut prompter = ScriptedPrompter::new(vec![
+            scripted("Choose a starting point:", Answer::String("Start from scratch".into())),
+            scripted("What value for imageVariant?", Answer::String("bookworm".into())),
+            scripted("Name for the devcontainer:", Answer::String("".into())),
+            scripted("Add a feature?", Answer::Boolean(false)),
+            scripted(
+                "Add commands to run as the container is created and started?",
+                Answer::Boolean(false),
+            ),
+            scripted(
+                "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                Answer::String("".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(false)),
+            scripted("Write the dev container above?", Answer::String("Accept".into())),
+            scripted("Edit devcontainer.json before writing?", Answer::Boolean(true)),
+            scripted(
+                "Edit devcontainer.json:",
+                Answer::String("{\n\t// Pinned for CI\n\t\"image\": \"debian:bookworm\"\n}".into()),
+            ),
+        ]);
+        let args = InitArgs {
+            workspace_folder: Some(workspace.clone()),
+            no_platform_check: true,
+            remove_comments: true,
+            ..Default::default()
+        };
 
-        if let Some(feature_refs) = include_features {
-            for feature_ref in feature_refs {
-                let feature = get_feature(index, &feature_ref).await?;
-                println!("Adding feature: {}", feature_ref.id());
-                template_builder.features.use_prompt_values(&feature)?;
-            }
-        }
+        let report = init_with_prompter(
+            &DevcontainerIndex::default(),
+            args,
+            &DownloadConfig::default(),
+            &ImpactConfig::default(),
+            &Policy::default(),
+            &mut prompter,
+        )
+        .await?;
 
-        loop {
-            let next = inquire::Confirm::new("Add a feature?").prompt()?;
+        assert_eq!(
+            fs::read_to_string(workspace.join(".devcontainer/devcontainer.json"))?,
+            "{\n\t\"image\": \"debian:bookworm\"\n}\n"
+        );
+        assert!(report.warnings.is_empty());
+
+        fs::remove_dir_all(&workspace)?;
+        Ok(())
+    }
 
-            if next {
-                let features_autocomplete = FeaturesAutocomplete::new(index, include_deprecated);
-                let input = inquire::Text::new("Choose or enter feature id (OCI REF):")
-                    .with_autocomplete(features_autocomplete)
-                    .prompt()?;
-                let feature_ref: OciReference = input.parse()?;
-                let feature = get_feature(index, &feature_ref).await?;
+    #[tokio::test]
+    async fn test_init_dry_run() -> anyhow::Result<()> {
+        let workspace = env::temp_dir().join(format!("tyedev-init-dry-run-{}", std::process::id()));
+        fs::create_dir_all(&workspace)?;
 
-                template_builder.features.use_prompt_values(&feature)?;
-            } else {
-                break;
-            }
-        }
+        let scripted = |message: &str, answer: Answer| ScriptedAnswer {
+            message: message.into(),
+            answer,
+        };
+        let mut prompter = ScriptedPrompter::new(vec![
+            scripted("Choose a starting point:", Answer::String("Start from scratch".into())),
+            scripted("What value for imageVariant?", Answer::String("bookworm".into())),
+            scripted("Name for the devcontainer:", Answer::String("".into())),
+            scripted("Add a feature?", Answer::Boolean(false)),
+            scripted(
+                "Add commands to run as the container is created and started?",
+                Answer::Boolean(false),
+            ),
+            scripted(
+                "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                Answer::String("".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(false)),
+            scripted("Write the dev container above?", Answer::String("Accept".into())),
+            scripted("Edit devcontainer.json before writing?", Answer::Boolean(false)),
+        ]);
+        let args = InitArgs {
+            workspace_folder: Some(workspace.clone()),
+            no_platform_check: true,
+            no_header: true,
+            dry_run: true,
+            ..Default::default()
+        };
+
+        let report = init_with_prompter(
+            &DevcontainerIndex::default(),
+            args,
+            &DownloadConfig::default(),
+            &ImpactConfig::default(),
+            &Policy::default(),
+            &mut prompter,
+        )
+        .await?;
+
+        assert!(report.dry_run);
+        assert_eq!(report.files.len(), 1);
+        assert_eq!(report.files[0].status, FileStatus::Created);
+        assert!(report.files[0]
+            .diff
+            .as_deref()
+            .is_some_and(|diff| diff.contains("+++ b/.devcontainer/devcontainer.json")));
+        assert!(!workspace.join(".devcontainer").exists());
+
+        fs::remove_dir_all(&workspace)?;
+        Ok(())
     }
 
-    template_builder.apply_context_and_features(attempt_single_file, &workspace)?;
-    log::debug!("init: done");
+    #[tokio::test]
+    async fn test_init_review() -> anyhow::Result<()> {
+        let workspace = env::temp_dir().join(format!("tyedev-init-review-{}", std::process::id()));
+        fs::create_dir_all(&workspace)?;
 
-    Ok(())
-}
+        let scripted = |message: &str, answer: Answer| ScriptedAnswer {
+            message: message.into(),
+            answer,
+        };
+        let start = || {
+            vec![
+                scripted("Choose a starting point:", Answer::String("Start from scratch".into())),
+                scripted("What value for imageVariant?", Answer::String("bookworm".into())),
+                scripted("Name for the devcontainer:", Answer::String("Reviewed".into())),
+                scripted("Add a feature?", Answer::Boolean(false)),
+                scripted(
+                    "Add commands to run as the container is created and started?",
+                    Answer::Boolean(false),
+                ),
+                scripted(
+                    "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                    Answer::String("".into()),
+                ),
+                scripted("Add an environment variable?", Answer::Boolean(false)),
+            ]
+        };
+        let args = || InitArgs {
+            workspace_folder: Some(workspace.clone()),
+            no_platform_check: true,
+            no_header: true,
+            ..Default::default()
+        };
+        let init = |prompter: ScriptedPrompter| async {
+            let mut prompter = prompter;
+            init_with_prompter(
+                &DevcontainerIndex::default(),
+                args(),
+                &DownloadConfig::default(),
+                &ImpactConfig::default(),
+                &Policy::default(),
+                &mut prompter,
+            )
+            .await
+        };
 
-// TODO these are more *proof of concept* than actual tests...
-#[cfg(test)]
-mod tests {
-    use super::{FeatureEntryBuilder, TemplateBuilder};
-    use serde_json::{self, Map, Value};
+        let mut answers = start();
+        answers.push(scripted(
+            "Write the dev container above?",
+            Answer::String("Quit".into()),
+        ));
+        let error = init(ScriptedPrompter::new(answers))
+            .await
+            .err()
+            .map(|error| error.to_string());
 
-    #[test]
-    fn test_feature_entry_builder_as_value() -> serde_json::error::Result<()> {
-        let mut feature_entry_builder = FeatureEntryBuilder::default();
+        assert_eq!(error.as_deref(), Some("Init cancelled, nothing was written"));
+        assert!(!workspace.join(".devcontainer").exists());
 
-        let git_id = "ghcr.io/devcontainers/git:1";
-        let git_options = Value::Object(Map::default());
-        feature_entry_builder.features.insert(git_id.to_owned(), git_options);
+        let mut answers = start();
+        answers.extend([
+            scripted(
+                "Write the dev container above?",
+                Answer::String("Restart with another template".into()),
+            ),
+            scripted("Choose a starting point:", Answer::String("Start from scratch".into())),
+            scripted("What value for imageVariant?", Answer::String("bullseye".into())),
+            scripted("Name for the devcontainer:", Answer::String("Reviewed".into())),
+            scripted("Add a feature?", Answer::Boolean(false)),
+            scripted(
+                "Add commands to run as the container is created and started?",
+                Answer::Boolean(false),
+            ),
+            scripted(
+                "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                Answer::String("".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(false)),
+            scripted("Write the dev container above?", Answer::String("Edit options".into())),
+            scripted("What value for imageVariant?", Answer::String("noble".into())),
+            scripted("Name for the devcontainer:", Answer::String("Edited".into())),
+            scripted(
+                "Add commands to run as the container is created and started?",
+                Answer::Boolean(true),
+            ),
+            scripted(
+                "Command for updateContentCommand, left empty for none:",
+                Answer::String("".into()),
+            ),
+            scripted(
+                "Command for postCreateCommand, left empty for none:",
+                Answer::String("[\"npm\", \"ci\"]".into()),
+            ),
+            scripted(
+                "Command for postStartCommand, left empty for none:",
+                Answer::String("git fetch".into()),
+            ),
+            scripted(
+                "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+                Answer::String("3000=Frontend db:5432".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(true)),
+            scripted(
+                "Environment variable, as NAME=VALUE:",
+                Answer::String("RUST_LOG=debug".into()),
+            ),
+            scripted(
+                "Set RUST_LOG for:",
+                Answer::String("Tools connecting to the container (remoteEnv)".into()),
+            ),
+            scripted("Add an environment variable?", Answer::Boolean(false)),
+            scripted("Write the dev container above?", Answer::String("Accept".into())),
+            scripted("Edit devcontainer.json before writing?", Answer::Boolean(false)),
+        ]);
+        init(ScriptedPrompter::new(answers)).await?;
 
-        let dind_id = "ghcr.io/devcontainers/docker-in-docker:2";
-        let dind_options = serde_json::json!({"moby": false});
-        feature_entry_builder.features.insert(dind_id.to_owned(), dind_options);
+        let devcontainer_json = fs::read_to_string(workspace.join(".devcontainer/devcontainer.json"))?;
 
-        let value = feature_entry_builder.as_value()?;
-        let json_str = serde_json::to_string_pretty(&value)?;
+        assert!(devcontainer_json.contains("\"name\": \"Edited\""));
+        assert!(devcontainer_json.contains("base:noble"));
 
-        println!("{{\"features\": {json_str}\n}}");
+        let devcontainer: Value = serde_jsonc::from_str(&devcontainer_json)?;
 
-        Ok(())
-    }
+        assert_eq!(devcontainer["postCreateCommand"], serde_json::json!(["npm", "ci"]));
+        assert_eq!(devcontainer["postStartCommand"], "git fetch");
+        assert!(devcontainer.get("updateContentCommand").is_none());
+        assert_eq!(devcontainer["forwardPorts"], serde_json::json!([3000, "db:5432"]));
+        assert_eq!(
+            devcontainer["portsAttributes"],
+            serde_json::json!({ "3000": { "label": "Frontend" } })
+        );
 
-    #[test]
-    fn test_create_empty_start_point() -> anyhow::Result<()> {
-        let _template_builder = TemplateBuilder::create_empty_start_point()?;
+        fs::remove_dir_all(&workspace)?;
         Ok(())
     }
 }
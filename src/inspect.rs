@@ -1,13 +1,17 @@
 use std::fmt::{self, Display};
-use std::io::{self, Read, Write};
+use std::io::{self, Write};
+use std::path::Path;
 
 use ascii_table::{Align, AsciiTable};
 use clap::{Args, ValueEnum};
 use human_format::Formatter;
-use tar::Archive;
 
+use crate::archive;
 use crate::oci_ref::OciReference;
+use crate::output::{self, Kind, OutputFormat};
 use crate::registry;
+use crate::suggest;
+use crate::theme;
 
 #[derive(Clone, Debug, Default, ValueEnum)]
 pub enum InspectDisplay {
@@ -15,6 +19,9 @@ pub enum InspectDisplay {
     Table,
     Json,
     None,
+    /// The table as tab separated values, chosen by `--plain`.
+    #[value(skip)]
+    Plain,
     // Csv,
     // Yaml,
     // Toml,
@@ -28,6 +35,7 @@ impl Display for InspectDisplay {
             Self::Table => write!(f, "table"),
             Self::Json => write!(f, "json"),
             Self::None => write!(f, "none"),
+            Self::Plain => write!(f, "plain"),
         }
     }
 }
@@ -42,6 +50,10 @@ pub struct InspectArgs {
     #[arg(short, long, value_name = "FORMAT", default_value = "table")]
     display_as: InspectDisplay,
 
+    /// Print the table as tab separated values without borders.
+    #[arg(long, visible_alias = "no-headers")]
+    plain: bool,
+
     /// Read the `install.sh` script of a given feature.
     #[arg(long)]
     install_sh: bool,
@@ -49,26 +61,35 @@ pub struct InspectArgs {
     /// List the filenames of a given feature or template.
     #[arg(long)]
     show_files: bool,
+
+    /// Exit with an error when the given id is deprecated.
+    #[arg(long)]
+    fail_on_deprecated: bool,
+
+    /// Exit with an error when the published configuration has fields unknown to the specification, such as a
+    /// misspelled option property.
+    #[arg(long)]
+    strict: bool,
 }
 
-struct TableData(Vec<[String; 2]>);
+pub struct TableData(pub Vec<[String; 2]>);
 
 impl TableData {
-    fn new() -> TableData {
+    pub fn new() -> TableData {
         TableData(Vec::new())
     }
 
-    fn push<L: Display, V: Display>(&mut self, label: L, value: V) {
+    pub fn push<L: Display, V: Display>(&mut self, label: L, value: V) {
         self.0.push([label.to_string(), value.to_string()]);
     }
 
-    fn maybe_push<L: Display, V: Display>(&mut self, label: L, value: Option<V>) {
+    pub fn maybe_push<L: Display, V: Display>(&mut self, label: L, value: Option<V>) {
         if let Some(v) = value {
             self.0.push([label.to_string(), v.to_string()]);
         }
     }
 
-    fn many_push<L: Display, V: Display>(&mut self, label: L, values: Option<impl IntoIterator<Item = V>>) {
+    pub fn many_push<L: Display, V: Display>(&mut self, label: L, values: Option<impl IntoIterator<Item = V>>) {
         if let Some(iterable) = values {
             let mut i = iterable.into_iter();
 
@@ -83,35 +104,60 @@ impl TableData {
     }
 }
 
-trait Displayable: serde::Serialize {
+pub trait Displayable: serde::Serialize {
     fn display_json(&self) -> serde_json::error::Result<()> {
         let json = serde_json::to_string_pretty(self)?;
         println!("{json}");
         Ok(())
     }
 
-    fn display_table(&self);
-}
-
-impl Displayable for registry::Collection {
     fn display_table(&self) {
         let mut table = AsciiTable::default();
-        let data = [
-            ["Name", self.source_information.name.as_ref()],
-            ["Maintainer", self.source_information.maintainer.as_ref()],
-            ["Contact", self.source_information.contact.as_ref()],
-            ["Repository", self.source_information.repository.as_ref()],
-            ["OCI Reference", self.source_information.oci_reference.as_ref()],
-        ];
+
+        let header = &theme::current().header;
+        let rows = self
+            .table_rows()
+            .into_iter()
+            .map(|[label, value]| [header.paint(label), value]);
 
         table.column(0).set_align(Align::Right);
-        table.print(data);
+        table.print(rows);
+    }
+
+    /// Every row is labeled, values of a list repeat the label of the first value.
+    fn display_plain(&self) {
+        let mut label = String::new();
+        let rows = self.table_rows().into_iter().map(|[row_label, value]| {
+            if !row_label.is_empty() {
+                label = row_label;
+            }
+
+            [label.clone(), value]
+        });
+
+        output::print_plain(rows);
+    }
+
+    /// Label and value pairs, in display order.
+    fn table_rows(&self) -> Vec<[String; 2]>;
+}
+
+impl Displayable for registry::Collection {
+    fn table_rows(&self) -> Vec<[String; 2]> {
+        let mut data = TableData::new();
+
+        data.push("Name", &self.source_information.name);
+        data.push("Maintainer", &self.source_information.maintainer);
+        data.push("Contact", &self.source_information.contact);
+        data.push("Repository", &self.source_information.repository);
+        data.push("OCI Reference", &self.source_information.oci_reference);
+
+        data.0
     }
 }
 
 impl Displayable for registry::Feature {
-    fn display_table(&self) {
-        let mut table = AsciiTable::default();
+    fn table_rows(&self) -> Vec<[String; 2]> {
         let mut data = TableData::new();
         let comma_join = |value: &Vec<String>| value.join(", ");
 
@@ -153,6 +199,19 @@ impl Displayable for registry::Feature {
 
         data.maybe_push("VS Code Extensions", vscode_extensions);
         data.maybe_push("Installs After", self.installs_after.as_ref().map(comma_join));
+        // A dependency is installed with the options it is declared with, which are as much a part of it as the id.
+        data.many_push(
+            "Depends On",
+            self.depends_on.as_ref().map(|depends_on| {
+                depends_on
+                    .iter()
+                    .map(|(reference, options)| match options.as_object() {
+                        Some(options) if options.is_empty() => reference.clone(),
+                        _ => format!("{reference} {options}"),
+                    })
+                    .collect::<Vec<String>>()
+            }),
+        );
         data.maybe_push("Legacy IDs", self.lecagy_ids.as_ref().map(comma_join));
         data.maybe_push("Deprecated", self.deprecated);
         data.many_push("Mounts", self.mounts.as_ref());
@@ -164,15 +223,12 @@ impl Displayable for registry::Feature {
         data.push("Owner", self.owner.clone());
         data.push("Major Version", self.major_version.clone());
 
-        let TableData(inner) = data;
-        table.column(0).set_align(Align::Right);
-        table.print(inner);
+        data.0
     }
 }
 
 impl Displayable for registry::Template {
-    fn display_table(&self) {
-        let mut table = AsciiTable::default();
+    fn table_rows(&self) -> Vec<[String; 2]> {
         let mut data = TableData::new();
         let comma_join = |value: &Vec<String>| value.join(", ");
 
@@ -199,9 +255,20 @@ impl Displayable for registry::Template {
         data.maybe_push("Feature IDs", self.feature_ids.as_ref().map(comma_join));
         data.push("Owner", self.owner.clone());
 
-        let TableData(inner) = data;
-        table.column(0).set_align(Align::Right);
-        table.print(inner);
+        data.0
+    }
+}
+
+/// Display as requested, unless the stable JSON output was asked for.
+pub fn display_or_envelope<T: Displayable>(
+    value: &T,
+    kind: Kind,
+    display_as: &InspectDisplay,
+    format: OutputFormat,
+) -> serde_json::error::Result<()> {
+    match format {
+        OutputFormat::Json => output::print_json(kind, value),
+        OutputFormat::Text => display(value, display_as),
     }
 }
 
@@ -211,30 +278,79 @@ fn display<T: ?Sized + Displayable>(value: &T, format: &InspectDisplay) -> serde
     match format {
         InspectDisplay::Json => value.display_json()?,
         InspectDisplay::Table => value.display_table(),
+        InspectDisplay::Plain => value.display_plain(),
         InspectDisplay::None => println!(),
     }
 
     Ok(())
 }
 
+/// Whether an archive path is the configuration of a feature or template.
+fn is_configuration(path: &Path) -> bool {
+    path.ends_with("devcontainer-feature.json") || path.ends_with("devcontainer-template.json")
+}
+
+/// Display the configuration found inside a feature or template archive, for artifacts that are not in the index.
+fn display_archive_config(bytes: &[u8], display_as: &InspectDisplay, format: OutputFormat) -> anyhow::Result<()> {
+    log::debug!("display_archive_config");
+
+    let Some((path, data)) = archive::find_entry(bytes, is_configuration)? else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "The archive contains neither a devcontainer-feature.json nor a devcontainer-template.json",
+        ))?
+    };
+
+    if path.ends_with("devcontainer-feature.json") {
+        let feature: registry::Feature = serde_json::from_slice(&data)?;
+        display_or_envelope(&feature, Kind::Feature, display_as, format)?;
+    } else {
+        let template: registry::Template = serde_json::from_slice(&data)?;
+        display_or_envelope(&template, Kind::Template, display_as, format)?;
+    }
+
+    Ok(())
+}
+
+/// Refuse a feature or template whose configuration has fields that parsing would silently ignore.
+async fn check_strict(oci_ref: &OciReference) -> anyhow::Result<()> {
+    log::debug!("check_strict");
+
+    let bytes = registry::pull_archive_bytes(oci_ref).await?;
+    let Some((path, data)) = archive::find_entry(bytes.as_slice(), is_configuration)? else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "The archive contains neither a devcontainer-feature.json nor a devcontainer-template.json",
+        ))?
+    };
+    let raw: serde_json::Value = serde_json::from_slice(&data)?;
+    let unknown = if path.ends_with("devcontainer-feature.json") {
+        registry::unknown_fields::<registry::Feature>(&raw)?
+    } else {
+        registry::unknown_fields::<registry::Template>(&raw)?
+    };
+
+    if !unknown.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unknown fields in {}: {}", path.display(), unknown.join(", ")),
+        ))?
+    }
+
+    Ok(())
+}
+
 async fn display_files(oci_ref: &OciReference) -> anyhow::Result<()> {
     log::debug!("display_files");
 
     let bytes = registry::pull_archive_bytes(oci_ref).await?;
-    let mut archive = Archive::new(bytes.as_slice());
-    let entries = archive.entries()?;
-
-    for entry in entries {
-        let entry = entry?;
-        let header = entry.header();
-        let size = header.size()?;
 
-        if size > 0 {
-            let human_size = Formatter::new().with_decimals(1).format(size as f64);
-            let filename = header.path()?;
+    for entry in archive::list_entries(bytes.as_slice())? {
+        if entry.size > 0 {
+            let human_size = Formatter::new().with_decimals(1).format(entry.size as f64);
 
             // Example max expected string length: "123.4 k" - which is seven characters.
-            println!("{:>width$}: {}", human_size.trim_end(), filename.display(), width = 7);
+            println!("{:>width$}: {}", human_size.trim_end(), entry.path.display(), width = 7);
         }
     }
 
@@ -245,27 +361,19 @@ async fn display_install_sh(oci_ref: &OciReference) -> anyhow::Result<()> {
     log::debug!("display_install_sh");
 
     let bytes = registry::pull_archive_bytes(oci_ref).await?;
-    let mut archive = Archive::new(bytes.as_slice());
-    let entries = archive.entries()?;
-
-    for entry in entries {
-        let mut entry = entry?;
-        let path = entry.path()?;
-
-        if path.to_str().is_some_and(|p| p.ends_with("install.sh")) {
-            let mut data: Vec<u8> = Vec::new();
-
-            entry.read_to_end(&mut data)?;
-            io::stdout().write_all(data.as_slice())?;
+    let found = archive::find_entry(bytes.as_slice(), |path| {
+        path.to_str().is_some_and(|p| p.ends_with("install.sh"))
+    })?;
+    let Some((_, data)) = found else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "The install.sh script was not found in the archive",
+        ))?
+    };
+
+    io::stdout().write_all(data.as_slice())?;
 
-            return Ok(());
-        }
-    }
-
-    Err(io::Error::new(
-        io::ErrorKind::NotFound,
-        "The install.sh script was not found in the archive",
-    ))?
+    Ok(())
 }
 
 pub async fn inspect(
@@ -273,21 +381,48 @@ pub async fn inspect(
     InspectArgs {
         oci_ref,
         display_as,
+        plain,
         install_sh,
         show_files,
+        fail_on_deprecated,
+        strict,
     }: InspectArgs,
+    format: OutputFormat,
 ) -> anyhow::Result<()> {
     log::debug!("inspect");
 
+    // Anything else printed to stdout would break the JSON output.
+    let (show_files, install_sh) = match format {
+        OutputFormat::Json if show_files || install_sh => {
+            log::warn!("Ignoring --show-files and --install-sh with --output json");
+            (false, false)
+        },
+        _ => (show_files, install_sh),
+    };
+
+    let display_as = match display_as {
+        InspectDisplay::Table if plain => InspectDisplay::Plain,
+        _ => display_as,
+    };
+
     let id = oci_ref.id();
     let collection = index.get_collection(&id);
     let feature = index.get_feature(&id);
     let template = index.get_template(&id);
 
+    // Checked first, so that nothing is printed for a configuration that is refused.
+    if strict {
+        match collection {
+            Some(_) => log::warn!("A collection has no configuration of its own to check with --strict."),
+            None if feature.is_some() || template.is_some() || oci_ref.is_local() => check_strict(&oci_ref).await?,
+            None => {},
+        }
+    }
+
     match (collection, feature, template) {
         (Some(c), None, None) => {
             log::debug!("inspect: collection");
-            display(c, &display_as)?;
+            display_or_envelope(c, Kind::Collection, &display_as, format)?;
 
             if show_files || install_sh {
                 log::warn!("A collection is container of features & templates, not files.");
@@ -297,7 +432,7 @@ pub async fn inspect(
         },
         (None, Some(f), None) => {
             log::debug!("inspect: feature");
-            display(f, &display_as)?;
+            display_or_envelope(f, Kind::Feature, &display_as, format)?;
 
             if show_files {
                 display_files(&oci_ref).await?;
@@ -311,7 +446,7 @@ pub async fn inspect(
         },
         (None, None, Some(t)) => {
             log::debug!("inspect: template");
-            display(t, &display_as)?;
+            display_or_envelope(t, Kind::Template, &display_as, format)?;
 
             if show_files {
                 display_files(&oci_ref).await?;
@@ -323,13 +458,38 @@ pub async fn inspect(
 
             Ok(())
         },
-        (None, None, None) => Err(io::Error::new(io::ErrorKind::NotFound, "No match found for given id.")),
+        (None, None, None) if oci_ref.is_local() => {
+            log::debug!("inspect: local artifact");
+            let bytes = registry::pull_archive_bytes(&oci_ref).await?;
+            display_archive_config(&bytes, &display_as, format)?;
+
+            if show_files {
+                display_files(&oci_ref).await?;
+            }
+
+            if install_sh {
+                display_install_sh(&oci_ref).await?;
+            }
+
+            Ok(())
+        },
+        (None, None, None) => {
+            let error = io::Error::new(io::ErrorKind::NotFound, "No match found for given id.");
+            Err(suggest::with_suggestions(error.into(), &id, index.iter_ids()))?
+        },
         _ => Err(io::Error::new(
             io::ErrorKind::Unsupported,
             "Multiple results found for given id.",
         )),
     }?;
 
+    if fail_on_deprecated && index.is_deprecated(&id) {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{id} is deprecated"),
+        ))?
+    }
+
     log::debug!("inspect: done");
 
     Ok(())
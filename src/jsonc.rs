@@ -0,0 +1,605 @@
+use std::ops::Range;
+
+use serde_json::{Map, Value};
+
+/// A value of a JSONC document along with where it is written.
+#[derive(Debug)]
+struct Node {
+    span: Range<usize>,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Object(Vec<Entry>),
+    Array(Vec<Entry>),
+    Scalar,
+}
+
+/// A member of an object, or an element of an array which has no key.
+#[derive(Debug)]
+struct Entry {
+    key: Option<(String, Range<usize>)>,
+    value: Node,
+    /// Position of the comma that follows the entry, which the last one may also have.
+    comma: Option<usize>,
+}
+
+impl Entry {
+    fn start(&self) -> usize {
+        self.key.as_ref().map_or(self.value.span.start, |(_, span)| span.start)
+    }
+
+    fn end(&self) -> usize {
+        self.value.span.end
+    }
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    bytes: &'t [u8],
+    pos: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn new(text: &'t str) -> Self {
+        Parser {
+            text,
+            bytes: text.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Skip whitespace and comments, failing on an unterminated block comment.
+    fn skip_trivia(&mut self) -> Option<()> {
+        loop {
+            match (self.peek(), self.bytes.get(self.pos + 1)) {
+                (Some(byte), _) if byte.is_ascii_whitespace() => self.pos += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    self.pos = (self.text[self.pos..].find('\n')).map_or(self.bytes.len(), |end| self.pos + end);
+                },
+                (Some(b'/'), Some(b'*')) => self.pos += self.text[self.pos + 2..].find("*/")? + 4,
+                _ => return Some(()),
+            }
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    fn string(&mut self) -> Option<Range<usize>> {
+        let start = self.pos;
+        self.expect(b'"')?;
+
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(start..self.pos);
+                },
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn value(&mut self) -> Option<Node> {
+        self.skip_trivia()?;
+        let start = self.pos;
+
+        match self.peek()? {
+            b'{' => self.container(b'}', true),
+            b'[' => self.container(b']', false),
+            b'"' => Some(Node {
+                span: self.string()?,
+                kind: Kind::Scalar,
+            }),
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|byte| byte.is_ascii_alphanumeric() || b"+-.".contains(&byte))
+                {
+                    self.pos += 1;
+                }
+
+                serde_json::from_str::<Value>(&self.text[start..self.pos]).ok()?;
+
+                Some(Node {
+                    span: start..self.pos,
+                    kind: Kind::Scalar,
+                })
+            },
+        }
+    }
+
+    fn container(&mut self, close: u8, keyed: bool) -> Option<Node> {
+        let start = self.pos;
+        let mut entries = Vec::new();
+        self.pos += 1;
+
+        loop {
+            self.skip_trivia()?;
+
+            if self.peek()? == close {
+                self.pos += 1;
+                break;
+            }
+
+            let key = if keyed {
+                let span = self.string()?;
+                let key: String = serde_json::from_str(&self.text[span.clone()]).ok()?;
+                self.skip_trivia()?;
+                self.expect(b':')?;
+                Some((key, span))
+            } else {
+                None
+            };
+            let value = self.value()?;
+            self.skip_trivia()?;
+            let comma = (self.peek() == Some(b',')).then_some(self.pos);
+
+            entries.push(Entry { key, value, comma });
+
+            if comma.is_some() {
+                self.pos += 1;
+            } else {
+                self.skip_trivia()?;
+                self.expect(close)?;
+                break;
+            }
+        }
+
+        let kind = if keyed {
+            Kind::Object(entries)
+        } else {
+            Kind::Array(entries)
+        };
+
+        Some(Node {
+            span: start..self.pos,
+            kind,
+        })
+    }
+
+    /// The single value of the document, with nothing but whitespace and comments around it.
+    fn document(mut self) -> Option<Node> {
+        let root = self.value()?;
+        self.skip_trivia()?;
+
+        (self.pos == self.bytes.len()).then_some(root)
+    }
+}
+
+/// The value a node is written as.
+fn node_value(text: &str, node: &Node) -> Value {
+    match &node.kind {
+        Kind::Object(entries) => Value::Object(
+            entries
+                .iter()
+                .filter_map(|entry| Some((entry.key.as_ref()?.0.clone(), node_value(text, &entry.value))))
+                .collect(),
+        ),
+        Kind::Array(entries) => Value::Array(entries.iter().map(|entry| node_value(text, &entry.value)).collect()),
+        Kind::Scalar => serde_json::from_str(&text[node.span.clone()]).unwrap_or(Value::Null),
+    }
+}
+
+/// Changes to the text of a document, each replacing a range with new text.
+struct Editor<'t> {
+    text: &'t str,
+    bytes: &'t [u8],
+    /// One level of indentation, as the document has it.
+    unit: String,
+    newline: &'static str,
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl<'t> Editor<'t> {
+    fn new(text: &'t str, root: &Node) -> Self {
+        let mut editor = Editor {
+            text,
+            bytes: text.as_bytes(),
+            unit: "\t".to_string(),
+            newline: if text.contains("\r\n") { "\r\n" } else { "\n" },
+            edits: Vec::new(),
+        };
+
+        if let Kind::Object(entries) = &root.kind {
+            if let Some(indent) = entries.first().and_then(|entry| editor.entry_indent(entry)) {
+                editor.unit = indent.to_string();
+            }
+        }
+
+        editor
+    }
+
+    /// The whitespace that starts the line holding `pos`.
+    fn line_indent(&self, pos: usize) -> &'t str {
+        let start = self.text[..pos].rfind('\n').map_or(0, |newline| newline + 1);
+        let indent = self.text[start..].find(|c: char| c != ' ' && c != '\t').unwrap_or(0);
+
+        &self.text[start..start + indent]
+    }
+
+    /// The indentation of an entry that starts its own line.
+    fn entry_indent(&self, entry: &Entry) -> Option<&'t str> {
+        let start = entry.start();
+        let indent = self.line_indent(start);
+        let line_start = self.text[..start].rfind('\n').map_or(0, |newline| newline + 1);
+
+        (line_start + indent.len() == start).then_some(indent)
+    }
+
+    /// Where the line holding `pos` ends, when only whitespace or a comment follows `pos` on it, otherwise `pos`.
+    fn line_end(&self, pos: usize) -> usize {
+        let mut end = pos;
+
+        while self.bytes.get(end).is_some_and(|byte| *byte == b' ' || *byte == b'\t') {
+            end += 1;
+        }
+
+        let rest = &self.text[end..];
+
+        if rest.starts_with("//") {
+            end += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            match rest.find("*/") {
+                Some(close) if !rest[..close].contains('\n') => end += close + 2,
+                _ => return pos,
+            }
+        }
+
+        match &self.text[end..] {
+            "" => end,
+            rest if rest.starts_with('\n') => end,
+            rest if rest.starts_with("\r\n") => end,
+            _ => pos,
+        }
+    }
+
+    /// The range of `start..end` along with the whitespace around it and a comment that ends its line. When it is on
+    /// its own line, the whole line is taken.
+    fn line_range(&self, start: usize, end: usize) -> Range<usize> {
+        let mut line_start = start;
+
+        while line_start > 0 && (self.bytes[line_start - 1] == b' ' || self.bytes[line_start - 1] == b'\t') {
+            line_start -= 1;
+        }
+
+        let own_line = line_start == 0 || self.bytes[line_start - 1] == b'\n';
+        let mut end = self.line_end(end);
+
+        if end == self.line_end(end) {
+            while self.bytes.get(end).is_some_and(|byte| *byte == b' ' || *byte == b'\t') {
+                end += 1;
+            }
+        }
+
+        if !own_line {
+            return start..end;
+        }
+
+        if self.text[end..].starts_with("\r\n") {
+            end += 2;
+        } else if self.text[end..].starts_with('\n') {
+            end += 1;
+        }
+
+        line_start..end
+    }
+
+    /// `value` as pretty JSON indented with the unit of the document, continuing lines at `indent`.
+    fn serialize(&self, value: &Value, indent: &str) -> String {
+        let mut bytes = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(self.unit.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut bytes, formatter);
+
+        if serde::Serialize::serialize(value, &mut serializer).is_err() {
+            return value.to_string();
+        }
+
+        String::from_utf8_lossy(&bytes).replace('\n', &format!("{}{indent}", self.newline))
+    }
+
+    fn entry_text(&self, key: Option<&str>, value: &Value, indent: &str, inline: bool) -> String {
+        let value = if inline {
+            value.to_string()
+        } else {
+            self.serialize(value, indent)
+        };
+
+        match key {
+            Some(key) => format!("{}: {value}", Value::String(key.to_string())),
+            None => value,
+        }
+    }
+
+    fn replace(&mut self, node: &Node, value: &Value) {
+        let indent = self.line_indent(node.span.start);
+        let text = self.serialize(value, indent);
+
+        self.edits.push((node.span.clone(), text));
+    }
+
+    fn update(&mut self, node: &Node, value: &Value) {
+        match (&node.kind, value) {
+            (Kind::Object(entries), Value::Object(object)) if !entries.is_empty() => {
+                self.update_object(node, entries, object)
+            },
+            (Kind::Array(entries), Value::Array(array)) if !entries.is_empty() => {
+                self.update_array(node, entries, array)
+            },
+            _ => {
+                if node_value(self.text, node) != *value {
+                    self.replace(node, value);
+                }
+            },
+        }
+    }
+
+    fn update_object(&mut self, node: &Node, entries: &[Entry], object: &Map<String, Value>) {
+        let key = |entry: &'_ Entry| entry.key.as_ref().map(|(key, _)| key.clone()).unwrap_or_default();
+        let keys: Vec<String> = entries.iter().map(key).collect();
+        // A key that takes the place of one that is gone is a rename, which keeps the entry where it is.
+        let renames: Vec<(usize, &String)> = object
+            .keys()
+            .enumerate()
+            .filter(|(position, new_key)| {
+                !keys.contains(new_key) && keys.get(*position).is_some_and(|old_key| !object.contains_key(old_key))
+            })
+            .collect();
+        let mut removed = Vec::new();
+
+        for (position, entry) in entries.iter().enumerate() {
+            let new_key = renames
+                .iter()
+                .find(|(renamed, _)| *renamed == position)
+                .map(|(_, new_key)| *new_key);
+
+            match (new_key, object.get(&keys[position])) {
+                (Some(new_key), _) => {
+                    if let Some((_, span)) = &entry.key {
+                        self.edits
+                            .push((span.clone(), Value::String(new_key.clone()).to_string()));
+                    }
+                    self.update(&entry.value, &object[new_key]);
+                },
+                (None, Some(value)) => self.update(&entry.value, value),
+                (None, None) => removed.push(position),
+            }
+        }
+
+        self.remove(entries, &removed);
+
+        // New keys go after the closest key before them that the object already has.
+        let mut anchor: Option<usize> = None;
+        let mut pending: Vec<(&str, &Value)> = Vec::new();
+
+        for (new_key, value) in object {
+            let position = keys.iter().position(|key| key == new_key).or_else(|| {
+                renames
+                    .iter()
+                    .find(|(_, renamed)| *renamed == new_key)
+                    .map(|(position, _)| *position)
+            });
+
+            match position {
+                Some(position) => {
+                    self.insert(node, entries, anchor, &pending);
+                    pending.clear();
+                    anchor = Some(position);
+                },
+                None => pending.push((new_key, value)),
+            }
+        }
+
+        self.insert(node, entries, anchor, &pending);
+    }
+
+    fn update_array(&mut self, node: &Node, entries: &[Entry], array: &[Value]) {
+        let current: Vec<Value> = entries
+            .iter()
+            .map(|entry| node_value(self.text, &entry.value))
+            .collect();
+
+        if array.len() == current.len() {
+            for (entry, value) in entries.iter().zip(array) {
+                self.update(&entry.value, value);
+            }
+        } else if array.len() > current.len() && array[..current.len()] == current[..] {
+            let appended: Vec<(&str, &Value)> = array[current.len()..].iter().map(|value| ("", value)).collect();
+
+            self.insert(node, entries, Some(entries.len() - 1), &appended);
+        } else {
+            self.replace(node, &Value::Array(array.to_vec()));
+        }
+    }
+
+    /// Remove the entries at `positions`, along with their commas, or the comma of the entry that becomes the last.
+    fn remove(&mut self, entries: &[Entry], positions: &[usize]) {
+        for &position in positions {
+            let entry = &entries[position];
+
+            match entry.comma {
+                Some(comma) => self
+                    .edits
+                    .push((self.line_range(entry.start(), comma + 1), String::new())),
+                None => {
+                    self.edits
+                        .push((self.line_range(entry.start(), entry.end()), String::new()));
+
+                    let kept = (0..position).rev().find(|kept| !positions.contains(kept));
+
+                    if let Some(comma) = kept.and_then(|kept| entries[kept].comma) {
+                        self.edits.push((comma..comma + 1, String::new()));
+                    }
+                },
+            }
+        }
+    }
+
+    /// Insert entries after the one at `anchor`, or first when there is none. Keys are empty for array elements.
+    fn insert(&mut self, node: &Node, entries: &[Entry], anchor: Option<usize>, inserted: &[(&str, &Value)]) {
+        if inserted.is_empty() {
+            return;
+        }
+
+        let keyed = matches!(node.kind, Kind::Object(_));
+        let inline = !self.text[node.span.clone()].contains('\n');
+        let indent = entries.first().and_then(|entry| self.entry_indent(entry)).map_or_else(
+            || format!("{}{}", self.line_indent(node.span.start), self.unit),
+            str::to_string,
+        );
+        let texts: Vec<String> = inserted
+            .iter()
+            .map(|(key, value)| self.entry_text(keyed.then_some(*key), value, &indent, inline))
+            .collect();
+        let newline = self.newline;
+        let lines = |separator: &str| -> Vec<String> {
+            texts
+                .iter()
+                .map(|text| match inline {
+                    true => format!("{separator}{text}"),
+                    false => format!("{newline}{indent}{text}"),
+                })
+                .collect()
+        };
+
+        match anchor.map(|anchor| &entries[anchor]) {
+            Some(Entry { comma: Some(comma), .. }) => {
+                let at = if inline { comma + 1 } else { self.line_end(comma + 1) };
+                let text: String = lines(" ").into_iter().map(|line| format!("{line},")).collect();
+
+                self.edits.push((at..at, text));
+            },
+            Some(entry) => {
+                let at = if inline {
+                    entry.end()
+                } else {
+                    self.line_end(entry.end())
+                };
+
+                self.edits.push((entry.end()..entry.end(), ",".to_string()));
+                self.edits.push((at..at, lines(" ").join(",")));
+            },
+            None => {
+                let at = node.span.start + 1;
+                let text: String = lines(" ").into_iter().map(|line| format!("{line},")).collect();
+
+                self.edits.push((at..at, text));
+            },
+        }
+    }
+
+    /// The text with every edit made, nothing when two of them overlap.
+    fn apply(mut self) -> Option<String> {
+        // A stable sort keeps insertions at the same place in the order they were made.
+        self.edits.sort_by_key(|(range, _)| range.start);
+
+        let mut text = String::with_capacity(self.text.len());
+        let mut pos = 0;
+
+        for (range, replacement) in &self.edits {
+            if range.start < pos {
+                return None;
+            }
+
+            text.push_str(&self.text[pos..range.start]);
+            text.push_str(replacement);
+            pos = range.end;
+        }
+
+        text.push_str(&self.text[pos..]);
+
+        Some(text)
+    }
+}
+
+/// Rewrite the JSONC `text` to hold `value`, changing only what differs so that comments and formatting are kept.
+/// Changed keys of an object are renamed in place, new keys are added after their neighbours, and arrays that only
+/// grew are appended to. Nothing is returned when the text cannot be parsed or the edits would not give `value`, in
+/// which case it is up to the caller to write the value anew.
+pub fn update(text: &str, value: &Value) -> Option<String> {
+    let root = Parser::new(text).document()?;
+    let mut editor = Editor::new(text, &root);
+
+    editor.update(&root, value);
+
+    let updated = editor.apply()?;
+    let parsed: Value = serde_jsonc::from_str(&updated).ok()?;
+
+    (parsed == *value).then_some(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::update;
+    use serde_json::json;
+
+    #[test]
+    fn test_update_keeps_comments() {
+        let text = "{\n\t// The image of the container\n\t\"image\": \"debian\", // pinned later\n\t\"features\": {\n\t\t\"ghcr.io/devcontainers/features/node:1\": {} /* runtime */\n\t}\n}\n";
+
+        assert_eq!(
+            update(
+                text,
+                &json!({
+                    "image": "debian",
+                    "features": {
+                        "ghcr.io/devcontainers/features/node:1": {},
+                        "ghcr.io/devcontainers/features/go:1": { "version": "1.22" }
+                    }
+                })
+            )
+            .as_deref(),
+            Some("{\n\t// The image of the container\n\t\"image\": \"debian\", // pinned later\n\t\"features\": {\n\t\t\"ghcr.io/devcontainers/features/node:1\": {}, /* runtime */\n\t\t\"ghcr.io/devcontainers/features/go:1\": {\n\t\t\t\"version\": \"1.22\"\n\t\t}\n\t}\n}\n")
+        );
+        assert_eq!(
+            update(
+                text,
+                &json!({
+                    "name": "Node",
+                    "image": "debian",
+                    "features": { "ghcr.io/devcontainers/features/node:2": {} }
+                })
+            )
+            .as_deref(),
+            Some("{\n\t\"name\": \"Node\",\n\t// The image of the container\n\t\"image\": \"debian\", // pinned later\n\t\"features\": {\n\t\t\"ghcr.io/devcontainers/features/node:2\": {} /* runtime */\n\t}\n}\n")
+        );
+        assert_eq!(
+            update(text, &json!({ "image": "debian" })).as_deref(),
+            Some("{\n\t// The image of the container\n\t\"image\": \"debian\" // pinned later\n}\n")
+        );
+    }
+
+    #[test]
+    fn test_update_arrays_and_inline() {
+        let text = "{\n  \"extensions\": [\n    \"rust-lang.rust-analyzer\" // language server\n  ],\n  \"args\": { \"VARIANT\": \"bookworm\" }\n}";
+
+        assert_eq!(
+            update(
+                text,
+                &json!({
+                    "extensions": ["rust-lang.rust-analyzer", "tamasfe.even-better-toml"],
+                    "args": { "VARIANT": "bookworm", "USER": "vscode" }
+                })
+            )
+            .as_deref(),
+            Some("{\n  \"extensions\": [\n    \"rust-lang.rust-analyzer\", // language server\n    \"tamasfe.even-better-toml\"\n  ],\n  \"args\": { \"VARIANT\": \"bookworm\", \"USER\": \"vscode\" }\n}")
+        );
+        assert_eq!(
+            update("{}", &json!({ "image": "debian" })).as_deref(),
+            Some("{\n\t\"image\": \"debian\"\n}")
+        );
+        assert_eq!(update("{ /* unterminated", &json!({})), None);
+        assert_eq!(
+            update(text, &json!(["not", "an", "object"])).as_deref(),
+            Some("[\n  \"not\",\n  \"an\",\n  \"object\"\n]")
+        );
+    }
+}
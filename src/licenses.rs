@@ -0,0 +1,147 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use ascii_table::AsciiTable;
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::oci_ref::OciReference;
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::DevcontainerIndex;
+use crate::sbom::{self, Component, ComponentKind};
+use crate::search::CollectionCategory;
+use crate::theme;
+
+#[derive(Debug, Args)]
+pub struct LicensesArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Template the configuration was created from, to include its license as well.
+    #[arg(short, long, value_name = "OCI_REF")]
+    template_id: Option<OciReference>,
+
+    /// Exit with an error when a feature or template has no license information.
+    #[arg(long)]
+    fail_on_missing: bool,
+
+    /// Print tab separated values without borders or headers.
+    #[arg(long, visible_alias = "no-headers")]
+    plain: bool,
+}
+
+/// The license of one feature or template of the workspace.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct License {
+    pub collection: CollectionCategory,
+    /// The id, or the key of the `features` object for a feature that could not be read.
+    pub id: String,
+    pub version: Option<String>,
+    #[serde(rename = "licenseURL")]
+    pub license_url: Option<String>,
+}
+
+impl License {
+    fn from_component(component: Component) -> Option<Self> {
+        let collection = match component.kind {
+            ComponentKind::Feature => CollectionCategory::Features,
+            ComponentKind::Template => CollectionCategory::Templates,
+            ComponentKind::Image => return None,
+        };
+
+        Some(License {
+            collection,
+            id: component.name,
+            version: component.version,
+            license_url: component.license_url,
+        })
+    }
+}
+
+fn print_licenses(licenses: &[License], plain: bool) {
+    let rows = licenses.iter().map(|license| {
+        [
+            license.collection.to_string(),
+            license.id.clone(),
+            license.version.clone().unwrap_or_default(),
+            license.license_url.clone().unwrap_or_default(),
+        ]
+    });
+
+    if plain {
+        output::print_plain(rows);
+        return;
+    }
+
+    let theme = theme::current();
+    let missing = theme.warning.paint("missing");
+    let data: Vec<[String; 4]> = rows
+        .map(|[collection, id, version, license_url]| {
+            let license_url = if license_url.is_empty() {
+                missing.clone()
+            } else {
+                license_url
+            };
+
+            [collection, id, version, license_url]
+        })
+        .collect();
+    let mut table = AsciiTable::default();
+
+    table.column(0).set_header(theme.header.paint("Type"));
+    table.column(1).set_header(theme.header.paint("ID"));
+    table.column(2).set_header(theme.header.paint("Version"));
+    table.column(3).set_header(theme.header.paint("License"));
+    table.print(data);
+}
+
+pub async fn licenses(
+    index: &DevcontainerIndex,
+    LicensesArgs {
+        workspace_folder,
+        template_id,
+        fail_on_missing,
+        plain,
+    }: LicensesArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("licenses");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let licenses: Vec<License> = sbom::components(index, &workspace, template_id.as_ref())
+        .await?
+        .into_iter()
+        .filter_map(License::from_component)
+        .collect();
+    let missing: Vec<&str> = licenses
+        .iter()
+        .filter(|license| license.license_url.is_none())
+        .map(|license| license.id.as_str())
+        .collect();
+
+    match format {
+        OutputFormat::Json => output::print_json(Kind::Licenses, &licenses)?,
+        OutputFormat::Text => print_licenses(&licenses, plain),
+    }
+
+    if !missing.is_empty() {
+        let message = format!("No license information for {}", missing.join(", "));
+
+        if !fail_on_missing {
+            log::warn!("{message}");
+        } else {
+            Err(output::failed(
+                format,
+                io::Error::new(io::ErrorKind::InvalidData, message),
+            ))?
+        }
+    }
+
+    log::debug!("licenses: done");
+
+    Ok(())
+}
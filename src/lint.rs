@@ -0,0 +1,380 @@
+use std::env;
+use std::fmt::{self, Display};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::{Args, ValueEnum};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::DevcontainerIndex;
+use crate::theme;
+use crate::workspace::{self, FeatureSource};
+
+/// Lifecycle hooks of devcontainer.json, which may each be a string, an array, or an object of either.
+const LIFECYCLE_HOOKS: [&str; 6] = [
+    "initializeCommand",
+    "onCreateCommand",
+    "updateContentCommand",
+    "postCreateCommand",
+    "postStartCommand",
+    "postAttachCommand",
+];
+
+#[derive(Debug, Args)]
+pub struct LintArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Silence a rule, in addition to those allowed by the configuration. May be repeated.
+    #[arg(short, long, value_name = "RULE")]
+    allow: Vec<Rule>,
+}
+
+/// The `[lint]` section of `config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LintConfig {
+    /// Rules that never report a finding.
+    pub allow: Vec<Rule>,
+}
+
+/// An opinion on how a devcontainer.json is best written, beyond what makes it valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// A feature that its collection or the index marks as deprecated.
+    DeprecatedFeature,
+    /// A feature referenced without a version, or with `latest`.
+    UnpinnedFeature,
+    /// A privileged container, asked for by `privileged` or by a feature. Allow it once the privilege is justified.
+    Privileged,
+    /// The same feature under more than one key.
+    DuplicateFeature,
+    /// No `name` to tell the dev container apart.
+    MissingName,
+    /// Lifecycle hooks written as strings along with others written as arrays.
+    MixedHookForms,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DeprecatedFeature => write!(f, "deprecated-feature"),
+            Self::UnpinnedFeature => write!(f, "unpinned-feature"),
+            Self::Privileged => write!(f, "privileged"),
+            Self::DuplicateFeature => write!(f, "duplicate-feature"),
+            Self::MissingName => write!(f, "missing-name"),
+            Self::MixedHookForms => write!(f, "mixed-hook-forms"),
+        }
+    }
+}
+
+/// One finding of a lint rule.
+#[derive(Clone, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LintFinding {
+    pub rule: Rule,
+    /// The top-level property at fault, missing when it concerns the whole file.
+    pub property: Option<String>,
+    pub message: String,
+}
+
+impl LintFinding {
+    fn new(rule: Rule, property: Option<&str>, message: impl Into<String>) -> Self {
+        LintFinding {
+            rule,
+            property: property.map(str::to_string),
+            message: message.into(),
+        }
+    }
+}
+
+/// The findings of one devcontainer.json, empty when it follows every rule.
+#[derive(Clone, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LintReport {
+    pub file: PathBuf,
+    pub findings: Vec<LintFinding>,
+}
+
+fn lint_features(config: &Map<String, Value>, index: &DevcontainerIndex, findings: &mut Vec<LintFinding>) {
+    let Some(Value::Object(features)) = config.get("features") else {
+        return;
+    };
+
+    let mut seen: Vec<(String, &str)> = Vec::new();
+
+    for key in features.keys() {
+        // Folders and tarballs have no version or id to go by, and invalid keys are for validate to report.
+        let Ok(FeatureSource::Registry(reference)) = FeatureSource::parse(key, Path::new("")) else {
+            continue;
+        };
+        let id = reference.id();
+
+        if index.is_deprecated(&id) {
+            findings.push(LintFinding::new(
+                Rule::DeprecatedFeature,
+                Some("features"),
+                format!("{id} is deprecated"),
+            ));
+        }
+
+        if !reference.is_local() && reference.tag_name() == "latest" {
+            findings.push(LintFinding::new(
+                Rule::UnpinnedFeature,
+                Some("features"),
+                format!("{key} is not pinned to a version"),
+            ));
+        }
+
+        if index
+            .get_feature(&id)
+            .is_some_and(|feature| feature.privileged == Some(true))
+        {
+            findings.push(LintFinding::new(
+                Rule::Privileged,
+                Some("features"),
+                format!("{id} runs the container privileged"),
+            ));
+        }
+
+        match seen.iter().find(|(seen_id, _)| *seen_id == id) {
+            Some((_, first)) => findings.push(LintFinding::new(
+                Rule::DuplicateFeature,
+                Some("features"),
+                format!("{key} is the same feature as {first}"),
+            )),
+            None => seen.push((id, key)),
+        }
+    }
+}
+
+/// Whether each hook is written as a string or an array, including the entries of the object form.
+fn hook_forms(config: &Map<String, Value>) -> (Vec<&str>, Vec<&str>) {
+    let mut strings = Vec::new();
+    let mut arrays = Vec::new();
+
+    for hook in LIFECYCLE_HOOKS {
+        let commands: Vec<&Value> = match config.get(hook) {
+            Some(Value::Object(commands)) => commands.values().collect(),
+            Some(command) => vec![command],
+            None => continue,
+        };
+
+        if commands.iter().any(|command| command.is_string()) {
+            strings.push(hook);
+        }
+
+        if commands.iter().any(|command| command.is_array()) {
+            arrays.push(hook);
+        }
+    }
+
+    (strings, arrays)
+}
+
+/// Lint a parsed devcontainer.json, leaving out the rules in `allow`.
+pub fn lint_config(config: &Value, index: &DevcontainerIndex, allow: &[Rule]) -> Vec<LintFinding> {
+    let Value::Object(config) = config else {
+        return Vec::new();
+    };
+
+    let mut findings = Vec::new();
+
+    if config
+        .get("name")
+        .and_then(Value::as_str)
+        .is_none_or(|name| name.trim().is_empty())
+    {
+        findings.push(LintFinding::new(
+            Rule::MissingName,
+            Some("name"),
+            "Name the dev container to tell it apart",
+        ));
+    }
+
+    if config.get("privileged").and_then(Value::as_bool) == Some(true) {
+        findings.push(LintFinding::new(
+            Rule::Privileged,
+            Some("privileged"),
+            "The container runs privileged",
+        ));
+    }
+
+    lint_features(config, index, &mut findings);
+
+    let (strings, arrays) = hook_forms(config);
+
+    if !strings.is_empty() && !arrays.is_empty() {
+        findings.push(LintFinding::new(
+            Rule::MixedHookForms,
+            None,
+            format!(
+                "Lifecycle hooks mix strings ({}) with arrays ({})",
+                strings.join(", "),
+                arrays.join(", ")
+            ),
+        ));
+    }
+
+    findings.retain(|finding| !allow.contains(&finding.rule));
+    findings
+}
+
+fn lint_workspace(workspace: &Path, index: &DevcontainerIndex, allow: &[Rule]) -> anyhow::Result<Vec<LintReport>> {
+    let mut reports = Vec::new();
+
+    for file in workspace::devcontainer_files(workspace)? {
+        let config = workspace::read_config(&file)?;
+        let findings = lint_config(&config, index, allow);
+        let file = file.strip_prefix(workspace).map(Path::to_path_buf).unwrap_or(file);
+
+        reports.push(LintReport { file, findings });
+    }
+
+    Ok(reports)
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+fn print_reports(reports: &[LintReport], format: OutputFormat) -> serde_json::Result<()> {
+    if format == OutputFormat::Json {
+        return output::print_json(Kind::LintFindings, reports);
+    }
+
+    let theme = theme::current();
+
+    for report in reports {
+        if report.findings.is_empty() {
+            println!("{}: ok", report.file.display());
+            continue;
+        }
+
+        println!("{}", theme.header.paint(report.file.display()));
+
+        for finding in &report.findings {
+            let rule = theme.warning.paint(finding.rule);
+
+            match &finding.property {
+                Some(property) => println!("  {rule}: {property}: {}", finding.message),
+                None => println!("  {rule}: {}", finding.message),
+            }
+        }
+    }
+
+    let findings = reports.iter().map(|report| report.findings.len()).sum();
+
+    println!("{}", plural(findings, "finding"));
+
+    Ok(())
+}
+
+/// Check the devcontainer.json files of the workspace against opinionated rules of best practice.
+pub fn lint(
+    index: &DevcontainerIndex,
+    LintArgs {
+        workspace_folder,
+        allow,
+    }: LintArgs,
+    format: OutputFormat,
+    config: &LintConfig,
+) -> anyhow::Result<()> {
+    log::debug!("lint");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let allow: Vec<Rule> = config.allow.iter().chain(&allow).copied().collect();
+    let reports = lint_workspace(&workspace, index, &allow)?;
+
+    if reports.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No devcontainer.json found in {}", workspace.display()),
+        ))?
+    }
+
+    print_reports(&reports, format)?;
+
+    let findings: usize = reports.iter().map(|report| report.findings.len()).sum();
+    if findings > 0 {
+        Err(output::failed(
+            format,
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Lint failed with {}", plural(findings, "finding")),
+            ),
+        ))?
+    }
+
+    log::debug!("lint: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lint_config, Rule};
+    use crate::registry::DevcontainerIndex;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint_config() {
+        let index = DevcontainerIndex::default();
+        let rules = |config: serde_json::Value, allow: &[Rule]| -> Vec<Rule> {
+            lint_config(&config, &index, allow)
+                .into_iter()
+                .map(|finding| finding.rule)
+                .collect()
+        };
+
+        assert!(rules(
+            json!({
+                "name": "Rust",
+                "image": "debian",
+                "features": { "ghcr.io/devcontainers/features/node:1": {}, "./local": {} },
+                "postCreateCommand": "cargo build",
+                "postStartCommand": { "server": "cargo run" },
+            }),
+            &[]
+        )
+        .is_empty());
+        assert_eq!(
+            rules(
+                json!({
+                    "image": "debian",
+                    "privileged": true,
+                    "features": {
+                        "ghcr.io/devcontainers/features/node": {},
+                        "ghcr.io/devcontainers/features/node:1": {},
+                    },
+                    "onCreateCommand": "npm ci",
+                    "postStartCommand": { "server": ["npm", "start"] },
+                }),
+                &[]
+            ),
+            [
+                Rule::MissingName,
+                Rule::Privileged,
+                Rule::UnpinnedFeature,
+                Rule::DuplicateFeature,
+                Rule::MixedHookForms,
+            ]
+        );
+        assert_eq!(
+            rules(
+                json!({ "privileged": true, "features": { "ghcr.io/devcontainers/features/node:latest": {} } }),
+                &[Rule::MissingName, Rule::Privileged]
+            ),
+            [Rule::UnpinnedFeature]
+        );
+    }
+}
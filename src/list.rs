@@ -1,36 +1,91 @@
+use std::io;
+
 use ascii_table::{Align, AsciiTable};
 use clap::Args;
+use schemars::JsonSchema;
+use serde::Serialize;
 
+use crate::output::{self, Kind, OutputFormat};
+use crate::published;
 use crate::registry::{Collection, DevcontainerIndex};
-use crate::search;
+use crate::search::{self, SearchResult, SortOrder};
+use crate::theme;
 
 #[derive(Debug, Args)]
 pub struct ListArgs {
     /// Display a given collection, including features and templates.
     #[arg(short = 'C', long, value_name = "OCI_REF")]
     collection_id: Option<String>,
+
+    /// Print tab separated values without borders or headers.
+    #[arg(long, visible_alias = "no-headers")]
+    plain: bool,
+
+    /// Look up when the latest version of each entry of the collection was published, from its registry manifest. Only
+    /// the table shows it, use `search --published` for JSON.
+    #[arg(long, requires = "collection_id")]
+    published: bool,
+
+    /// Order of the entries of the collection.
+    #[arg(long, value_name = "ORDER", default_value = "index", requires = "collection_id")]
+    sort: SortOrder,
+}
+
+/// The features and then the templates of a collection.
+fn collection_entries(collection: &Collection) -> Vec<SearchResult> {
+    let features = collection.features.iter().map(search::SearchResult::from);
+    let templates = collection.templates.iter().map(search::SearchResult::from);
+
+    features.chain(templates).collect()
+}
+
+/// Only the features and templates, with full ids, so each line can be passed on to another command.
+fn plain_templates_and_features(entries: Vec<SearchResult>, published: bool) {
+    log::debug!("plain_templates_and_features");
+
+    let rows = entries.into_iter().map(|r| {
+        let description = r
+            .description
+            .as_ref()
+            .and_then(|d| d.lines().next())
+            .unwrap_or_default()
+            .to_string();
+        let mut row = vec![r.collection.to_string(), r.id, r.name, description];
+
+        if published {
+            row.push(r.published.unwrap_or_default());
+        }
+
+        row
+    });
+
+    output::print_plain(rows);
 }
 
-fn collection_templates_and_features(oci_reference: &str, collection: &Collection) {
+fn collection_templates_and_features(
+    oci_reference: &str,
+    collection: &Collection,
+    entries: Vec<SearchResult>,
+    published: bool,
+) {
     log::debug!("collection_templates_and_features");
 
     let source_information = &collection.source_information;
-
-    println!("Name:          {}", &source_information.name);
-    println!("Maintainer:    {}", &source_information.maintainer);
-    println!("Contact:       {}", &source_information.contact);
-    println!("Repository:    {}", &source_information.repository);
-    println!("OCI Reference: {}", &source_information.oci_reference);
-
-    let count = collection.features.len() + collection.templates.len();
-
-    if count > 0 {
-        let search_results = {
-            let features = collection.features.iter().map(search::SearchResult::from);
-            let templates = collection.templates.iter().map(search::SearchResult::from);
-            features.chain(templates)
-        };
-        let data: Vec<[String; 5]> = search_results
+    let header = &theme::current().header;
+
+    println!("{}          {}", header.paint("Name:"), &source_information.name);
+    println!("{}    {}", header.paint("Maintainer:"), &source_information.maintainer);
+    println!("{}       {}", header.paint("Contact:"), &source_information.contact);
+    println!("{}    {}", header.paint("Repository:"), &source_information.repository);
+    println!(
+        "{} {}",
+        header.paint("OCI Reference:"),
+        &source_information.oci_reference
+    );
+
+    if !entries.is_empty() {
+        let data: Vec<Vec<String>> = entries
+            .into_iter()
             .enumerate()
             .map(|(i, r)| {
                 let description = r
@@ -38,36 +93,77 @@ fn collection_templates_and_features(oci_reference: &str, collection: &Collectio
                     .as_ref()
                     .and_then(|d| d.lines().next())
                     .unwrap_or_default();
-                [
+                let mut row = vec![
                     (i + 1).to_string(),
                     r.collection.to_string(),
                     r.id.replace(oci_reference, "~"),
                     r.name.to_string(),
                     description.to_string(),
-                ]
+                ];
+
+                if published {
+                    row.push(published::display_date(r.published.as_deref()));
+                }
+
+                row
             })
             .collect();
         let mut table = ascii_table::AsciiTable::default();
 
         table.column(0).set_align(ascii_table::Align::Right);
-        table.column(1).set_header("Type");
-        table.column(2).set_header("OCI Reference");
-        table.column(3).set_header("Name").set_max_width(40);
-        table.column(4).set_header("Description").set_max_width(75);
+        table.column(1).set_header(header.paint("Type"));
+        table.column(2).set_header(header.paint("OCI Reference"));
+        table.column(3).set_header(header.paint("Name")).set_max_width(40);
+        table
+            .column(4)
+            .set_header(header.paint("Description"))
+            .set_max_width(75);
+
+        if published {
+            table.column(5).set_header(header.paint("Published"));
+        }
 
         table.print(data);
     }
 }
 
-fn overview_collections(index: &DevcontainerIndex) {
+/// One entry of the collections overview.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionSummary<'c> {
+    pub name: &'c str,
+    pub oci_reference: &'c str,
+    pub features: usize,
+    pub templates: usize,
+}
+
+impl<'c> From<&'c Collection> for CollectionSummary<'c> {
+    fn from(collection: &'c Collection) -> Self {
+        CollectionSummary {
+            name: &collection.source_information.name,
+            oci_reference: &collection.source_information.oci_reference,
+            features: collection.features.len(),
+            templates: collection.templates.len(),
+        }
+    }
+}
+
+fn overview_collections(index: &DevcontainerIndex, plain: bool) {
     log::debug!("overview_collections");
 
     let mut table = AsciiTable::default();
-
-    table.column(0).set_header("Name");
-    table.column(1).set_header("OCI Reference");
-    table.column(2).set_header("Features").set_align(Align::Right);
-    table.column(3).set_header("Templates").set_align(Align::Right);
+    let header = &theme::current().header;
+
+    table.column(0).set_header(header.paint("Name"));
+    table.column(1).set_header(header.paint("OCI Reference"));
+    table
+        .column(2)
+        .set_header(header.paint("Features"))
+        .set_align(Align::Right);
+    table
+        .column(3)
+        .set_header(header.paint("Templates"))
+        .set_align(Align::Right);
 
     let result: Vec<[String; 4]> = index
         .collections()
@@ -82,19 +178,58 @@ fn overview_collections(index: &DevcontainerIndex) {
         })
         .collect();
 
-    table.print(result);
+    if plain {
+        output::print_plain(result);
+    } else {
+        table.print(result);
+    }
 }
 
-pub fn list(index: &DevcontainerIndex, ListArgs { collection_id }: ListArgs) {
+pub async fn list(
+    index: &DevcontainerIndex,
+    ListArgs {
+        collection_id,
+        plain,
+        published,
+        sort,
+    }: ListArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
     log::debug!("list");
 
-    match collection_id {
-        Some(oci_reference) => match index.get_collection(&oci_reference) {
-            Some(collection) => collection_templates_and_features(&oci_reference, collection),
+    match (collection_id, format) {
+        (Some(oci_reference), OutputFormat::Json) => match index.get_collection(&oci_reference) {
+            Some(collection) => output::print_json(Kind::Collection, collection)?,
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No collection found by the given OCI Reference: {oci_reference}"),
+            ))?,
+        },
+        (Some(oci_reference), OutputFormat::Text) => match index.get_collection(&oci_reference) {
+            Some(collection) => {
+                let published = published || matches!(sort, SortOrder::Recent);
+                let mut entries = collection_entries(collection);
+
+                if published {
+                    SearchResult::add_published_times(&mut entries, &sort).await;
+                }
+
+                if plain {
+                    plain_templates_and_features(entries, published);
+                } else {
+                    collection_templates_and_features(&oci_reference, collection, entries, published);
+                }
+            },
             None => println!("No collection found by the given OCI Reference: {oci_reference}"),
         },
-        None => overview_collections(index),
+        (None, OutputFormat::Json) => {
+            let summaries: Vec<CollectionSummary> = index.collections().iter().map(CollectionSummary::from).collect();
+            output::print_json(Kind::Collections, &summaries)?;
+        },
+        (None, OutputFormat::Text) => overview_collections(index, plain),
     }
 
     log::debug!("list: done");
+
+    Ok(())
 }
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write;
+use crate::oci_ref::OciReference;
+use crate::registry;
+
+/// Name of the lockfile at the top of a workspace.
+pub const LOCKFILE: &str = "tyedev.lock";
+
+/// The exact version and manifest digest of every template and feature pulled for a workspace, by the reference as
+/// written, so that later runs can insist on the same artifacts.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Lockfile {
+    pub templates: BTreeMap<String, LockedArtifact>,
+    pub features: BTreeMap<String, LockedArtifact>,
+}
+
+/// What a reference resolved to when it was locked.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LockedArtifact {
+    pub version: String,
+    /// Digest of the manifest, such as `sha256:…`.
+    pub digest: String,
+}
+
+impl Lockfile {
+    fn get(&self, reference: &str) -> Option<&LockedArtifact> {
+        self.templates.get(reference).or_else(|| self.features.get(reference))
+    }
+
+    /// Refuse a reference the lock does not have. Artifacts read from disk are never locked.
+    pub fn require(&self, reference: &OciReference) -> io::Result<()> {
+        if reference.is_local() || self.get(&reference.to_string()).is_some() {
+            return Ok(());
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{reference} is not in {LOCKFILE}, run without --locked to resolve it"),
+        ))
+    }
+
+    /// Refuse artifacts that are missing from the lock or resolved to anything other than what it holds.
+    pub fn verify(&self, pulled: &Lockfile) -> io::Result<()> {
+        let mismatches: Vec<String> = (pulled.templates.iter())
+            .chain(&pulled.features)
+            .filter_map(|(reference, artifact)| match self.get(reference) {
+                None => Some(format!("{reference} is not locked")),
+                Some(locked) if locked.digest != artifact.digest => Some(format!(
+                    "{reference} resolves to {} ({}), locked at {} ({})",
+                    artifact.digest, artifact.version, locked.digest, locked.version
+                )),
+                Some(_) => None,
+            })
+            .collect();
+
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Resolved artifacts differ from {LOCKFILE}:\n  {}",
+                mismatches.join("\n  ")
+            ),
+        ))
+    }
+}
+
+/// The locked form of an artifact pulled from a registry, nothing for one read from disk. The digest is looked up
+/// unless it is already known.
+pub async fn locked_artifact(
+    reference: &OciReference,
+    version: &str,
+    digest: Option<&str>,
+) -> anyhow::Result<Option<LockedArtifact>> {
+    let OciReference::Registry(image) = reference else {
+        return Ok(None);
+    };
+    let digest = match digest {
+        Some(digest) => digest.to_string(),
+        None => registry::manifest_digest(image).await?,
+    };
+
+    Ok(Some(LockedArtifact {
+        version: version.to_string(),
+        digest,
+    }))
+}
+
+fn lockfile_path(workspace: &Path) -> PathBuf {
+    workspace.join(LOCKFILE)
+}
+
+/// Read the lockfile of the workspace, nothing when it has none.
+pub fn read_lockfile(workspace: &Path) -> anyhow::Result<Option<Lockfile>> {
+    let filename = lockfile_path(workspace);
+
+    log::debug!("read_lockfile: {}", filename.display());
+
+    let contents = match fs::read_to_string(&filename) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => Err(error)?,
+    };
+
+    let lockfile = toml::from_str(&contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid lockfile {}: {error}", filename.display()),
+        )
+    })?;
+
+    Ok(Some(lockfile))
+}
+
+/// The lockfile of the workspace, an error naming `--locked` when it has none.
+pub fn require_lockfile(workspace: &Path) -> anyhow::Result<Lockfile> {
+    read_lockfile(workspace)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("--locked needs a {LOCKFILE} in {}", workspace.display()),
+        )
+        .into()
+    })
+}
+
+/// Write the lockfile of the workspace, replacing the one there.
+pub fn write_lockfile(workspace: &Path, lockfile: &Lockfile) -> anyhow::Result<()> {
+    let filename = lockfile_path(workspace);
+
+    log::debug!("write_lockfile: {}", filename.display());
+
+    let contents = format!(
+        "# Written by {}, versions and digests of the artifacts pulled for this workspace.\n{}",
+        env!("CARGO_PKG_NAME"),
+        toml::to_string(lockfile)?
+    );
+    atomic_write::write(&filename, contents)?;
+    log::info!("Wrote {}", filename.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LockedArtifact, Lockfile};
+
+    #[test]
+    fn test_lockfile() -> anyhow::Result<()> {
+        let artifact = |version: &str, digest: &str| LockedArtifact {
+            version: version.to_string(),
+            digest: digest.to_string(),
+        };
+        let mut lockfile = Lockfile::default();
+        lockfile.templates.insert(
+            "ghcr.io/devcontainers/templates/rust:5".into(),
+            artifact("5.0.1", "sha256:aaa"),
+        );
+        lockfile.features.insert(
+            "ghcr.io/devcontainers/features/node:1".into(),
+            artifact("1.6.0", "sha256:bbb"),
+        );
+
+        let contents = toml::to_string(&lockfile)?;
+        assert_eq!(toml::from_str::<Lockfile>(&contents)?, lockfile);
+
+        assert!(lockfile
+            .require(&"ghcr.io/devcontainers/features/node:1".parse()?)
+            .is_ok());
+        assert!(lockfile
+            .require(&"ghcr.io/devcontainers/features/go:1".parse()?)
+            .is_err());
+        assert!(lockfile.require(&"file:///tmp/feature.tar".parse()?).is_ok());
+
+        let mut pulled = Lockfile::default();
+        pulled.features.insert(
+            "ghcr.io/devcontainers/features/node:1".into(),
+            artifact("1.6.0", "sha256:bbb"),
+        );
+        assert!(lockfile.verify(&pulled).is_ok());
+
+        pulled.features.insert(
+            "ghcr.io/devcontainers/features/node:1".into(),
+            artifact("1.6.1", "sha256:ccc"),
+        );
+        assert!(lockfile.verify(&pulled).is_err());
+
+        Ok(())
+    }
+}
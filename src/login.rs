@@ -0,0 +1,158 @@
+use std::io::{self, BufRead};
+use std::path::Path;
+
+use clap::Args;
+
+use crate::auth::TOKEN_USERNAME;
+use crate::credentials::{self, Credentials, Store};
+use crate::prompt::{self, DefaultPrompter, InteractivePrompter, Prompter};
+
+#[derive(Debug, Args)]
+pub struct LoginArgs {
+    /// Registry host, such as `ghcr.io`.
+    #[arg(value_name = "REGISTRY")]
+    registry: String,
+
+    /// User name, prompted for when omitted.
+    #[arg(short, long)]
+    username: Option<String>,
+
+    /// Read the token or password from standard input instead of prompting.
+    #[arg(long)]
+    password_stdin: bool,
+
+    /// Save the credentials in a plaintext file of the data directory instead of the system keyring.
+    #[arg(long)]
+    file_store: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct LogoutArgs {
+    /// Registry host, such as `ghcr.io`.
+    #[arg(value_name = "REGISTRY")]
+    registry: String,
+}
+
+fn read_secret(registry: &str, password_stdin: bool, prompter: &mut dyn Prompter) -> anyhow::Result<String> {
+    let secret = if password_stdin {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        line.trim_end_matches(['\r', '\n']).to_string()
+    } else {
+        prompter.password(&format!("Token or password for {registry}:"))?
+    };
+
+    if secret.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The token or password is empty",
+        ))?
+    }
+
+    Ok(secret)
+}
+
+/// Store credentials for a registry, used by every later pull from it.
+pub fn login(data_dir: &Path, args: LoginArgs) -> anyhow::Result<()> {
+    log::debug!("login");
+
+    // With --password-stdin the user name is not asked for either, stdin holds the secret.
+    let mut prompter: Box<dyn Prompter> = if args.password_stdin {
+        Box::new(DefaultPrompter)
+    } else if prompt::is_interactive() {
+        Box::new(InteractivePrompter)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Standard input is not a terminal, pass --password-stdin to read the token from it",
+        ))?
+    };
+
+    login_with_prompter(data_dir, args, prompter.as_mut())
+}
+
+fn login_with_prompter(
+    data_dir: &Path,
+    LoginArgs {
+        registry,
+        username,
+        password_stdin,
+        file_store,
+    }: LoginArgs,
+    prompter: &mut dyn Prompter,
+) -> anyhow::Result<()> {
+    let username = match username {
+        Some(username) => username,
+        None => prompter.text(&format!("User name for {registry}:"), Some(TOKEN_USERNAME), None)?,
+    };
+    let secret = read_secret(&registry, password_stdin, prompter)?;
+    let store = if file_store { Store::File } else { Store::Keyring };
+    let file = credentials::credentials_file(data_dir);
+
+    credentials::store(&file, &registry, &Credentials { username, secret }, store)?;
+
+    match store {
+        Store::Keyring => eprintln!("Saved the credentials of {registry} in the system keyring."),
+        Store::File => eprintln!("Saved the credentials of {registry} in {}.", file.display()),
+    }
+
+    log::debug!("login: done");
+
+    Ok(())
+}
+
+/// Forget the stored credentials of a registry.
+pub fn logout(data_dir: &Path, LogoutArgs { registry }: LogoutArgs) -> anyhow::Result<()> {
+    log::debug!("logout");
+
+    let removed = credentials::remove(&credentials::credentials_file(data_dir), &registry)?;
+
+    if removed.is_empty() {
+        eprintln!("No credentials stored for {registry}.");
+    } else {
+        eprintln!("Removed the credentials of {registry}.");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{login_with_prompter, LoginArgs};
+    use crate::credentials;
+    use crate::prompt::{Answer, ScriptedAnswer, ScriptedPrompter};
+    use std::{env, fs};
+
+    #[test]
+    fn test_login_prompts() -> anyhow::Result<()> {
+        let data_dir = env::temp_dir().join(format!("tyedev-test-login-{}", std::process::id()));
+        let mut prompter = ScriptedPrompter::new(vec![
+            ScriptedAnswer {
+                message: "User name for ghcr.io:".into(),
+                answer: Answer::String("me".into()),
+            },
+            ScriptedAnswer {
+                message: "Token or password for ghcr.io:".into(),
+                answer: Answer::String("hunter2".into()),
+            },
+        ]);
+        let args = LoginArgs {
+            registry: "ghcr.io".into(),
+            username: None,
+            password_stdin: false,
+            file_store: true,
+        };
+
+        login_with_prompter(&data_dir, args, &mut prompter)?;
+
+        let stored = credentials::load(&credentials::credentials_file(&data_dir), "ghcr.io")?;
+        assert_eq!(
+            stored.map(|stored| (stored.username, stored.secret)),
+            Some(("me".to_string(), "hunter2".to_string()))
+        );
+
+        fs::remove_dir_all(&data_dir)?;
+
+        Ok(())
+    }
+}
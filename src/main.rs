@@ -1,23 +1,78 @@
 use std::env;
+use std::ffi::OsString;
 use std::fs;
 use std::io;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
+#[cfg(feature = "completions")]
+use clap::CommandFactory;
 use clap::{Parser, Subcommand};
 use clap_verbosity_flag::{Verbosity, WarnLevel};
-#[cfg(feature = "completions")]
-use ::{
-    clap::CommandFactory,
-    clap_complete::{generate, shells::Shell},
-};
 
+mod add;
+mod answers;
+mod archive;
+mod atomic_write;
+mod audit;
+mod auth;
+mod batch;
+#[cfg(feature = "browse")]
+mod browse;
+mod cache;
+mod catalog;
+mod clean;
+#[cfg(feature = "completions")]
+mod completions;
+mod config;
+mod credentials;
+mod diff;
+mod download;
+mod github;
+mod graph;
+mod impact;
+mod index;
 mod init;
 mod inspect;
+mod jsonc;
+mod licenses;
+mod lint;
 mod list;
+mod lock;
+mod login;
+mod merge;
+mod messages;
+mod migrate;
+mod oci_layout;
 mod oci_ref;
+mod outdated;
+mod output;
+mod platform;
+mod plugin;
+mod policy;
+mod prompt;
+mod provenance;
+mod published;
+mod regenerate;
 mod registry;
+mod resolve;
+mod sbom;
+mod schema;
 mod search;
+mod self_update;
+mod show;
+mod stats;
+mod status;
+mod suggest;
+mod theme;
+mod timestamp;
+mod update_check;
+mod upgrade;
+mod validate;
+mod verify;
+mod vscode;
+mod workspace;
 
 /// Easily manage devcontainer configuration files.
 #[derive(Parser, Debug)]
@@ -27,6 +82,18 @@ struct Args {
     #[arg(short, long)]
     pull_index: bool,
 
+    /// Skip the daily check for a newer release
+    #[arg(long, env = update_check::OPT_OUT_VARIABLE)]
+    no_update_check: bool,
+
+    /// Pull templates and features again instead of reusing the archive cache
+    #[arg(long, env = cache::NO_CACHE_VARIABLE)]
+    no_cache: bool,
+
+    /// Format of list, search, inspect, show, resolve, stats, status, validate, lint, verify, outdated, audit, licenses, init, and index diff results, as well as errors
+    #[arg(long, value_name = "FORMAT", default_value_t, env = output::OUTPUT_VARIABLE)]
+    output: output::OutputFormat,
+
     #[command(flatten)]
     verbose: Verbosity<WarnLevel>,
 
@@ -36,17 +103,71 @@ struct Args {
 
 #[derive(Subcommand, Debug)]
 enum Commands {
+    /// Copy the features of another devcontainer.json into the workspace configuration.
+    Add(add::AddArgs),
+    /// Scan the install scripts of the features used by a workspace for risky patterns.
+    Audit(audit::AuditArgs),
+    /// Explore the index interactively.
+    #[cfg(feature = "browse")]
+    Browse(browse::BrowseArgs),
+    /// Report disk usage of the data managed by tyedev, and remove selected parts of it.
+    Clean(clean::CleanArgs),
     /// Generate shell auto-complete configuration.
     #[cfg(feature = "completions")]
-    Completions { shell: Shell },
+    Completions(completions::CompletionsArgs),
+    /// Print how the devcontainer.json of a workspace differs from the defaults of its template.
+    Diff(diff::DiffArgs),
+    /// Render the index, or selected collections, as a Markdown or HTML catalog.
+    ExportCatalog(catalog::ExportCatalogArgs),
+    /// Print the order the features of a workspace install in, as text, DOT, or Mermaid.
+    Graph(graph::GraphArgs),
+    /// Inspect the pulled index of features & templates.
+    Index(index::IndexArgs),
     /// Create new devcontainer.
-    Init(init::InitArgs),
+    Init(Box<init::InitArgs>),
     /// Display details of a specific feature, template, or collection.
     Inspect(inspect::InspectArgs),
+    /// Summarize the licenses of the features and template used by a workspace.
+    Licenses(licenses::LicensesArgs),
+    /// Check the devcontainer.json files of a workspace against rules of best practice.
+    Lint(lint::LintArgs),
     /// Overview of collections.
     List(list::ListArgs),
+    /// Store credentials for a registry in the system keyring.
+    Login(login::LoginArgs),
+    /// Remove the stored credentials of a registry.
+    Logout(login::LogoutArgs),
+    /// Rewrite legacy properties and feature ids of devcontainer.json into the form of the specification.
+    Migrate(migrate::MigrateArgs),
+    /// Report a template of the workspace that has a newer version in the index, with the files that changed.
+    Outdated(outdated::OutdatedArgs),
+    /// Render the template of a workspace again with the choices recorded by init, optionally at a newer version.
+    Regenerate(regenerate::RegenerateArgs),
+    /// Print the full reference of a feature or template given by a short name.
+    Resolve(resolve::ResolveArgs),
+    /// Print a software bill of materials of the devcontainer configured in a workspace.
+    Sbom(sbom::SbomArgs),
+    /// Print the JSON Schema of the `--output json` results.
+    Schema(schema::SchemaArgs),
     /// Text search the `id`, `keywords`, and `description` fields of templates or features.
     Search(search::SearchArgs),
+    /// Replace this executable with the latest release.
+    SelfUpdate(self_update::SelfUpdateArgs),
+    /// Print the devcontainer.json of a workspace at a glance, the way inspect prints a feature or template.
+    Show(show::ShowArgs),
+    /// Print statistics of the index, such as top keywords and features per owner.
+    Stats(stats::StatsArgs),
+    /// List every devcontainer.json of a repository, with its template, features, and validation state.
+    Status(status::StatusArgs),
+    /// Bump the versions of outdated features in devcontainer.json.
+    Upgrade(upgrade::UpgradeArgs),
+    /// Check devcontainer.json files of a workspace against the specification and common mistakes.
+    Validate(validate::ValidateArgs),
+    /// Compare a published feature or template with its entry in the index.
+    Verify(verify::VerifyArgs),
+    /// Run a `tyedev-<name>` executable found on PATH.
+    #[command(external_subcommand)]
+    External(Vec<OsString>),
 }
 
 fn data_directory<P: AsRef<Path>>(namespace: P) -> io::Result<PathBuf> {
@@ -61,8 +182,62 @@ fn data_directory<P: AsRef<Path>>(namespace: P) -> io::Result<PathBuf> {
     }
 }
 
+async fn run(
+    command: Commands,
+    index_file: &Path,
+    format: output::OutputFormat,
+    config: &config::Config,
+) -> anyhow::Result<()> {
+    if !index_file.exists() {
+        // suggested user action
+        log::error!(
+            "Missing devcontainer-index.json.\n\n\tRun `{} --pull-index`.\n",
+            env!("CARGO_BIN_NAME")
+        );
+    }
+
+    let index = registry::read_devcontainer_index(index_file)?;
+
+    match command {
+        #[cfg(feature = "browse")]
+        Commands::Browse(args) => browse::browse(&index, args, config).await?,
+        #[cfg(feature = "completions")]
+        Commands::Completions(_) => unreachable!(),
+        Commands::Clean(_) => unreachable!(),
+        Commands::External(_) => unreachable!(),
+        Commands::Login(_) | Commands::Logout(_) => unreachable!(),
+        Commands::Schema(_) => unreachable!(),
+        Commands::SelfUpdate(_) => unreachable!(),
+        Commands::Add(args) => add::add(args).await?,
+        Commands::Audit(args) => audit::audit(args, format).await?,
+        Commands::Diff(args) => diff::diff(args).await?,
+        Commands::ExportCatalog(args) => catalog::export_catalog(&index, args)?,
+        Commands::Graph(args) => graph::graph(&index, args).await?,
+        Commands::Index(args) => index::index(&index, index_file, args, format).await?,
+        Commands::Init(args) => init::init(&index, *args, format, config).await?,
+        Commands::Inspect(args) => inspect::inspect(&index, args, format).await?,
+        Commands::Licenses(args) => licenses::licenses(&index, args, format).await?,
+        Commands::Lint(args) => lint::lint(&index, args, format, &config.lint)?,
+        Commands::List(args) => list::list(&index, args, format).await?,
+        Commands::Migrate(args) => migrate::migrate(&index, args)?,
+        Commands::Outdated(args) => outdated::outdated(&index, args, format).await?,
+        Commands::Regenerate(args) => regenerate::regenerate(&index, args, config).await?,
+        Commands::Resolve(args) => resolve::resolve(&index, args, format)?,
+        Commands::Sbom(args) => sbom::sbom(&index, args).await?,
+        Commands::Search(args) => search::search(&index, args, format).await?,
+        Commands::Show(args) => show::show(args, format)?,
+        Commands::Stats(args) => stats::stats(&index, args, format)?,
+        Commands::Status(args) => status::status(&index, args, format)?,
+        Commands::Upgrade(args) => upgrade::upgrade(&index, args).await?,
+        Commands::Validate(args) => validate::validate(&index, args, format).await?,
+        Commands::Verify(args) => verify::verify(&index, args, format).await?,
+    };
+
+    Ok(())
+}
+
 #[tokio::main]
-async fn main() -> Result<(), anyhow::Error> {
+async fn main() -> Result<ExitCode, anyhow::Error> {
     let args = Args::parse();
 
     env_logger::Builder::new()
@@ -70,16 +245,48 @@ async fn main() -> Result<(), anyhow::Error> {
         .format_timestamp_millis()
         .init();
 
+    let config = config::read_config()?;
+    theme::init(&config.theme);
+    messages::init(
+        &config.messages,
+        config::config_file().as_deref().and_then(Path::parent),
+    )?;
+
     const BIN_NAME: &str = env!("CARGO_BIN_NAME");
 
     #[cfg(feature = "completions")]
-    if let Some(Commands::Completions { shell }) = args.command {
-        generate(shell, &mut Args::command_for_update(), BIN_NAME, &mut io::stdout());
-        return Ok(());
+    if let Some(Commands::Completions(args)) = args.command {
+        completions::completions(args, Args::command_for_update())?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Commands::Schema(args)) = args.command {
+        schema::schema(args)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Commands::SelfUpdate(args)) = args.command {
+        self_update::self_update(args).await?;
+        return Ok(ExitCode::SUCCESS);
     }
 
     let data_dir = data_directory(BIN_NAME)?;
-    let index_file = data_dir.join("devcontainer-index.json");
+    let index_file = data_dir.join(registry::INDEX_FILENAME);
+
+    auth::init(&config.registries, &credentials::credentials_file(&data_dir));
+
+    match args.command {
+        Some(Commands::Clean(args)) => return clean::clean(&data_dir, args).map(|()| ExitCode::SUCCESS),
+        Some(Commands::Login(args)) => return login::login(&data_dir, args).map(|()| ExitCode::SUCCESS),
+        Some(Commands::Logout(args)) => return login::logout(&data_dir, args).map(|()| ExitCode::SUCCESS),
+        _ => {},
+    }
+
+    registry::init_client(&config.download);
+    cache::init(cache::ArchiveCache::new(
+        data_dir.join(cache::ARCHIVES_DIRECTORY),
+        !args.no_cache,
+    ));
 
     if args.pull_index {
         if !data_dir.exists() {
@@ -87,30 +294,45 @@ async fn main() -> Result<(), anyhow::Error> {
             fs::create_dir_all(&data_dir)?;
         }
 
-        registry::pull_devcontainer_index(&index_file).await?;
+        registry::pull_devcontainer_index(&index_file, &config.index.pins).await?;
         log::info!("Saved to {}", index_file.display());
     }
 
+    if let Some(Commands::External(plugin_args)) = &args.command {
+        let context = plugin::PluginContext {
+            data_dir: &data_dir,
+            index_file: &index_file,
+            log_level: args.verbose.log_level_filter(),
+            output: args.output,
+            no_cache: args.no_cache,
+            pull_index: args.pull_index,
+        };
+        let status = plugin::run_plugin(plugin_args, &context)?;
+
+        return Ok(ExitCode::from(
+            status.code().and_then(|code| u8::try_from(code).ok()).unwrap_or(1),
+        ));
+    }
+
     if let Some(command) = args.command {
-        if !index_file.exists() {
-            // suggested user action
-            log::error!(
-                "Missing devcontainer-index.json.\n\n\tRun `{} --pull-index`.\n",
-                BIN_NAME
-            );
+        let result = run(command, &index_file, args.output, &config).await;
+
+        if let Err(error) = &result {
+            if args.output == output::OutputFormat::Json {
+                if error.downcast_ref::<output::ResultPrinted>().is_none() {
+                    output::print_error(error)?;
+                }
+
+                return Ok(ExitCode::FAILURE);
+            }
         }
 
-        let index = registry::read_devcontainer_index(index_file)?;
+        result?;
+    }
 
-        match command {
-            #[cfg(feature = "completions")]
-            Commands::Completions { .. } => unreachable!(),
-            Commands::Init(args) => init::init(&index, args).await?,
-            Commands::Inspect(args) => inspect::inspect(&index, args).await?,
-            Commands::List(args) => list::list(&index, args),
-            Commands::Search(args) => search::search(&index, args)?,
-        };
+    if !args.no_update_check {
+        update_check::notify_if_outdated(&data_dir).await;
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,151 @@
+use serde_json::{Map, Value};
+
+use crate::oci_ref::OciReference;
+use crate::upgrade;
+
+/// Three way merge of JSON values, where nothing stands for a missing value: the changes from `base` to `new` are
+/// made to `current`, and what only `current` changed is kept. Where both changed a value, `new` wins and the path
+/// is returned along with the merged value. Objects are merged key by key, in the order of `current`. Without a
+/// base, values that differ are taken from `new` without counting as conflicts.
+pub fn merge(base: Option<&Value>, current: Option<&Value>, new: Option<&Value>) -> (Option<Value>, Vec<String>) {
+    let mut conflicts = Vec::new();
+    let merged = merge_at("", base.is_some(), base, current, new, &mut conflicts);
+
+    (merged, conflicts)
+}
+
+fn merge_at(
+    path: &str,
+    known: bool,
+    base: Option<&Value>,
+    current: Option<&Value>,
+    new: Option<&Value>,
+    conflicts: &mut Vec<String>,
+) -> Option<Value> {
+    if new == base || current == new {
+        return current.cloned();
+    }
+
+    if current == base {
+        return new.cloned();
+    }
+
+    match (base, current, new) {
+        (None | Some(Value::Object(_)), Some(Value::Object(current)), Some(Value::Object(new))) => {
+            let base = base.and_then(Value::as_object);
+            let keys = current
+                .keys()
+                .chain(new.keys().filter(|key| !current.contains_key(*key)));
+            let merged: Map<String, Value> = keys
+                .filter_map(|key| {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    let value = merge_at(
+                        &path,
+                        known,
+                        base.and_then(|base| base.get(key)),
+                        current.get(key),
+                        new.get(key),
+                        conflicts,
+                    )?;
+
+                    Some((key.clone(), value))
+                })
+                .collect();
+
+            Some(Value::Object(merged))
+        },
+        _ => {
+            if known {
+                conflicts.push(path.to_string());
+            }
+
+            new.cloned()
+        },
+    }
+}
+
+/// Rename the features of `config` to the key `new` has for the same feature id, such as `node:1` to `node:2`, so
+/// that a feature is merged rather than added twice.
+pub fn align_feature_keys(config: &mut Value, new: &Value) {
+    let id = |key: &str| key.parse::<OciReference>().ok().map(|reference| reference.id());
+    let (Some(Value::Object(features)), Some(Value::Object(new_features))) =
+        (config.get("features"), new.get("features"))
+    else {
+        return;
+    };
+    let renames: Vec<(String, String)> = features
+        .keys()
+        .filter(|key| !new_features.contains_key(*key))
+        .filter_map(|key| {
+            let feature_id = id(key)?;
+            let new_key = new_features
+                .keys()
+                .find(|new_key| !features.contains_key(*new_key) && id(new_key).as_ref() == Some(&feature_id))?;
+
+            Some((key.clone(), new_key.clone()))
+        })
+        .collect();
+
+    upgrade::rename_features(config, &renames);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{align_feature_keys, merge};
+    use serde_json::json;
+
+    #[test]
+    fn test_merge() {
+        let base = json!({
+            "name": "Rust",
+            "image": "rust:1-bookworm",
+            "features": { "ghcr.io/devcontainers/features/node:1": {} },
+        });
+        let current = json!({
+            "name": "Rust",
+            "image": "rust:1-bookworm",
+            "postCreateCommand": "cargo build",
+            "features": {
+                "ghcr.io/devcontainers/features/node:1": {},
+                "ghcr.io/devcontainers/features/go:1": {},
+            },
+        });
+        let mut new = json!({
+            "name": "Rust",
+            "image": "rust:1-bullseye",
+            "features": { "ghcr.io/devcontainers/features/node:2": { "version": "lts" } },
+        });
+        let mut aligned = [base, current];
+
+        for config in &mut aligned {
+            align_feature_keys(config, &new);
+        }
+
+        let [base, current] = aligned;
+
+        assert_eq!(
+            merge(Some(&base), Some(&current), Some(&new)),
+            (
+                Some(json!({
+                    "name": "Rust",
+                    "image": "rust:1-bullseye",
+                    "postCreateCommand": "cargo build",
+                    "features": {
+                        "ghcr.io/devcontainers/features/node:2": { "version": "lts" },
+                        "ghcr.io/devcontainers/features/go:1": {},
+                    },
+                })),
+                Vec::new()
+            )
+        );
+
+        new["postCreateCommand"] = json!("cargo check");
+        assert_eq!(merge(Some(&base), Some(&current), Some(&new)).1, ["postCreateCommand"]);
+        assert_eq!(merge(None, Some(&current), Some(&new)).1, Vec::<String>::new());
+        assert_eq!(merge(Some(&base), Some(&current), Some(&base)).0, Some(current));
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+/// Folder of catalog files, named by locale such as `de.toml` or `pt-BR.toml`, inside the `tyedev` config directory.
+const CATALOG_DIRECTORY: &str = "messages";
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Wording of prompts, for translations and for organizations that embed `tyedev` in their onboarding.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MessagesConfig {
+    /// Locale of the catalog, such as `de` or `pt-BR`, taken from `LC_ALL`, `LC_MESSAGES`, or `LANG` when missing.
+    pub locale: Option<String>,
+    /// Text by message id, replacing the catalog of the locale.
+    pub text: BTreeMap<String, String>,
+}
+
+/// Every prompt of `tyedev`, and the choices offered by them. Text in braces, such as `{id}`, is replaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Message {
+    StartPoint,
+    StartPointExisting,
+    StartPointEnter,
+    StartPointEmpty,
+    PickTemplate,
+    EnterTemplate,
+    DevcontainerName,
+    PickFeatures,
+    AddFeature,
+    AddOtherFeature,
+    ChooseFeature,
+    AddingFeature,
+    AddDependency,
+    AddInstallsAfter,
+    AddingDependency,
+    OptionInclude,
+    OptionChoose,
+    OptionValue,
+    FileExists,
+    FileOverwrite,
+    FileSkip,
+    FileShowDiff,
+    EditBefore,
+    Edit,
+    EditErrors,
+    EditAgain,
+    EditWriteAnyway,
+    EditDiscard,
+    DownloadConfirm,
+    DownloadCancelled,
+    ImpactConfirm,
+    ImpactCancelled,
+    UpgradeFeature,
+    Review,
+    ReviewAccept,
+    ReviewEdit,
+    ReviewRestart,
+    ReviewQuit,
+    ReviewCancelled,
+    Existing,
+    ExistingOverwrite,
+    ExistingMerge,
+    ExistingAlongside,
+    ExistingAbort,
+    ExistingName,
+    ExistingAborted,
+    Extensions,
+    LifecycleCommands,
+    LifecycleCommand,
+    ForwardPorts,
+    AddEnvironment,
+    Environment,
+    EnvironmentTarget,
+    EnvironmentContainer,
+    EnvironmentRemote,
+}
+
+impl Message {
+    pub const ALL: [Message; 55] = [
+        Message::StartPoint,
+        Message::StartPointExisting,
+        Message::StartPointEnter,
+        Message::StartPointEmpty,
+        Message::PickTemplate,
+        Message::EnterTemplate,
+        Message::DevcontainerName,
+        Message::PickFeatures,
+        Message::AddFeature,
+        Message::AddOtherFeature,
+        Message::ChooseFeature,
+        Message::AddingFeature,
+        Message::AddDependency,
+        Message::AddInstallsAfter,
+        Message::AddingDependency,
+        Message::OptionInclude,
+        Message::OptionChoose,
+        Message::OptionValue,
+        Message::FileExists,
+        Message::FileOverwrite,
+        Message::FileSkip,
+        Message::FileShowDiff,
+        Message::EditBefore,
+        Message::Edit,
+        Message::EditErrors,
+        Message::EditAgain,
+        Message::EditWriteAnyway,
+        Message::EditDiscard,
+        Message::DownloadConfirm,
+        Message::DownloadCancelled,
+        Message::ImpactConfirm,
+        Message::ImpactCancelled,
+        Message::UpgradeFeature,
+        Message::Review,
+        Message::ReviewAccept,
+        Message::ReviewEdit,
+        Message::ReviewRestart,
+        Message::ReviewQuit,
+        Message::ReviewCancelled,
+        Message::Existing,
+        Message::ExistingOverwrite,
+        Message::ExistingMerge,
+        Message::ExistingAlongside,
+        Message::ExistingAbort,
+        Message::ExistingName,
+        Message::ExistingAborted,
+        Message::Extensions,
+        Message::LifecycleCommands,
+        Message::LifecycleCommand,
+        Message::ForwardPorts,
+        Message::AddEnvironment,
+        Message::Environment,
+        Message::EnvironmentTarget,
+        Message::EnvironmentContainer,
+        Message::EnvironmentRemote,
+    ];
+
+    /// Key of the message in catalog files and in the `[messages.text]` config.
+    pub fn id(self) -> &'static str {
+        match self {
+            Message::StartPoint => "init.start-point",
+            Message::StartPointExisting => "init.start-point.existing",
+            Message::StartPointEnter => "init.start-point.enter",
+            Message::StartPointEmpty => "init.start-point.empty",
+            Message::PickTemplate => "init.pick-template",
+            Message::EnterTemplate => "init.enter-template",
+            Message::DevcontainerName => "init.name",
+            Message::PickFeatures => "init.pick-features",
+            Message::AddFeature => "init.add-feature",
+            Message::AddOtherFeature => "init.add-other-feature",
+            Message::ChooseFeature => "init.choose-feature",
+            Message::AddingFeature => "init.adding-feature",
+            Message::AddDependency => "init.add-dependency",
+            Message::AddInstallsAfter => "init.add-installs-after",
+            Message::AddingDependency => "init.adding-dependency",
+            Message::OptionInclude => "init.option.include",
+            Message::OptionChoose => "init.option.choose",
+            Message::OptionValue => "init.option.value",
+            Message::FileExists => "init.file-exists",
+            Message::FileOverwrite => "init.file-exists.overwrite",
+            Message::FileSkip => "init.file-exists.skip",
+            Message::FileShowDiff => "init.file-exists.show-diff",
+            Message::EditBefore => "init.edit-before",
+            Message::Edit => "init.edit",
+            Message::EditErrors => "init.edit-errors",
+            Message::EditAgain => "init.edit-errors.again",
+            Message::EditWriteAnyway => "init.edit-errors.write-anyway",
+            Message::EditDiscard => "init.edit-errors.discard",
+            Message::DownloadConfirm => "download.confirm",
+            Message::DownloadCancelled => "download.cancelled",
+            Message::ImpactConfirm => "impact.confirm",
+            Message::ImpactCancelled => "impact.cancelled",
+            Message::UpgradeFeature => "upgrade.feature",
+            Message::Review => "init.review",
+            Message::ReviewAccept => "init.review.accept",
+            Message::ReviewEdit => "init.review.edit",
+            Message::ReviewRestart => "init.review.restart",
+            Message::ReviewQuit => "init.review.quit",
+            Message::ReviewCancelled => "init.review.cancelled",
+            Message::Existing => "init.existing",
+            Message::ExistingOverwrite => "init.existing.overwrite",
+            Message::ExistingMerge => "init.existing.merge",
+            Message::ExistingAlongside => "init.existing.alongside",
+            Message::ExistingAbort => "init.existing.abort",
+            Message::ExistingName => "init.existing.name",
+            Message::ExistingAborted => "init.existing.aborted",
+            Message::Extensions => "init.extensions",
+            Message::LifecycleCommands => "init.lifecycle",
+            Message::LifecycleCommand => "init.lifecycle.command",
+            Message::ForwardPorts => "init.forward-ports",
+            Message::AddEnvironment => "init.add-env",
+            Message::Environment => "init.env",
+            Message::EnvironmentTarget => "init.env.target",
+            Message::EnvironmentContainer => "init.env.target.container",
+            Message::EnvironmentRemote => "init.env.target.remote",
+        }
+    }
+
+    /// The built-in English text.
+    fn english(self) -> &'static str {
+        match self {
+            Message::StartPoint => "Choose a starting point:",
+            Message::StartPointExisting => "Pick existing template",
+            Message::StartPointEnter => "Enter known template OCI reference",
+            Message::StartPointEmpty => "Start from scratch",
+            Message::PickTemplate => "Pick existing template from the index:",
+            Message::EnterTemplate => "Enter template by providing the OCI reference:",
+            Message::DevcontainerName => "Name for the devcontainer:",
+            Message::PickFeatures => "Choose the features to add, type to filter:",
+            Message::AddFeature => "Add a feature?",
+            Message::AddOtherFeature => "Add another feature by reference?",
+            Message::ChooseFeature => "Choose or enter feature id (OCI REF):",
+            Message::AddingFeature => "Adding feature: {reference}",
+            Message::AddDependency => "{feature} depends on {id}, add it?",
+            Message::AddInstallsAfter => "{feature} installs after {id}, add it?",
+            Message::AddingDependency => "Adding dependency: {reference}",
+            Message::OptionInclude => "Include {name}?",
+            Message::OptionChoose => "Choose value for {name}:",
+            Message::OptionValue => "What value for {name}?",
+            Message::FileExists => "{file} already exists:",
+            Message::FileOverwrite => "Overwrite",
+            Message::FileSkip => "Skip",
+            Message::FileShowDiff => "Show diff",
+            Message::EditBefore => "Edit devcontainer.json before writing?",
+            Message::Edit => "Edit devcontainer.json:",
+            Message::EditErrors => "The edited devcontainer.json has errors:",
+            Message::EditAgain => "Edit again",
+            Message::EditWriteAnyway => "Write anyway",
+            Message::EditDiscard => "Discard edits",
+            Message::DownloadConfirm => "Download {total}, more than {limit}?",
+            Message::DownloadCancelled => "Download cancelled",
+            Message::ImpactConfirm => "Over the limits of the dev container with {limits}, continue?",
+            Message::ImpactCancelled => "Init cancelled",
+            Message::UpgradeFeature => "Upgrade {key} to {version}?",
+            Message::Review => "Write the dev container above?",
+            Message::ReviewAccept => "Accept",
+            Message::ReviewEdit => "Edit options",
+            Message::ReviewRestart => "Restart with another template",
+            Message::ReviewQuit => "Quit",
+            Message::ReviewCancelled => "Init cancelled, nothing was written",
+            Message::Existing => "{file} already exists in the workspace:",
+            Message::ExistingOverwrite => "Overwrite it",
+            Message::ExistingMerge => "Merge into it",
+            Message::ExistingAlongside => "Write alongside it under a new name",
+            Message::ExistingAbort => "Abort",
+            Message::ExistingName => {
+                "Name of the new configuration, written to .devcontainer/<name>/devcontainer.json:"
+            },
+            Message::ExistingAborted => "Init aborted, nothing was written",
+            Message::Extensions => {
+                "VS Code extensions recommended by the features, choose any to add to devcontainer.json:"
+            },
+            Message::LifecycleCommands => "Add commands to run as the container is created and started?",
+            Message::LifecycleCommand => "Command for {property}, left empty for none:",
+            Message::ForwardPorts => "Ports to forward, separated by spaces, such as 3000 or 5432=Database:",
+            Message::AddEnvironment => "Add an environment variable?",
+            Message::Environment => "Environment variable, as NAME=VALUE:",
+            Message::EnvironmentTarget => "Set {name} for:",
+            Message::EnvironmentContainer => "The whole container (containerEnv)",
+            Message::EnvironmentRemote => "Tools connecting to the container (remoteEnv)",
+        }
+    }
+}
+
+/// The text of every message for this run.
+#[derive(Debug, Default)]
+struct Catalog {
+    text: HashMap<Message, String>,
+}
+
+impl Catalog {
+    /// Layer `entries` over the catalog, warning about ids that are not known.
+    fn extend(&mut self, entries: BTreeMap<String, String>, source: &str) {
+        for (id, text) in entries {
+            match Message::ALL.iter().find(|message| message.id() == id) {
+                Some(message) => {
+                    self.text.insert(*message, text);
+                },
+                None => log::warn!("Unknown message {id} in {source}"),
+            }
+        }
+    }
+
+    fn get(&self, message: Message) -> &str {
+        self.text
+            .get(&message)
+            .map_or_else(|| message.english(), String::as_str)
+    }
+}
+
+/// Locale of a POSIX locale name such as `pt_BR.UTF-8`, or none for the `C` and `POSIX` locales.
+fn parse_locale(name: &str) -> Option<String> {
+    let name = name.split(['.', '@']).next().unwrap_or_default();
+
+    match name {
+        "" | "C" | "POSIX" => None,
+        name => Some(name.replace('_', "-")),
+    }
+}
+
+/// The locale of the environment, with the same precedence as `gettext`.
+fn environment_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|variable| std::env::var(variable).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|value| parse_locale(&value))
+}
+
+/// Catalog file names to try for a locale, the most specific first, such as `pt-BR.toml` then `pt.toml`.
+fn catalog_filenames(locale: &str) -> Vec<String> {
+    let mut filenames = vec![format!("{locale}.toml")];
+
+    if let Some((language, _)) = locale.split_once('-') {
+        filenames.push(format!("{language}.toml"));
+    }
+
+    filenames
+}
+
+/// Read the first catalog file of the locale that exists in `directory`.
+fn read_catalog(directory: &Path, locale: &str) -> anyhow::Result<Option<(String, BTreeMap<String, String>)>> {
+    for filename in catalog_filenames(locale) {
+        let path = directory.join(filename);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => Err(error)?,
+        };
+        let entries = toml::from_str(&contents).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid message catalog {}: {error}", path.display()),
+            )
+        })?;
+
+        return Ok(Some((path.display().to_string(), entries)));
+    }
+
+    Ok(None)
+}
+
+/// Choose the wording of prompts for this run: the catalog of the locale in `config_directory`, when there is one,
+/// then the text of the config. English is the fallback of every message. Only the first call has an effect.
+pub fn init(config: &MessagesConfig, config_directory: Option<&Path>) -> anyhow::Result<()> {
+    log::debug!("init");
+
+    let mut catalog = Catalog::default();
+    let locale = config.locale.clone().or_else(environment_locale);
+
+    if let (Some(locale), Some(config_directory)) = (locale, config_directory) {
+        let directory = config_directory.join(CATALOG_DIRECTORY);
+
+        match read_catalog(&directory, &locale)? {
+            Some((source, entries)) => catalog.extend(entries, &source),
+            None => log::debug!("init: no catalog for {locale}"),
+        }
+    }
+
+    catalog.extend(config.text.clone(), "config");
+
+    let _ = CATALOG.set(catalog);
+
+    Ok(())
+}
+
+fn current() -> &'static Catalog {
+    CATALOG.get_or_init(Catalog::default)
+}
+
+/// The text of a message without placeholders.
+pub fn text(message: Message) -> String {
+    current().get(message).to_string()
+}
+
+/// The text of a message with each `{name}` replaced by its value.
+pub fn format(message: Message, values: &[(&str, &dyn Display)]) -> String {
+    values
+        .iter()
+        .fold(current().get(message).to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{catalog_filenames, parse_locale, Catalog, Message};
+    use std::collections::{BTreeMap, HashSet};
+
+    #[test]
+    fn test_message_ids() {
+        let ids: HashSet<&str> = Message::ALL.iter().map(|message| message.id()).collect();
+
+        assert_eq!(ids.len(), Message::ALL.len());
+    }
+
+    #[test]
+    fn test_locale() {
+        assert_eq!(parse_locale("pt_BR.UTF-8"), Some("pt-BR".to_string()));
+        assert_eq!(parse_locale("de_DE@euro"), Some("de-DE".to_string()));
+        assert_eq!(parse_locale("C.UTF-8"), None);
+        assert_eq!(catalog_filenames("pt-BR"), ["pt-BR.toml", "pt.toml"]);
+        assert_eq!(catalog_filenames("de"), ["de.toml"]);
+    }
+
+    #[test]
+    fn test_catalog() {
+        let mut catalog = Catalog::default();
+        let entries = BTreeMap::from([
+            ("init.add-feature".to_string(), "Feature hinzufügen?".to_string()),
+            ("init.unknown".to_string(), "Ignored".to_string()),
+        ]);
+
+        catalog.extend(entries, "test");
+
+        assert_eq!(catalog.get(Message::AddFeature), "Feature hinzufügen?");
+        assert_eq!(catalog.get(Message::FileSkip), "Skip");
+        assert_eq!(catalog.text.len(), 1);
+    }
+}
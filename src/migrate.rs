@@ -0,0 +1,314 @@
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Args;
+use serde_json::{Map, Value};
+
+use crate::oci_ref::OciReference;
+use crate::registry::{DevcontainerIndex, Feature};
+use crate::upgrade;
+use crate::validate::LEGACY_PROPERTIES;
+use crate::workspace;
+
+/// Namespace of the features that were once referenced by a bare id, such as `docker-in-docker`.
+const BARE_ID_NAMESPACE: &str = "ghcr.io/devcontainers/features";
+
+/// Properties from before the specification that nothing replaced, with why they are no longer needed.
+const REMOVED_PROPERTIES: &[(&str, &str)] = &[("devPort", "VS Code picks the port of its server itself")];
+
+#[derive(Debug, Args)]
+pub struct MigrateArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Print the migrations without changing any file.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// One rewrite of a devcontainer.json into the form of the specification.
+#[derive(Debug, PartialEq)]
+pub enum Migration {
+    /// A legacy property moved to where the specification has it.
+    Moved { from: String, to: String },
+    /// A legacy property that nothing replaced.
+    Removed { property: String, reason: String },
+    /// A feature referenced by a legacy id.
+    Renamed { from: String, to: String },
+    /// A legacy property that could not be moved, left as is.
+    Skipped { property: String, reason: String },
+}
+
+impl Display for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Moved { from, to } => write!(f, "moved {from} to {to}"),
+            Self::Removed { property, reason } => write!(f, "removed {property}, {reason}"),
+            Self::Renamed { from, to } => write!(f, "renamed feature {from} to {to}"),
+            Self::Skipped { property, reason } => write!(f, "left {property} as is, {reason}"),
+        }
+    }
+}
+
+/// The object at `path` below `config`, created along the way where missing. Nothing is returned when a property of
+/// the path holds something other than an object.
+fn object_at<'c>(config: &'c mut Map<String, Value>, path: &[&str]) -> Option<&'c mut Map<String, Value>> {
+    let mut object = config;
+
+    for part in path {
+        object = object
+            .entry(*part)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()?;
+    }
+
+    Some(object)
+}
+
+/// Move the legacy property `from` to the dotted path `to`. Arrays and objects are merged into what is already there,
+/// keeping the entries of the current form, while any other value already there leaves the legacy property alone.
+fn move_property(config: &mut Map<String, Value>, from: &str, to: &str) -> Option<Migration> {
+    let value = config.get(from)?.clone();
+    let skipped = |reason: String| Migration::Skipped {
+        property: from.to_string(),
+        reason,
+    };
+    let path: Vec<&str> = to.split('.').collect();
+    let (name, parents) = path.split_last()?;
+    let Some(target) = object_at(config, parents) else {
+        return Some(skipped(format!("{} is not an object", parents.join("."))));
+    };
+
+    match (target.get_mut(*name), value) {
+        (None, value) => {
+            target.insert(name.to_string(), value);
+        },
+        (Some(Value::Array(current)), Value::Array(legacy)) => {
+            for item in legacy {
+                if !current.contains(&item) {
+                    current.push(item);
+                }
+            }
+        },
+        (Some(Value::Object(current)), Value::Object(legacy)) => {
+            for (key, item) in legacy {
+                current.entry(key).or_insert(item);
+            }
+        },
+        (Some(_), _) => return Some(skipped(format!("{to} is already set"))),
+    }
+
+    config.shift_remove(from);
+
+    Some(Migration::Moved {
+        from: from.to_string(),
+        to: to.to_string(),
+    })
+}
+
+/// Major version of a feature, the tag that follows it the way references usually do.
+fn major_version(feature: &Feature) -> &str {
+    feature.version.split('.').next().unwrap_or(&feature.version)
+}
+
+/// The key of a feature referenced by a legacy id, nothing when the key already is current or is unknown. A bare id
+/// becomes a reference of its namespace, with the major version of the index when it has no tag. A renamed feature
+/// keeps the tag as written, and one pinned by digest is left alone, as the digest is of the artifact under the old
+/// name.
+fn current_feature_key(key: &str, index: &DevcontainerIndex) -> Option<String> {
+    let renamed = |namespace: &str, name: &str| {
+        index.iter_features(true).find(|feature| {
+            feature
+                .id
+                .strip_prefix(namespace)
+                .and_then(|id| id.strip_prefix('/'))
+                .is_some_and(|id| {
+                    id == name
+                        || feature
+                            .lecagy_ids
+                            .as_ref()
+                            .is_some_and(|ids| ids.iter().any(|id| id == name))
+                })
+        })
+    };
+
+    if !key.contains('/') {
+        let (name, tag) = key.split_once(':').map_or((key, None), |(name, tag)| (name, Some(tag)));
+        let feature = renamed(BARE_ID_NAMESPACE, name)?;
+
+        return Some(format!("{}:{}", feature.id, tag.unwrap_or(major_version(feature))));
+    }
+
+    let reference = key
+        .parse::<OciReference>()
+        .ok()
+        .filter(|reference| !reference.is_local())?;
+    let id = reference.id();
+
+    if reference.digest().is_some() || index.get_feature(&id).is_some() {
+        return None;
+    }
+
+    let (namespace, name) = id.rsplit_once('/')?;
+    let feature = renamed(namespace, name)?;
+    let tag = key.strip_prefix(&id).unwrap_or_default();
+
+    Some(format!("{}{tag}", feature.id))
+}
+
+/// Rewrite the legacy properties and feature ids of `config`, leaving everything else as it is.
+pub fn migrate_config(config: &mut Value, index: &DevcontainerIndex) -> Vec<Migration> {
+    let Value::Object(object) = config else {
+        return Vec::new();
+    };
+
+    let mut migrations: Vec<Migration> = LEGACY_PROPERTIES
+        .iter()
+        .filter_map(|(from, to)| move_property(object, from, to))
+        .collect();
+
+    for (property, reason) in REMOVED_PROPERTIES {
+        if object.shift_remove(*property).is_some() {
+            migrations.push(Migration::Removed {
+                property: property.to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+
+    let renames: Vec<(String, String)> = match object.get("features") {
+        Some(Value::Object(features)) => features
+            .keys()
+            .filter(|key| !key.starts_with("./") && !key.starts_with("../") && !key.contains("://"))
+            .filter_map(|key| Some((key.clone(), current_feature_key(key, index)?)))
+            .filter(|(_, to)| !features.contains_key(to))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    upgrade::rename_features(config, &renames);
+    migrations.extend(renames.into_iter().map(|(from, to)| Migration::Renamed { from, to }));
+    migrations
+}
+
+/// Rewrite legacy properties of the devcontainer.json files of the workspace into the form of the specification.
+pub fn migrate(
+    index: &DevcontainerIndex,
+    MigrateArgs {
+        workspace_folder,
+        dry_run,
+    }: MigrateArgs,
+) -> anyhow::Result<()> {
+    log::debug!("migrate");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let mut migrated = 0;
+
+    for file in workspace::devcontainer_files(&workspace)? {
+        let display_name = file.strip_prefix(&workspace).unwrap_or(&file).display().to_string();
+        let contents = fs::read_to_string(&file)?;
+        let mut config = workspace::read_config(&file)?;
+        let mut changed = false;
+
+        for migration in migrate_config(&mut config, index) {
+            if let Migration::Skipped { .. } = migration {
+                log::warn!("{display_name}: {migration}");
+                continue;
+            }
+
+            println!("{display_name}: {migration}");
+            changed = true;
+            migrated += 1;
+        }
+
+        if changed && !dry_run {
+            workspace::write_config(&file, &contents, &config, &display_name)?;
+        }
+    }
+
+    if migrated == 0 {
+        println!("Nothing to migrate");
+    }
+
+    log::debug!("migrate: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migrate_config, Migration};
+    use crate::registry::DevcontainerIndex;
+    use serde_json::json;
+
+    #[test]
+    fn test_migrate_config() -> anyhow::Result<()> {
+        let index: DevcontainerIndex = serde_json::from_str(
+            r#"{"collections": [{
+                "sourceInformation": {"name": "features", "maintainer": "Dev Container Spec Maintainers", "contact": "",
+                    "repository": "", "ociReference": "ghcr.io/devcontainers/features"},
+                "features": [
+                    {"id": "ghcr.io/devcontainers/features/docker-in-docker", "version": "2.12.0", "name": "Docker"},
+                    {"id": "ghcr.io/devcontainers/features/docker-outside-of-docker", "version": "1.6.0", "name": "Docker",
+                        "legacyIds": ["docker-from-docker"]}
+                ],
+                "templates": []
+            }]}"#,
+        )?;
+        let mut config = json!({
+            "name": "Legacy",
+            "dockerFile": "Dockerfile",
+            "build": { "args": { "VARIANT": "bookworm" } },
+            "extensions": ["rust-lang.rust-analyzer", "tamasfe.even-better-toml"],
+            "settings": { "editor.formatOnSave": true, "files.eol": "\r\n" },
+            "customizations": { "vscode": { "extensions": ["tamasfe.even-better-toml"], "settings": { "files.eol": "\n" } } },
+            "devPort": 8000,
+            "features": {
+                "docker-in-docker": {},
+                "ghcr.io/devcontainers/features/docker-from-docker:1": { "moby": false },
+                "ghcr.io/devcontainers/features/node:1": {},
+            },
+        });
+        let migrations = migrate_config(&mut config, &index);
+
+        assert_eq!(
+            config,
+            json!({
+                "name": "Legacy",
+                "build": { "args": { "VARIANT": "bookworm" }, "dockerfile": "Dockerfile" },
+                "customizations": {
+                    "vscode": {
+                        "extensions": ["tamasfe.even-better-toml", "rust-lang.rust-analyzer"],
+                        "settings": { "files.eol": "\n", "editor.formatOnSave": true },
+                    }
+                },
+                "features": {
+                    "ghcr.io/devcontainers/features/docker-in-docker:2": {},
+                    "ghcr.io/devcontainers/features/docker-outside-of-docker:1": { "moby": false },
+                    "ghcr.io/devcontainers/features/node:1": {},
+                },
+            })
+        );
+        assert_eq!(migrations.len(), 6);
+        assert!(migrations.contains(&Migration::Renamed {
+            from: "ghcr.io/devcontainers/features/docker-from-docker:1".to_string(),
+            to: "ghcr.io/devcontainers/features/docker-outside-of-docker:1".to_string(),
+        }));
+
+        let mut config = json!({ "image": "debian", "context": "..", "build": "Dockerfile" });
+
+        assert_eq!(
+            migrate_config(&mut config, &index),
+            [Migration::Skipped {
+                property: "context".to_string(),
+                reason: "build is not an object".to_string(),
+            }]
+        );
+        assert_eq!(config["context"], "..");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Annotation holding the tag of a manifest within an image layout's `index.json`.
+const REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Descriptor {
+    #[serde(default)]
+    media_type: Option<String>,
+    digest: String,
+    #[serde(default)]
+    annotations: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageIndex {
+    manifests: Vec<Descriptor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageManifest {
+    layers: Vec<Descriptor>,
+}
+
+/// Location of the content with the given digest, as `{root}/{algorithm}/{encoded}`. The digest is validated, it must
+/// not be able to escape `root`.
+pub fn digest_path(root: &Path, digest: &str) -> io::Result<PathBuf> {
+    match digest.split_once(':') {
+        Some((algorithm, encoded))
+            if !algorithm.is_empty()
+                && algorithm.chars().all(|c| c.is_ascii_alphanumeric())
+                && !encoded.is_empty()
+                && encoded.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            Ok(root.join(algorithm).join(encoded))
+        },
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid digest: {digest}"),
+        )),
+    }
+}
+
+/// Read a blob of the layout, verifying that its content matches the digest.
+pub fn read_blob(layout: &Path, digest: &str) -> io::Result<Vec<u8>> {
+    let blob = fs::read(digest_path(&layout.join("blobs"), digest)?)?;
+
+    if let Some(expected) = digest.strip_prefix("sha256:") {
+        let actual = format!("{:x}", Sha256::digest(&blob));
+
+        if expected != actual {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Blob {digest} does not match its content, found sha256:{actual}"),
+            ))?
+        }
+    }
+
+    Ok(blob)
+}
+
+/// Choose the manifest for `tag`. Without a tag a layout holding a single manifest, or a "latest" one, is unambiguous.
+fn select_manifest<'i>(index: &'i ImageIndex, tag: Option<&str>) -> io::Result<&'i Descriptor> {
+    let tagged = |tag: &str| {
+        index.manifests.iter().find(|manifest| {
+            manifest
+                .annotations
+                .get(REF_NAME_ANNOTATION)
+                .is_some_and(|name| name == tag)
+        })
+    };
+
+    let manifest = match (tag, index.manifests.as_slice()) {
+        (Some(tag), _) => tagged(tag),
+        (None, [manifest]) => Some(manifest),
+        (None, _) => tagged("latest"),
+    };
+
+    manifest.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            match tag {
+                Some(tag) => format!("No manifest tagged \"{tag}\" in the image layout"),
+                None => "The image layout has several manifests, add a \":tag\" to choose one".to_string(),
+            },
+        )
+    })
+}
+
+/// Read the first layer of the given media type from an OCI image layout directory.
+pub fn read_layer(layout: &Path, tag: Option<&str>, media_type: &str) -> io::Result<Vec<u8>> {
+    log::debug!("read_layer: {}", layout.display());
+
+    let index: ImageIndex = serde_json::from_slice(&fs::read(layout.join("index.json"))?)?;
+    let manifest = select_manifest(&index, tag)?;
+    let manifest: ImageManifest = serde_json::from_slice(&read_blob(layout, &manifest.digest)?)?;
+    let layer = manifest
+        .layers
+        .iter()
+        .find(|layer| layer.media_type.as_deref() == Some(media_type))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No {media_type} layer in the image layout"),
+            )
+        })?;
+
+    read_blob(layout, &layer.digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_layer;
+    use sha2::{Digest, Sha256};
+    use std::{env, fs};
+
+    #[test]
+    fn test_read_layer() -> anyhow::Result<()> {
+        let layout = env::temp_dir().join(format!("tyedev-oci-layout-{}", std::process::id()));
+        let blobs = layout.join("blobs/sha256");
+        fs::create_dir_all(&blobs)?;
+
+        let write_blob = |contents: &[u8]| -> std::io::Result<String> {
+            let encoded = format!("{:x}", Sha256::digest(contents));
+            fs::write(blobs.join(&encoded), contents)?;
+            Ok(format!("sha256:{encoded}"))
+        };
+
+        let media_type = "application/vnd.devcontainers.layer.v1+tar";
+        let layer = write_blob(b"feature archive")?;
+        let manifest = serde_json::json!({
+            "schemaVersion": 2,
+            "layers": [{ "mediaType": media_type, "digest": layer, "size": 15 }],
+        });
+        let manifest = write_blob(serde_json::to_string(&manifest)?.as_bytes())?;
+        let index = serde_json::json!({
+            "schemaVersion": 2,
+            "manifests": [{
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "digest": manifest,
+                "annotations": { "org.opencontainers.image.ref.name": "1.0.0" },
+            }],
+        });
+        fs::write(layout.join("index.json"), serde_json::to_string(&index)?)?;
+
+        assert_eq!(read_layer(&layout, None, media_type)?, b"feature archive");
+        assert_eq!(read_layer(&layout, Some("1.0.0"), media_type)?, b"feature archive");
+        assert!(read_layer(&layout, Some("2.0.0"), media_type).is_err());
+        assert!(read_layer(&layout, None, "application/json").is_err());
+
+        fs::remove_dir_all(&layout)?;
+        Ok(())
+    }
+}
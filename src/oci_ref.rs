@@ -1,17 +1,66 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
 use std::str::FromStr;
 
+const LAYOUT_SCHEME: &str = "oci-layout:";
+const FILE_SCHEME: &str = "file://";
+
 /// Opaque type for implementing additional `ImageName` features
+///
+/// Besides a registry reference, an artifact may be read from disk. Either from an OCI image layout directory written
+/// as `oci-layout:/path[:tag]`, or from the artifact tar archive itself written as `file:///path/to/artifact.tar`.
 #[derive(Debug, Clone)]
-pub struct OciReference(pub oci_client::Reference);
+pub enum OciReference {
+    Registry(oci_client::Reference),
+    Layout { path: PathBuf, tag: Option<String> },
+    File(PathBuf),
+}
 
 impl OciReference {
     pub fn id(&self) -> String {
-        let id = format!("{}/{}", self.0.registry(), self.0.repository());
-        id
+        match self {
+            Self::Registry(reference) => format!("{}/{}", reference.registry(), reference.repository()),
+            Self::Layout { path, .. } => format!("{LAYOUT_SCHEME}{}", path.display()),
+            Self::File(path) => format!("{FILE_SCHEME}{}", path.display()),
+        }
     }
 
+    /// The tag, or the digest of a reference pinned by digest alone. Defaults to "latest" when neither is given.
     pub fn tag_name(&self) -> String {
-        self.0.tag().unwrap_or("latest").to_string()
+        match self {
+            Self::Registry(reference) => reference
+                .tag()
+                .or_else(|| reference.digest())
+                .unwrap_or("latest")
+                .to_string(),
+            Self::Layout { tag, .. } => tag.as_deref().unwrap_or("latest").to_string(),
+            Self::File(_) => "latest".to_string(),
+        }
+    }
+
+    /// The manifest digest of a reference such as `ghcr.io/devcontainers/features/go@sha256:…`.
+    pub fn digest(&self) -> Option<&str> {
+        match self {
+            Self::Registry(reference) => reference.digest(),
+            _ => None,
+        }
+    }
+
+    /// Whether the artifact is read from disk rather than pulled from a registry.
+    pub fn is_local(&self) -> bool {
+        !matches!(self, Self::Registry(_))
+    }
+}
+
+impl Display for OciReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Registry(reference) => write!(f, "{reference}"),
+            Self::Layout { path, tag: Some(tag) } => write!(f, "{LAYOUT_SCHEME}{}:{tag}", path.display()),
+            Self::Layout { path, tag: None } => write!(f, "{LAYOUT_SCHEME}{}", path.display()),
+            Self::File(path) => write!(f, "{FILE_SCHEME}{}", path.display()),
+        }
     }
 }
 
@@ -19,8 +68,42 @@ impl FromStr for OciReference {
     type Err = anyhow::Error;
 
     fn from_str(name: &str) -> Result<Self, Self::Err> {
-        let reference = oci_client::Reference::from_str(name)?;
-        Ok(Self(reference))
+        if let Some(location) = name.strip_prefix(LAYOUT_SCHEME) {
+            // A trailing ":tag" never contains a path separator, which keeps "C:\layout" intact.
+            let (path, tag) = match location.rsplit_once(':') {
+                Some((path, tag)) if !path.is_empty() && !tag.is_empty() && !tag.contains(['/', '\\']) => {
+                    (path, Some(tag.to_string()))
+                },
+                _ => (location, None),
+            };
+
+            return Ok(Self::Layout {
+                path: PathBuf::from(path),
+                tag,
+            });
+        }
+
+        if let Some(path) = name.strip_prefix(FILE_SCHEME) {
+            return Ok(Self::File(PathBuf::from(path)));
+        }
+
+        let reference = oci_client::Reference::from_str(name).map_err(|error| {
+            // Mixed case is the most common mistake, and the parser does not say so.
+            let lowercase = name.to_lowercase();
+            let hint = match oci_client::Reference::from_str(&lowercase) {
+                Ok(_) if lowercase != name => format!(" Repository names are lowercase, did you mean \"{lowercase}\"?"),
+                _ => String::new(),
+            };
+
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Invalid OCI reference \"{name}\": {error}.{hint} Expected [REGISTRY/]REPOSITORY[:TAG][@DIGEST] \
+                     such as \"ghcr.io/devcontainers/features/go:1\", or a local \"oci-layout:PATH\" or \"file://PATH\""
+                ),
+            )
+        })?;
+        Ok(Self::Registry(reference))
     }
 }
 
@@ -31,9 +114,10 @@ mod tests {
 
     #[test]
     fn test_parse() -> Result<()> {
-        let OciReference(image_name) = str::parse("ghcr.io/devcontainers/templates/rust")?;
+        let oci_ref: OciReference = str::parse("ghcr.io/devcontainers/templates/rust")?;
 
-        assert_eq!(image_name.to_string(), "ghcr.io/devcontainers/templates/rust:latest");
+        assert_eq!(oci_ref.to_string(), "ghcr.io/devcontainers/templates/rust:latest");
+        assert!(!oci_ref.is_local());
 
         Ok(())
     }
@@ -55,4 +139,53 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_local() -> Result<()> {
+        let layout: OciReference = str::parse("oci-layout:/tmp/go-feature:1.2.0")?;
+
+        assert_eq!(layout.id(), "oci-layout:/tmp/go-feature");
+        assert_eq!(layout.tag_name(), "1.2.0");
+        assert!(layout.is_local());
+
+        let untagged: OciReference = str::parse("oci-layout:./layout")?;
+
+        assert_eq!(untagged.to_string(), "oci-layout:./layout");
+        assert_eq!(untagged.tag_name(), "latest");
+
+        let file: OciReference = str::parse("file:///tmp/devcontainer-feature-go.tgz")?;
+
+        assert_eq!(file.id(), "file:///tmp/devcontainer-feature-go.tgz");
+        assert!(file.is_local());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest() -> Result<()> {
+        let digest = "sha256:4b1a5cc6e46d07e8b9e1ef8ff0f98db5ae8c5e6ecd3c0f4e0e1d0e9fca51b0e2";
+        let oci_ref: OciReference = format!("ghcr.io/devcontainers/features/go@{digest}").parse()?;
+
+        assert_eq!(oci_ref.id(), "ghcr.io/devcontainers/features/go");
+        assert_eq!(oci_ref.tag_name(), digest);
+        assert_eq!(oci_ref.digest(), Some(digest));
+        assert_eq!(
+            oci_ref.to_string(),
+            format!("ghcr.io/devcontainers/features/go@{digest}")
+        );
+
+        let tagged: OciReference = format!("ghcr.io/devcontainers/features/go:1@{digest}").parse()?;
+
+        assert_eq!(tagged.tag_name(), "1");
+        assert_eq!(tagged.digest(), Some(digest));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = str::parse::<OciReference>("ghcr.io/devcontainers/Features/node").err();
+
+        assert!(error.is_some_and(|error| error.to_string().contains("\"ghcr.io/devcontainers/features/node\"")));
+    }
 }
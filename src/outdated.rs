@@ -0,0 +1,503 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ascii_table::AsciiTable;
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::archive;
+use crate::init;
+use crate::oci_ref::OciReference;
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::{self, DevcontainerIndex};
+use crate::theme;
+use crate::workspace;
+
+#[derive(Debug, Args)]
+pub struct OutdatedArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Compare only the template noted in the generation header of devcontainer.json with the index.
+    #[arg(long, conflicts_with = "features")]
+    index: bool,
+
+    /// Compare only the pinned versions of features with the index.
+    #[arg(long)]
+    features: bool,
+
+    /// List the changed files of a newer template without their diff.
+    #[arg(long)]
+    name_only: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A file of the template that differs between the recorded and the latest version.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct ChangedFile {
+    pub path: String,
+    pub change: FileChange,
+}
+
+/// The template a devcontainer.json was generated from, compared with the index.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TemplateDrift {
+    /// The devcontainer.json with the generation header, relative to the workspace.
+    pub file: String,
+    /// The template as noted by the generation header.
+    pub reference: String,
+    pub digest: Option<String>,
+    /// Version of the recorded template, missing when it could not be pulled.
+    pub current: Option<String>,
+    /// Version of the template in the index.
+    pub latest: String,
+    pub outdated: bool,
+    /// Files of the template changed since the recorded version, empty when it is up to date.
+    pub changes: Vec<ChangedFile>,
+}
+
+/// Where the latest version of a feature was looked up.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum VersionSource {
+    Index,
+    /// The tags of the registry, for features the index does not have.
+    Registry,
+}
+
+/// A feature of a devcontainer.json, its pinned version compared with the latest one.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FeatureDrift {
+    /// The devcontainer.json using the feature, relative to the workspace.
+    pub file: String,
+    /// The key of the feature as written in the `features` object.
+    pub key: String,
+    /// The tag or digest the key pins.
+    pub pinned: String,
+    /// Version the pin stands for, missing when it follows a major or minor version.
+    pub current: Option<String>,
+    /// Latest version, missing when it could not be looked up.
+    pub latest: Option<String>,
+    pub source: VersionSource,
+    /// Whether the latest version is outside of the pin.
+    pub outdated: bool,
+}
+
+/// Result of `tyedev outdated`.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Outdated {
+    pub templates: Vec<TemplateDrift>,
+    pub features: Vec<FeatureDrift>,
+}
+
+/// Whether `latest` is a newer version than `current`. Versions that are not semantic versions are newer when they
+/// differ at all.
+pub fn is_newer(current: &str, latest: &str) -> bool {
+    match (semver::Version::parse(current), semver::Version::parse(latest)) {
+        (Ok(current), Ok(latest)) => latest > current,
+        _ => current != latest,
+    }
+}
+
+/// Whether a feature pinned to `tag` gets the `latest` version. A tag such as `1` or `1.2` follows every release of
+/// that major or minor version, while `latest` follows all of them.
+pub fn pin_admits(tag: &str, latest: &str) -> bool {
+    if tag == "latest" {
+        return true;
+    }
+
+    let parts: Result<Vec<u64>, _> = tag.split('.').map(str::parse::<u64>).collect();
+
+    match (parts, semver::Version::parse(latest)) {
+        (Ok(parts), Ok(latest)) if parts.len() <= 3 => parts
+            .iter()
+            .zip([latest.major, latest.minor, latest.patch])
+            .all(|(part, component)| *part == component),
+        _ => tag == latest,
+    }
+}
+
+/// Files of a template archive by path, without the leading `./`.
+fn archive_files(bytes: &[u8]) -> std::io::Result<BTreeMap<String, Vec<u8>>> {
+    Ok(archive::read_files(bytes, |_, _| true)?.into_iter().collect())
+}
+
+/// Every path that was added, removed, or modified from `current` to `latest`, in path order.
+fn changed_files(current: &BTreeMap<String, Vec<u8>>, latest: &BTreeMap<String, Vec<u8>>) -> Vec<ChangedFile> {
+    let mut paths: Vec<&String> = current.keys().chain(latest.keys()).collect();
+
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let change = match (current.get(path), latest.get(path)) {
+                (Some(_), None) => FileChange::Removed,
+                (None, Some(_)) => FileChange::Added,
+                (Some(current), Some(latest)) if current != latest => FileChange::Modified,
+                _ => return None,
+            };
+
+            Some(ChangedFile {
+                path: path.clone(),
+                change,
+            })
+        })
+        .collect()
+}
+
+/// The recorded artifact, pinned by its digest when the header has one, since a tag may have moved on since.
+pub fn recorded_reference(reference: &OciReference, digest: Option<&str>) -> anyhow::Result<OciReference> {
+    match digest {
+        Some(digest) if !reference.is_local() => format!("{}@{digest}", reference.id()).parse(),
+        _ => Ok(reference.clone()),
+    }
+}
+
+/// Compare the template of one devcontainer.json with the index, along with the files of both archives when the index
+/// is ahead. Nothing is returned for files without a generation header or with a template the index does not have.
+async fn template_drift(
+    index: &DevcontainerIndex,
+    workspace: &Path,
+    file: &Path,
+    name_only: bool,
+) -> anyhow::Result<Option<(TemplateDrift, Vec<(String, Vec<u8>, Vec<u8>)>)>> {
+    let display_name = file.strip_prefix(workspace).unwrap_or(file).display().to_string();
+    let Some((reference, digest)) = init::recorded_template(&fs::read_to_string(file)?) else {
+        log::info!("{display_name} has no generation header that notes a template");
+        return Ok(None);
+    };
+    let template_ref: OciReference = reference.parse()?;
+    let Some(template) = index.get_template(&template_ref.id()) else {
+        log::warn!("{display_name}: {} is not in the index", template_ref.id());
+        return Ok(None);
+    };
+    let recorded_ref = recorded_reference(&template_ref, digest.as_deref())?;
+    let recorded_archive = match registry::pull_archive_bytes(&recorded_ref).await {
+        Ok(bytes) => Some(bytes),
+        Err(error) => {
+            log::warn!("Unable to pull {recorded_ref}: {error:#}");
+            None
+        },
+    };
+    let current = match &recorded_archive {
+        Some(bytes) => {
+            let configuration = registry::template_configuration_bytes(bytes)?;
+            Some(serde_json::from_slice::<registry::Template>(&configuration)?.version)
+        },
+        None => None,
+    };
+    let outdated = current
+        .as_deref()
+        .is_none_or(|current| is_newer(current, &template.version));
+    let mut changes = Vec::new();
+    let mut diffs = Vec::new();
+
+    if let (true, Some(recorded_archive)) = (outdated, &recorded_archive) {
+        let latest_ref: OciReference = format!("{}:{}", template_ref.id(), template.version).parse()?;
+        let current_files = archive_files(recorded_archive)?;
+        let latest_files = archive_files(&registry::pull_archive_bytes(&latest_ref).await?)?;
+
+        changes = changed_files(&current_files, &latest_files);
+
+        if !name_only {
+            let empty = Vec::new();
+
+            diffs = changes
+                .iter()
+                .map(|changed| {
+                    (
+                        changed.path.clone(),
+                        current_files.get(&changed.path).unwrap_or(&empty).clone(),
+                        latest_files.get(&changed.path).unwrap_or(&empty).clone(),
+                    )
+                })
+                .collect();
+        }
+    }
+
+    let drift = TemplateDrift {
+        file: display_name,
+        reference,
+        digest,
+        current,
+        latest: template.version.clone(),
+        outdated,
+        changes,
+    };
+
+    Ok(Some((drift, diffs)))
+}
+
+/// Compare each feature of one devcontainer.json pinned in a registry with the index, or with the tags of its registry
+/// when the index does not have it.
+pub async fn feature_drift(
+    index: &DevcontainerIndex,
+    workspace: &Path,
+    file: &Path,
+) -> anyhow::Result<Vec<FeatureDrift>> {
+    let display_name = file.strip_prefix(workspace).unwrap_or(file).display().to_string();
+    let config = workspace::read_config(file)?;
+    let directory = file.parent().unwrap_or(workspace);
+    let mut features = Vec::new();
+    let Some(Value::Object(entries)) = config.get("features") else {
+        return Ok(features);
+    };
+
+    for key in entries.keys() {
+        let feature_ref = match workspace::FeatureSource::parse(key, directory)? {
+            workspace::FeatureSource::Registry(feature_ref) if !feature_ref.is_local() => feature_ref,
+            _ => {
+                log::info!("{display_name}: {key} is not in a registry, skipped");
+                continue;
+            },
+        };
+        let (latest, source) = match index.get_feature(&feature_ref.id()) {
+            Some(feature) => (Some(feature.version.clone()), VersionSource::Index),
+            None => match registry::latest_version_tag(&feature_ref).await {
+                Ok(latest) => (latest, VersionSource::Registry),
+                Err(error) => {
+                    log::warn!("Unable to list the versions of {}: {error:#}", feature_ref.id());
+                    (None, VersionSource::Registry)
+                },
+            },
+        };
+        let pinned = feature_ref.tag_name();
+        let current = match feature_ref.digest() {
+            Some(_) => match registry::pull_feature_configuration(&feature_ref).await {
+                Ok(feature) => Some(feature.version),
+                Err(error) => {
+                    log::warn!("Unable to pull {feature_ref}: {error:#}");
+                    None
+                },
+            },
+            None => semver::Version::parse(&pinned).ok().map(|_| pinned.clone()),
+        };
+        let outdated = match (&latest, feature_ref.digest(), &current) {
+            (Some(latest), Some(_), Some(current)) => is_newer(current, latest),
+            (Some(latest), None, _) => !pin_admits(&pinned, latest),
+            _ => false,
+        };
+
+        features.push(FeatureDrift {
+            file: display_name.clone(),
+            key: key.clone(),
+            pinned,
+            current,
+            latest,
+            source,
+            outdated,
+        });
+    }
+
+    Ok(features)
+}
+
+fn print_features(features: &[FeatureDrift]) {
+    if features.is_empty() {
+        println!("No feature of the workspace is pinned in a registry.");
+        return;
+    }
+
+    let header = &theme::current().header;
+    let data: Vec<[String; 5]> = features
+        .iter()
+        .map(|drift| {
+            let latest = match (&drift.latest, drift.source) {
+                (Some(latest), VersionSource::Index) => latest.clone(),
+                (Some(latest), VersionSource::Registry) => format!("{latest} (registry)"),
+                (None, _) => "?".to_string(),
+            };
+            let status = if drift.outdated { "outdated" } else { "" };
+
+            [
+                drift.file.clone(),
+                drift.key.clone(),
+                drift.current.clone().unwrap_or_else(|| drift.pinned.clone()),
+                latest,
+                status.to_string(),
+            ]
+        })
+        .collect();
+    let mut table = AsciiTable::default();
+
+    table.column(0).set_header(header.paint("File"));
+    table.column(1).set_header(header.paint("Feature"));
+    table.column(2).set_header(header.paint("Current"));
+    table.column(3).set_header(header.paint("Latest"));
+    table.column(4).set_header(header.paint("Status"));
+    table.print(data);
+}
+
+fn print_templates(templates: &[TemplateDrift]) {
+    if templates.is_empty() {
+        println!("No template of the workspace could be compared with the index.");
+        return;
+    }
+
+    let header = &theme::current().header;
+    let data: Vec<[String; 4]> = templates
+        .iter()
+        .map(|drift| {
+            [
+                drift.file.clone(),
+                drift.reference.clone(),
+                drift.current.clone().unwrap_or_else(|| "?".to_string()),
+                drift.latest.clone(),
+            ]
+        })
+        .collect();
+    let mut table = AsciiTable::default();
+
+    table.column(0).set_header(header.paint("File"));
+    table.column(1).set_header(header.paint("Template"));
+    table.column(2).set_header(header.paint("Current"));
+    table.column(3).set_header(header.paint("Latest"));
+    table.print(data);
+
+    for drift in templates.iter().filter(|drift| !drift.changes.is_empty()) {
+        println!();
+        println!("{}", header.paint(format!("Changed files of {}:", drift.reference)));
+
+        for changed in &drift.changes {
+            let change = match changed.change {
+                FileChange::Added => "added",
+                FileChange::Removed => "removed",
+                FileChange::Modified => "modified",
+            };
+
+            println!("  {change:<10}{}", changed.path);
+        }
+    }
+}
+
+/// Report templates of the workspace that have a newer version in the index, with the files that changed since, and
+/// features pinned to a version older than the latest one.
+pub async fn outdated(
+    index: &DevcontainerIndex,
+    OutdatedArgs {
+        workspace_folder,
+        index: templates_only,
+        features: features_only,
+        name_only,
+    }: OutdatedArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("outdated");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let mut templates = Vec::new();
+    let mut features = Vec::new();
+    let mut diffs = Vec::new();
+
+    for file in workspace::devcontainer_files(&workspace)? {
+        if !features_only {
+            if let Some((drift, file_diffs)) = template_drift(index, &workspace, &file, name_only).await? {
+                templates.push(drift);
+                diffs.extend(file_diffs);
+            }
+        }
+
+        if !templates_only {
+            features.extend(feature_drift(index, &workspace, &file).await?);
+        }
+    }
+
+    let outdated = Outdated { templates, features };
+
+    match format {
+        OutputFormat::Json => output::print_json(Kind::Outdated, &outdated)?,
+        OutputFormat::Text => {
+            if !features_only {
+                print_templates(&outdated.templates);
+
+                for (path, current, latest) in &diffs {
+                    println!();
+                    init::print_diff(path, current, latest);
+                }
+            }
+
+            if !templates_only {
+                if !features_only {
+                    println!();
+                }
+
+                print_features(&outdated.features);
+            }
+        },
+    }
+
+    log::debug!("outdated: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed_files, is_newer, pin_admits, ChangedFile, FileChange};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("4.0.1", "4.1.0"));
+        assert!(!is_newer("4.1.0", "4.1.0"));
+        assert!(!is_newer("4.2.0", "4.1.0"));
+        assert!(is_newer("dev", "4.1.0"));
+    }
+
+    #[test]
+    fn test_pin_admits() {
+        assert!(pin_admits("1", "1.4.2"));
+        assert!(pin_admits("1.4", "1.4.2"));
+        assert!(pin_admits("latest", "2.0.0"));
+        assert!(!pin_admits("1", "2.0.0"));
+        assert!(!pin_admits("1.3", "1.4.0"));
+        assert!(!pin_admits("1.4.1", "1.4.2"));
+        assert!(pin_admits("dev", "dev"));
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let files = |entries: &[(&str, &str)]| -> BTreeMap<String, Vec<u8>> {
+            entries
+                .iter()
+                .map(|(path, contents)| (path.to_string(), contents.as_bytes().to_vec()))
+                .collect()
+        };
+        let current = files(&[
+            (".devcontainer/devcontainer.json", "{}"),
+            (".devcontainer/Dockerfile", "FROM rust"),
+            ("NOTES.md", "notes"),
+        ]);
+        let latest = files(&[
+            (".devcontainer/devcontainer.json", "{\"name\": \"Rust\"}"),
+            (".devcontainer/Dockerfile", "FROM rust"),
+            (".github/dependabot.yml", "version: 2"),
+        ]);
+        let changed = |path: &str, change| ChangedFile {
+            path: path.to_string(),
+            change,
+        };
+
+        assert_eq!(
+            changed_files(&current, &latest),
+            vec![
+                changed(".devcontainer/devcontainer.json", FileChange::Modified),
+                changed(".github/dependabot.yml", FileChange::Added),
+                changed("NOTES.md", FileChange::Removed),
+            ]
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Version of the JSON envelope. Incremented only for changes that may break existing consumers.
+pub const API_VERSION: u32 = 1;
+
+/// Environment alternative to the `--output` flag.
+pub const OUTPUT_VARIABLE: &str = "TYEDEV_OUTPUT";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable tables and text.
+    #[default]
+    Text,
+    /// A versioned JSON envelope, stable across releases.
+    Json,
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// What the `data` of an envelope holds.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum Kind {
+    AuditFindings,
+    Collection,
+    Collections,
+    CommunityResults,
+    Error,
+    Feature,
+    IndexDiff,
+    IndexStats,
+    InitBatchReport,
+    InitReport,
+    Licenses,
+    LintFindings,
+    Outdated,
+    Resolutions,
+    SearchResults,
+    Status,
+    Template,
+    Validation,
+    Verification,
+    WorkspaceConfig,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope<'d, T: Serialize + ?Sized> {
+    api_version: u32,
+    kind: Kind,
+    data: &'d T,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ErrorData {
+    /// The outermost error message.
+    message: String,
+    /// Underlying errors, from the outermost to the root cause.
+    causes: Vec<String>,
+}
+
+/// Print rows as tab separated values, without borders or headers, to compose with `cut`, `awk`, and `fzf`.
+///
+/// A reader that closes the pipe early, such as `head`, quietly ends the output.
+pub fn print_plain<R, V>(rows: impl IntoIterator<Item = R>)
+where
+    R: IntoIterator<Item = V>,
+    V: Display,
+{
+    let mut stdout = io::stdout().lock();
+
+    for row in rows {
+        let values: Vec<String> = row
+            .into_iter()
+            .map(|value| value.to_string().replace(['\t', '\n', '\r'], " "))
+            .collect();
+
+        match writeln!(stdout, "{}", values.join("\t")) {
+            Ok(()) => {},
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => return,
+            Err(error) => {
+                log::error!("{error}");
+                return;
+            },
+        }
+    }
+}
+
+/// Print `data` wrapped in the versioned envelope, as a single line.
+pub fn print_json<T: Serialize + ?Sized>(kind: Kind, data: &T) -> serde_json::Result<()> {
+    let envelope = Envelope {
+        api_version: API_VERSION,
+        kind,
+        data,
+    };
+
+    println!("{}", serde_json::to_string(&envelope)?);
+
+    Ok(())
+}
+
+/// A command that failed after printing its JSON result, which already tells what failed. The run ends with a failing
+/// exit status, without an error envelope after the result that would break consumers.
+#[derive(Debug)]
+pub struct ResultPrinted(anyhow::Error);
+
+impl Display for ResultPrinted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ResultPrinted {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// The error of a command that fails once its result is printed in `format`.
+pub fn failed(format: OutputFormat, error: impl Into<anyhow::Error>) -> anyhow::Error {
+    match format {
+        OutputFormat::Json => ResultPrinted(error.into()).into(),
+        OutputFormat::Text => error.into(),
+    }
+}
+
+/// Print a failure as an envelope of kind "error", so scripts can rely on stdout being JSON.
+pub fn print_error(error: &anyhow::Error) -> serde_json::Result<()> {
+    let data = ErrorData {
+        message: error.to_string(),
+        causes: error.chain().skip(1).map(|cause| cause.to_string()).collect(),
+    };
+
+    print_json(Kind::Error, &data)
+}
@@ -0,0 +1,98 @@
+use crate::registry;
+
+/// Features that only install x86_64 binaries, so the container fails to build on any other architecture.
+const X86_ONLY_FEATURES: [&str; 1] = ["ghcr.io/devcontainers/features/nvidia-cuda"];
+
+/// Architecture of this machine, named as in the platforms of an OCI image.
+pub fn host_architecture() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "x86" => "386",
+        "aarch64" => "arm64",
+        "powerpc64" => "ppc64le",
+        arch => arch,
+    }
+}
+
+/// Warning for an image that is not published for `host`. Nothing is known about an image without architectures.
+fn image_warning(image: &str, architectures: &[String], host: &str) -> Option<String> {
+    if architectures.is_empty() || architectures.iter().any(|architecture| architecture == host) {
+        return None;
+    }
+
+    Some(format!(
+        "The image {image} is published for {}, but this machine is {host}",
+        architectures.join(", ")
+    ))
+}
+
+/// Warnings for features known to support only x86_64, when `host` is something else.
+fn feature_warnings<'f>(feature_ids: impl IntoIterator<Item = &'f str>, host: &str) -> Vec<String> {
+    if host == "amd64" {
+        return Vec::new();
+    }
+
+    feature_ids
+        .into_iter()
+        .filter(|id| X86_ONLY_FEATURES.contains(id))
+        .map(|id| format!("The feature {id} only supports amd64, but this machine is {host}"))
+        .collect()
+}
+
+/// Warn about a base image or features that will not build on this machine. Failing to look up the image is not
+/// an error, the check is only advice.
+pub async fn check_compatibility<'f>(image: Option<&str>, feature_ids: impl IntoIterator<Item = &'f str>) {
+    log::debug!("check_compatibility");
+
+    let host = host_architecture();
+
+    if let Some(image) = image {
+        match tokio::time::timeout(registry::lookup_timeout(), registry::image_architectures(image)).await {
+            Ok(Ok(architectures)) => {
+                log::debug!("check_compatibility: {image} is published for {architectures:?}");
+
+                if let Some(warning) = image_warning(image, &architectures, host) {
+                    log::warn!("{warning}");
+                }
+            },
+            Ok(Err(error)) => log::info!("Unable to check the platforms of {image}: {error:#}"),
+            Err(_) => log::info!("Unable to check the platforms of {image}: timed out"),
+        }
+    }
+
+    for warning in feature_warnings(feature_ids, host) {
+        log::warn!("{warning}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{feature_warnings, image_warning};
+
+    #[test]
+    fn test_image_warning() {
+        let image = "mcr.microsoft.com/devcontainers/base:bookworm";
+        let multi_arch = vec!["amd64".to_string(), "arm64".to_string()];
+        let x86_only = vec!["amd64".to_string()];
+
+        assert_eq!(image_warning(image, &multi_arch, "arm64"), None);
+        assert_eq!(image_warning(image, &[], "arm64"), None);
+        assert_eq!(
+            image_warning(image, &x86_only, "arm64"),
+            Some(format!(
+                "The image {image} is published for amd64, but this machine is arm64"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_feature_warnings() {
+        let ids = [
+            "ghcr.io/devcontainers/features/node",
+            "ghcr.io/devcontainers/features/nvidia-cuda",
+        ];
+
+        assert!(feature_warnings(ids, "amd64").is_empty());
+        assert_eq!(feature_warnings(ids, "arm64").len(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+use log::LevelFilter;
+
+use crate::cache;
+use crate::output::{self, OutputFormat};
+
+/// Executables named `tyedev-<name>` extend the command line, similar to cargo and git.
+const PLUGIN_PREFIX: &str = concat!(env!("CARGO_BIN_NAME"), "-");
+
+/// Values handed to a plugin through the environment.
+pub struct PluginContext<'a> {
+    pub data_dir: &'a Path,
+    pub index_file: &'a Path,
+    pub log_level: LevelFilter,
+    pub output: OutputFormat,
+    pub no_cache: bool,
+    pub pull_index: bool,
+}
+
+fn executable_names(name: &OsStr) -> Vec<OsString> {
+    let mut base = OsString::from(PLUGIN_PREFIX);
+    base.push(name);
+
+    if cfg!(windows) {
+        let extensions = env::var_os("PATHEXT").unwrap_or_else(|| OsString::from(".COM;.EXE;.BAT;.CMD"));
+        let extensions = extensions.to_string_lossy().into_owned();
+
+        extensions
+            .split(';')
+            .filter(|extension| !extension.is_empty())
+            .map(|extension| {
+                let mut filename = base.clone();
+                filename.push(extension.to_lowercase());
+                filename
+            })
+            .collect()
+    } else {
+        vec![base]
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Windows runs any file with an extension of `PATHEXT`.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Search `PATH` for the plugin executable of the given subcommand name.
+pub fn find_plugin(name: &OsStr) -> Option<PathBuf> {
+    log::debug!("find_plugin");
+
+    let paths = env::var_os("PATH")?;
+    let names = executable_names(name);
+
+    env::split_paths(&paths)
+        .flat_map(|directory| names.iter().map(move |filename| directory.join(filename)))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Run the plugin for `args[0]`, passing along the remaining arguments.
+pub fn run_plugin(args: &[OsString], context: &PluginContext) -> anyhow::Result<ExitStatus> {
+    log::debug!("run_plugin");
+
+    let (name, plugin_args) = args
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Missing subcommand name"))?;
+    let executable = find_plugin(name).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "No such command: `{}`. External commands are executables named `{PLUGIN_PREFIX}{}` on PATH.",
+                name.to_string_lossy(),
+                name.to_string_lossy(),
+            ),
+        )
+    })?;
+
+    log::debug!("run_plugin: {}", executable.display());
+
+    let status = Command::new(&executable)
+        .args(plugin_args)
+        .env("TYEDEV", env::current_exe()?)
+        .env("TYEDEV_DATA_DIR", context.data_dir)
+        .env("TYEDEV_INDEX_FILE", context.index_file)
+        .env("TYEDEV_LOG_LEVEL", context.log_level.to_string())
+        .env(output::OUTPUT_VARIABLE, context.output.to_string())
+        .env(cache::NO_CACHE_VARIABLE, context.no_cache.to_string())
+        .env("TYEDEV_PULL_INDEX", context.pull_index.to_string())
+        .status()?;
+
+    Ok(status)
+}
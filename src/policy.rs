@@ -0,0 +1,254 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::oci_ref::OciReference;
+use crate::registry::{self, Feature};
+
+/// Name of the policy file, inside the `tyedev` config directory.
+const POLICY_FILENAME: &str = "policy.toml";
+
+/// Environment alternative to the default policy file location.
+pub const POLICY_VARIABLE: &str = "TYEDEV_POLICY";
+
+/// Constraints on the features and templates that may be used, read from `policy.toml`.
+///
+/// Patterns match the id of a reference, such as `ghcr.io/devcontainers/features/node`. A pattern matches the id
+/// itself and everything below it, so `ghcr.io` names a registry and `ghcr.io/devcontainers` an owner. A `*`
+/// matches any run of characters.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Policy {
+    /// When not empty, only ids matching one of these patterns are allowed.
+    pub allow: Vec<String>,
+    /// Ids matching any of these patterns are refused, even when allowed.
+    pub deny: Vec<String>,
+    /// Registry references must be pinned by digest, such as `…/node@sha256:…`.
+    pub require_digest: bool,
+    /// Refuse features that run the container privileged.
+    pub forbid_privileged: bool,
+    /// Allow artifacts read from disk, which are not subject to the patterns.
+    pub allow_local: bool,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            require_digest: false,
+            forbid_privileged: false,
+            allow_local: true,
+        }
+    }
+}
+
+/// Whether `pattern` matches `text` in full, with `*` matching any run of characters.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Whether the pattern matches the id, or a registry or namespace containing it.
+fn pattern_matches(pattern: &str, id: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+
+    glob_match(pattern, id) || glob_match(&format!("{pattern}/*"), id)
+}
+
+impl Policy {
+    /// Reasons the reference violates the policy, empty when it is allowed.
+    pub fn check_reference(&self, reference: &OciReference) -> Vec<String> {
+        if reference.is_local() {
+            return if self.allow_local {
+                Vec::new()
+            } else {
+                vec![format!(
+                    "{reference} is read from disk, which the policy does not allow"
+                )]
+            };
+        }
+
+        let id = reference.id();
+        let mut violations = Vec::new();
+
+        if !self.allow.is_empty() && !self.allow.iter().any(|pattern| pattern_matches(pattern, &id)) {
+            violations.push(format!("{id} is not in the allow list of the policy"));
+        }
+
+        if let Some(pattern) = self.deny.iter().find(|pattern| pattern_matches(pattern, &id)) {
+            violations.push(format!("{id} is denied by the policy pattern \"{pattern}\""));
+        }
+
+        if self.require_digest && reference.digest().is_none() {
+            violations.push(format!("{reference} must be pinned by digest according to the policy"));
+        }
+
+        violations
+    }
+
+    /// Reasons the configuration of a feature violates the policy, which is only known once it has been looked up.
+    pub fn check_feature(&self, reference: &OciReference, feature: &Feature) -> Vec<String> {
+        let mut violations = Vec::new();
+
+        if self.forbid_privileged && feature.privileged == Some(true) {
+            violations.push(format!(
+                "{} runs the container privileged, which the policy forbids",
+                reference.id()
+            ));
+        }
+
+        violations
+    }
+
+    /// Refuse keys of a `features` object that are about to be written, as init refuses the features it adds. The
+    /// configuration of each feature is only looked up when the policy forbids privileged features. Keys that are not
+    /// OCI references, such as the URL of a tarball, are left alone.
+    pub async fn enforce_feature_keys(&self, keys: &[String]) -> anyhow::Result<()> {
+        log::debug!("Policy::enforce_feature_keys");
+
+        let references: Vec<OciReference> = keys.iter().filter_map(|key| key.parse().ok()).collect();
+        let mut violations: Vec<String> = (references.iter())
+            .flat_map(|reference| self.check_reference(reference))
+            .collect();
+
+        if violations.is_empty() && self.forbid_privileged {
+            for reference in &references {
+                let feature = registry::pull_feature_configuration(reference).await?;
+                violations.extend(self.check_feature(reference, &feature));
+            }
+        }
+
+        Ok(Self::enforce(violations)?)
+    }
+
+    /// Refuse to continue when there are any violations, listing all of them.
+    pub fn enforce(violations: Vec<String>) -> io::Result<()> {
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("Policy violation:\n  {}", violations.join("\n  ")),
+        ))
+    }
+}
+
+/// Location of `policy.toml`, either given by the environment or within the platform config directory.
+fn policy_file() -> Option<PathBuf> {
+    match std::env::var_os(POLICY_VARIABLE) {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        _ => dirs::config_dir().map(|path| path.join(env!("CARGO_BIN_NAME")).join(POLICY_FILENAME)),
+    }
+}
+
+/// Read the policy. Without a policy file everything is allowed.
+pub fn read_policy() -> anyhow::Result<Policy> {
+    log::debug!("read_policy");
+
+    let Some(filename) = policy_file() else {
+        return Ok(Policy::default());
+    };
+
+    let contents = match fs::read_to_string(&filename) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Policy::default()),
+        Err(error) => Err(error)?,
+    };
+
+    let policy = toml::from_str(&contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid policy file {}: {error}", filename.display()),
+        )
+    })?;
+
+    Ok(policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, Policy};
+    use crate::oci_ref::OciReference;
+    use crate::registry::Feature;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(
+            "ghcr.io/devcontainers/*",
+            "ghcr.io/devcontainers/features/node"
+        ));
+        assert!(glob_match("*/features/node", "ghcr.io/devcontainers/features/node"));
+        assert!(glob_match(
+            "ghcr.io/*/features/*",
+            "ghcr.io/devcontainers/features/node"
+        ));
+        assert!(!glob_match(
+            "ghcr.io/devcontainers",
+            "ghcr.io/devcontainers/features/node"
+        ));
+        assert!(!glob_match("docker.io/*", "ghcr.io/devcontainers/features/node"));
+    }
+
+    #[test]
+    fn test_check_feature() -> anyhow::Result<()> {
+        let policy: Policy = toml::from_str(
+            r#"
+            allow = ["ghcr.io/devcontainers"]
+            deny = ["ghcr.io/devcontainers/features/docker-*"]
+            forbid-privileged = true
+            allow-local = false
+            "#,
+        )?;
+        let check = |reference: &str, privileged: bool| -> anyhow::Result<usize> {
+            let reference: OciReference = reference.parse()?;
+            let feature = Feature {
+                privileged: Some(privileged),
+                ..Default::default()
+            };
+
+            Ok(policy.check_reference(&reference).len() + policy.check_feature(&reference, &feature).len())
+        };
+
+        assert_eq!(check("ghcr.io/devcontainers/features/node:1", false)?, 0);
+        assert_eq!(check("ghcr.io/devcontainers/features/docker-in-docker:2", true)?, 2);
+        assert_eq!(check("ghcr.io/devcontainers-extra/features/just:1", false)?, 1);
+        assert_eq!(check("file:///tmp/feature.tgz", false)?, 1);
+
+        let pinned: Policy = toml::from_str("require-digest = true")?;
+        let digest = format!("sha256:{}", "0".repeat(64));
+
+        assert_eq!(
+            pinned
+                .check_reference(&"ghcr.io/devcontainers/features/node:1".parse()?)
+                .len(),
+            1
+        );
+        assert!(pinned
+            .check_reference(&format!("ghcr.io/devcontainers/features/node@{digest}").parse()?)
+            .is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,571 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+
+use inquire::{
+    autocompletion::Replacement, Autocomplete, Confirm, CustomUserError, Editor, MultiSelect, Password,
+    PasswordDisplayMode, Select, Text,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write;
+
+/// Whether the user can be prompted. Prompts read answers from stdin and are drawn on stderr, neither works through a
+/// pipe or in CI.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Every interaction with the user goes through this trait, so that the same flow can be driven by a terminal, by
+/// defaults, or by a script.
+pub trait Prompter {
+    /// Ask a yes or no question.
+    fn confirm(&mut self, message: &str, default: Option<bool>) -> anyhow::Result<bool>;
+
+    /// Pick one of `options`, returning the index of the chosen option.
+    fn select(&mut self, message: &str, options: &[String], starting_cursor: usize) -> anyhow::Result<usize>;
+
+    /// Pick any number of `options`, those at `defaults` checked to begin with, returning the indexes of the chosen
+    /// options in order.
+    fn multi_select(&mut self, message: &str, options: &[String], defaults: &[usize]) -> anyhow::Result<Vec<usize>>;
+
+    /// Like `select`, with a line of `details` shown along with each option where there is a terminal to show it.
+    fn select_with_details(
+        &mut self,
+        message: &str,
+        options: &[String],
+        _details: &[String],
+        starting_cursor: usize,
+    ) -> anyhow::Result<usize> {
+        self.select(message, options, starting_cursor)
+    }
+
+    /// Like `multi_select`, with a line of `details` shown along with each option where there is a terminal to show it.
+    fn multi_select_with_details(
+        &mut self,
+        message: &str,
+        options: &[String],
+        _details: &[String],
+        defaults: &[usize],
+    ) -> anyhow::Result<Vec<usize>> {
+        self.multi_select(message, options, defaults)
+    }
+
+    /// Free form text input, optionally with suggestions.
+    fn text(
+        &mut self,
+        message: &str,
+        default: Option<&str>,
+        autocomplete: Option<Box<dyn Autocomplete>>,
+    ) -> anyhow::Result<String>;
+
+    /// Secret text input, such as a token, which is masked as it is typed.
+    fn password(&mut self, message: &str) -> anyhow::Result<String>;
+
+    /// Edit `text` in the user's editor, returning the saved text. `extension` selects the syntax of the editor.
+    fn editor(&mut self, message: &str, text: &str, extension: &str) -> anyhow::Result<String>;
+}
+
+/// Wrapper that allows a boxed autocomplete to be handed back to inquire.
+#[derive(Clone)]
+struct BoxedAutocomplete(Box<dyn Autocomplete>);
+
+impl Autocomplete for BoxedAutocomplete {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        self.0.get_suggestions(input)
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        self.0.get_completion(input, highlighted_suggestion)
+    }
+}
+
+/// An option of a terminal prompt followed by its details, which typing filters by as well.
+struct DetailedOption<'o> {
+    label: &'o str,
+    details: Option<&'o String>,
+}
+
+impl Display for DetailedOption<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.details.filter(|details| !details.is_empty()) {
+            Some(details) => write!(f, "{} — {details}", self.label),
+            None => write!(f, "{}", self.label),
+        }
+    }
+}
+
+fn detailed_options<'o>(options: &'o [String], details: &'o [String]) -> Vec<DetailedOption<'o>> {
+    (options.iter().enumerate())
+        .map(|(index, label)| DetailedOption {
+            label,
+            details: details.get(index),
+        })
+        .collect()
+}
+
+/// Prompt on the terminal using `inquire`.
+#[derive(Debug, Default)]
+pub struct InteractivePrompter;
+
+impl Prompter for InteractivePrompter {
+    fn confirm(&mut self, message: &str, default: Option<bool>) -> anyhow::Result<bool> {
+        let prompt = Confirm::new(message);
+        let prompt = match default {
+            Some(value) => prompt.with_default(value),
+            None => prompt,
+        };
+
+        Ok(prompt.prompt()?)
+    }
+
+    fn select(&mut self, message: &str, options: &[String], starting_cursor: usize) -> anyhow::Result<usize> {
+        let choices = options.iter().collect();
+        let answer = Select::new(message, choices)
+            .with_starting_cursor(starting_cursor)
+            .raw_prompt()?;
+
+        Ok(answer.index)
+    }
+
+    fn multi_select(&mut self, message: &str, options: &[String], defaults: &[usize]) -> anyhow::Result<Vec<usize>> {
+        let choices = options.iter().collect();
+        let answer = MultiSelect::new(message, choices).with_default(defaults).raw_prompt()?;
+
+        Ok(answer.into_iter().map(|choice| choice.index).collect())
+    }
+
+    fn select_with_details(
+        &mut self,
+        message: &str,
+        options: &[String],
+        details: &[String],
+        starting_cursor: usize,
+    ) -> anyhow::Result<usize> {
+        let answer = Select::new(message, detailed_options(options, details))
+            .with_starting_cursor(starting_cursor)
+            .with_formatter(&|choice| choice.value.label.to_string())
+            .raw_prompt()?;
+
+        Ok(answer.index)
+    }
+
+    fn multi_select_with_details(
+        &mut self,
+        message: &str,
+        options: &[String],
+        details: &[String],
+        defaults: &[usize],
+    ) -> anyhow::Result<Vec<usize>> {
+        let answer = MultiSelect::new(message, detailed_options(options, details))
+            .with_default(defaults)
+            .with_formatter(&|choices| {
+                (choices.iter())
+                    .map(|choice| choice.value.label)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .raw_prompt()?;
+
+        Ok(answer.into_iter().map(|choice| choice.index).collect())
+    }
+
+    fn text(
+        &mut self,
+        message: &str,
+        default: Option<&str>,
+        autocomplete: Option<Box<dyn Autocomplete>>,
+    ) -> anyhow::Result<String> {
+        let prompt = Text::new(message);
+        let prompt = match default {
+            Some(value) => prompt.with_default(value),
+            None => prompt,
+        };
+        let prompt = match autocomplete {
+            Some(ac) => prompt.with_autocomplete(BoxedAutocomplete(ac)),
+            None => prompt,
+        };
+
+        Ok(prompt.prompt()?)
+    }
+
+    fn password(&mut self, message: &str) -> anyhow::Result<String> {
+        let answer = Password::new(message)
+            .with_display_mode(PasswordDisplayMode::Masked)
+            .without_confirmation()
+            .prompt()?;
+
+        Ok(answer)
+    }
+
+    fn editor(&mut self, message: &str, text: &str, extension: &str) -> anyhow::Result<String> {
+        let answer = Editor::new(message)
+            .with_predefined_text(text)
+            .with_file_extension(extension)
+            .prompt()?;
+
+        Ok(answer)
+    }
+}
+
+/// Accept the default answer of every prompt without asking.
+#[derive(Debug, Default)]
+pub struct DefaultPrompter;
+
+impl Prompter for DefaultPrompter {
+    fn confirm(&mut self, message: &str, default: Option<bool>) -> anyhow::Result<bool> {
+        let answer = default.unwrap_or(false);
+        log::debug!("DefaultPrompter::confirm: {message} {answer}");
+        Ok(answer)
+    }
+
+    fn select(&mut self, message: &str, options: &[String], starting_cursor: usize) -> anyhow::Result<usize> {
+        if starting_cursor >= options.len() {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("No default choice available for: {message}"),
+            ))?
+        }
+
+        log::debug!("DefaultPrompter::select: {message} {}", options[starting_cursor]);
+        Ok(starting_cursor)
+    }
+
+    fn multi_select(&mut self, message: &str, options: &[String], defaults: &[usize]) -> anyhow::Result<Vec<usize>> {
+        let mut answer: Vec<usize> = defaults
+            .iter()
+            .copied()
+            .filter(|index| *index < options.len())
+            .collect();
+        answer.sort_unstable();
+        answer.dedup();
+        log::debug!("DefaultPrompter::multi_select: {message} {answer:?}");
+        Ok(answer)
+    }
+
+    fn text(
+        &mut self,
+        message: &str,
+        default: Option<&str>,
+        _autocomplete: Option<Box<dyn Autocomplete>>,
+    ) -> anyhow::Result<String> {
+        let answer = default.unwrap_or_default().to_string();
+        log::debug!("DefaultPrompter::text: {message} {answer}");
+        Ok(answer)
+    }
+
+    fn password(&mut self, message: &str) -> anyhow::Result<String> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("No default answer available for: {message}"),
+        ))?
+    }
+
+    fn editor(&mut self, message: &str, text: &str, _extension: &str) -> anyhow::Result<String> {
+        log::debug!("DefaultPrompter::editor: {message} unchanged");
+        Ok(text.to_string())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Answer {
+    Boolean(bool),
+    String(String),
+    /// The choices of a multi select.
+    List(Vec<String>),
+}
+
+/// A single recorded interaction.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ScriptedAnswer {
+    pub message: String,
+    pub answer: Answer,
+}
+
+/// Answer prompts from a list prepared in advance, in order.
+#[derive(Debug, Default)]
+pub struct ScriptedPrompter {
+    answers: VecDeque<ScriptedAnswer>,
+}
+
+impl ScriptedPrompter {
+    pub fn new(answers: Vec<ScriptedAnswer>) -> Self {
+        ScriptedPrompter {
+            answers: answers.into(),
+        }
+    }
+
+    /// Read a JSON array of `{"message": ..., "answer": ...}` objects.
+    pub fn from_file<P: AsRef<Path>>(filename: P) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(filename)?;
+        let answers: Vec<ScriptedAnswer> = serde_json::from_str(&contents)?;
+
+        Ok(ScriptedPrompter::new(answers))
+    }
+
+    fn next_answer(&mut self, message: &str) -> anyhow::Result<Answer> {
+        let scripted = self.answers.pop_front().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("No scripted answer left for: {message}"),
+            )
+        })?;
+
+        if scripted.message != message {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Scripted answer is for \"{}\" but the prompt is \"{message}\"",
+                    scripted.message
+                ),
+            ))?
+        }
+
+        Ok(scripted.answer)
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn confirm(&mut self, message: &str, _default: Option<bool>) -> anyhow::Result<bool> {
+        match self.next_answer(message)? {
+            Answer::Boolean(value) => Ok(value),
+            Answer::String(value) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected a boolean answer for \"{message}\", found \"{value}\""),
+            ))?,
+            Answer::List(values) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected a boolean answer for \"{message}\", found {values:?}"),
+            ))?,
+        }
+    }
+
+    fn select(&mut self, message: &str, options: &[String], _starting_cursor: usize) -> anyhow::Result<usize> {
+        let answer = match self.next_answer(message)? {
+            Answer::String(value) => value,
+            Answer::Boolean(value) => value.to_string(),
+            Answer::List(values) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected a single choice for \"{message}\", found {values:?}"),
+            ))?,
+        };
+
+        choice_index(message, options, &answer)
+    }
+
+    fn multi_select(&mut self, message: &str, options: &[String], _defaults: &[usize]) -> anyhow::Result<Vec<usize>> {
+        let answers = match self.next_answer(message)? {
+            Answer::List(values) => values,
+            Answer::String(value) => vec![value],
+            Answer::Boolean(value) => vec![value.to_string()],
+        };
+
+        (answers.iter())
+            .map(|answer| choice_index(message, options, answer))
+            .collect()
+    }
+
+    fn text(
+        &mut self,
+        message: &str,
+        _default: Option<&str>,
+        _autocomplete: Option<Box<dyn Autocomplete>>,
+    ) -> anyhow::Result<String> {
+        match self.next_answer(message)? {
+            Answer::String(value) => Ok(value),
+            Answer::Boolean(value) => Ok(value.to_string()),
+            Answer::List(values) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected a text answer for \"{message}\", found {values:?}"),
+            ))?,
+        }
+    }
+
+    fn password(&mut self, message: &str) -> anyhow::Result<String> {
+        self.text(message, None, None)
+    }
+
+    fn editor(&mut self, message: &str, _text: &str, _extension: &str) -> anyhow::Result<String> {
+        self.text(message, None, None)
+    }
+}
+
+/// Index of the scripted `answer` among `options`.
+fn choice_index(message: &str, options: &[String], answer: &str) -> anyhow::Result<usize> {
+    options.iter().position(|option| option == answer).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Scripted answer \"{answer}\" is not a choice for \"{message}\""),
+        )
+        .into()
+    })
+}
+
+/// Forward prompts to another prompter, keeping a copy of every answer given.
+pub struct RecordingPrompter {
+    inner: Box<dyn Prompter>,
+    answers: Vec<ScriptedAnswer>,
+}
+
+impl RecordingPrompter {
+    pub fn new(inner: Box<dyn Prompter>) -> Self {
+        RecordingPrompter {
+            inner,
+            answers: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, message: &str, answer: Answer) {
+        self.answers.push(ScriptedAnswer {
+            message: message.to_string(),
+            answer,
+        });
+    }
+
+    /// Write the answers given so far in the format read by `ScriptedPrompter::from_file`.
+    pub fn save<P: AsRef<Path>>(&self, filename: P) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(&self.answers)?;
+        atomic_write::write(filename, json)?;
+
+        Ok(())
+    }
+}
+
+impl Prompter for RecordingPrompter {
+    fn confirm(&mut self, message: &str, default: Option<bool>) -> anyhow::Result<bool> {
+        let answer = self.inner.confirm(message, default)?;
+        self.record(message, Answer::Boolean(answer));
+        Ok(answer)
+    }
+
+    fn select(&mut self, message: &str, options: &[String], starting_cursor: usize) -> anyhow::Result<usize> {
+        let answer = self.inner.select(message, options, starting_cursor)?;
+        self.record(message, Answer::String(options[answer].clone()));
+        Ok(answer)
+    }
+
+    fn multi_select(&mut self, message: &str, options: &[String], defaults: &[usize]) -> anyhow::Result<Vec<usize>> {
+        let answer = self.inner.multi_select(message, options, defaults)?;
+        let chosen = answer.iter().map(|index| options[*index].clone()).collect();
+        self.record(message, Answer::List(chosen));
+        Ok(answer)
+    }
+
+    fn select_with_details(
+        &mut self,
+        message: &str,
+        options: &[String],
+        details: &[String],
+        starting_cursor: usize,
+    ) -> anyhow::Result<usize> {
+        let answer = self
+            .inner
+            .select_with_details(message, options, details, starting_cursor)?;
+        self.record(message, Answer::String(options[answer].clone()));
+        Ok(answer)
+    }
+
+    fn multi_select_with_details(
+        &mut self,
+        message: &str,
+        options: &[String],
+        details: &[String],
+        defaults: &[usize],
+    ) -> anyhow::Result<Vec<usize>> {
+        let answer = self
+            .inner
+            .multi_select_with_details(message, options, details, defaults)?;
+        let chosen = answer.iter().map(|index| options[*index].clone()).collect();
+        self.record(message, Answer::List(chosen));
+        Ok(answer)
+    }
+
+    fn text(
+        &mut self,
+        message: &str,
+        default: Option<&str>,
+        autocomplete: Option<Box<dyn Autocomplete>>,
+    ) -> anyhow::Result<String> {
+        let answer = self.inner.text(message, default, autocomplete)?;
+        self.record(message, Answer::String(answer.clone()));
+        Ok(answer)
+    }
+
+    /// Secrets are not written to the answers file, so a replay stops at this prompt.
+    fn password(&mut self, message: &str) -> anyhow::Result<String> {
+        self.inner.password(message)
+    }
+
+    fn editor(&mut self, message: &str, text: &str, extension: &str) -> anyhow::Result<String> {
+        let answer = self.inner.editor(message, text, extension)?;
+        self.record(message, Answer::String(answer.clone()));
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Answer, DefaultPrompter, Prompter, RecordingPrompter, ScriptedAnswer, ScriptedPrompter};
+
+    #[test]
+    fn test_scripted_prompter_in_order() -> anyhow::Result<()> {
+        let mut prompter = ScriptedPrompter::new(vec![
+            ScriptedAnswer {
+                message: "Pick one:".into(),
+                answer: Answer::String("b".into()),
+            },
+            ScriptedAnswer {
+                message: "Continue?".into(),
+                answer: Answer::Boolean(false),
+            },
+        ]);
+        let options = vec!["a".to_string(), "b".to_string()];
+
+        assert_eq!(prompter.select("Pick one:", &options, 0)?, 1);
+        assert!(!prompter.confirm("Continue?", Some(true))?);
+        assert!(prompter.confirm("Continue?", Some(true)).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scripted_prompter_from_file() -> anyhow::Result<()> {
+        let filename = std::env::temp_dir().join(format!("tyedev-answers-{}.json", std::process::id()));
+        std::fs::write(&filename, r#"[{"message": "Name?", "answer": "tyedev"}]"#)?;
+
+        let mut prompter = ScriptedPrompter::from_file(&filename)?;
+
+        assert_eq!(prompter.text("Name?", None, None)?, "tyedev");
+
+        std::fs::remove_file(&filename)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_recording_prompter_replays() -> anyhow::Result<()> {
+        let filename = std::env::temp_dir().join(format!("tyedev-session-{}.json", std::process::id()));
+        let options = vec!["jammy".to_string(), "bookworm".to_string()];
+        let mut recorder = RecordingPrompter::new(Box::new(DefaultPrompter));
+
+        recorder.select("Variant?", &options, 1)?;
+        recorder.confirm("Add a feature?", None)?;
+        recorder.multi_select("Extensions?", &options, &[1, 0])?;
+        recorder.save(&filename)?;
+
+        let mut replay = ScriptedPrompter::from_file(&filename)?;
+
+        assert_eq!(replay.select("Variant?", &options, 0)?, 1);
+        assert!(!replay.confirm("Add a feature?", Some(true))?);
+        assert_eq!(replay.multi_select("Extensions?", &options, &[])?, [0, 1]);
+
+        std::fs::remove_file(&filename)?;
+        Ok(())
+    }
+}
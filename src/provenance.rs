@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::answers::Answers;
+use crate::atomic_write;
+use crate::init::InitReport;
+
+/// Name of the file at the top of a workspace that records how init generated it.
+pub const PROVENANCE_FILE: &str = ".tyedev.toml";
+
+/// The template, version, options, and features init rendered a workspace from, read by `tyedev regenerate`.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Provenance {
+    /// Reference of the template as it was given, a bare image was used when missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// Version of the template that was rendered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Digest of the template that was rendered, such as `sha256:…`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Display name of the devcontainer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Values of the template options by name.
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+    /// Features added with the values of their options, as in the `features` object of devcontainer.json.
+    #[serde(default)]
+    pub features: Map<String, Value>,
+}
+
+impl Provenance {
+    /// The provenance of the init of the report.
+    pub fn from_report(report: &InitReport) -> Self {
+        Provenance {
+            template: report.template.clone(),
+            version: report.template_version.clone(),
+            digest: report.template_digest.clone(),
+            name: report.name.clone(),
+            options: report.template_options.clone(),
+            features: (report.features.iter())
+                .map(|feature| {
+                    let reference = feature.source.as_ref().unwrap_or(&feature.reference);
+                    (reference.clone(), feature.options.clone())
+                })
+                .collect(),
+        }
+    }
+
+    /// The choices to render again, leaving the template to the caller.
+    pub fn answers(&self) -> Answers {
+        Answers {
+            template: self.template.clone(),
+            name: self.name.clone(),
+            options: (self.options.iter())
+                .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+                .collect(),
+            features: self.features.clone(),
+        }
+    }
+}
+
+fn provenance_path(workspace: &Path) -> PathBuf {
+    workspace.join(PROVENANCE_FILE)
+}
+
+/// Read the provenance of the workspace, nothing when it has none.
+pub fn read_provenance(workspace: &Path) -> anyhow::Result<Option<Provenance>> {
+    let filename = provenance_path(workspace);
+
+    log::debug!("read_provenance: {}", filename.display());
+
+    let contents = match fs::read_to_string(&filename) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(error) => Err(error)?,
+    };
+
+    let provenance = toml::from_str(&contents).map_err(|error| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Invalid provenance {}: {error}", filename.display()),
+        )
+    })?;
+
+    Ok(Some(provenance))
+}
+
+/// The provenance of the workspace, an error naming init when it has none.
+pub fn require_provenance(workspace: &Path) -> anyhow::Result<Provenance> {
+    read_provenance(workspace)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "No {PROVENANCE_FILE} in {}, it is written by `{} init`",
+                workspace.display(),
+                env!("CARGO_BIN_NAME")
+            ),
+        )
+        .into()
+    })
+}
+
+/// Write the provenance of the workspace, replacing the one there.
+pub fn write_provenance(workspace: &Path, provenance: &Provenance) -> anyhow::Result<()> {
+    let filename = provenance_path(workspace);
+
+    log::debug!("write_provenance: {}", filename.display());
+
+    let contents = format!(
+        "# Written by {} init, the choices `{} regenerate` renders again.\n{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_BIN_NAME"),
+        toml::to_string(provenance)?
+    );
+    atomic_write::write(&filename, contents)?;
+    log::info!("Wrote {}", filename.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Provenance;
+    use serde_json::json;
+
+    #[test]
+    fn test_provenance_toml() -> anyhow::Result<()> {
+        let provenance = Provenance {
+            template: Some("ghcr.io/devcontainers/templates/rust:5".into()),
+            version: Some("5.0.1".into()),
+            digest: Some("sha256:aaa".into()),
+            name: Some("Rust".into()),
+            options: [("imageVariant".to_string(), "bookworm".to_string())].into(),
+            features: json!({
+                "ghcr.io/devcontainers/features/node:1": { "version": "lts", "installYarn": false },
+                "ghcr.io/devcontainers/features/git:1": {},
+            })
+            .as_object()
+            .cloned()
+            .unwrap_or_default(),
+        };
+
+        let contents = toml::to_string(&provenance)?;
+        assert_eq!(toml::from_str::<Provenance>(&contents)?, provenance);
+
+        let answers = provenance.answers();
+        assert_eq!(answers.template, provenance.template);
+        assert_eq!(answers.options["imageVariant"], "bookworm");
+        assert_eq!(answers.features, provenance.features);
+
+        assert!(toml::from_str::<Provenance>("template = \"x\"\nworkspace = \".\"\n").is_err());
+
+        Ok(())
+    }
+}
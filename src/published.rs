@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::atomic_write;
+use crate::cache;
+use crate::oci_ref::OciReference;
+use crate::registry;
+
+/// Name of the file, within the archive cache, that remembers the publish times looked up before.
+const CACHE_FILENAME: &str = "published.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Number of manifests requested at once, so a large collection does not open a connection per entry.
+const CONCURRENT_LOOKUPS: usize = 8;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct PublishedTime {
+    /// Seconds since the unix epoch.
+    checked_at: u64,
+    /// Creation annotation of the manifest, missing when the publisher does not set one.
+    published: Option<String>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn cache_file() -> Option<PathBuf> {
+    cache::current().map(|cache| cache.directory().join(CACHE_FILENAME))
+}
+
+fn read_cache() -> HashMap<String, PublishedTime> {
+    let Some(contents) = cache_file().and_then(|filename| fs::read_to_string(filename).ok()) else {
+        return HashMap::new();
+    };
+
+    serde_json::from_str(&contents).unwrap_or_default()
+}
+
+fn write_cache(times: &HashMap<String, PublishedTime>) -> anyhow::Result<()> {
+    let Some(filename) = cache_file() else {
+        return Ok(());
+    };
+
+    if let Some(directory) = filename.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    atomic_write::write(&filename, serde_json::to_string(times)?)?;
+
+    Ok(())
+}
+
+/// Whether a cached lookup is recent enough to use.
+fn is_fresh(time: &PublishedTime, now: u64) -> bool {
+    now.saturating_sub(time.checked_at) < CHECK_INTERVAL.as_secs()
+}
+
+async fn lookup(id: String) -> (String, Option<Option<String>>) {
+    let published = match format!("{id}:latest").parse::<OciReference>() {
+        Ok(image) => match tokio::time::timeout(registry::lookup_timeout(), registry::published_time(&image)).await {
+            Ok(Ok(published)) => Some(published),
+            Ok(Err(error)) => {
+                log::info!("Unable to look up when {id} was published: {error:#}");
+                None
+            },
+            Err(_) => {
+                log::info!("Unable to look up when {id} was published: timed out");
+                None
+            },
+        },
+        Err(error) => {
+            log::info!("Unable to look up when {id} was published: {error:#}");
+            None
+        },
+    };
+
+    (id, published)
+}
+
+/// When the latest version of each id was published, as an RFC 3339 timestamp. Lookups are remembered for a day in
+/// the archive cache, failed ones are not remembered, and an id is missing from the result when its time is unknown.
+pub async fn published_times(ids: &[&str]) -> HashMap<String, String> {
+    log::debug!("published_times");
+
+    let now = now();
+    let mut times = read_cache();
+    let reuse = cache::current().is_some_and(|cache| cache.reuse());
+    let mut pending: Vec<String> = ids
+        .iter()
+        .filter(|id| !reuse || !times.get(**id).is_some_and(|time| is_fresh(time, now)))
+        .map(|id| id.to_string())
+        .collect();
+    let looked_up = !pending.is_empty();
+
+    pending.sort();
+    pending.dedup();
+
+    for chunk in pending.chunks(CONCURRENT_LOOKUPS) {
+        let mut lookups = JoinSet::new();
+
+        for id in chunk {
+            lookups.spawn(lookup(id.clone()));
+        }
+
+        while let Some(result) = lookups.join_next().await {
+            match result {
+                Ok((id, Some(published))) => {
+                    times.insert(
+                        id,
+                        PublishedTime {
+                            checked_at: now,
+                            published,
+                        },
+                    );
+                },
+                Ok((_, None)) => {},
+                Err(error) => log::info!("Unable to look up a publish time: {error}"),
+            }
+        }
+    }
+
+    if looked_up {
+        times.retain(|_, time| is_fresh(time, now));
+
+        if let Err(error) = write_cache(&times) {
+            log::warn!("Unable to remember publish times: {error:#}");
+        }
+    }
+
+    log::debug!("published_times: done");
+
+    ids.iter()
+        .filter_map(|id| {
+            let published = times.get(*id)?.published.clone()?;
+            Some((id.to_string(), published))
+        })
+        .collect()
+}
+
+/// Sort by publish time, most recent first, keeping entries of an unknown time last in their current order.
+pub fn sort_recent<T>(items: &mut [T], published: impl Fn(&T) -> Option<&str>) {
+    items.sort_by(|a, b| match (published(a), published(b)) {
+        (Some(a), Some(b)) => b.cmp(a),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
+/// The date of a publish time, for tables.
+pub fn display_date(published: Option<&str>) -> String {
+    published
+        .and_then(|published| published.get(..10))
+        .unwrap_or("-")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{display_date, is_fresh, sort_recent, PublishedTime, CHECK_INTERVAL};
+
+    #[test]
+    fn test_sort_recent() {
+        let mut items = vec![
+            ("a", None),
+            ("b", Some("2024-01-05T10:00:00Z")),
+            ("c", None),
+            ("d", Some("2025-03-01T00:00:00Z")),
+        ];
+
+        sort_recent(&mut items, |(_, published)| *published);
+
+        assert_eq!(
+            items.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            ["d", "b", "a", "c"]
+        );
+        assert_eq!(display_date(items[0].1), "2025-03-01");
+        assert_eq!(display_date(None), "-");
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let time = PublishedTime {
+            checked_at: 1_000,
+            published: None,
+        };
+
+        assert!(is_fresh(&time, 1_000 + CHECK_INTERVAL.as_secs() - 1));
+        assert!(!is_fresh(&time, 1_000 + CHECK_INTERVAL.as_secs()));
+    }
+}
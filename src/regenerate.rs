@@ -0,0 +1,104 @@
+use std::env;
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::config::Config;
+use crate::init::{self, InitArgs};
+use crate::oci_ref::OciReference;
+use crate::outdated;
+use crate::policy;
+use crate::prompt::DefaultPrompter;
+use crate::provenance::{self, Provenance};
+use crate::registry::DevcontainerIndex;
+
+#[derive(Debug, Args)]
+pub struct RegenerateArgs {
+    /// Workspace holding the `.tyedev.toml` written by init.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Render the newest version the recorded tag points to, rather than the recorded digest.
+    #[arg(long)]
+    latest: bool,
+
+    /// Template to render the recorded choices with, such as a newer major version, rather than the recorded one.
+    #[arg(short, long, value_name = "OCI_REF", conflicts_with = "latest")]
+    template_id: Option<OciReference>,
+
+    /// Print a diff of the files that would change, without writing any.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Overwrite existing files without first copying each to `<name>.bak`.
+    #[arg(long)]
+    no_backup: bool,
+}
+
+/// Render the template of the workspace again with the choices recorded by init, overwriting the files it generated.
+pub async fn regenerate(
+    index: &DevcontainerIndex,
+    RegenerateArgs {
+        workspace_folder,
+        latest,
+        template_id,
+        dry_run,
+        no_backup,
+    }: RegenerateArgs,
+    config: &Config,
+) -> anyhow::Result<()> {
+    log::debug!("regenerate");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let recorded = provenance::require_provenance(&workspace)?;
+    let template_ref = match (&template_id, &recorded.template) {
+        (Some(template_id), _) => Some(template_id.clone()),
+        (None, Some(template)) if latest => Some(template.parse()?),
+        (None, Some(template)) => Some(outdated::recorded_reference(
+            &template.parse()?,
+            recorded.digest.as_deref(),
+        )?),
+        (None, None) => None,
+    };
+
+    match (&template_ref, &recorded.version) {
+        (Some(template_ref), Some(version)) => log::info!("Regenerating from {template_ref}, recorded at {version}"),
+        (Some(template_ref), None) => log::info!("Regenerating from {template_ref}"),
+        (None, _) => log::info!("Regenerating from a bare image"),
+    }
+
+    let args = InitArgs::for_regenerate(
+        recorded.answers(),
+        template_ref,
+        workspace,
+        dry_run,
+        no_backup,
+        !config.init.header,
+    )?;
+    let policy = policy::read_policy()?;
+    let report = init::init_with_prompter(
+        index,
+        args,
+        &config.download,
+        &config.impact,
+        &policy,
+        &mut DefaultPrompter,
+    )
+    .await?;
+
+    if !dry_run {
+        let mut provenance = Provenance::from_report(&report);
+
+        // Keep the reference as it was given, so that a later `--latest` still follows its tag.
+        provenance.template = template_id
+            .map(|template_id| template_id.to_string())
+            .or(recorded.template);
+        provenance::write_provenance(&report.workspace, &provenance)?;
+    }
+
+    init::print_report(&report);
+
+    log::debug!("regenerate: done");
+
+    Ok(())
+}
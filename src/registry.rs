@@ -1,20 +1,32 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::fmt::{self, Display};
-use std::fs::{self, File};
-use std::io::{Error, ErrorKind, Write};
-use std::ops::Not;
-use std::path::Path;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use oci_client::secrets::RegistryAuth;
+use indexmap::IndexMap;
+use oci_client::client::ClientConfig;
+use oci_client::manifest::OciManifest;
 use oci_client::Client;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
+use crate::archive;
+use crate::atomic_write;
+use crate::auth;
+use crate::cache::{self, ArchiveCache};
+use crate::download::DownloadConfig;
+use crate::oci_layout;
 use crate::oci_ref::OciReference;
 
 // PartialOrd, Hash, Eq, Ord
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum DockerMountType {
     #[default]
@@ -31,7 +43,7 @@ impl Display for DockerMountType {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct DockerMount {
     pub source: String,
     pub target: String,
@@ -48,12 +60,12 @@ impl Display for DockerMount {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum LifecycleHook {
     Single(String),
     Multiple(Vec<String>),
-    Named(HashMap<String, Box<LifecycleHook>>),
+    Named(IndexMap<String, Box<LifecycleHook>>),
 }
 
 impl Default for LifecycleHook {
@@ -84,7 +96,27 @@ impl Display for LifecycleHook {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+/// A command as given on the command line: a JSON array is run without a shell, a JSON object runs its commands in
+/// parallel, and anything else is a command line for the shell.
+impl FromStr for LifecycleHook {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let trimmed = s.trim();
+
+        if trimmed.is_empty() {
+            return Err("expected a command".to_string());
+        }
+
+        if trimmed.starts_with('[') || trimmed.starts_with('{') {
+            return serde_json::from_str(trimmed).map_err(|error| format!("invalid command {s:?}: {error}"));
+        }
+
+        Ok(LifecycleHook::Single(s.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceInformation {
     pub name: String,
@@ -94,7 +126,7 @@ pub struct SourceInformation {
     pub oci_reference: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum BooleanDefaultType {
     String(String),
@@ -110,7 +142,7 @@ impl Display for BooleanDefaultType {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum StringDevOption {
     EnumValues {
@@ -173,7 +205,7 @@ impl Display for StringDevOption {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum DevOption {
     Boolean {
@@ -209,6 +241,18 @@ impl Default for DevOption {
 }
 
 impl DevOption {
+    /// A string option with neither a default nor proposals, which has no value unless one is given.
+    pub fn is_required(&self) -> bool {
+        match self {
+            DevOption::String(StringDevOption::Proposals {
+                default: None,
+                proposals,
+                ..
+            }) => proposals.as_ref().is_none_or(Vec::is_empty),
+            _ => false,
+        }
+    }
+
     pub fn configured_default(&self) -> String {
         match self {
             DevOption::Boolean { default, .. } => match default {
@@ -227,7 +271,7 @@ impl DevOption {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct Customizations(serde_json::Value);
 
 impl Customizations {
@@ -250,7 +294,7 @@ impl Customizations {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Feature {
     pub id: String,
@@ -265,9 +309,9 @@ pub struct Feature {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub keywords: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub options: Option<HashMap<String, DevOption>>,
+    pub options: Option<IndexMap<String, DevOption>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub container_env: Option<HashMap<String, String>>,
+    pub container_env: Option<IndexMap<String, String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub privileged: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -282,7 +326,11 @@ pub struct Feature {
     pub customizations: Option<Customizations>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub installs_after: Option<Vec<String>>,
+    /// Features that must be installed first, by reference, with the options to install them with.
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<IndexMap<String, JsonValue>>,
+    /// Ids the feature had before a rename, within its collection. Published metadata spells it `legacyIds`.
+    #[serde(alias = "legacyIds", skip_serializing_if = "Option::is_none")]
     pub lecagy_ids: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deprecated: Option<bool>,
@@ -298,11 +346,21 @@ pub struct Feature {
     pub post_start_command: Option<LifecycleHook>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub post_attach_command: Option<LifecycleHook>,
+    /// Only present in the index, a devcontainer-feature.json read from an archive has no owner.
+    #[serde(default)]
     pub owner: String,
+    /// Only present in the index, see `version` otherwise.
+    #[serde(default)]
     pub major_version: String,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+impl Feature {
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecated.is_some_and(|deprecated| deprecated)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum TemplateType {
     #[default]
@@ -321,7 +379,7 @@ impl Display for TemplateType {
     }
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Template {
     pub id: String,
@@ -334,7 +392,7 @@ pub struct Template {
     #[serde(rename = "licenseURL", skip_serializing_if = "Option::is_none")]
     pub license_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub options: Option<HashMap<String, DevOption>>,
+    pub options: Option<IndexMap<String, DevOption>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub platforms: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -347,10 +405,12 @@ pub struct Template {
     pub file_count: Option<i32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub feature_ids: Option<Vec<String>>,
+    /// Only present in the index, a devcontainer-template.json read from an archive has no owner.
+    #[serde(default)]
     pub owner: String,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Collection {
     pub source_information: SourceInformation,
@@ -358,7 +418,14 @@ pub struct Collection {
     pub templates: Vec<Template>,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+impl Collection {
+    /// There is one known collection that is deprecated, which is marked in the "maintainer" field.
+    pub fn is_deprecated(&self) -> bool {
+        self.source_information.maintainer.to_lowercase().contains("deprecated")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub struct DevcontainerIndex {
     collections: Vec<Collection>,
 }
@@ -368,15 +435,51 @@ impl DevcontainerIndex {
         self.collections.as_slice()
     }
 
+    /// Put `collection` in place of the one with the same OCI reference, or add it when there is none.
+    pub fn replace_collection(&mut self, collection: Collection) {
+        let oci_reference = &collection.source_information.oci_reference;
+
+        match self
+            .collections
+            .iter_mut()
+            .find(|existing| existing.source_information.oci_reference == *oci_reference)
+        {
+            Some(existing) => *existing = collection,
+            None => self.collections.push(collection),
+        }
+    }
+
     pub fn get_collection(&self, oci_reference: &str) -> Option<&Collection> {
         self.collections
             .iter()
             .find(|&collection| collection.source_information.oci_reference == oci_reference)
     }
 
+    /// Every collection, feature, and template id, deprecated or not.
+    pub fn iter_ids(&self) -> impl Iterator<Item = &str> {
+        self.collections.iter().flat_map(|collection| {
+            std::iter::once(collection.source_information.oci_reference.as_str())
+                .chain(collection.features.iter().map(|feature| feature.id.as_str()))
+                .chain(collection.templates.iter().map(|template| template.id.as_str()))
+        })
+    }
+
+    /// Whether the collection, feature, or template with the given id is deprecated, either on its own or because the
+    /// collection it belongs to is.
+    pub fn is_deprecated(&self, id: &str) -> bool {
+        self.collections.iter().any(|collection| {
+            let feature = collection.features.iter().find(|&feature| feature.id == id);
+            let is_member = collection.source_information.oci_reference == id
+                || feature.is_some()
+                || collection.templates.iter().any(|template| template.id == id);
+
+            (is_member && collection.is_deprecated()) || feature.is_some_and(Feature::is_deprecated)
+        })
+    }
+
     pub fn iter_features(&self, include_deprecated: bool) -> impl Iterator<Item = &Feature> {
         let all = |_: &&Feature| true;
-        let not_deprecated = |&feature: &&Feature| feature.deprecated.map_or(true, Not::not);
+        let not_deprecated = |&feature: &&Feature| !feature.is_deprecated();
 
         self.collections
             .iter()
@@ -390,14 +493,7 @@ impl DevcontainerIndex {
 
     pub fn iter_templates(&self, include_deprecated: bool) -> impl Iterator<Item = &Template> {
         let all = |_: &&Collection| true;
-        // There is one known collection that is deprecated, which is marked in the "maintainer" field.
-        let not_deprecated = |&collection: &&Collection| {
-            !collection
-                .source_information
-                .maintainer
-                .to_lowercase()
-                .contains("deprecated")
-        };
+        let not_deprecated = |&collection: &&Collection| !collection.is_deprecated();
 
         self.collections
             .iter()
@@ -410,46 +506,202 @@ impl DevcontainerIndex {
     }
 }
 
-/// Pull OCI Artifact "ghcr.io/devcontainers/index:latest" and download the JSON layer to the given filename.
-pub async fn pull_devcontainer_index<P: AsRef<Path>>(filename: P) -> Result<()> {
+/// Pull OCI Artifact "ghcr.io/devcontainers/index:latest" and download the JSON layer to the given filename. Pinned
+/// collections, by namespace and manifest digest, are read from their own metadata at that digest instead, so they stay
+/// the same from one pull to the next.
+pub async fn pull_devcontainer_index<P: AsRef<Path>>(filename: P, pins: &BTreeMap<String, String>) -> Result<()> {
     log::debug!("pull_devcontainer_index");
 
     let image: OciReference = "ghcr.io/devcontainers/index:latest".parse()?;
     let media_type = "application/vnd.devcontainers.index.layer.v1+json";
-    let blob = get_layer_bytes(&image, media_type)
+    let mut blob = get_layer_bytes(&image, media_type)
         .await
         .context("Failed to pull devcontainer index")?;
-    let mut file = File::create(filename)?;
 
-    file.write_all(&blob[..])?;
+    if !pins.is_empty() {
+        let mut index: DevcontainerIndex = serde_json::from_slice(&blob)?;
+
+        for (namespace, digest) in pins {
+            let image: OciReference = format!("{namespace}@{digest}").parse()?;
+            let metadata = pull_collection_metadata(&image)
+                .await
+                .with_context(|| format!("Failed to pull pinned collection {namespace}"))?;
+
+            log::info!("Pinned {namespace} at {digest}");
+            index.replace_collection(metadata.into_collection(namespace, index.get_collection(namespace)));
+        }
+
+        blob = serde_json::to_vec(&index)?;
+    }
+
+    let filename = filename.as_ref();
+
+    atomic_write::write_keeping_previous(filename, &blob[..], &previous_index_file(filename))?;
 
     log::debug!("pull_devcontainer_index: wrote {} bytes", blob.len());
 
     Ok(())
 }
 
+/// Name of the pulled index, within the data directory.
+pub const INDEX_FILENAME: &str = "devcontainer-index.json";
+
+/// Name of the index replaced by the latest pull, kept next to it for `tyedev index diff`.
+const PREVIOUS_INDEX_FILENAME: &str = "devcontainer-index.previous.json";
+
+pub fn previous_index_file(index_file: &Path) -> PathBuf {
+    index_file.with_file_name(PREVIOUS_INDEX_FILENAME)
+}
+
+/// Media type of the tar archive layer of a Feature or Template.
+const ARCHIVE_MEDIA_TYPE: &str = "application/vnd.devcontainers.layer.v1+tar";
+const COLLECTION_MEDIA_TYPE: &str = "application/vnd.devcontainers.collection.layer.v1+json";
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+static LOOKUP_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Set up the registry client shared by every pull of the process, which reuses connections, and the bearer token of
+/// each repository until it expires, across artifacts. Only the first call has an effect.
+pub fn init_client(config: &DownloadConfig) {
+    let client = Client::new(ClientConfig {
+        connect_timeout: Some(Duration::from_secs(config.connect_timeout)),
+        read_timeout: Some(Duration::from_secs(config.read_timeout)),
+        ..Default::default()
+    });
+
+    let _ = CLIENT.set(client);
+    let _ = LOOKUP_TIMEOUT.set(Duration::from_secs(config.lookup_timeout));
+}
+
+/// How long to wait for a lookup that is only advice, after which it is given up on.
+pub fn lookup_timeout() -> Duration {
+    *LOOKUP_TIMEOUT.get_or_init(|| Duration::from_secs(DownloadConfig::default().lookup_timeout))
+}
+
+/// The shared registry client, with the default configuration when [`init_client`] was not called.
+fn client() -> &'static Client {
+    CLIENT.get_or_init(|| Client::new(Default::default()))
+}
+
+/// Size of the archive layer of the given registry artifact, read from its manifest without pulling the layer.
+pub async fn archive_size(image: &OciReference) -> Result<u64> {
+    log::debug!("archive_size");
+
+    let OciReference::Registry(reference) = image else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{image} is not in a registry"),
+        ))?
+    };
+    let auth = auth::registry_auth(reference.registry())?;
+    let client = client();
+    let (manifest, _) = client
+        .pull_image_manifest(reference, &auth)
+        .await
+        .context("Failed to pull manifest")?;
+    let size = manifest
+        .layers
+        .iter()
+        .filter(|layer| layer.media_type == ARCHIVE_MEDIA_TYPE)
+        .map(|layer| u64::try_from(layer.size).unwrap_or_default())
+        .sum();
+
+    Ok(size)
+}
+
+/// The highest semantic version among the tags of the given registry artifact, for artifacts the index does not have.
+/// Tags that are not full semantic versions, such as `1` or `latest`, are left out.
+pub async fn latest_version_tag(image: &OciReference) -> Result<Option<String>> {
+    log::debug!("latest_version_tag: {image}");
+
+    let OciReference::Registry(reference) = image else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{image} is not in a registry"),
+        ))?
+    };
+    let auth = auth::registry_auth(reference.registry())?;
+    let response = client()
+        .list_tags(reference, &auth, None, None)
+        .await
+        .context("Failed to list tags")?;
+    let latest = response
+        .tags
+        .iter()
+        .filter_map(|tag| semver::Version::parse(tag).ok())
+        .filter(|version| version.pre.is_empty())
+        .max()
+        .map(|version| version.to_string());
+
+    Ok(latest)
+}
+
+/// Annotation of a manifest with the time the artifact was created, as defined by the OCI image specification.
+const CREATED_ANNOTATION: &str = "org.opencontainers.image.created";
+
+/// When the given registry artifact was published, read from the creation annotation of its manifest. Unknown when
+/// the publishing tool does not set the annotation.
+pub async fn published_time(image: &OciReference) -> Result<Option<String>> {
+    log::debug!("published_time: {image}");
+
+    let OciReference::Registry(reference) = image else {
+        return Ok(None);
+    };
+    let auth = auth::registry_auth(reference.registry())?;
+    let (manifest, _) = client()
+        .pull_image_manifest(reference, &auth)
+        .await
+        .context("Failed to pull manifest")?;
+
+    Ok(manifest
+        .annotations
+        .and_then(|annotations| annotations.get(CREATED_ANNOTATION).cloned()))
+}
+
 /// Pull bytes of the given OCI artifact, which is a reference to a given Feature or Template tar archive.
 pub async fn pull_archive_bytes(image: &OciReference) -> Result<Vec<u8>> {
     log::debug!("pull_archive_bytes");
 
-    let media_type = "application/vnd.devcontainers.layer.v1+tar";
-    let blob = get_layer_bytes(image, media_type)
-        .await
-        .context("Failed to pull archive bytes")?;
+    let blob = match (image, cache::current()) {
+        (OciReference::Registry(reference), Some(cache)) => pull_cached_archive(reference, cache).await,
+        _ => get_layer_bytes(image, ARCHIVE_MEDIA_TYPE).await,
+    }
+    .context("Failed to pull archive bytes")?;
 
-    log::debug!("pull_archive_bytes: Pulled {} bytes for {}", blob.len(), &image.0);
+    log::debug!("pull_archive_bytes: Pulled {} bytes for {}", blob.len(), image);
 
     Ok(blob)
 }
 
-async fn get_layer_bytes(OciReference(image): &OciReference, media_type: &str) -> Result<Vec<u8>> {
-    let auth = RegistryAuth::Anonymous;
-    let client = Client::new(Default::default());
+async fn get_layer_bytes(image: &OciReference, media_type: &str) -> Result<Vec<u8>> {
+    let image = match image {
+        OciReference::Registry(reference) => reference,
+        OciReference::Layout { path, tag } => return Ok(oci_layout::read_layer(path, tag.as_deref(), media_type)?),
+        // The artifact archive is the layer itself.
+        OciReference::File(path) => return Ok(fs::read(path)?),
+    };
+    let client = client();
+    let (blob, _) = pull_registry_layer(client, image, media_type).await?;
+
+    Ok(blob)
+}
+
+/// Pull the first layer of the given media type, along with the digest of the manifest when the registry reports it.
+async fn pull_registry_layer(
+    client: &Client,
+    image: &oci_client::Reference,
+    media_type: &str,
+) -> Result<(Vec<u8>, Option<String>)> {
+    let auth = auth::registry_auth(image.registry())?;
     let accepted_media_types = vec![media_type];
     let image_data = client
         .pull(image, &auth, accepted_media_types)
         .await
         .context("Failed to pull image data")?;
+    match &image_data.digest {
+        Some(digest) => log::info!("Pulled {image} at {digest}"),
+        None => log::info!("Pulled {image}"),
+    }
     let blob = image_data
         .layers
         .into_iter()
@@ -457,9 +709,280 @@ async fn get_layer_bytes(OciReference(image): &OciReference, media_type: &str) -
         .context("Missing Layer")
         .map(|layer| layer.data)?;
 
+    Ok((blob, image_data.digest))
+}
+
+/// The digest of a reference pinned by digest, otherwise the digest of the manifest the registry currently serves.
+async fn fetch_manifest_digest(client: &Client, image: &oci_client::Reference) -> Result<String> {
+    match image.digest() {
+        Some(digest) => Ok(digest.to_string()),
+        None => client
+            .fetch_manifest_digest(image, &auth::registry_auth(image.registry())?)
+            .await
+            .context("Failed to look up the manifest digest"),
+    }
+}
+
+/// Digest of the manifest of an image or artifact, such as `sha256:…`.
+pub async fn manifest_digest(image: &oci_client::Reference) -> Result<String> {
+    log::debug!("manifest_digest");
+
+    fetch_manifest_digest(client(), image).await
+}
+
+/// Reuse the cached archive while the registry still serves the same manifest, which takes a single HEAD request.
+/// Otherwise pull the archive and cache it.
+async fn pull_cached_archive(image: &oci_client::Reference, cache: &ArchiveCache) -> Result<Vec<u8>> {
+    let client = client();
+
+    if cache.reuse() {
+        let manifest_digest = fetch_manifest_digest(client, image).await?;
+
+        if let Some(blob) = cache.get(&manifest_digest) {
+            log::info!("Using cached {image} at {manifest_digest}");
+            return Ok(blob);
+        }
+    }
+
+    let (blob, manifest_digest) = pull_registry_layer(client, image, ARCHIVE_MEDIA_TYPE).await?;
+
+    if let Some(manifest_digest) = manifest_digest {
+        if let Err(error) = cache.put(&manifest_digest, &blob) {
+            log::warn!("Unable to cache {image}: {error}");
+        }
+    }
+
     Ok(blob)
 }
 
+/// Fields of `raw` that are lost when parsing it as `T`, as dotted paths such as `options.version.defualt`. Every field
+/// that `T` knows survives writing the parsed value back, so whatever is missing from it was ignored.
+pub fn unknown_fields<T: DeserializeOwned + Serialize>(raw: &JsonValue) -> serde_json::Result<Vec<String>> {
+    fn collect(raw: &JsonValue, parsed: &JsonValue, path: &str, unknown: &mut Vec<String>) {
+        let join = |key: &str| match path {
+            "" => key.to_string(),
+            path => format!("{path}.{key}"),
+        };
+
+        match (raw, parsed) {
+            (JsonValue::Object(raw), JsonValue::Object(parsed)) => {
+                // A null is the same as leaving the field out.
+                for (key, value) in raw.iter().filter(|(_, value)| !value.is_null()) {
+                    match parsed.get(key) {
+                        Some(parsed) => collect(value, parsed, &join(key), unknown),
+                        None => unknown.push(join(key)),
+                    }
+                }
+            },
+            (JsonValue::Array(raw), JsonValue::Array(parsed)) => {
+                for (i, (raw, parsed)) in raw.iter().zip(parsed).enumerate() {
+                    collect(raw, parsed, &join(&i.to_string()), unknown);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    let parsed = serde_json::to_value(serde_json::from_value::<T>(raw.clone())?)?;
+    let mut unknown = Vec::new();
+    collect(raw, &parsed, "", &mut unknown);
+
+    Ok(unknown)
+}
+
+/// The configuration file of a feature or template archive, such as `devcontainer-feature.json`.
+fn configuration_bytes(archive: &[u8], filename: &str) -> std::io::Result<Vec<u8>> {
+    match archive::find_entry(archive, |path| path.ends_with(filename))? {
+        Some((_, data)) => Ok(data),
+        None => Err(Error::new(
+            ErrorKind::NotFound,
+            format!("No {filename} found in archive"),
+        )),
+    }
+}
+
+/// Features and templates of a collection as published next to them, the `devcontainer-collection.json` of the
+/// namespace. Ids are relative to the namespace.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CollectionMetadata {
+    pub features: Vec<Feature>,
+    pub templates: Vec<Template>,
+}
+
+impl CollectionMetadata {
+    /// The collection as the index lists it, with ids qualified by the namespace. The source information and owner are
+    /// taken from the `indexed` collection, the namespace stands in for them when it is not in the index.
+    pub fn into_collection(self, namespace: &str, indexed: Option<&Collection>) -> Collection {
+        let source_information = indexed.map_or_else(
+            || SourceInformation {
+                name: namespace.to_string(),
+                oci_reference: namespace.to_string(),
+                ..Default::default()
+            },
+            |collection| collection.source_information.clone(),
+        );
+        let owner = indexed
+            .and_then(|collection| collection.features.first())
+            .map(|feature| feature.owner.clone())
+            .or_else(|| namespace.split('/').nth(1).map(str::to_string))
+            .unwrap_or_default();
+        let features = self
+            .features
+            .into_iter()
+            .map(|feature| Feature {
+                id: format!("{namespace}/{}", feature.id),
+                owner: owner.clone(),
+                major_version: feature.version.split('.').next().unwrap_or_default().to_string(),
+                ..feature
+            })
+            .collect();
+        let templates = self
+            .templates
+            .into_iter()
+            .map(|template| Template {
+                id: format!("{namespace}/{}", template.id),
+                owner: owner.clone(),
+                ..template
+            })
+            .collect();
+
+        Collection {
+            source_information,
+            features,
+            templates,
+        }
+    }
+}
+
+/// Pull the metadata of a collection, such as `ghcr.io/devcontainers/features:latest`.
+pub async fn pull_collection_metadata(image: &OciReference) -> Result<CollectionMetadata> {
+    log::debug!("pull_collection_metadata: {image}");
+
+    let bytes = get_layer_bytes(image, COLLECTION_MEDIA_TYPE)
+        .await
+        .context("Failed to pull collection metadata")?;
+
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// The `devcontainer-feature.json` of a feature archive.
+fn feature_configuration_bytes(archive: &[u8]) -> std::io::Result<Vec<u8>> {
+    configuration_bytes(archive, "devcontainer-feature.json")
+}
+
+/// The `devcontainer-template.json` of a template archive.
+pub fn template_configuration_bytes(archive: &[u8]) -> std::io::Result<Vec<u8>> {
+    configuration_bytes(archive, "devcontainer-template.json")
+}
+
+/// Pull the `devcontainer-template.json` of a template.
+pub async fn pull_template_configuration(image: &OciReference) -> Result<Template> {
+    log::debug!("pull_template_configuration");
+
+    let bytes = template_configuration_bytes(&pull_archive_bytes(image).await?)?;
+
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+/// Pull the configuration of a feature. Registry features are cached by manifest digest, so a feature that is missing
+/// from the index costs a single HEAD request once it has been read.
+pub async fn pull_feature_configuration(image: &OciReference) -> Result<Feature> {
+    log::debug!("pull_feature_configuration");
+
+    let (OciReference::Registry(reference), Some(cache)) = (image, cache::current()) else {
+        let bytes = feature_configuration_bytes(&pull_archive_bytes(image).await?)?;
+        return Ok(serde_json::from_slice(&bytes)?);
+    };
+    let client = client();
+
+    if cache.reuse() {
+        let manifest_digest = fetch_manifest_digest(client, reference).await?;
+
+        if let Some(bytes) = cache.get_configuration(&manifest_digest) {
+            match serde_json::from_slice(&bytes) {
+                Ok(feature) => {
+                    log::info!("Using cached configuration of {reference} at {manifest_digest}");
+                    return Ok(feature);
+                },
+                Err(error) => log::warn!("Ignoring cached configuration of {manifest_digest}: {error}"),
+            }
+        }
+
+        if let Some(blob) = cache.get(&manifest_digest) {
+            log::info!("Using cached {reference} at {manifest_digest}");
+            let bytes = feature_configuration_bytes(&blob)?;
+            let feature = serde_json::from_slice(&bytes)?;
+
+            if let Err(error) = cache.put_configuration(&manifest_digest, &bytes) {
+                log::warn!("Unable to cache the configuration of {reference}: {error}");
+            }
+
+            return Ok(feature);
+        }
+    }
+
+    let (blob, manifest_digest) = pull_registry_layer(client, reference, ARCHIVE_MEDIA_TYPE)
+        .await
+        .context("Failed to pull archive bytes")?;
+    let bytes = feature_configuration_bytes(&blob)?;
+    let feature = serde_json::from_slice(&bytes)?;
+
+    if let Some(manifest_digest) = manifest_digest {
+        let cached = cache
+            .put(&manifest_digest, &blob)
+            .and_then(|()| cache.put_configuration(&manifest_digest, &bytes));
+
+        if let Err(error) = cached {
+            log::warn!("Unable to cache {reference}: {error}");
+        }
+    }
+
+    Ok(feature)
+}
+
+/// Architectures the given image is published for, from its manifest list or else from the config of its manifest.
+pub async fn image_architectures(image: &str) -> Result<Vec<String>> {
+    log::debug!("image_architectures");
+
+    let reference: oci_client::Reference = image
+        .parse()
+        .with_context(|| format!("Invalid image reference: {image}"))?;
+    let auth = auth::registry_auth(reference.registry())?;
+    let client = client();
+    let (manifest, _) = client
+        .pull_manifest(&reference, &auth)
+        .await
+        .context("Failed to pull image manifest")?;
+
+    let architectures = match manifest {
+        OciManifest::ImageIndex(index) => index
+            .manifests
+            .into_iter()
+            .filter_map(|entry| entry.platform)
+            // Attestation manifests are listed with an "unknown" platform.
+            .filter(|platform| platform.os != "unknown")
+            .map(|platform| platform.architecture)
+            .collect(),
+        OciManifest::Image(_) => {
+            let (_, _, config) = client
+                .pull_manifest_and_config(&reference, &auth)
+                .await
+                .context("Failed to pull image config")?;
+            let config: JsonValue = serde_json::from_str(&config)?;
+
+            config
+                .get("architecture")
+                .and_then(JsonValue::as_str)
+                .map(String::from)
+                .into_iter()
+                .collect()
+        },
+    };
+
+    Ok(architectures)
+}
+
 /// Read and parse the given filename.
 pub fn read_devcontainer_index<P: AsRef<Path>>(filename: P) -> Result<DevcontainerIndex, Error> {
     log::debug!("read_devcontainer_index");
@@ -562,3 +1085,71 @@ pub fn read_devcontainer_index<P: AsRef<Path>>(filename: P) -> Result<Devcontain
 
     Ok(DevcontainerIndex { collections })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{unknown_fields, Collection, CollectionMetadata, Feature, LifecycleHook};
+
+    #[test]
+    fn test_unknown_fields() -> serde_json::Result<()> {
+        let raw = serde_json::json!({
+            "id": "node",
+            "version": "1.0.0",
+            "name": "Node.js",
+            "description": null,
+            "instalsAfter": ["common-utils"],
+            "options": {"version": {"type": "string", "default": "lts", "defualt": "18"}},
+        });
+
+        assert_eq!(
+            unknown_fields::<Feature>(&raw)?,
+            ["instalsAfter", "options.version.defualt"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_into_collection() -> serde_json::Result<()> {
+        let indexed: Collection = serde_json::from_str(
+            r#"{"sourceInformation": {"name": "Official", "maintainer": "Dev Containers", "contact": "", "repository": "", "ociReference": "ghcr.io/devcontainers/features"},
+                "features": [{"id": "ghcr.io/devcontainers/features/node", "version": "1.7.0", "name": "Node.js", "owner": "devcontainers"}],
+                "templates": []}"#,
+        )?;
+        let metadata: CollectionMetadata = serde_json::from_str(
+            r#"{"sourceInformation": {"source": "devcontainer-cli"},
+                "features": [{"id": "node", "version": "1.6.1", "name": "Node.js"}]}"#,
+        )?;
+        let collection = metadata.into_collection("ghcr.io/devcontainers/features", Some(&indexed));
+
+        assert_eq!(collection.source_information, indexed.source_information);
+        assert_eq!(collection.features[0].id, "ghcr.io/devcontainers/features/node");
+        assert_eq!(collection.features[0].version, "1.6.1");
+        assert_eq!(collection.features[0].major_version, "1");
+        assert_eq!(collection.features[0].owner, "devcontainers");
+
+        let unindexed = CollectionMetadata::default().into_collection("ghcr.io/me/features", None);
+
+        assert_eq!(unindexed.source_information.oci_reference, "ghcr.io/me/features");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lifecycle_hook_from_str() {
+        assert_eq!(
+            "npm ci && npm test".parse(),
+            Ok(LifecycleHook::Single("npm ci && npm test".into()))
+        );
+        assert_eq!(
+            r#"["npm", "ci"]"#.parse(),
+            Ok(LifecycleHook::Multiple(vec!["npm".into(), "ci".into()]))
+        );
+        assert!(matches!(
+            r#"{"fetch": "git fetch"}"#.parse(),
+            Ok(LifecycleHook::Named(_))
+        ));
+        assert!("[npm".parse::<LifecycleHook>().is_err());
+        assert!(" ".parse::<LifecycleHook>().is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use std::io;
+
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::{Collection, DevcontainerIndex};
+use crate::search::CollectionCategory;
+use crate::suggest;
+
+#[derive(Debug, Args)]
+pub struct ResolveArgs {
+    /// A short name such as `node`, a partial path such as `devcontainers/features/node`, or a full id.
+    #[arg(value_name = "NAME")]
+    fragment: String,
+
+    /// Only match features or templates.
+    #[arg(short, long)]
+    collection: Option<CollectionCategory>,
+
+    /// Print every match instead of failing when the name is ambiguous.
+    #[arg(short, long)]
+    all: bool,
+
+    /// Include deprecated features and templates.
+    #[arg(long)]
+    include_deprecated: bool,
+}
+
+/// A feature or template matching a short name.
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Resolution {
+    pub collection: CollectionCategory,
+    pub id: String,
+    pub version: String,
+    /// The id with its major version tag, as it is written in a devcontainer.json.
+    pub reference: String,
+    pub collection_name: String,
+}
+
+impl Resolution {
+    fn new(category: CollectionCategory, id: &str, version: &str, collection: &Collection) -> Self {
+        let major_version = version.split('.').next().unwrap_or(version);
+
+        Resolution {
+            collection: category,
+            id: id.to_string(),
+            version: version.to_string(),
+            reference: format!("{id}:{major_version}"),
+            collection_name: collection.source_information.name.clone(),
+        }
+    }
+}
+
+/// Every feature and template of the index, optionally limited to one category.
+fn candidates(
+    index: &DevcontainerIndex,
+    category: Option<&CollectionCategory>,
+    include_deprecated: bool,
+) -> Vec<Resolution> {
+    let with_features = !matches!(category, Some(CollectionCategory::Templates));
+    let with_templates = !matches!(category, Some(CollectionCategory::Features));
+
+    index
+        .collections()
+        .iter()
+        .filter(|collection| include_deprecated || !collection.is_deprecated())
+        .flat_map(|collection| {
+            let features = collection
+                .features
+                .iter()
+                .filter(move |_| with_features)
+                .filter(|feature| include_deprecated || !feature.is_deprecated())
+                .map(|feature| {
+                    Resolution::new(CollectionCategory::Features, &feature.id, &feature.version, collection)
+                });
+            let templates = collection
+                .templates
+                .iter()
+                .filter(move |_| with_templates)
+                .map(|template| {
+                    Resolution::new(
+                        CollectionCategory::Templates,
+                        &template.id,
+                        &template.version,
+                        collection,
+                    )
+                });
+
+            features.chain(templates)
+        })
+        .collect()
+}
+
+/// Match a full id first, otherwise every id ending with the fragment as whole path segments.
+pub fn resolve_fragment(
+    index: &DevcontainerIndex,
+    fragment: &str,
+    category: Option<&CollectionCategory>,
+    include_deprecated: bool,
+) -> Vec<Resolution> {
+    let candidates = candidates(index, category, include_deprecated);
+    let fragment = fragment.trim_matches('/');
+    let suffix = format!("/{fragment}");
+
+    let exact: Vec<Resolution> = candidates.iter().filter(|c| c.id == fragment).cloned().collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+
+    candidates.into_iter().filter(|c| c.id.ends_with(&suffix)).collect()
+}
+
+pub fn resolve(
+    index: &DevcontainerIndex,
+    ResolveArgs {
+        fragment,
+        collection,
+        all,
+        include_deprecated,
+    }: ResolveArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("resolve");
+
+    let resolutions = resolve_fragment(index, &fragment, collection.as_ref(), include_deprecated);
+
+    match resolutions.as_slice() {
+        [] => {
+            let ids: Vec<String> = candidates(index, collection.as_ref(), include_deprecated)
+                .into_iter()
+                .map(|c| c.id)
+                .collect();
+            let error = io::Error::new(io::ErrorKind::NotFound, format!("Nothing matches \"{fragment}\""));
+
+            Err(suggest::with_suggestions(
+                error.into(),
+                &fragment,
+                ids.iter().map(String::as_str),
+            ))?
+        },
+        [_, _, ..] if !all => {
+            let matches: Vec<String> = resolutions
+                .iter()
+                .map(|r| {
+                    format!(
+                        "  {} ({} {}, {})",
+                        r.reference, r.collection, r.version, r.collection_name
+                    )
+                })
+                .collect();
+
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "\"{fragment}\" is ambiguous, use a longer name or --all:\n{}",
+                    matches.join("\n")
+                ),
+            ))?
+        },
+        _ if format == OutputFormat::Json => output::print_json(Kind::Resolutions, &resolutions)?,
+        _ => {
+            for resolution in &resolutions {
+                println!("{}", resolution.reference);
+            }
+        },
+    }
+
+    log::debug!("resolve: done");
+
+    Ok(())
+}
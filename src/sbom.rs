@@ -0,0 +1,424 @@
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Args, ValueEnum};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::init;
+use crate::oci_ref::OciReference;
+use crate::registry::{self, DevcontainerIndex, Feature};
+use crate::timestamp;
+use crate::workspace::{self, FeatureSource};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum SbomFormat {
+    /// CycloneDX 1.5 JSON.
+    #[default]
+    Cyclonedx,
+    /// SPDX 2.3 JSON.
+    Spdx,
+}
+
+#[derive(Debug, Args)]
+pub struct SbomArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Document format.
+    #[arg(long, value_name = "FORMAT", default_value = "cyclonedx")]
+    format: SbomFormat,
+
+    /// Template the configuration was created from, to record its provenance.
+    #[arg(short, long, value_name = "OCI_REF")]
+    template_id: Option<OciReference>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ComponentKind {
+    Image,
+    Feature,
+    Template,
+}
+
+impl Display for ComponentKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Image => write!(f, "image"),
+            Self::Feature => write!(f, "feature"),
+            Self::Template => write!(f, "template"),
+        }
+    }
+}
+
+/// The base image, a feature, or the template of a workspace configuration.
+#[derive(Clone, Debug)]
+pub struct Component {
+    pub kind: ComponentKind,
+    /// The id of a feature or template, or the repository of an image.
+    pub name: String,
+    /// The reference as written in the configuration.
+    pub reference: String,
+    pub version: Option<String>,
+    pub license_url: Option<String>,
+    /// The manifest digest for registry references, or the archive digest of a local file.
+    pub digest: Option<String>,
+    registry_reference: Option<oci_client::Reference>,
+}
+
+impl Component {
+    fn new(kind: ComponentKind, name: String, reference: String) -> Self {
+        Component {
+            kind,
+            name,
+            reference,
+            version: None,
+            license_url: None,
+            digest: None,
+            registry_reference: None,
+        }
+    }
+
+    fn image(image: &str) -> Self {
+        match image.parse::<oci_client::Reference>() {
+            Ok(reference) => Component {
+                version: reference.tag().map(String::from),
+                registry_reference: Some(reference.clone()),
+                ..Component::new(
+                    ComponentKind::Image,
+                    format!("{}/{}", reference.registry(), reference.repository()),
+                    image.to_string(),
+                )
+            },
+            Err(_) => Component::new(ComponentKind::Image, image.to_string(), image.to_string()),
+        }
+    }
+
+    fn with_metadata(self, version: &str, license_url: Option<&String>) -> Self {
+        Component {
+            version: Some(version.to_string()),
+            license_url: license_url.cloned(),
+            ..self
+        }
+    }
+
+    /// Package URL of a registry reference, such as `pkg:oci/node@sha256%3A…?repository_url=ghcr.io/…&tag=1`.
+    fn purl(&self) -> Option<String> {
+        let reference = self.registry_reference.as_ref()?;
+        let repository = reference.repository();
+        let name = repository.rsplit('/').next().unwrap_or(repository);
+        let version = self
+            .digest
+            .as_ref()
+            .map(|digest| format!("@{}", digest.replace(':', "%3A")))
+            .unwrap_or_default();
+        let tag = reference.tag().map(|tag| format!("&tag={tag}")).unwrap_or_default();
+
+        Some(format!(
+            "pkg:oci/{name}{version}?repository_url={}/{repository}{tag}",
+            reference.registry()
+        ))
+    }
+
+    /// The algorithm and encoded value of the digest, such as `("sha256", "…")`.
+    fn hash(&self) -> Option<(&str, &str)> {
+        self.digest.as_deref()?.split_once(':')
+    }
+}
+
+async fn feature_component(index: &DevcontainerIndex, key: &str, source: &FeatureSource) -> Component {
+    let component = Component::new(ComponentKind::Feature, key.to_string(), key.to_string());
+
+    match source {
+        FeatureSource::Registry(reference) => {
+            let component = Component {
+                name: reference.id(),
+                registry_reference: match reference {
+                    OciReference::Registry(reference) => Some(reference.clone()),
+                    _ => None,
+                },
+                digest: match reference {
+                    OciReference::File(path) => fs::read(path)
+                        .ok()
+                        .map(|bytes| format!("sha256:{:x}", Sha256::digest(bytes))),
+                    _ => None,
+                },
+                ..component
+            };
+
+            match init::get_feature(index, reference).await {
+                Ok(feature) => component.with_metadata(&feature.version, feature.license_url.as_ref()),
+                Err(error) => {
+                    log::warn!("Unable to read the configuration of {key}: {error:#}");
+                    component
+                },
+            }
+        },
+        FeatureSource::Folder(folder) => {
+            let feature = fs::read_to_string(folder.join("devcontainer-feature.json"))
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| Ok(serde_jsonc::from_str::<Feature>(&contents)?));
+
+            match feature {
+                Ok(feature) => Component {
+                    name: feature.id.clone(),
+                    ..component.with_metadata(&feature.version, feature.license_url.as_ref())
+                },
+                Err(error) => {
+                    log::warn!("Unable to read the configuration of {key}: {error:#}");
+                    component
+                },
+            }
+        },
+        FeatureSource::Url(_) => component,
+    }
+}
+
+fn template_component(index: &DevcontainerIndex, template_ref: &OciReference) -> Component {
+    let id = template_ref.id();
+    let component = Component {
+        registry_reference: match template_ref {
+            OciReference::Registry(reference) => Some(reference.clone()),
+            _ => None,
+        },
+        ..Component::new(ComponentKind::Template, id.clone(), template_ref.to_string())
+    };
+
+    match index.get_template(&id) {
+        Some(template) => component.with_metadata(&template.version, template.license_url.as_ref()),
+        None => component,
+    }
+}
+
+/// The base images, features, and template of a workspace, each once, without digests of registry references.
+pub async fn components(
+    index: &DevcontainerIndex,
+    workspace: &Path,
+    template_ref: Option<&OciReference>,
+) -> anyhow::Result<Vec<Component>> {
+    log::debug!("components");
+
+    let mut components: Vec<Component> = Vec::new();
+
+    for file in workspace::devcontainer_files(workspace)? {
+        let config = workspace::read_config(&file)?;
+
+        if let Some(image) = workspace::base_image(&file, &config) {
+            if !components.iter().any(|component| component.reference == image) {
+                components.push(Component::image(&image));
+            }
+        }
+    }
+
+    for feature in workspace::workspace_features(workspace)? {
+        components.push(feature_component(index, &feature.key, &feature.source).await);
+    }
+
+    if let Some(template_ref) = template_ref {
+        components.push(template_component(index, template_ref));
+    }
+
+    Ok(components)
+}
+
+/// Look up the manifest digest of every registry reference. A failed lookup leaves the digest out with a warning.
+async fn resolve_digests(components: &mut [Component]) {
+    for component in components {
+        let Some(reference) = &component.registry_reference else {
+            continue;
+        };
+
+        match tokio::time::timeout(registry::lookup_timeout(), registry::manifest_digest(reference)).await {
+            Ok(Ok(digest)) => component.digest = Some(digest),
+            Ok(Err(error)) => log::warn!("Unable to look up the digest of {}: {error:#}", component.reference),
+            Err(_) => log::warn!("Unable to look up the digest of {}: timed out", component.reference),
+        }
+    }
+}
+
+fn cyclonedx(name: &str, timestamp: &str, components: &[Component]) -> Value {
+    let entries: Vec<Value> = components
+        .iter()
+        .map(|component| {
+            let mut entry = json!({
+                "type": match component.kind {
+                    ComponentKind::Image => "container",
+                    ComponentKind::Feature => "application",
+                    ComponentKind::Template => "file",
+                },
+                "bom-ref": component.reference,
+                "name": component.name,
+                "properties": [{ "name": "tyedev:kind", "value": component.kind.to_string() }],
+            });
+
+            if let Some(version) = &component.version {
+                entry["version"] = json!(version);
+            }
+
+            if let Some(purl) = component.purl() {
+                entry["purl"] = json!(purl);
+            }
+
+            if let Some(("sha256", encoded)) = component.hash() {
+                entry["hashes"] = json!([{ "alg": "SHA-256", "content": encoded }]);
+            }
+
+            if let Some(license_url) = &component.license_url {
+                entry["externalReferences"] = json!([{ "type": "license", "url": license_url }]);
+            }
+
+            entry
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp,
+            "tools": {
+                "components": [{ "type": "application", "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") }],
+            },
+            "component": { "type": "application", "bom-ref": "workspace", "name": name },
+        },
+        "components": entries,
+        "dependencies": [{
+            "ref": "workspace",
+            "dependsOn": components.iter().map(|component| &component.reference).collect::<Vec<_>>(),
+        }],
+    })
+}
+
+fn spdx(name: &str, timestamp: &str, components: &[Component]) -> Value {
+    let packages: Vec<Value> = components
+        .iter()
+        .enumerate()
+        .map(|(number, component)| {
+            let mut package = json!({
+                "SPDXID": format!("SPDXRef-Package-{}", number + 1),
+                "name": component.name,
+                "versionInfo": component.version.as_deref().unwrap_or("NOASSERTION"),
+                "downloadLocation": component.reference,
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": "NOASSERTION",
+                "primaryPackagePurpose": match component.kind {
+                    ComponentKind::Image => "CONTAINER",
+                    ComponentKind::Feature => "APPLICATION",
+                    ComponentKind::Template => "SOURCE",
+                },
+                "comment": format!("devcontainer {}", component.kind),
+            });
+
+            if let Some(purl) = component.purl() {
+                package["externalRefs"] = json!([{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": purl,
+                }]);
+            }
+
+            if let Some(("sha256", encoded)) = component.hash() {
+                package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": encoded }]);
+            }
+
+            if let Some(license_url) = &component.license_url {
+                package["licenseComments"] = json!(format!("License: {license_url}"));
+            }
+
+            package
+        })
+        .collect();
+
+    let relationships: Vec<Value> = packages
+        .iter()
+        .map(|package| {
+            json!({
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": package["SPDXID"],
+            })
+        })
+        .collect();
+
+    // The namespace must be unique per document, the content and time of creation make it so.
+    let mut hasher = Sha256::new();
+    hasher.update(timestamp);
+    for component in components {
+        hasher.update(&component.reference);
+    }
+    let unique = format!("{:x}", hasher.finalize());
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/{}-{name}-{}", env!("CARGO_PKG_NAME"), &unique[..16]),
+        "creationInfo": {
+            "created": timestamp,
+            "creators": [format!("Tool: {}-{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+pub async fn sbom(
+    index: &DevcontainerIndex,
+    SbomArgs {
+        workspace_folder,
+        format,
+        template_id,
+    }: SbomArgs,
+) -> anyhow::Result<()> {
+    log::debug!("sbom");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let workspace = workspace.canonicalize().unwrap_or(workspace);
+    let name = workspace
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "workspace".to_string());
+
+    let mut components = components(index, &workspace, template_id.as_ref()).await?;
+    resolve_digests(&mut components).await;
+
+    let timestamp = timestamp::rfc3339(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs());
+    let document = match format {
+        SbomFormat::Cyclonedx => cyclonedx(&name, &timestamp, &components),
+        SbomFormat::Spdx => spdx(&name, &timestamp, &components),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&document)?);
+
+    log::debug!("sbom: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Component;
+
+    #[test]
+    fn test_purl() {
+        let mut component = Component::image("ghcr.io/devcontainers/features/node:1");
+        assert_eq!(
+            component.purl().as_deref(),
+            Some("pkg:oci/node?repository_url=ghcr.io/devcontainers/features/node&tag=1")
+        );
+
+        component.digest = Some("sha256:abc".to_string());
+        assert_eq!(
+            component.purl().as_deref(),
+            Some("pkg:oci/node@sha256%3Aabc?repository_url=ghcr.io/devcontainers/features/node&tag=1")
+        );
+        assert_eq!(component.hash(), Some(("sha256", "abc")));
+    }
+}
@@ -0,0 +1,98 @@
+use clap::{Args, ValueEnum};
+use schemars::{schema_for, JsonSchema};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::audit::AuditReport;
+use crate::batch::BatchReport;
+use crate::index::Change;
+use crate::init::InitReport;
+use crate::licenses::License;
+use crate::lint::LintReport;
+use crate::list::CollectionSummary;
+use crate::outdated::Outdated;
+use crate::output::{Envelope, ErrorData, Kind, API_VERSION};
+use crate::registry::{Collection, Feature, Template};
+use crate::resolve::Resolution;
+use crate::search::{CommunityResult, SearchResult};
+use crate::show::WorkspaceConfig;
+use crate::stats::IndexStats;
+use crate::status::ConfigStatus;
+use crate::validate::FileReport;
+use crate::verify::Verification;
+
+#[derive(Debug, Args)]
+pub struct SchemaArgs {
+    /// Only print the schema of the given kind.
+    #[arg(value_name = "KIND")]
+    kind: Option<Kind>,
+}
+
+/// Schema of the envelope around `T`, with `apiVersion` and `kind` narrowed to constants.
+fn envelope_schema<T: JsonSchema + Serialize + 'static>(kind: Kind) -> serde_json::Result<Value> {
+    let mut schema = serde_json::to_value(schema_for!(Envelope<'static, T>))?;
+
+    if let Some(properties) = schema.get_mut("properties").and_then(Value::as_object_mut) {
+        properties.insert("apiVersion".into(), serde_json::json!({ "const": API_VERSION }));
+        properties.insert("kind".into(), serde_json::json!({ "const": kind }));
+    }
+
+    if let Some(definitions) = schema.get_mut("definitions").and_then(Value::as_object_mut) {
+        definitions.remove("Kind");
+    }
+
+    if let Some(root) = schema.as_object_mut() {
+        let title = serde_json::to_value(kind)?;
+        root.insert("title".into(), title);
+    }
+
+    Ok(schema)
+}
+
+fn kind_schema(kind: Kind) -> serde_json::Result<Value> {
+    match kind {
+        Kind::AuditFindings => envelope_schema::<AuditReport>(kind),
+        Kind::Collection => envelope_schema::<Collection>(kind),
+        Kind::Collections => envelope_schema::<Vec<CollectionSummary>>(kind),
+        Kind::CommunityResults => envelope_schema::<Vec<CommunityResult>>(kind),
+        Kind::Error => envelope_schema::<ErrorData>(kind),
+        Kind::Feature => envelope_schema::<Feature>(kind),
+        Kind::IndexDiff => envelope_schema::<Vec<Change>>(kind),
+        Kind::IndexStats => envelope_schema::<IndexStats>(kind),
+        Kind::InitBatchReport => envelope_schema::<BatchReport>(kind),
+        Kind::InitReport => envelope_schema::<InitReport>(kind),
+        Kind::Licenses => envelope_schema::<Vec<License>>(kind),
+        Kind::LintFindings => envelope_schema::<Vec<LintReport>>(kind),
+        Kind::Outdated => envelope_schema::<Outdated>(kind),
+        Kind::Resolutions => envelope_schema::<Vec<Resolution>>(kind),
+        Kind::SearchResults => envelope_schema::<Vec<SearchResult>>(kind),
+        Kind::Status => envelope_schema::<Vec<ConfigStatus>>(kind),
+        Kind::Template => envelope_schema::<Template>(kind),
+        Kind::Validation => envelope_schema::<Vec<FileReport>>(kind),
+        Kind::Verification => envelope_schema::<Verification>(kind),
+        Kind::WorkspaceConfig => envelope_schema::<WorkspaceConfig>(kind),
+    }
+}
+
+/// Print the JSON Schema of one output kind, or an object of every kind keyed by name.
+pub fn schema(SchemaArgs { kind }: SchemaArgs) -> serde_json::Result<()> {
+    log::debug!("schema");
+
+    let value = match kind {
+        Some(kind) => kind_schema(kind)?,
+        None => {
+            let mut schemas = Map::new();
+
+            for &kind in Kind::value_variants() {
+                let name = serde_json::to_value(kind)?.as_str().unwrap_or_default().to_string();
+                schemas.insert(name, kind_schema(kind)?);
+            }
+
+            Value::Object(schemas)
+        },
+    };
+
+    println!("{}", serde_json::to_string_pretty(&value)?);
+
+    Ok(())
+}
@@ -2,11 +2,20 @@ use std::fmt;
 
 use clap::builder::PossibleValue;
 use clap::{Args, ValueEnum};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::github;
+use crate::output::{self, Kind, OutputFormat};
+use crate::published;
 use crate::registry;
+use crate::theme;
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+/// Number of GitHub repositories a community search looks into, the anonymous rate limit of the registry is shared by
+/// all of them.
+const COMMUNITY_REPOSITORIES: usize = 10;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub enum CollectionCategory {
     #[default]
     Templates,
@@ -44,7 +53,7 @@ pub enum SearchFields {
     Keywords,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
 pub struct SearchResult {
     /// The search result type, here for JSON type tagging. -- Reserves the right to transform this struct into an enum later.
     pub collection: CollectionCategory,
@@ -53,6 +62,25 @@ pub struct SearchResult {
     pub name: String,
     pub description: Option<String>,
     pub keywords: Option<Vec<String>>,
+    /// When the latest version was published, only looked up when asked for and missing when unknown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+}
+
+impl SearchResult {
+    /// Look up when each of the results was published, sorting them by recency when asked.
+    pub async fn add_published_times(results: &mut [SearchResult], sort: &SortOrder) {
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        let mut times = published::published_times(&ids).await;
+
+        for result in results.iter_mut() {
+            result.published = times.remove(&result.id);
+        }
+
+        if let SortOrder::Recent = sort {
+            published::sort_recent(results, |r| r.published.as_deref());
+        }
+    }
 }
 
 impl From<&registry::Feature> for SearchResult {
@@ -64,6 +92,7 @@ impl From<&registry::Feature> for SearchResult {
             name: value.name.clone(),
             description: value.description.clone(),
             keywords: value.keywords.clone(),
+            published: None,
         }
     }
 }
@@ -77,10 +106,36 @@ impl From<&registry::Template> for SearchResult {
             name: value.name.clone(),
             description: value.description.clone(),
             keywords: value.keywords.clone(),
+            published: None,
         }
     }
 }
 
+/// A feature or template of a collection that is published on GitHub but missing from the index. Nothing vouches for
+/// these.
+#[derive(Clone, Debug, Default, Serialize, JsonSchema)]
+pub struct CommunityResult {
+    pub collection: CollectionCategory,
+    /// Reference to inspect or pull, the namespace of the collection when its entries could not be listed.
+    pub id: String,
+    pub version: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    /// URL of the GitHub repository the collection is published from.
+    pub repository: String,
+    pub stars: u64,
+}
+
+/// Order of listed features and templates.
+#[derive(Clone, Debug, Default, ValueEnum)]
+pub enum SortOrder {
+    /// The order of the index.
+    #[default]
+    Index,
+    /// Most recently published first, which implies `--published`.
+    Recent,
+}
+
 #[derive(Clone, Debug, Default, ValueEnum)]
 enum SearchDisplay {
     #[default]
@@ -106,6 +161,10 @@ pub struct SearchArgs {
     #[arg(short, long, value_name = "FORMAT", default_value = "table")]
     display_as: SearchDisplay,
 
+    /// Print the table as tab separated values without borders or headers.
+    #[arg(long, visible_alias = "no-headers")]
+    plain: bool,
+
     /// Match only within the given fields.
     #[arg(short, long, value_name = "FIELD")]
     fields: Option<Vec<SearchFields>>,
@@ -113,6 +172,18 @@ pub struct SearchArgs {
     /// Display deprecated results.
     #[arg(long)]
     include_deprecated: bool,
+
+    /// Search GitHub for community collections that are not in the index instead, their results are unverified.
+    #[arg(long)]
+    community: bool,
+
+    /// Look up when the latest version of each result was published, from its registry manifest.
+    #[arg(long, conflicts_with = "community")]
+    published: bool,
+
+    /// Order of the results.
+    #[arg(long, value_name = "ORDER", default_value = "index", conflicts_with = "community")]
+    sort: SortOrder,
 }
 
 /// Take the lowercase `target` to check if it contains the lowercase `inside` value.
@@ -155,22 +226,193 @@ fn search_match<T: SearchMatcher>(value: &T, text: &str, search_fields: &[Search
     search_fields.iter().any(|field| value.is_match(field, text))
 }
 
-pub fn search(
+/// The GitHub namespace a repository publishes its collection to, following the convention of the publishing action.
+fn github_namespace(repository: &github::Repository) -> String {
+    format!("ghcr.io/{}/{}", repository.owner.login, repository.name).to_lowercase()
+}
+
+/// Whether the index already has the collection of the namespace, or one nested below it.
+fn is_indexed(index: &registry::DevcontainerIndex, namespace: &str) -> bool {
+    index.collections().iter().any(|collection| {
+        let oci_reference = collection.source_information.oci_reference.to_lowercase();
+        oci_reference == namespace || oci_reference.starts_with(&format!("{namespace}/"))
+    })
+}
+
+/// Matching entries of the collection a repository publishes, or the repository itself when its collection could not
+/// be listed or nothing in it matches.
+async fn community_results(
+    repository: &github::Repository,
+    collection: &CollectionCategory,
+    text: &str,
+    search_fields: &[SearchFields],
+) -> Vec<CommunityResult> {
+    let namespace = github_namespace(repository);
+    let result = |id: String, version: Option<String>, name: String, description: Option<String>| CommunityResult {
+        collection: collection.clone(),
+        id,
+        version,
+        name,
+        description,
+        repository: repository.html_url.clone(),
+        stars: repository.stargazers_count,
+    };
+    let metadata = match format!("{namespace}:latest").parse() {
+        Ok(image) => registry::pull_collection_metadata(&image).await,
+        Err(error) => Err(error),
+    };
+    let results: Vec<CommunityResult> = match metadata {
+        Ok(metadata) => match collection {
+            CollectionCategory::Features => metadata
+                .features
+                .iter()
+                .filter(|feature| search_match(*feature, text, search_fields))
+                .map(|feature| {
+                    result(
+                        format!("{namespace}/{}", feature.id),
+                        Some(feature.version.clone()),
+                        feature.name.clone(),
+                        feature.description.clone(),
+                    )
+                })
+                .collect(),
+            CollectionCategory::Templates => metadata
+                .templates
+                .iter()
+                .filter(|template| search_match(*template, text, search_fields))
+                .map(|template| {
+                    result(
+                        format!("{namespace}/{}", template.id),
+                        Some(template.version.clone()),
+                        template.name.clone(),
+                        template.description.clone(),
+                    )
+                })
+                .collect(),
+        },
+        Err(error) => {
+            log::info!("Unable to list the collection of {}: {error:#}", repository.full_name);
+            Vec::new()
+        },
+    };
+
+    if results.is_empty() {
+        vec![result(
+            namespace,
+            None,
+            repository.full_name.clone(),
+            repository.description.clone(),
+        )]
+    } else {
+        results
+    }
+}
+
+/// Search the repositories tagged with the devcontainer topics on GitHub, skipping collections the index has.
+async fn search_community(
+    index: &registry::DevcontainerIndex,
+    text: &str,
+    collection: &CollectionCategory,
+    search_fields: &[SearchFields],
+) -> anyhow::Result<Vec<CommunityResult>> {
+    log::debug!("search_community");
+
+    let topic = match collection {
+        CollectionCategory::Features => "devcontainer-feature",
+        CollectionCategory::Templates => "devcontainer-template",
+    };
+    let mut results = Vec::new();
+
+    for repository in github::search_repositories(text, topic, COMMUNITY_REPOSITORIES).await? {
+        if is_indexed(index, &github_namespace(&repository)) {
+            log::debug!("search_community: {} is in the index", repository.full_name);
+            continue;
+        }
+
+        results.extend(community_results(&repository, collection, text, search_fields).await);
+    }
+
+    Ok(results)
+}
+
+fn print_community(results: &[CommunityResult], plain: bool) {
+    if plain {
+        output::print_plain(
+            results
+                .iter()
+                .map(|r| [r.id.as_str(), r.version.as_deref().unwrap_or_default(), r.name.as_str()]),
+        );
+        return;
+    }
+
+    if results.is_empty() {
+        println!("No results found");
+        return;
+    }
+
+    let mut table = ascii_table::AsciiTable::default();
+    let header = &theme::current().header;
+    table.column(0).set_header(header.paint("ID"));
+    table.column(1).set_header(header.paint("Version"));
+    table.column(2).set_header(header.paint("Name"));
+    table
+        .column(3)
+        .set_header(header.paint("Stars"))
+        .set_align(ascii_table::Align::Right);
+    let data: Vec<[String; 4]> = results
+        .iter()
+        .map(|r| {
+            [
+                r.id.clone(),
+                r.version.clone().unwrap_or_else(|| "-".to_string()),
+                r.name.clone(),
+                r.stars.to_string(),
+            ]
+        })
+        .collect();
+    table.print(data);
+    println!(
+        "{}",
+        theme::current().warning.paint(
+            "Community results are unverified, they are not part of the official index. Review the source before use."
+        )
+    );
+}
+
+pub async fn search(
     index: &registry::DevcontainerIndex,
     SearchArgs {
         value: text,
         collection,
         display_as,
+        plain,
         fields,
         include_deprecated,
+        community,
+        published,
+        sort,
     }: SearchArgs,
-) -> serde_json::error::Result<()> {
+    format: OutputFormat,
+) -> anyhow::Result<()> {
     log::debug!("search");
 
     let search_fields =
         fields.unwrap_or_else(|| vec![SearchFields::Id, SearchFields::Name, SearchFields::Description]);
     log::debug!("search: search_fields = {:?}", &search_fields);
-    let results: Vec<SearchResult> = match collection {
+
+    if community {
+        let results = search_community(index, &text, &collection, &search_fields).await?;
+
+        match format {
+            OutputFormat::Json => output::print_json(Kind::CommunityResults, &results)?,
+            OutputFormat::Text => print_community(&results, plain),
+        }
+
+        log::debug!("search: done");
+
+        return Ok(());
+    }
+    let mut results: Vec<SearchResult> = match collection {
         CollectionCategory::Features => {
             log::debug!("search: features");
             index
@@ -199,13 +441,62 @@ pub fn search(
         },
     };
 
+    let published = published || matches!(sort, SortOrder::Recent);
+
+    if published {
+        SearchResult::add_published_times(&mut results, &sort).await;
+    }
+
+    if format == OutputFormat::Json {
+        output::print_json(Kind::SearchResults, &results)?;
+        return Ok(());
+    }
+
     match display_as {
+        SearchDisplay::Table if plain && published => {
+            output::print_plain(results.iter().map(|r| {
+                [
+                    r.id.clone(),
+                    r.version.clone(),
+                    r.name.clone(),
+                    r.published.clone().unwrap_or_default(),
+                ]
+            }));
+        },
+        SearchDisplay::Table if plain => {
+            output::print_plain(
+                results
+                    .iter()
+                    .map(|r| [r.id.as_str(), r.version.as_str(), r.name.as_str()]),
+            );
+        },
         SearchDisplay::Table if results.is_empty() => println!("No results found"),
+        SearchDisplay::Table if published => {
+            let mut table = ascii_table::AsciiTable::default();
+            let header = &theme::current().header;
+            table.column(0).set_header(header.paint("ID"));
+            table.column(1).set_header(header.paint("Version"));
+            table.column(2).set_header(header.paint("Name"));
+            table.column(3).set_header(header.paint("Published"));
+            let data: Vec<[String; 4]> = results
+                .iter()
+                .map(|r| {
+                    [
+                        r.id.clone(),
+                        r.version.clone(),
+                        r.name.clone(),
+                        published::display_date(r.published.as_deref()),
+                    ]
+                })
+                .collect();
+            table.print(data);
+        },
         SearchDisplay::Table => {
             let mut table = ascii_table::AsciiTable::default();
-            table.column(0).set_header("ID");
-            table.column(1).set_header("Version");
-            table.column(2).set_header("Name");
+            let header = &theme::current().header;
+            table.column(0).set_header(header.paint("ID"));
+            table.column(1).set_header(header.paint("Version"));
+            table.column(2).set_header(header.paint("Name"));
             // table.column(3).set_header("Description");
             let data: Vec<[&str; 3]> = results
                 .iter()
@@ -230,3 +521,48 @@ pub fn search(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{github_namespace, is_indexed};
+    use crate::github::{Owner, Repository};
+    use crate::registry::DevcontainerIndex;
+
+    #[test]
+    fn test_is_indexed() -> serde_json::Result<()> {
+        let index: DevcontainerIndex = serde_json::from_str(
+            r#"{"collections": [
+                {"sourceInformation": {"name": "a", "maintainer": "A", "contact": "", "repository": "", "ociReference": "ghcr.io/devcontainers/features"},
+                    "features": [], "templates": []},
+                {"sourceInformation": {"name": "b", "maintainer": "B", "contact": "", "repository": "", "ociReference": "ghcr.io/me/devcontainers/src"},
+                    "features": [], "templates": []}
+            ]}"#,
+        )?;
+        let repository = |login: &str, name: &str| Repository {
+            name: name.to_string(),
+            full_name: format!("{login}/{name}"),
+            owner: Owner {
+                login: login.to_string(),
+            },
+            html_url: format!("https://github.com/{login}/{name}"),
+            description: None,
+            stargazers_count: 0,
+        };
+
+        assert_eq!(
+            github_namespace(&repository("DevContainers", "Features")),
+            "ghcr.io/devcontainers/features"
+        );
+        assert!(is_indexed(
+            &index,
+            &github_namespace(&repository("devcontainers", "features"))
+        ));
+        assert!(is_indexed(
+            &index,
+            &github_namespace(&repository("me", "devcontainers"))
+        ));
+        assert!(!is_indexed(&index, &github_namespace(&repository("me", "features"))));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::env;
+use std::io;
+use std::path::Path;
+
+use clap::Args;
+use flate2::read::GzDecoder;
+use sha2::{Digest, Sha256};
+
+use crate::archive;
+use crate::atomic_write;
+use crate::github;
+
+const TARGET: &str = env!("TYEDEV_TARGET");
+
+#[derive(Debug, Args)]
+pub struct SelfUpdateArgs {
+    /// Only report whether a newer version is available.
+    #[arg(long)]
+    check: bool,
+}
+
+/// Compare against the first hex token of a `shasum` or `certutil` style checksum file.
+fn verify_checksum(archive: &[u8], checksum_file: &[u8]) -> io::Result<()> {
+    let expected = String::from_utf8_lossy(checksum_file)
+        .split_whitespace()
+        .next()
+        .map(|hex| hex.to_lowercase())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "The checksum file is empty"))?;
+    let actual = format!("{:x}", Sha256::digest(archive));
+
+    if expected != actual {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Checksum mismatch, expected {expected} but downloaded {actual}"),
+        ))?
+    }
+
+    Ok(())
+}
+
+/// Pull the executable out of the release archive, which contains a single top level directory.
+fn extract_executable(archive: &[u8]) -> io::Result<Vec<u8>> {
+    let executable_name = format!("{}{}", env!("CARGO_BIN_NAME"), env::consts::EXE_SUFFIX);
+    let found = archive::find_entry(GzDecoder::new(archive), |path| {
+        path.file_name()
+            .is_some_and(|filename| filename == executable_name.as_str())
+    })?;
+
+    match found {
+        Some((_, data)) => Ok(data),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("The release archive does not contain {executable_name}"),
+        )),
+    }
+}
+
+#[cfg(unix)]
+fn replace_executable(current: &Path, data: &[u8]) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    atomic_write::write_with_permissions(current, data, std::fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn replace_executable(current: &Path, data: &[u8]) -> io::Result<()> {
+    // A running executable cannot be overwritten on Windows, but it can be renamed out of the way.
+    let previous = current.with_extension("old.exe");
+    let _ = std::fs::remove_file(&previous);
+    std::fs::rename(current, &previous)?;
+    atomic_write::write(current, data)
+}
+
+pub async fn self_update(SelfUpdateArgs { check }: SelfUpdateArgs) -> anyhow::Result<()> {
+    log::debug!("self_update");
+
+    let current = semver::Version::parse(env!("CARGO_PKG_VERSION"))?;
+    let release = github::latest_release().await?;
+    let latest = release.version()?;
+
+    if latest <= current {
+        println!("{} {current} is up to date", env!("CARGO_PKG_NAME"));
+        return Ok(());
+    }
+
+    if check {
+        println!("A newer version is available: {current} -> {latest}");
+        println!("{}", release.html_url);
+        return Ok(());
+    }
+
+    let release_name = format!("{}-{latest}-{TARGET}", env!("CARGO_PKG_NAME"));
+    let archive_asset = release.asset(&format!("{release_name}.tar.gz"));
+    let checksum_asset = release.asset(&format!("{release_name}.sha256"));
+    let (Some(archive_asset), Some(checksum_asset)) = (archive_asset, checksum_asset) else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Release {} has no assets for {TARGET}", release.tag_name),
+        ))?
+    };
+
+    let archive = github::download(&archive_asset.browser_download_url).await?;
+    let checksum = github::download(&checksum_asset.browser_download_url).await?;
+
+    verify_checksum(&archive, &checksum)?;
+    log::info!("Verified checksum of {}", archive_asset.name);
+
+    let executable = extract_executable(&archive)?;
+    let current_exe = env::current_exe()?;
+
+    replace_executable(&current_exe, &executable)?;
+    println!("Updated {} from {current} to {latest}", current_exe.display());
+
+    log::debug!("self_update: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_checksum;
+
+    #[test]
+    fn test_verify_checksum() {
+        let archive = b"tyedev";
+        let checksum = b"2f5b7eb1a6b8b4bc7a0d8c8ed4d9d3c94bbb7e68b1fd69cfb3bd5d6ecae01c80  tyedev-0.4.1.tar.gz\n";
+
+        assert!(verify_checksum(archive, checksum).is_err());
+        assert!(verify_checksum(archive, b"").is_err());
+
+        let shasum = b"06ef10c8e7974ae97ba77aaaf2633faa47b6a4227adf601c4f32f4a3391c53bb  tyedev-0.4.1.tar.gz\n";
+        assert!(verify_checksum(archive, shasum).is_ok());
+
+        let certutil = b"06EF10C8E7974AE97BA77AAAF2633FAA47B6A4227ADF601C4F32F4A3391C53BB\r\n";
+        assert!(verify_checksum(archive, certutil).is_ok());
+    }
+}
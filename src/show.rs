@@ -0,0 +1,298 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::inspect::{self, Displayable, InspectDisplay, TableData};
+use crate::output::{Kind, OutputFormat};
+use crate::registry::LifecycleHook;
+use crate::workspace;
+
+/// Lifecycle hooks of devcontainer.json with their labels, in the order they run.
+const LIFECYCLE_HOOKS: [(&str, &str); 6] = [
+    ("initializeCommand", "Initialize Command"),
+    ("onCreateCommand", "On Create Command"),
+    ("updateContentCommand", "Update Content Command"),
+    ("postCreateCommand", "Post Create Command"),
+    ("postStartCommand", "Post Start Command"),
+    ("postAttachCommand", "Post Attach Command"),
+];
+
+#[derive(Debug, Args)]
+pub struct ShowArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// The devcontainer.json to show, relative to the workspace, rather than the first one found.
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Format for displaying the configuration.
+    #[arg(short, long, value_name = "FORMAT", default_value = "table")]
+    display_as: InspectDisplay,
+
+    /// Print the table as tab separated values without borders.
+    #[arg(long, visible_alias = "no-headers")]
+    plain: bool,
+}
+
+/// A feature of the `features` object, with the options as written.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct ConfiguredFeature {
+    pub key: String,
+    pub options: Value,
+}
+
+/// A lifecycle hook of devcontainer.json.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct ConfiguredHook {
+    pub name: String,
+    pub command: LifecycleHook,
+}
+
+/// The parts of a devcontainer.json that describe the dev container, for reading at a glance.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceConfig {
+    /// The devcontainer.json, relative to the workspace.
+    pub file: String,
+    pub name: Option<String>,
+    pub image: Option<String>,
+    /// The Dockerfile of `build`, or of the legacy `dockerFile`.
+    pub dockerfile: Option<String>,
+    pub context: Option<String>,
+    pub docker_compose_file: Vec<String>,
+    pub service: Option<String>,
+    pub remote_user: Option<String>,
+    pub features: Vec<ConfiguredFeature>,
+    pub forward_ports: Vec<Value>,
+    pub lifecycle_hooks: Vec<ConfiguredHook>,
+}
+
+/// A value that is a string, a number, or a boolean as plain text, and anything else as JSON.
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl WorkspaceConfig {
+    fn new(file: String, config: &Value) -> Self {
+        let text = |pointer: &str| config.pointer(pointer).and_then(Value::as_str).map(str::to_string);
+        let docker_compose_file = match config.get("dockerComposeFile") {
+            Some(Value::String(file)) => vec![file.clone()],
+            Some(Value::Array(files)) => files.iter().map(value_text).collect(),
+            _ => Vec::new(),
+        };
+        let features = match config.get("features") {
+            Some(Value::Object(features)) => features
+                .iter()
+                .map(|(key, options)| ConfiguredFeature {
+                    key: key.clone(),
+                    options: options.clone(),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let lifecycle_hooks = LIFECYCLE_HOOKS
+            .iter()
+            .filter_map(|(name, _)| {
+                let command = serde_json::from_value(config.get(*name)?.clone()).ok()?;
+                Some(ConfiguredHook {
+                    name: name.to_string(),
+                    command,
+                })
+            })
+            .collect();
+
+        WorkspaceConfig {
+            file,
+            name: text("/name"),
+            image: text("/image"),
+            dockerfile: text("/build/dockerfile").or_else(|| text("/dockerFile")),
+            context: text("/build/context").or_else(|| text("/context")),
+            docker_compose_file,
+            service: text("/service"),
+            remote_user: text("/remoteUser"),
+            features,
+            forward_ports: config
+                .get("forwardPorts")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default(),
+            lifecycle_hooks,
+        }
+    }
+}
+
+/// The options of a feature on one line, such as `version=20, nodeGypDependencies=false`.
+fn options_text(options: &Value) -> String {
+    match options {
+        Value::Object(options) if options.is_empty() => String::new(),
+        Value::Object(options) => options
+            .iter()
+            .map(|(name, value)| format!("{name}={}", value_text(value)))
+            .collect::<Vec<String>>()
+            .join(", "),
+        other => value_text(other),
+    }
+}
+
+impl Displayable for WorkspaceConfig {
+    fn table_rows(&self) -> Vec<[String; 2]> {
+        let mut data = TableData::new();
+        let non_empty = |values: &Vec<String>| (!values.is_empty()).then(|| values.join(", "));
+
+        data.push("File", &self.file);
+        data.maybe_push("Name", self.name.as_ref());
+        data.maybe_push("Image", self.image.as_ref());
+        data.maybe_push("Dockerfile", self.dockerfile.as_ref());
+        data.maybe_push("Context", self.context.as_ref());
+        data.maybe_push("Compose File", non_empty(&self.docker_compose_file));
+        data.maybe_push("Service", self.service.as_ref());
+        data.maybe_push("Remote User", self.remote_user.as_ref());
+        data.many_push(
+            "Features",
+            Some(
+                self.features
+                    .iter()
+                    .map(|feature| match options_text(&feature.options) {
+                        options if options.is_empty() => feature.key.clone(),
+                        options => format!("{} ({options})", feature.key),
+                    }),
+            ),
+        );
+        data.maybe_push(
+            "Forward Ports",
+            non_empty(&self.forward_ports.iter().map(value_text).collect()),
+        );
+
+        for hook in &self.lifecycle_hooks {
+            let label = LIFECYCLE_HOOKS
+                .iter()
+                .find(|(name, _)| *name == hook.name)
+                .map_or(hook.name.as_str(), |(_, label)| label);
+
+            data.push(label, &hook.command);
+        }
+
+        data.0
+    }
+}
+
+/// Print a devcontainer.json of the workspace the way `inspect` prints a feature or template.
+pub fn show(
+    ShowArgs {
+        workspace_folder,
+        config,
+        display_as,
+        plain,
+    }: ShowArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("show");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let file = match config {
+        Some(config) => workspace.join(config),
+        None => workspace::devcontainer_files(&workspace)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No devcontainer.json in {}", workspace.display()),
+                )
+            })?,
+    };
+    let display_as = match display_as {
+        InspectDisplay::Table if plain => InspectDisplay::Plain,
+        _ => display_as,
+    };
+    let display_name = file.strip_prefix(&workspace).unwrap_or(&file).display().to_string();
+
+    if !file.is_file() {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{display_name} does not exist"),
+        ))?
+    }
+
+    let config = workspace::read_config(&file)?;
+
+    if !config.is_object() {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Expected an object at the top level of {display_name}"),
+        ))?
+    }
+
+    let workspace_config = WorkspaceConfig::new(display_name, &config);
+
+    inspect::display_or_envelope(&workspace_config, Kind::WorkspaceConfig, &display_as, format)?;
+
+    log::debug!("show: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{options_text, WorkspaceConfig};
+    use crate::inspect::Displayable;
+    use serde_json::json;
+
+    #[test]
+    fn test_workspace_config() {
+        let config = json!({
+            "name": "Rust",
+            "build": { "dockerfile": "Dockerfile", "context": ".." },
+            "features": {
+                "ghcr.io/devcontainers/features/node:1": { "version": "20", "nodeGypDependencies": false },
+                "ghcr.io/devcontainers/features/go:1": {}
+            },
+            "forwardPorts": [3000, "db:5432"],
+            "postCreateCommand": "cargo build",
+            "onCreateCommand": ["rustup", "update"]
+        });
+        let workspace_config = WorkspaceConfig::new(".devcontainer/devcontainer.json".to_string(), &config);
+
+        assert_eq!(workspace_config.dockerfile.as_deref(), Some("Dockerfile"));
+        assert_eq!(
+            workspace_config
+                .lifecycle_hooks
+                .iter()
+                .map(|hook| hook.name.as_str())
+                .collect::<Vec<_>>(),
+            ["onCreateCommand", "postCreateCommand"]
+        );
+        assert_eq!(
+            options_text(&config["features"]["ghcr.io/devcontainers/features/node:1"]),
+            "version=20, nodeGypDependencies=false"
+        );
+        assert_eq!(
+            workspace_config.table_rows(),
+            [
+                ["File", ".devcontainer/devcontainer.json"],
+                ["Name", "Rust"],
+                ["Dockerfile", "Dockerfile"],
+                ["Context", ".."],
+                [
+                    "Features",
+                    "ghcr.io/devcontainers/features/node:1 (version=20, nodeGypDependencies=false)"
+                ],
+                ["", "ghcr.io/devcontainers/features/go:1"],
+                ["Forward Ports", "3000, db:5432"],
+                ["On Create Command", "rustup, update"],
+                ["Post Create Command", "cargo build"],
+            ]
+            .map(|[label, value]| [label.to_string(), value.to_string()])
+        );
+    }
+}
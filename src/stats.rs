@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+
+use ascii_table::{Align, AsciiTable};
+use clap::Args;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::{DevOption, DevcontainerIndex};
+use crate::theme;
+
+#[derive(Debug, Args)]
+pub struct StatsArgs {
+    /// Number of entries in the keyword and owner rankings.
+    #[arg(short, long, value_name = "COUNT", default_value_t = 10)]
+    top: usize,
+}
+
+/// Occurrences of one keyword, owner, or template type.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct Count {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Totals of one kind of index entry.
+#[derive(Debug, Default, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Totals {
+    pub collections: usize,
+    pub features: usize,
+    pub templates: usize,
+}
+
+/// Statistics of the whole index, deprecated entries included.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexStats {
+    pub total: Totals,
+    /// Deprecated on their own, or by belonging to a deprecated collection.
+    pub deprecated: Totals,
+    /// Keywords of features and templates, the most frequent first.
+    pub top_keywords: Vec<Count>,
+    /// Features published by each owner, the largest first.
+    pub features_per_owner: Vec<Count>,
+    /// Templates of each type, a template without one is `unspecified`.
+    pub templates_per_type: Vec<Count>,
+    pub average_feature_options: f64,
+    pub average_template_options: f64,
+}
+
+/// Order by count descending, then by name, keeping at most `top` entries.
+fn ranking(counts: BTreeMap<String, usize>, top: usize) -> Vec<Count> {
+    let mut ranking: Vec<Count> = counts.into_iter().map(|(name, count)| Count { name, count }).collect();
+
+    // The sort is stable, so entries with the same count stay in name order.
+    ranking.sort_by_key(|count| std::cmp::Reverse(count.count));
+    ranking.truncate(top);
+
+    ranking
+}
+
+fn average<'o>(options: impl Iterator<Item = Option<&'o IndexMap<String, DevOption>>>) -> f64 {
+    let (count, sum) = options.fold((0, 0), |(count, sum), options| {
+        (count + 1, sum + options.map_or(0, |options| options.len()))
+    });
+
+    if count == 0 {
+        0.0
+    } else {
+        sum as f64 / count as f64
+    }
+}
+
+fn index_stats(index: &DevcontainerIndex, top: usize) -> IndexStats {
+    log::debug!("index_stats");
+
+    let mut keywords = BTreeMap::<String, usize>::new();
+    let mut owners = BTreeMap::<String, usize>::new();
+    let mut types = BTreeMap::<String, usize>::new();
+
+    for feature in index.iter_features(true) {
+        *owners.entry(feature.owner.clone()).or_default() += 1;
+    }
+
+    for template in index.iter_templates(true) {
+        let r#type = template
+            .r#type
+            .as_ref()
+            .map_or_else(|| "unspecified".to_string(), ToString::to_string);
+        *types.entry(r#type).or_default() += 1;
+    }
+
+    let feature_keywords = index.iter_features(true).map(|feature| &feature.keywords);
+    let template_keywords = index.iter_templates(true).map(|template| &template.keywords);
+
+    for keyword in feature_keywords.chain(template_keywords).flatten().flatten() {
+        *keywords.entry(keyword.to_lowercase()).or_default() += 1;
+    }
+
+    let collections = index.collections();
+
+    IndexStats {
+        total: Totals {
+            collections: collections.len(),
+            features: index.iter_features(true).count(),
+            templates: index.iter_templates(true).count(),
+        },
+        deprecated: Totals {
+            collections: collections
+                .iter()
+                .filter(|collection| collection.is_deprecated())
+                .count(),
+            features: index
+                .iter_features(true)
+                .filter(|feature| index.is_deprecated(&feature.id))
+                .count(),
+            templates: index
+                .iter_templates(true)
+                .filter(|template| index.is_deprecated(&template.id))
+                .count(),
+        },
+        top_keywords: ranking(keywords, top),
+        features_per_owner: ranking(owners, top),
+        templates_per_type: ranking(types, usize::MAX),
+        average_feature_options: average(index.iter_features(true).map(|feature| feature.options.as_ref())),
+        average_template_options: average(index.iter_templates(true).map(|template| template.options.as_ref())),
+    }
+}
+
+fn print_counts(title: &str, counts: &[Count]) {
+    let header = &theme::current().header;
+    let data: Vec<[String; 2]> = counts
+        .iter()
+        .map(|count| [count.name.clone(), count.count.to_string()])
+        .collect();
+    let mut table = AsciiTable::default();
+
+    table.column(0).set_header(header.paint(title));
+    table
+        .column(1)
+        .set_header(header.paint("Count"))
+        .set_align(Align::Right);
+    table.print(data);
+}
+
+fn print_stats(stats: &IndexStats) {
+    let header = &theme::current().header;
+    let mut table = AsciiTable::default();
+
+    table.column(0).set_header(header.paint(""));
+    table
+        .column(1)
+        .set_header(header.paint("Total"))
+        .set_align(Align::Right);
+    table
+        .column(2)
+        .set_header(header.paint("Deprecated"))
+        .set_align(Align::Right);
+    table
+        .column(3)
+        .set_header(header.paint("Average Options"))
+        .set_align(Align::Right);
+    table.print([
+        [
+            "Collections".to_string(),
+            stats.total.collections.to_string(),
+            stats.deprecated.collections.to_string(),
+            String::new(),
+        ],
+        [
+            "Features".to_string(),
+            stats.total.features.to_string(),
+            stats.deprecated.features.to_string(),
+            format!("{:.1}", stats.average_feature_options),
+        ],
+        [
+            "Templates".to_string(),
+            stats.total.templates.to_string(),
+            stats.deprecated.templates.to_string(),
+            format!("{:.1}", stats.average_template_options),
+        ],
+    ]);
+
+    for (title, counts) in [
+        ("Template Type", &stats.templates_per_type),
+        ("Owner", &stats.features_per_owner),
+        ("Keyword", &stats.top_keywords),
+    ] {
+        println!();
+        print_counts(title, counts);
+    }
+}
+
+/// Summarize the index, for ecosystem reports and sanity checks of a pull.
+pub fn stats(index: &DevcontainerIndex, StatsArgs { top }: StatsArgs, format: OutputFormat) -> anyhow::Result<()> {
+    log::debug!("stats");
+
+    let stats = index_stats(index, top);
+
+    match format {
+        OutputFormat::Json => output::print_json(Kind::IndexStats, &stats)?,
+        OutputFormat::Text => print_stats(&stats),
+    }
+
+    log::debug!("stats: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{index_stats, Count, Totals};
+    use crate::registry::DevcontainerIndex;
+
+    #[test]
+    fn test_index_stats() -> serde_json::Result<()> {
+        let index: DevcontainerIndex = serde_json::from_str(
+            r#"{"collections": [
+                {
+                    "sourceInformation": {"name": "a", "maintainer": "A", "contact": "", "repository": "", "ociReference": "ghcr.io/a/features"},
+                    "features": [
+                        {"id": "ghcr.io/a/features/x", "version": "1.0.0", "name": "x", "owner": "a", "keywords": ["Node", "js"],
+                            "options": {"version": {"type": "string", "default": "lts"}}},
+                        {"id": "ghcr.io/a/features/y", "version": "1.0.0", "name": "y", "owner": "a", "keywords": ["node"], "deprecated": true}
+                    ],
+                    "templates": [
+                        {"id": "ghcr.io/a/templates/z", "version": "1.0.0", "name": "z", "type": "dockerCompose", "keywords": ["js"]}
+                    ]
+                },
+                {
+                    "sourceInformation": {"name": "b", "maintainer": "Deprecated", "contact": "", "repository": "", "ociReference": "ghcr.io/b/features"},
+                    "features": [{"id": "ghcr.io/b/features/w", "version": "1.0.0", "name": "w", "owner": "b"}],
+                    "templates": [{"id": "ghcr.io/b/templates/v", "version": "1.0.0", "name": "v"}]
+                }
+            ]}"#,
+        )?;
+        let stats = index_stats(&index, 1);
+        let count = |name: &str, count: usize| Count {
+            name: name.to_string(),
+            count,
+        };
+
+        assert_eq!(
+            stats.total,
+            Totals {
+                collections: 2,
+                features: 3,
+                templates: 2
+            }
+        );
+        assert_eq!(
+            stats.deprecated,
+            Totals {
+                collections: 1,
+                features: 2,
+                templates: 1
+            }
+        );
+        assert_eq!(stats.top_keywords, vec![count("js", 2)]);
+        assert_eq!(stats.features_per_owner, vec![count("a", 2)]);
+        assert_eq!(
+            stats.templates_per_type,
+            vec![count("docker-compose", 1), count("unspecified", 1)]
+        );
+        assert!((stats.average_feature_options - 1.0 / 3.0).abs() < f64::EPSILON);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ascii_table::{Align, AsciiTable};
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::init;
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::DevcontainerIndex;
+use crate::theme;
+use crate::validate::{self, Severity};
+use crate::workspace;
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// Folder to search, the current directory by default.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+}
+
+/// One devcontainer.json found in the repository.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct ConfigStatus {
+    /// Relative to the searched folder.
+    pub file: PathBuf,
+    /// The `name` of the configuration.
+    pub name: Option<String>,
+    /// The template noted by the generation header.
+    pub template: Option<String>,
+    /// Keys of the `features` object.
+    pub features: Vec<String>,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+fn config_status(root: &Path, file: &Path, index: &DevcontainerIndex) -> ConfigStatus {
+    let config = workspace::read_config(file).unwrap_or(Value::Null);
+    let template = fs::read_to_string(file)
+        .ok()
+        .and_then(|contents| init::recorded_template(&contents))
+        .map(|(reference, _)| reference);
+    let features = match config.get("features") {
+        Some(Value::Object(features)) => features.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    let diagnostics = validate::validate_file(file, index, false);
+    let count = |severity: Severity| {
+        diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == severity)
+            .count()
+    };
+
+    ConfigStatus {
+        file: file
+            .strip_prefix(root)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| file.to_path_buf()),
+        name: config.get("name").and_then(Value::as_str).map(String::from),
+        template,
+        features,
+        errors: count(Severity::Error),
+        warnings: count(Severity::Warning),
+    }
+}
+
+/// Validation state as a short text, such as `ok` or `2 errors, 1 warning`.
+fn validation(status: &ConfigStatus) -> String {
+    let plural = |count: usize, noun: &str| {
+        if count == 1 {
+            format!("{count} {noun}")
+        } else {
+            format!("{count} {noun}s")
+        }
+    };
+
+    match (status.errors, status.warnings) {
+        (0, 0) => "ok".to_string(),
+        (0, warnings) => plural(warnings, "warning"),
+        (errors, 0) => plural(errors, "error"),
+        (errors, warnings) => format!("{}, {}", plural(errors, "error"), plural(warnings, "warning")),
+    }
+}
+
+fn print_status(statuses: &[ConfigStatus]) {
+    if statuses.is_empty() {
+        println!("No devcontainer.json found");
+        return;
+    }
+
+    let theme = theme::current();
+    let header = &theme.header;
+    let data: Vec<[String; 5]> = statuses
+        .iter()
+        .map(|status| {
+            let state = validation(status);
+            let state = match (status.errors, status.warnings) {
+                (0, 0) => state,
+                (0, _) => theme.warning.paint(state),
+                _ => theme.error.paint(state),
+            };
+
+            [
+                status.file.display().to_string(),
+                status.name.clone().unwrap_or_default(),
+                status.template.clone().unwrap_or_default(),
+                status.features.len().to_string(),
+                state,
+            ]
+        })
+        .collect();
+    let mut table = AsciiTable::default();
+
+    table.column(0).set_header(header.paint("File"));
+    table.column(1).set_header(header.paint("Name"));
+    table.column(2).set_header(header.paint("Template"));
+    table
+        .column(3)
+        .set_header(header.paint("Features"))
+        .set_align(Align::Right);
+    table.column(4).set_header(header.paint("Validation"));
+    table.print(data);
+}
+
+/// Find every devcontainer.json of the repository, listing their template, features, and validation state.
+pub fn status(
+    index: &DevcontainerIndex,
+    StatusArgs { workspace_folder }: StatusArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("status");
+
+    let root = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let statuses: Vec<ConfigStatus> = workspace::discover_devcontainer_files(&root)?
+        .iter()
+        .map(|file| config_status(&root, file, index))
+        .collect();
+
+    match format {
+        OutputFormat::Json => output::print_json(Kind::Status, &statuses)?,
+        OutputFormat::Text => print_status(&statuses),
+    }
+
+    log::debug!("status: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{config_status, validation};
+    use crate::registry::DevcontainerIndex;
+    use std::path::PathBuf;
+    use std::{env, fs};
+
+    #[test]
+    fn test_status() -> anyhow::Result<()> {
+        let root = env::temp_dir().join(format!("tyedev-test-status-{}", std::process::id()));
+        let service = root.join("services/api/.devcontainer");
+        let ignored = root.join("node_modules/pkg");
+
+        fs::create_dir_all(&service)?;
+        fs::create_dir_all(&ignored)?;
+        fs::write(root.join(".devcontainer.json"), r#"{"image": "rust"}"#)?;
+        fs::write(
+            service.join("devcontainer.json"),
+            "// Generated by tyedev 0.4.0 on 1970-01-01T00:00:00Z.\n// Template: ghcr.io/devcontainers/templates/go:3\n\
+             {\"name\": \"API\", \"features\": {\"ghcr.io/devcontainers/features/node:1\": {}}}",
+        )?;
+        fs::write(ignored.join(".devcontainer.json"), "{}")?;
+
+        let index = DevcontainerIndex::default();
+        let statuses: Vec<_> = crate::workspace::discover_devcontainer_files(&root)?
+            .iter()
+            .map(|file| config_status(&root, file, &index))
+            .collect();
+
+        fs::remove_dir_all(&root)?;
+
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].file, PathBuf::from(".devcontainer.json"));
+        assert_eq!(validation(&statuses[0]), "ok");
+        assert_eq!(
+            statuses[1].file,
+            PathBuf::from("services/api/.devcontainer/devcontainer.json")
+        );
+        assert_eq!(statuses[1].name.as_deref(), Some("API"));
+        assert_eq!(
+            statuses[1].template.as_deref(),
+            Some("ghcr.io/devcontainers/templates/go:3")
+        );
+        assert_eq!(statuses[1].features, ["ghcr.io/devcontainers/features/node:1"]);
+
+        Ok(())
+    }
+}
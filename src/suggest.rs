@@ -0,0 +1,167 @@
+/// Suggestions are limited to a handful, the closest first.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Minimum Jaro-Winkler similarity of two names for a suggestion.
+const SIMILARITY_THRESHOLD: f64 = 0.9;
+
+/// Number of single character insertions, deletions, or substitutions to turn `a` into `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, a_char) in a.chars().enumerate() {
+        current[0] = i + 1;
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Similarity between 0 and 1 that favors a shared prefix, which catches abbreviations such as "common" for
+/// "common-utils" that are too far apart by edit distance.
+fn jaro_winkler(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    if a.is_empty() || b.is_empty() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut a_matched = vec![false; a.len()];
+    let mut b_matched = vec![false; b.len()];
+    let mut matches = 0;
+
+    for (i, a_char) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+
+        for j in start..end {
+            if !b_matched[j] && b[j] == *a_char {
+                a_matched[i] = true;
+                b_matched[j] = true;
+                matches += 1;
+                break;
+            }
+        }
+    }
+
+    if matches == 0 {
+        return 0.0;
+    }
+
+    let a_sequence = a.iter().zip(&a_matched).filter(|(_, &m)| m).map(|(c, _)| c);
+    let b_sequence = b.iter().zip(&b_matched).filter(|(_, &m)| m).map(|(c, _)| c);
+    let transpositions = a_sequence
+        .zip(b_sequence)
+        .filter(|(a_char, b_char)| a_char != b_char)
+        .count()
+        / 2;
+
+    let matches = matches as f64;
+    let jaro =
+        (matches / a.len() as f64 + matches / b.len() as f64 + (matches - transpositions as f64) / matches) / 3.0;
+    let prefix = a
+        .iter()
+        .zip(&b)
+        .take(4)
+        .take_while(|(a_char, b_char)| a_char == b_char)
+        .count();
+
+    jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+/// The last path segment, where typos matter. Ids share long registry and namespace prefixes.
+fn name(id: &str) -> &str {
+    id.rsplit('/').next().unwrap_or(id)
+}
+
+/// Candidates within a small edit distance of `query`, closest first. An exact match is not a suggestion.
+pub fn closest<'c>(query: &str, candidates: impl IntoIterator<Item = &'c str>) -> Vec<&'c str> {
+    let threshold = (name(query).chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter(|&candidate| candidate != query)
+        .map(|candidate| (levenshtein(query, candidate), candidate))
+        .filter(|&(distance, candidate)| {
+            distance <= threshold || jaro_winkler(name(query), name(candidate)) >= SIMILARITY_THRESHOLD
+        })
+        .collect();
+
+    scored.sort();
+    scored.dedup();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Explain that `query` was not found, pointing out near misses among `candidates` if there are any.
+pub fn with_suggestions<'c>(
+    error: anyhow::Error,
+    query: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+) -> anyhow::Error {
+    let suggestions = closest(query, candidates);
+
+    if suggestions.is_empty() {
+        error
+    } else {
+        error.context(format!(
+            "Could not find {query}. Did you mean {}?",
+            suggestions.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{closest, jaro_winkler, levenshtein};
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("node", "node"), 0);
+        assert_eq!(levenshtein("nodee", "node"), 1);
+        assert_eq!(levenshtein("go", "git"), 2);
+        assert_eq!(levenshtein("", "rust"), 4);
+    }
+
+    #[test]
+    fn test_jaro_winkler() {
+        assert_eq!(jaro_winkler("node", "node"), 1.0);
+        assert!(jaro_winkler("common", "common-utils") >= 0.9);
+        assert!(jaro_winkler("rust", "python") < 0.5);
+    }
+
+    #[test]
+    fn test_closest() {
+        let ids = [
+            "ghcr.io/devcontainers/features/node",
+            "ghcr.io/devcontainers/features/git",
+            "ghcr.io/devcontainers/features/common-utils",
+        ];
+
+        assert_eq!(
+            closest("ghcr.io/devcontainers/features/nodee", ids),
+            vec!["ghcr.io/devcontainers/features/node"]
+        );
+        assert_eq!(
+            closest("ghcr.io/devcontainer/features/git", ids),
+            vec!["ghcr.io/devcontainers/features/git"]
+        );
+        assert_eq!(
+            closest("ghcr.io/devcontainers/features/common", ids),
+            vec!["ghcr.io/devcontainers/features/common-utils"]
+        );
+        assert!(closest("ghcr.io/devcontainers/features/node", ids).is_empty());
+        assert!(closest("docker.io/library/debian", ids).is_empty());
+    }
+}
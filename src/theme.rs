@@ -0,0 +1,385 @@
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use inquire::ui::{Attributes, RenderConfig, StyleSheet, Styled};
+use serde::Deserialize;
+
+/// Standard environment variable that turns off colors, see <https://no-color.org>.
+const NO_COLOR_VARIABLE: &str = "NO_COLOR";
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// The sixteen terminal colors, so a theme follows the palette of the terminal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+
+impl Color {
+    fn ansi_code(self) -> u8 {
+        match self {
+            Self::Black => 30,
+            Self::Red => 31,
+            Self::Green => 32,
+            Self::Yellow => 33,
+            Self::Blue => 34,
+            Self::Magenta => 35,
+            Self::Cyan => 36,
+            Self::White => 37,
+            Self::BrightBlack => 90,
+            Self::BrightRed => 91,
+            Self::BrightGreen => 92,
+            Self::BrightYellow => 93,
+            Self::BrightBlue => 94,
+            Self::BrightMagenta => 95,
+            Self::BrightCyan => 96,
+            Self::BrightWhite => 97,
+        }
+    }
+
+    fn inquire_color(self) -> inquire::ui::Color {
+        use inquire::ui::Color as Inquire;
+
+        match self {
+            Self::Black => Inquire::Black,
+            Self::Red => Inquire::DarkRed,
+            Self::Green => Inquire::DarkGreen,
+            Self::Yellow => Inquire::DarkYellow,
+            Self::Blue => Inquire::DarkBlue,
+            Self::Magenta => Inquire::DarkMagenta,
+            Self::Cyan => Inquire::DarkCyan,
+            Self::White => Inquire::Grey,
+            Self::BrightBlack => Inquire::DarkGrey,
+            Self::BrightRed => Inquire::LightRed,
+            Self::BrightGreen => Inquire::LightGreen,
+            Self::BrightYellow => Inquire::LightYellow,
+            Self::BrightBlue => Inquire::LightBlue,
+            Self::BrightMagenta => Inquire::LightMagenta,
+            Self::BrightCyan => Inquire::LightCyan,
+            Self::BrightWhite => Inquire::White,
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let color = match s {
+            "black" => Self::Black,
+            "red" => Self::Red,
+            "green" => Self::Green,
+            "yellow" => Self::Yellow,
+            "blue" => Self::Blue,
+            "magenta" => Self::Magenta,
+            "cyan" => Self::Cyan,
+            "white" => Self::White,
+            "bright-black" | "grey" | "gray" => Self::BrightBlack,
+            "bright-red" => Self::BrightRed,
+            "bright-green" => Self::BrightGreen,
+            "bright-yellow" => Self::BrightYellow,
+            "bright-blue" => Self::BrightBlue,
+            "bright-magenta" => Self::BrightMagenta,
+            "bright-cyan" => Self::BrightCyan,
+            "bright-white" => Self::BrightWhite,
+            _ => Err(format!("Unknown color \"{s}\""))?,
+        };
+
+        Ok(color)
+    }
+}
+
+/// A color with optional emphasis, written in the config as words such as `"bold bright-red"`, or `"none"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+    underline: bool,
+}
+
+impl Style {
+    const NONE: Style = Style {
+        color: None,
+        bold: false,
+        underline: false,
+    };
+
+    const fn color(color: Color) -> Self {
+        Style {
+            color: Some(color),
+            bold: false,
+            underline: false,
+        }
+    }
+
+    const fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    fn ansi_codes(&self) -> Vec<String> {
+        let mut codes = Vec::new();
+
+        if self.bold {
+            codes.push("1".to_string());
+        }
+
+        if self.underline {
+            codes.push("4".to_string());
+        }
+
+        if let Some(color) = self.color {
+            codes.push(color.ansi_code().to_string());
+        }
+
+        codes
+    }
+
+    /// Wrap `text` in escape codes, unless colors are turned off.
+    pub fn paint<T: fmt::Display>(&self, text: T) -> String {
+        let codes = self.ansi_codes();
+
+        if codes.is_empty() || !current().enabled {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{text}\x1b[0m", codes.join(";"))
+        }
+    }
+
+    fn style_sheet(&self) -> StyleSheet {
+        let mut attributes = Attributes::empty();
+
+        if self.bold {
+            attributes |= Attributes::BOLD;
+        }
+
+        let style_sheet = StyleSheet::new().with_attr(attributes);
+
+        match self.color {
+            Some(color) => style_sheet.with_fg(color.inquire_color()),
+            None => style_sheet,
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut style = Style::NONE;
+
+        for word in s.split_whitespace() {
+            match word {
+                "none" => {},
+                "bold" => style.bold = true,
+                "underline" => style.underline = true,
+                color => style.color = Some(color.parse()?),
+            }
+        }
+
+        Ok(style)
+    }
+}
+
+impl TryFrom<String> for Style {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Starting point of a theme, before the individual styles of the config are applied.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    #[default]
+    Default,
+    NoColor,
+    HighContrast,
+}
+
+/// The `[theme]` section of `config.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ThemeConfig {
+    pub preset: Preset,
+    /// Table headers and labels.
+    pub header: Option<Style>,
+    /// Warnings about deprecated features and templates.
+    pub deprecated: Option<Style>,
+    pub diff_added: Option<Style>,
+    pub diff_removed: Option<Style>,
+    /// The file names and line numbers of a diff.
+    pub diff_header: Option<Style>,
+    /// Prompt markers, the highlighted option, and answers.
+    pub prompt: Option<Style>,
+    /// Problems found by validation that must be fixed.
+    pub error: Option<Style>,
+    /// Problems found by validation that are worth a look.
+    pub warning: Option<Style>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    /// Whether escape codes are written at all.
+    enabled: bool,
+    pub header: Style,
+    pub deprecated: Style,
+    pub diff_added: Style,
+    pub diff_removed: Style,
+    pub diff_header: Style,
+    pub prompt: Style,
+    pub error: Style,
+    pub warning: Style,
+}
+
+impl Theme {
+    fn preset(preset: Preset) -> Self {
+        match preset {
+            Preset::Default => Theme {
+                enabled: true,
+                header: Style::NONE.bold(),
+                deprecated: Style::color(Color::Yellow),
+                diff_added: Style::color(Color::Green),
+                diff_removed: Style::color(Color::Red),
+                diff_header: Style::color(Color::Cyan),
+                prompt: Style::color(Color::BrightGreen),
+                error: Style::color(Color::Red).bold(),
+                warning: Style::color(Color::Yellow),
+            },
+            Preset::NoColor => Theme {
+                enabled: false,
+                header: Style::NONE,
+                deprecated: Style::NONE,
+                diff_added: Style::NONE,
+                diff_removed: Style::NONE,
+                diff_header: Style::NONE,
+                prompt: Style::NONE,
+                error: Style::NONE,
+                warning: Style::NONE,
+            },
+            Preset::HighContrast => Theme {
+                enabled: true,
+                header: Style::color(Color::BrightWhite).bold(),
+                deprecated: Style::color(Color::BrightYellow).bold(),
+                diff_added: Style::color(Color::BrightGreen).bold(),
+                diff_removed: Style::color(Color::BrightRed).bold(),
+                diff_header: Style::color(Color::BrightCyan).bold(),
+                prompt: Style::color(Color::BrightCyan).bold(),
+                error: Style::color(Color::BrightRed).bold(),
+                warning: Style::color(Color::BrightYellow).bold(),
+            },
+        }
+    }
+
+    /// Apply the config on top of its preset. Colors are off for `NO_COLOR` or when stdout is not a terminal.
+    pub fn new(config: &ThemeConfig, terminal: bool) -> Self {
+        let preset = Theme::preset(config.preset);
+
+        Theme {
+            enabled: preset.enabled && terminal,
+            header: config.header.unwrap_or(preset.header),
+            deprecated: config.deprecated.unwrap_or(preset.deprecated),
+            diff_added: config.diff_added.unwrap_or(preset.diff_added),
+            diff_removed: config.diff_removed.unwrap_or(preset.diff_removed),
+            diff_header: config.diff_header.unwrap_or(preset.diff_header),
+            prompt: config.prompt.unwrap_or(preset.prompt),
+            error: config.error.unwrap_or(preset.error),
+            warning: config.warning.unwrap_or(preset.warning),
+        }
+    }
+
+    fn render_config(&self) -> RenderConfig {
+        if !self.enabled {
+            return RenderConfig::empty();
+        }
+
+        let prompt = self.prompt.style_sheet();
+        let mut render_config = RenderConfig::default_colored()
+            .with_prompt_prefix(Styled::new("?").with_style_sheet(prompt))
+            .with_highlighted_option_prefix(Styled::new(">").with_style_sheet(prompt))
+            .with_answer(prompt);
+
+        render_config.answered_prompt_prefix = Styled::new(">").with_style_sheet(prompt);
+        render_config
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset(Preset::NoColor)
+    }
+}
+
+/// Choose the theme for this run, including the style of prompts. Only the first call has an effect.
+pub fn init(config: &ThemeConfig) {
+    let no_color = std::env::var_os(NO_COLOR_VARIABLE).is_some_and(|value| !value.is_empty());
+    let theme = Theme::new(config, !no_color && io::stdout().is_terminal());
+
+    inquire::set_global_render_config(theme.render_config());
+
+    let _ = THEME.set(theme);
+}
+
+/// The theme chosen by [`init`], without colors when it was never called.
+pub fn current() -> &'static Theme {
+    THEME.get_or_init(Theme::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Preset, Style, Theme, ThemeConfig};
+
+    #[test]
+    fn test_parse_style() -> Result<(), String> {
+        assert_eq!("none".parse::<Style>()?, Style::NONE);
+        assert_eq!("red".parse::<Style>()?, Style::color(Color::Red));
+        assert_eq!(
+            "bold bright-cyan".parse::<Style>()?,
+            Style::color(Color::BrightCyan).bold()
+        );
+        assert!("purple".parse::<Style>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_theme_config() -> anyhow::Result<()> {
+        let config: ThemeConfig = toml::from_str("preset = \"high-contrast\"\ndeprecated = \"underline magenta\"")?;
+        let theme = Theme::new(&config, true);
+
+        assert_eq!(config.preset, Preset::HighContrast);
+        assert_eq!(theme.header, Style::color(Color::BrightWhite).bold());
+        assert_eq!(
+            theme.deprecated,
+            Style {
+                color: Some(Color::Magenta),
+                bold: false,
+                underline: true,
+            }
+        );
+        assert!(theme.enabled);
+        assert!(!Theme::new(&config, false).enabled);
+        assert!(!Theme::new(&toml::from_str("preset = \"no-color\"")?, true).enabled);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic_write;
+use crate::github;
+
+/// Environment alternative to the `--no-update-check` flag.
+pub const OPT_OUT_VARIABLE: &str = "TYEDEV_NO_UPDATE_CHECK";
+
+/// Name of the file, within the data directory, that remembers the last check.
+pub const CACHE_FILENAME: &str = "update-check.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct UpdateCheck {
+    /// Seconds since the unix epoch.
+    checked_at: u64,
+    latest_version: String,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn read_cache(filename: &Path) -> Option<UpdateCheck> {
+    let contents = fs::read_to_string(filename).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+async fn refresh_cache(filename: &Path) -> anyhow::Result<UpdateCheck> {
+    log::debug!("refresh_cache");
+
+    let release = tokio::time::timeout(REQUEST_TIMEOUT, github::latest_release()).await??;
+    let check = UpdateCheck {
+        checked_at: now(),
+        latest_version: release.version()?.to_string(),
+    };
+
+    atomic_write::write(filename, serde_json::to_string(&check)?)?;
+
+    Ok(check)
+}
+
+/// Print a one line hint when a newer release exists. Looks up the latest release at most once a day.
+pub async fn notify_if_outdated(data_dir: &Path) {
+    log::debug!("notify_if_outdated");
+
+    if !io::stderr().is_terminal() || !data_dir.exists() {
+        return;
+    }
+
+    let filename = data_dir.join(CACHE_FILENAME);
+    let cached = read_cache(&filename);
+    let check = match cached {
+        Some(check) if now().saturating_sub(check.checked_at) < CHECK_INTERVAL.as_secs() => check,
+        _ => match refresh_cache(&filename).await {
+            Ok(check) => check,
+            Err(error) => {
+                log::debug!("notify_if_outdated: {error}");
+                // Remember the attempt, so being offline does not cost a timeout on every run.
+                let check = UpdateCheck {
+                    checked_at: now(),
+                    latest_version: cached.map_or_else(|| env!("CARGO_PKG_VERSION").to_string(), |c| c.latest_version),
+                };
+                if let Ok(json) = serde_json::to_string(&check) {
+                    let _ = atomic_write::write(&filename, json);
+                }
+                return;
+            },
+        },
+    };
+
+    let current = semver::Version::parse(env!("CARGO_PKG_VERSION"));
+    let latest = semver::Version::parse(&check.latest_version);
+
+    if let (Ok(current), Ok(latest)) = (current, latest) {
+        if latest > current {
+            eprintln!(
+                "A new release of {} is available: {current} -> {latest}. Run `{} self-update` to upgrade.",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_BIN_NAME"),
+            );
+        }
+    }
+}
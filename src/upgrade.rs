@@ -0,0 +1,251 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use serde_json::{Map, Value};
+
+use crate::messages::{self, Message};
+use crate::oci_ref::OciReference;
+use crate::outdated;
+use crate::policy;
+use crate::prompt::{self, DefaultPrompter, InteractivePrompter, Prompter};
+use crate::provenance;
+use crate::registry::DevcontainerIndex;
+use crate::workspace;
+
+#[derive(Debug, Args)]
+pub struct UpgradeArgs {
+    /// Upgrade only these features, by id or by the key as written, rather than every outdated one.
+    #[arg(value_name = "FEATURE")]
+    features: Vec<String>,
+
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Print the upgrades without changing any file.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Upgrade every outdated feature without asking.
+    #[arg(short, long)]
+    yes: bool,
+}
+
+/// The tag that follows `latest` as closely as `pinned` followed its version: `1` becomes `2` and `1.4` becomes
+/// `2.3` for a latest of `2.3.1`, while a full version becomes `latest` itself. Nothing is returned for a tag that is not a version, such as
+/// `latest` or a digest, or when the tag would not change.
+fn upgraded_tag(pinned: &str, latest: &str) -> Option<String> {
+    let parts = pinned
+        .split('.')
+        .map(str::parse::<u64>)
+        .collect::<Result<Vec<u64>, _>>()
+        .ok()?;
+    let latest = semver::Version::parse(latest).ok()?;
+    let components = [latest.major, latest.minor, latest.patch];
+    let tag = components
+        .get(..parts.len())?
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<String>>()
+        .join(".");
+
+    (tag != pinned).then_some(tag)
+}
+
+/// Rename the keys of the `features` object of `config`, keeping their order and options.
+pub fn rename_features(config: &mut Value, renames: &[(String, String)]) {
+    let Some(Value::Object(features)) = config.get_mut("features") else {
+        return;
+    };
+    let renamed: Map<String, Value> = std::mem::take(features)
+        .into_iter()
+        .map(|(key, options)| {
+            let key = renames
+                .iter()
+                .find(|(from, _)| *from == key)
+                .map_or(key, |(_, to)| to.clone());
+            (key, options)
+        })
+        .collect();
+
+    *features = renamed;
+}
+
+/// The command that renders the template recorded by init at the newer version of the index, when there is one. A
+/// template is rendered into the files of the workspace rather than referenced by them, so it cannot be upgraded by
+/// rewriting a reference the way a feature is.
+fn template_upgrade(index: &DevcontainerIndex, workspace: &Path) -> anyhow::Result<Option<String>> {
+    let Some(provenance) = provenance::read_provenance(workspace)? else {
+        return Ok(None);
+    };
+    let (Some(template), Some(version)) = (provenance.template, provenance.version) else {
+        return Ok(None);
+    };
+    let reference: OciReference = template.parse()?;
+    let Some(latest) = (index.get_template(&reference.id()))
+        .map(|entry| entry.version.clone())
+        .filter(|latest| outdated::is_newer(&version, latest))
+    else {
+        return Ok(None);
+    };
+    let tag = reference.tag_name();
+    let command = if outdated::pin_admits(&tag, &latest) {
+        format!("{} regenerate --latest", env!("CARGO_BIN_NAME"))
+    } else {
+        let tag = upgraded_tag(&tag, &latest).unwrap_or_else(|| latest.clone());
+        format!(
+            "{} regenerate --template-id {}:{tag}",
+            env!("CARGO_BIN_NAME"),
+            reference.id()
+        )
+    };
+
+    Ok(Some(format!(
+        "The template {template} is at {version} and the index has {latest}, render it again with `{command}`"
+    )))
+}
+
+/// Bump the version of outdated features in the devcontainer.json files of the workspace.
+pub async fn upgrade(
+    index: &DevcontainerIndex,
+    UpgradeArgs {
+        features,
+        workspace_folder,
+        dry_run,
+        yes,
+    }: UpgradeArgs,
+) -> anyhow::Result<()> {
+    log::debug!("upgrade");
+
+    let workspace = workspace_folder.map_or_else(env::current_dir, Ok)?;
+    let mut prompter: Box<dyn Prompter> = if yes || dry_run {
+        Box::new(DefaultPrompter)
+    } else if prompt::is_interactive() {
+        Box::new(InteractivePrompter)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Not running in a terminal to confirm upgrades, pass --yes to upgrade every outdated feature",
+        ))?
+    };
+    let policy = policy::read_policy()?;
+    let mut upgraded = 0;
+
+    for file in workspace::devcontainer_files(&workspace)? {
+        let display_name = file.strip_prefix(&workspace).unwrap_or(&file).display().to_string();
+        let mut renames = Vec::new();
+
+        for drift in outdated::feature_drift(index, &workspace, &file).await? {
+            let id = drift.key.parse::<OciReference>()?.id();
+
+            if !features.is_empty() && !features.iter().any(|feature| *feature == drift.key || *feature == id) {
+                continue;
+            }
+
+            let Some(latest) = drift.latest.as_deref().filter(|_| drift.outdated) else {
+                continue;
+            };
+            let Some(tag) = upgraded_tag(&drift.pinned, latest) else {
+                log::warn!("{display_name}: {} is not pinned to a version, left as is", drift.key);
+                continue;
+            };
+            let key = format!("{id}:{tag}");
+
+            if dry_run {
+                println!("{display_name}: {} -> {key}", drift.key);
+                upgraded += 1;
+                continue;
+            }
+
+            let message = messages::format(Message::UpgradeFeature, &[("key", &drift.key), ("version", &tag)]);
+
+            if prompter.confirm(&message, Some(true))? {
+                renames.push((drift.key, key));
+            }
+        }
+
+        if renames.is_empty() {
+            continue;
+        }
+
+        let keys: Vec<String> = renames.iter().map(|(_, to)| to.clone()).collect();
+        policy.enforce_feature_keys(&keys).await?;
+
+        let contents = fs::read_to_string(&file)?;
+        let mut config = workspace::read_config(&file)?;
+
+        rename_features(&mut config, &renames);
+        workspace::write_config(&file, &contents, &config, &display_name)?;
+
+        for (from, to) in &renames {
+            println!("{display_name}: upgraded {from} to {to}");
+        }
+
+        upgraded += renames.len();
+    }
+
+    if upgraded == 0 {
+        println!("No features upgraded");
+    }
+
+    if let Some(hint) = template_upgrade(index, &workspace)? {
+        println!("{hint}");
+    }
+
+    log::debug!("upgrade: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rename_features, upgraded_tag};
+    use serde_json::json;
+
+    #[test]
+    fn test_upgraded_tag() {
+        assert_eq!(upgraded_tag("1", "2.3.1").as_deref(), Some("2"));
+        assert_eq!(upgraded_tag("1.4", "2.3.1").as_deref(), Some("2.3"));
+        assert_eq!(upgraded_tag("1.4.0", "1.4.2").as_deref(), Some("1.4.2"));
+        assert_eq!(upgraded_tag("2", "2.3.1"), None);
+        assert_eq!(upgraded_tag("latest", "2.3.1"), None);
+        assert_eq!(upgraded_tag("sha256:abc", "2.3.1"), None);
+    }
+
+    #[test]
+    fn test_rename_features() {
+        let mut config = json!({
+            "image": "debian",
+            "features": {
+                "ghcr.io/devcontainers/features/node:1": {"version": "20"},
+                "ghcr.io/devcontainers/features/go:1": {}
+            }
+        });
+
+        rename_features(
+            &mut config,
+            &[(
+                "ghcr.io/devcontainers/features/node:1".to_string(),
+                "ghcr.io/devcontainers/features/node:2".to_string(),
+            )],
+        );
+
+        assert_eq!(
+            config["features"],
+            json!({
+                "ghcr.io/devcontainers/features/node:2": {"version": "20"},
+                "ghcr.io/devcontainers/features/go:1": {}
+            })
+        );
+        assert_eq!(
+            config["features"]
+                .as_object()
+                .and_then(|features| features.keys().next())
+                .map(String::as_str),
+            Some("ghcr.io/devcontainers/features/node:2")
+        );
+    }
+}
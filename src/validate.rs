@@ -0,0 +1,803 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use clap::Args;
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::DevcontainerIndex;
+use crate::theme;
+use crate::workspace::{self, FeatureSource};
+
+/// How often the files of a workspace are checked for changes in watch mode.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+    /// Workspace holding the devcontainer configuration.
+    #[arg(short, long, value_name = "DIRECTORY")]
+    workspace_folder: Option<PathBuf>,
+
+    /// Keep running, and validate again whenever a file below `.devcontainer` changes.
+    #[arg(long)]
+    watch: bool,
+
+    /// Treat unknown properties as errors, including those of `build` and options that a feature does not declare.
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// One problem of a devcontainer.json.
+#[derive(Clone, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The top-level property at fault, missing when it concerns the whole file.
+    pub property: Option<String>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn error(property: Option<&str>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            property: property.map(str::to_string),
+            message: message.into(),
+        }
+    }
+
+    fn warning(property: Option<&str>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            property: property.map(str::to_string),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.property {
+            Some(property) => write!(f, "{}: {property}: {}", self.severity, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+/// The diagnostics of one devcontainer.json, empty when it is valid.
+#[derive(Clone, Debug, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReport {
+    pub file: PathBuf,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum JsonType {
+    String,
+    Boolean,
+    Number,
+    Array,
+    Object,
+}
+
+impl JsonType {
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(_) => Some(Self::String),
+            Value::Bool(_) => Some(Self::Boolean),
+            Value::Number(_) => Some(Self::Number),
+            Value::Array(_) => Some(Self::Array),
+            Value::Object(_) => Some(Self::Object),
+            Value::Null => None,
+        }
+    }
+}
+
+impl Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::String => write!(f, "a string"),
+            Self::Boolean => write!(f, "a boolean"),
+            Self::Number => write!(f, "a number"),
+            Self::Array => write!(f, "an array"),
+            Self::Object => write!(f, "an object"),
+        }
+    }
+}
+
+use JsonType::{Array, Boolean, Number, Object, String as Text};
+
+/// Top-level properties of the devcontainer.json reference, with the types each one accepts.
+const PROPERTIES: &[(&str, &[JsonType])] = &[
+    ("$schema", &[Text]),
+    ("name", &[Text]),
+    ("image", &[Text]),
+    ("build", &[Object]),
+    ("dockerComposeFile", &[Text, Array]),
+    ("service", &[Text]),
+    ("runServices", &[Array]),
+    ("workspaceFolder", &[Text]),
+    ("workspaceMount", &[Text]),
+    ("forwardPorts", &[Array]),
+    ("portsAttributes", &[Object]),
+    ("otherPortsAttributes", &[Object]),
+    ("appPort", &[Number, Text, Array]),
+    ("containerEnv", &[Object]),
+    ("remoteEnv", &[Object]),
+    ("containerUser", &[Text]),
+    ("remoteUser", &[Text]),
+    ("updateRemoteUserUID", &[Boolean]),
+    ("userEnvProbe", &[Text]),
+    ("overrideCommand", &[Boolean]),
+    ("shutdownAction", &[Text]),
+    ("init", &[Boolean]),
+    ("privileged", &[Boolean]),
+    ("capAdd", &[Array]),
+    ("securityOpt", &[Array]),
+    ("mounts", &[Array]),
+    ("runArgs", &[Array]),
+    ("features", &[Object]),
+    ("overrideFeatureInstallOrder", &[Array]),
+    ("customizations", &[Object]),
+    ("hostRequirements", &[Object]),
+    ("secrets", &[Object]),
+    ("waitFor", &[Text]),
+    ("initializeCommand", &[Text, Array, Object]),
+    ("onCreateCommand", &[Text, Array, Object]),
+    ("updateContentCommand", &[Text, Array, Object]),
+    ("postCreateCommand", &[Text, Array, Object]),
+    ("postStartCommand", &[Text, Array, Object]),
+    ("postAttachCommand", &[Text, Array, Object]),
+];
+
+/// Properties of `build`.
+const BUILD_PROPERTIES: &[&str] = &["dockerfile", "context", "args", "options", "target", "cacheFrom"];
+
+/// Properties from before the specification, with what replaced them.
+pub const LEGACY_PROPERTIES: &[(&str, &str)] = &[
+    ("dockerFile", "build.dockerfile"),
+    ("context", "build.context"),
+    ("extensions", "customizations.vscode.extensions"),
+    ("settings", "customizations.vscode.settings"),
+];
+
+/// Properties that accept one of a fixed set of strings.
+const ENUM_PROPERTIES: &[(&str, &[&str])] = &[
+    ("shutdownAction", &["none", "stopContainer", "stopCompose"]),
+    (
+        "userEnvProbe",
+        &["none", "loginShell", "loginInteractiveShell", "interactiveShell"],
+    ),
+    (
+        "waitFor",
+        &[
+            "initializeCommand",
+            "onCreateCommand",
+            "updateContentCommand",
+            "postCreateCommand",
+            "postStartCommand",
+            "postAttachCommand",
+        ],
+    ),
+];
+
+/// Mount types of the `type` of a mount string, as Docker accepts them.
+const MOUNT_TYPES: &[&str] = &["bind", "volume", "tmpfs"];
+
+fn check_types(config: &Map<String, Value>, strict: bool, diagnostics: &mut Vec<Diagnostic>) {
+    for (property, value) in config {
+        let Some(found) = JsonType::of(value) else {
+            continue;
+        };
+
+        if let Some((_, accepted)) = PROPERTIES.iter().find(|(name, _)| name == property) {
+            if !accepted.contains(&found) {
+                let expected: Vec<String> = accepted.iter().map(JsonType::to_string).collect();
+                diagnostics.push(Diagnostic::error(
+                    Some(property),
+                    format!("Expected {}, found {found}", expected.join(" or ")),
+                ));
+            }
+        } else if let Some((_, replacement)) = LEGACY_PROPERTIES.iter().find(|(name, _)| name == property) {
+            diagnostics.push(Diagnostic::warning(
+                Some(property),
+                format!("Deprecated, use {replacement} instead"),
+            ));
+        } else if strict {
+            diagnostics.push(Diagnostic::error(Some(property), "Unknown property"));
+        } else {
+            diagnostics.push(Diagnostic::warning(Some(property), "Unknown property"));
+        }
+    }
+}
+
+/// Properties that only strict validation reports: those of `build`, and options that the index does not know for
+/// a feature, which are most often typos.
+fn check_unknown(config: &Map<String, Value>, index: &DevcontainerIndex, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(Value::Object(build)) = config.get("build") {
+        for property in build
+            .keys()
+            .filter(|property| !BUILD_PROPERTIES.contains(&property.as_str()))
+        {
+            diagnostics.push(Diagnostic::error(Some("build"), format!("Unknown property {property}")));
+        }
+    }
+
+    let Some(Value::Object(features)) = config.get("features") else {
+        return;
+    };
+
+    for (key, options) in features {
+        let (Ok(FeatureSource::Registry(reference)), Value::Object(options)) =
+            (FeatureSource::parse(key, Path::new("")), options)
+        else {
+            continue;
+        };
+        let Some(feature) = index.get_feature(&reference.id()) else {
+            continue;
+        };
+        let declared = feature.options.as_ref();
+
+        for name in options
+            .keys()
+            .filter(|name| !declared.is_some_and(|declared| declared.contains_key(*name)))
+        {
+            diagnostics.push(Diagnostic::error(
+                Some("features"),
+                format!("{key} has no option {name}"),
+            ));
+        }
+    }
+}
+
+fn check_enums(config: &Map<String, Value>, diagnostics: &mut Vec<Diagnostic>) {
+    for (property, accepted) in ENUM_PROPERTIES {
+        if let Some(Value::String(value)) = config.get(*property) {
+            if !accepted.contains(&value.as_str()) {
+                diagnostics.push(Diagnostic::error(
+                    Some(property),
+                    format!("Expected one of {}, found {value}", accepted.join(", ")),
+                ));
+            }
+        }
+    }
+}
+
+/// Problems of a mount written as a Docker `--mount` string, such as `type=bind,source=/tmp,target=/tmp`.
+fn mount_string_errors(mount: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut has_target = false;
+
+    for field in mount.split(',') {
+        match field.split_once('=') {
+            Some(("type", kind)) if !MOUNT_TYPES.contains(&kind) => {
+                errors.push(format!("Unknown mount type {kind} in {mount}"));
+            },
+            Some(("target" | "destination" | "dst", target)) => has_target = !target.is_empty(),
+            Some(_) => {},
+            // Flags such as `readonly` stand alone.
+            None if matches!(field, "readonly" | "ro") => {},
+            None => errors.push(format!("Expected key=value, found {field} in {mount}")),
+        }
+    }
+
+    if !has_target {
+        errors.push(format!("Missing target in {mount}"));
+    }
+
+    errors
+}
+
+/// The `mounts` array, whose entries are mount strings or objects, and the `workspaceMount` string.
+fn check_mounts(config: &Map<String, Value>, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(Value::String(mount)) = config.get("workspaceMount") {
+        for error in mount_string_errors(mount) {
+            diagnostics.push(Diagnostic::error(Some("workspaceMount"), error));
+        }
+    }
+
+    let Some(Value::Array(mounts)) = config.get("mounts") else {
+        return;
+    };
+
+    for mount in mounts {
+        let errors = match mount {
+            Value::String(mount) => mount_string_errors(mount),
+            Value::Object(mount) => {
+                let mut errors = Vec::new();
+
+                match mount.get("type") {
+                    Some(Value::String(kind)) if kind == "bind" || kind == "volume" => {},
+                    Some(kind) => errors.push(format!("Expected a mount type of bind or volume, found {kind}")),
+                    None => errors.push("Missing type of a mount".to_string()),
+                }
+
+                if !matches!(mount.get("target"), Some(Value::String(_))) {
+                    errors.push("Missing target of a mount".to_string());
+                }
+
+                errors
+            },
+            other => vec![format!("Expected a string or an object, found {other}")],
+        };
+
+        for error in errors {
+            diagnostics.push(Diagnostic::error(Some("mounts"), error));
+        }
+    }
+}
+
+/// Entries of `forwardPorts`, which are port numbers or `host:port` strings.
+fn check_ports(config: &Map<String, Value>, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(Value::Array(ports)) = config.get("forwardPorts") else {
+        return;
+    };
+
+    for port in ports {
+        let valid = match port {
+            Value::Number(number) => number.as_u64().is_some_and(|number| (1..=65535).contains(&number)),
+            Value::String(port) => port
+                .rsplit_once(':')
+                .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok_and(|port| port > 0)),
+            _ => false,
+        };
+
+        if !valid {
+            diagnostics.push(Diagnostic::error(
+                Some("forwardPorts"),
+                format!("Expected a port number or host:port, found {port}"),
+            ));
+        }
+    }
+}
+
+fn check_container_source(config: &Map<String, Value>, diagnostics: &mut Vec<Diagnostic>) {
+    let sources: Vec<&str> = ["image", "build", "dockerFile", "dockerComposeFile"]
+        .into_iter()
+        .filter(|&property| config.contains_key(property))
+        .collect();
+
+    match sources.as_slice() {
+        [] => diagnostics.push(Diagnostic::error(
+            None,
+            "Missing one of image, build, or dockerComposeFile",
+        )),
+        ["build", "dockerFile"] | [_] => {},
+        _ => diagnostics.push(Diagnostic::error(
+            None,
+            format!("Only one of {} may be used", sources.join(", ")),
+        )),
+    }
+
+    if config.contains_key("dockerComposeFile") && !config.contains_key("service") {
+        diagnostics.push(Diagnostic::error(
+            Some("service"),
+            "Required along with dockerComposeFile",
+        ));
+    }
+}
+
+fn check_features(config: &Map<String, Value>, index: &DevcontainerIndex, diagnostics: &mut Vec<Diagnostic>) {
+    let Some(Value::Object(features)) = config.get("features") else {
+        return;
+    };
+
+    for (key, options) in features {
+        if !matches!(options, Value::Object(_) | Value::String(_) | Value::Bool(_)) {
+            diagnostics.push(Diagnostic::error(
+                Some("features"),
+                format!("Options of {key} must be an object, a version string, or a boolean"),
+            ));
+        }
+
+        match FeatureSource::parse(key, Path::new("")) {
+            Ok(FeatureSource::Registry(reference)) if index.is_deprecated(&reference.id()) => diagnostics.push(
+                Diagnostic::warning(Some("features"), format!("{} is deprecated", reference.id())),
+            ),
+            Ok(_) => {},
+            Err(error) => diagnostics.push(Diagnostic::error(Some("features"), error.to_string())),
+        }
+    }
+}
+
+/// Schema and lint checks of a parsed devcontainer.json, which do not depend on other files.
+pub fn check_config(config: &Value, index: &DevcontainerIndex, strict: bool) -> Vec<Diagnostic> {
+    let Value::Object(config) = config else {
+        return vec![Diagnostic::error(None, "Expected an object at the top level")];
+    };
+
+    let mut diagnostics = Vec::new();
+
+    check_types(config, strict, &mut diagnostics);
+    check_enums(config, &mut diagnostics);
+    check_mounts(config, &mut diagnostics);
+    check_ports(config, &mut diagnostics);
+    check_container_source(config, &mut diagnostics);
+    check_features(config, index, &mut diagnostics);
+
+    if strict {
+        check_unknown(config, index, &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+/// Schema and lint checks of the text of a devcontainer.json, such as one edited before it is written.
+pub fn check_text(contents: &str, index: &DevcontainerIndex) -> Vec<Diagnostic> {
+    let mut diagnostics = match serde_jsonc::from_str(contents) {
+        Ok(config) => check_config(&config, index, false),
+        Err(error) => vec![Diagnostic::error(None, format!("Invalid JSON: {error}"))],
+    };
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+    diagnostics
+}
+
+/// Files the configuration refers to, which are resolved relative to the folder of the devcontainer.json.
+fn check_referenced_files(config: &Value, directory: &Path, diagnostics: &mut Vec<Diagnostic>) {
+    let mut references: Vec<(&str, &str)> = Vec::new();
+
+    if let Some(dockerfile) = config.pointer("/build/dockerfile").and_then(Value::as_str) {
+        references.push(("build", dockerfile));
+    }
+
+    if let Some(dockerfile) = config.get("dockerFile").and_then(Value::as_str) {
+        references.push(("dockerFile", dockerfile));
+    }
+
+    match config.get("dockerComposeFile") {
+        Some(Value::String(file)) => references.push(("dockerComposeFile", file)),
+        Some(Value::Array(files)) => references.extend(
+            files
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|file| ("dockerComposeFile", file)),
+        ),
+        _ => {},
+    }
+
+    if let Some(Value::Object(features)) = config.get("features") {
+        references.extend(
+            features
+                .keys()
+                .filter(|key| matches!(FeatureSource::parse(key, Path::new("")), Ok(FeatureSource::Folder(_))))
+                .map(|key| ("features", key.as_str())),
+        );
+    }
+
+    for (property, reference) in references {
+        if !directory.join(reference).exists() {
+            diagnostics.push(Diagnostic::error(Some(property), format!("{reference} does not exist")));
+        }
+    }
+}
+
+/// Validate one devcontainer.json on disk.
+pub fn validate_file(file: &Path, index: &DevcontainerIndex, strict: bool) -> Vec<Diagnostic> {
+    log::debug!("validate_file: {}", file.display());
+
+    let contents = match fs::read_to_string(file) {
+        Ok(contents) => contents,
+        Err(error) => return vec![Diagnostic::error(None, error.to_string())],
+    };
+
+    let config: Value = match serde_jsonc::from_str(&contents) {
+        Ok(config) => config,
+        Err(error) => return vec![Diagnostic::error(None, format!("Invalid JSON: {error}"))],
+    };
+
+    let mut diagnostics = check_config(&config, index, strict);
+
+    if let Some(directory) = file.parent() {
+        check_referenced_files(&config, directory, &mut diagnostics);
+    }
+
+    diagnostics.sort_by_key(|diagnostic| diagnostic.severity);
+    diagnostics
+}
+
+fn validate_workspace(workspace: &Path, index: &DevcontainerIndex, strict: bool) -> io::Result<Vec<FileReport>> {
+    let reports = workspace::devcontainer_files(workspace)?
+        .into_iter()
+        .map(|file| {
+            let diagnostics = validate_file(&file, index, strict);
+            let file = file.strip_prefix(workspace).map(Path::to_path_buf).unwrap_or(file);
+
+            FileReport { file, diagnostics }
+        })
+        .collect();
+
+    Ok(reports)
+}
+
+fn count_errors(reports: &[&FileReport]) -> usize {
+    reports
+        .iter()
+        .flat_map(|report| &report.diagnostics)
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count()
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+fn print_reports(reports: &[&FileReport], format: OutputFormat) -> serde_json::Result<()> {
+    if format == OutputFormat::Json {
+        return output::print_json(Kind::Validation, reports);
+    }
+
+    let theme = theme::current();
+
+    for report in reports {
+        if report.diagnostics.is_empty() {
+            println!("{}: ok", report.file.display());
+            continue;
+        }
+
+        println!("{}", theme.header.paint(report.file.display()));
+
+        for diagnostic in &report.diagnostics {
+            let severity = match diagnostic.severity {
+                Severity::Error => theme.error.paint(diagnostic.severity),
+                Severity::Warning => theme.warning.paint(diagnostic.severity),
+            };
+
+            match &diagnostic.property {
+                Some(property) => println!("  {severity}: {property}: {}", diagnostic.message),
+                None => println!("  {severity}: {}", diagnostic.message),
+            }
+        }
+    }
+
+    let errors = count_errors(reports);
+    let warnings = reports.iter().map(|report| report.diagnostics.len()).sum::<usize>() - errors;
+
+    println!("{}, {}", plural(errors, "error"), plural(warnings, "warning"));
+
+    Ok(())
+}
+
+/// Modification time and size of every watched file, to notice saves without relying on platform file events.
+fn snapshot(workspace: &Path) -> io::Result<BTreeMap<PathBuf, (SystemTime, u64)>> {
+    let mut files = BTreeMap::new();
+
+    for file in workspace::watched_files(workspace)? {
+        // A file removed between listing and reading is picked up on the next poll.
+        if let Ok(metadata) = fs::metadata(&file) {
+            files.insert(file, (metadata.modified()?, metadata.len()));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Validate on every change until interrupted, printing only the files whose results changed.
+async fn watch(workspace: &Path, index: &DevcontainerIndex, strict: bool, format: OutputFormat) -> anyhow::Result<()> {
+    log::debug!("watch");
+
+    let mut files = snapshot(workspace)?;
+    let mut reports = validate_workspace(workspace, index, strict)?;
+
+    print_reports(&reports.iter().collect::<Vec<_>>(), format)?;
+    eprintln!("Watching {} for changes, press Ctrl-C to stop.", workspace.display());
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let current = snapshot(workspace)?;
+        if current == files {
+            continue;
+        }
+
+        files = current;
+
+        let previous = std::mem::replace(&mut reports, validate_workspace(workspace, index, strict)?);
+        let changed: Vec<&FileReport> = reports.iter().filter(|report| !previous.contains(report)).collect();
+
+        for report in &previous {
+            if !reports.iter().any(|current| current.file == report.file) {
+                eprintln!("{}: removed", report.file.display());
+            }
+        }
+
+        if !changed.is_empty() {
+            print_reports(&changed, format)?;
+        }
+    }
+}
+
+pub async fn validate(index: &DevcontainerIndex, args: ValidateArgs, format: OutputFormat) -> anyhow::Result<()> {
+    log::debug!("validate");
+
+    let workspace = args.workspace_folder.map_or_else(env::current_dir, Ok)?;
+
+    if args.watch {
+        return watch(&workspace, index, args.strict, format).await;
+    }
+
+    let reports = validate_workspace(&workspace, index, args.strict)?;
+
+    if reports.is_empty() {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No devcontainer.json found in {}", workspace.display()),
+        ))?
+    }
+
+    let reports: Vec<&FileReport> = reports.iter().collect();
+    print_reports(&reports, format)?;
+
+    let errors = count_errors(&reports);
+    if errors > 0 {
+        Err(output::failed(
+            format,
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Validation failed with {}", plural(errors, "error")),
+            ),
+        ))?
+    }
+
+    log::debug!("validate: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_config, Severity};
+    use crate::registry::DevcontainerIndex;
+
+    #[test]
+    fn test_check_config() -> anyhow::Result<()> {
+        let index = DevcontainerIndex::default();
+        let errors = |config: serde_json::Value| -> Vec<String> {
+            check_config(&config, &index, false)
+                .into_iter()
+                .map(|diagnostic| {
+                    format!(
+                        "{:?} {:?} {}",
+                        diagnostic.severity, diagnostic.property, diagnostic.message
+                    )
+                })
+                .collect()
+        };
+
+        assert!(errors(serde_json::json!({
+            "image": "mcr.microsoft.com/devcontainers/base:debian",
+            "features": { "ghcr.io/devcontainers/features/node:1": {}, "./local": true },
+            "forwardPorts": [3000],
+        }))
+        .is_empty());
+        assert_eq!(
+            errors(serde_json::json!({ "image": "debian", "forwardPorts": "3000" })),
+            vec!["Error Some(\"forwardPorts\") Expected an array, found a string"]
+        );
+        assert_eq!(
+            errors(serde_json::json!({ "name": "missing" })),
+            vec!["Error None Missing one of image, build, or dockerComposeFile"]
+        );
+        assert_eq!(
+            errors(serde_json::json!({ "dockerComposeFile": "compose.yml" })),
+            vec!["Error Some(\"service\") Required along with dockerComposeFile"]
+        );
+
+        let diagnostics = check_config(
+            &serde_json::json!({ "image": "debian", "extensions": [], "features": { "Bad Name": {} } }),
+            &index,
+            false,
+        );
+        let severities: Vec<Severity> = diagnostics.iter().map(|diagnostic| diagnostic.severity).collect();
+
+        assert_eq!(severities, vec![Severity::Warning, Severity::Error]);
+        assert!(check_config(&serde_json::json!([]), &index, false)[0].severity == Severity::Error);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_mounts_and_ports() {
+        let index = DevcontainerIndex::default();
+        let messages = |config: serde_json::Value| -> Vec<String> {
+            check_config(&config, &index, false)
+                .into_iter()
+                .map(|diagnostic| diagnostic.message)
+                .collect()
+        };
+
+        assert!(messages(serde_json::json!({
+            "image": "debian",
+            "mounts": [
+                "source=cache,target=/cache,type=volume",
+                { "type": "bind", "source": "/tmp", "target": "/tmp" }
+            ],
+            "workspaceMount": "source=${localWorkspaceFolder},target=/workspace,type=bind,readonly",
+            "forwardPorts": [3000, "db:5432"],
+            "shutdownAction": "stopContainer",
+        }))
+        .is_empty());
+        assert_eq!(
+            messages(serde_json::json!({
+                "image": "debian",
+                "mounts": ["source=cache,type=disk", { "type": "tmpfs", "target": "/tmp" }, 42],
+                "forwardPorts": [0, "5432"],
+                "waitFor": "postStart",
+            })),
+            [
+                "Expected one of initializeCommand, onCreateCommand, updateContentCommand, postCreateCommand, \
+                 postStartCommand, postAttachCommand, found postStart",
+                "Unknown mount type disk in source=cache,type=disk",
+                "Missing target in source=cache,type=disk",
+                "Expected a mount type of bind or volume, found \"tmpfs\"",
+                "Expected a string or an object, found 42",
+                "Expected a port number or host:port, found 0",
+                "Expected a port number or host:port, found \"5432\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_config_strict() -> anyhow::Result<()> {
+        let index: DevcontainerIndex = serde_json::from_str(
+            r#"{"collections": [{
+                "sourceInformation": {"name": "a", "maintainer": "A", "contact": "", "repository": "", "ociReference": "ghcr.io/a/features"},
+                "features": [{"id": "ghcr.io/a/features/node", "version": "1.0.0", "name": "node",
+                    "options": {"version": {"type": "string", "default": "lts"}}}],
+                "templates": []
+            }]}"#,
+        )?;
+        let config = serde_json::json!({
+            "build": { "dockerfile": "Dockerfile", "contxt": ".." },
+            "features": { "ghcr.io/a/features/node:1": { "version": "20", "verison": "18" } },
+            "hostRequirement": {},
+        });
+        let messages: Vec<String> = check_config(&config, &index, true)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            [
+                "Unknown property",
+                "Unknown property contxt",
+                "ghcr.io/a/features/node:1 has no option verison"
+            ]
+        );
+        assert!(check_config(&config, &index, false)
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use std::io;
+
+use ascii_table::AsciiTable;
+use clap::Args;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::oci_ref::OciReference;
+use crate::output::{self, Kind, OutputFormat};
+use crate::registry::{self, DevOption, DevcontainerIndex, Feature, Template};
+use crate::search::CollectionCategory;
+use crate::suggest;
+use crate::theme;
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// The feature or template to compare with its index entry, the `latest` tag when none is given.
+    #[arg(value_name = "OCI_REF")]
+    oci_ref: OciReference,
+}
+
+/// A field that differs between the index entry and the published configuration.
+#[derive(Debug, PartialEq, Serialize, JsonSchema)]
+pub struct Drift {
+    /// Name of the field, such as `version` or `options.version`.
+    pub field: String,
+    /// Value in the index, missing when only the published configuration has it.
+    pub index: Option<String>,
+    /// Value in the published configuration, missing when only the index has it.
+    pub published: Option<String>,
+}
+
+/// Result of comparing a published artifact with its index entry.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Verification {
+    pub collection: CollectionCategory,
+    pub reference: String,
+    /// Empty when the index is up to date.
+    pub drift: Vec<Drift>,
+}
+
+/// The fields of a feature or template that are compared.
+struct Fields<'f> {
+    version: &'f str,
+    name: &'f str,
+    description: Option<&'f str>,
+    deprecated: Option<bool>,
+    options: Option<&'f IndexMap<String, DevOption>>,
+}
+
+impl<'f> From<&'f Feature> for Fields<'f> {
+    fn from(feature: &'f Feature) -> Self {
+        Fields {
+            version: &feature.version,
+            name: &feature.name,
+            description: feature.description.as_deref(),
+            deprecated: Some(feature.is_deprecated()),
+            options: feature.options.as_ref(),
+        }
+    }
+}
+
+impl<'f> From<&'f Template> for Fields<'f> {
+    fn from(template: &'f Template) -> Self {
+        Fields {
+            version: &template.version,
+            name: &template.name,
+            description: template.description.as_deref(),
+            deprecated: None,
+            options: template.options.as_ref(),
+        }
+    }
+}
+
+fn push_drift(drift: &mut Vec<Drift>, field: &str, index: Option<String>, published: Option<String>) {
+    if index != published {
+        drift.push(Drift {
+            field: field.to_string(),
+            index,
+            published,
+        });
+    }
+}
+
+/// Every compared field that differs, options by name in the order of the index and then of the publication.
+fn compare(index: Fields, published: Fields) -> Vec<Drift> {
+    let mut drift = Vec::new();
+
+    push_drift(
+        &mut drift,
+        "version",
+        Some(index.version.to_string()),
+        Some(published.version.to_string()),
+    );
+    push_drift(
+        &mut drift,
+        "name",
+        Some(index.name.to_string()),
+        Some(published.name.to_string()),
+    );
+    push_drift(
+        &mut drift,
+        "description",
+        index.description.map(str::to_string),
+        published.description.map(str::to_string),
+    );
+    push_drift(
+        &mut drift,
+        "deprecated",
+        index.deprecated.map(|deprecated| deprecated.to_string()),
+        published.deprecated.map(|deprecated| deprecated.to_string()),
+    );
+
+    let empty = IndexMap::new();
+    let index_options = index.options.unwrap_or(&empty);
+    let published_options = published.options.unwrap_or(&empty);
+    let names = index_options.keys().chain(
+        published_options
+            .keys()
+            .filter(|name| !index_options.contains_key(*name)),
+    );
+
+    for name in names {
+        let index_option = index_options.get(name);
+        let published_option = published_options.get(name);
+
+        if index_option != published_option {
+            drift.push(Drift {
+                field: format!("options.{name}"),
+                index: index_option.map(ToString::to_string),
+                published: published_option.map(ToString::to_string),
+            });
+        }
+    }
+
+    drift
+}
+
+fn print_verification(verification: &Verification) {
+    if verification.drift.is_empty() {
+        println!("The index is up to date with {}.", verification.reference);
+        return;
+    }
+
+    let header = &theme::current().header;
+    let missing = || "-".to_string();
+    let data: Vec<[String; 3]> = verification
+        .drift
+        .iter()
+        .map(|drift| {
+            [
+                drift.field.clone(),
+                drift.index.clone().unwrap_or_else(missing),
+                drift.published.clone().unwrap_or_else(missing),
+            ]
+        })
+        .collect();
+    let mut table = AsciiTable::default();
+
+    table.column(0).set_header(header.paint("Field"));
+    table.column(1).set_header(header.paint("Index")).set_max_width(50);
+    table.column(2).set_header(header.paint("Published")).set_max_width(50);
+    table.print(data);
+}
+
+/// Compare the configuration of a published feature or template with its index entry, to notice an index that lags
+/// behind a publish.
+pub async fn verify(
+    index: &DevcontainerIndex,
+    VerifyArgs { oci_ref }: VerifyArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    log::debug!("verify");
+
+    let id = oci_ref.id();
+    let (collection, drift) = match (index.get_feature(&id), index.get_template(&id)) {
+        (Some(feature), _) => {
+            let published = registry::pull_feature_configuration(&oci_ref).await?;
+            (
+                CollectionCategory::Features,
+                compare(feature.into(), (&published).into()),
+            )
+        },
+        (None, Some(template)) => {
+            let published = registry::pull_template_configuration(&oci_ref).await?;
+            (
+                CollectionCategory::Templates,
+                compare(template.into(), (&published).into()),
+            )
+        },
+        (None, None) => {
+            let ids = index
+                .iter_features(true)
+                .map(|feature| feature.id.as_str())
+                .chain(index.iter_templates(true).map(|template| template.id.as_str()));
+            let error = io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{id} is not in the index, there is nothing to compare with"),
+            );
+
+            Err(suggest::with_suggestions(error.into(), &id, ids))?
+        },
+    };
+    let verification = Verification {
+        collection,
+        reference: oci_ref.to_string(),
+        drift,
+    };
+
+    match format {
+        OutputFormat::Json => output::print_json(Kind::Verification, &verification)?,
+        OutputFormat::Text => print_verification(&verification),
+    }
+
+    if !verification.drift.is_empty() {
+        Err(output::failed(
+            format,
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The index differs from {} in {} fields",
+                    verification.reference,
+                    verification.drift.len()
+                ),
+            ),
+        ))?
+    }
+
+    log::debug!("verify: done");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, Drift};
+    use crate::registry::Feature;
+
+    #[test]
+    fn test_compare() -> serde_json::Result<()> {
+        let index: Feature = serde_json::from_str(
+            r#"{"id": "node", "version": "1.2.0", "name": "Node.js",
+                "options": {"version": {"type": "string", "default": "lts"}, "nvm": {"type": "boolean", "default": true}}}"#,
+        )?;
+        let published: Feature = serde_json::from_str(
+            r#"{"id": "node", "version": "1.3.0", "name": "Node.js",
+                "options": {"version": {"type": "string", "default": "lts"}, "pnpm": {"type": "boolean", "default": true}}}"#,
+        )?;
+        let drift = compare((&index).into(), (&published).into());
+        let fields: Vec<&str> = drift.iter().map(|drift| drift.field.as_str()).collect();
+
+        assert_eq!(fields, ["version", "options.nvm", "options.pnpm"]);
+        assert_eq!(
+            drift[0],
+            Drift {
+                field: "version".into(),
+                index: Some("1.2.0".into()),
+                published: Some("1.3.0".into()),
+            }
+        );
+        assert!(drift[1].published.is_none());
+        assert!(compare((&index).into(), (&index).into()).is_empty());
+
+        Ok(())
+    }
+}
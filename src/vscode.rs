@@ -0,0 +1,110 @@
+use std::ffi::OsString;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::workspace;
+
+/// The VS Code command line launcher, a batch file on Windows.
+#[cfg(windows)]
+const CODE: &str = "code.cmd";
+#[cfg(not(windows))]
+const CODE: &str = "code";
+
+/// How to hand the workspace over to VS Code once init is done.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum OpenCode {
+    /// Leave the workspace alone.
+    #[default]
+    Never,
+    /// Open the workspace folder, VS Code then offers to reopen it in the container.
+    Folder,
+    /// Reopen the workspace in the devcontainer directly.
+    Container,
+}
+
+/// The folder of the workspace inside the container, `workspaceFolder` or else the default of the specification.
+fn container_folder(workspace: &Path) -> String {
+    let configured = workspace::devcontainer_files(workspace)
+        .ok()
+        .and_then(|files| files.into_iter().next())
+        .and_then(|file| workspace::read_config(&file).ok())
+        .and_then(|config| config.get("workspaceFolder").and_then(Value::as_str).map(String::from));
+
+    configured.unwrap_or_else(|| {
+        let name = workspace.file_name().unwrap_or_default().to_string_lossy();
+        format!("/workspaces/{name}")
+    })
+}
+
+/// Authority of the remote, the host path of the workspace encoded as hex.
+fn dev_container_authority(workspace: &Path) -> String {
+    let hex: String = workspace
+        .to_string_lossy()
+        .bytes()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+
+    format!("dev-container+{hex}")
+}
+
+/// Launch VS Code on the workspace. Without VS Code on PATH a deep link is printed instead, which opens it from a
+/// browser or another machine's terminal.
+pub fn open(workspace: &Path, mode: OpenCode) -> anyhow::Result<()> {
+    log::debug!("open: {mode:?}");
+
+    let workspace = workspace.canonicalize()?;
+    let (args, link): (Vec<OsString>, String) = match mode {
+        OpenCode::Never => return Ok(()),
+        OpenCode::Folder => (
+            vec![workspace.clone().into_os_string()],
+            format!("vscode://file{}", workspace.to_string_lossy().replace('\\', "/")),
+        ),
+        OpenCode::Container => {
+            let path = format!(
+                "{}{}",
+                dev_container_authority(&workspace),
+                container_folder(&workspace)
+            );
+
+            (
+                vec!["--folder-uri".into(), format!("vscode-remote://{path}").into()],
+                format!("vscode://vscode-remote/{path}"),
+            )
+        },
+    };
+
+    match Command::new(CODE).args(&args).status() {
+        Ok(status) if status.success() => {},
+        Ok(status) => log::warn!("{CODE} exited with {status}, open {link} to continue"),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            println!("VS Code was not found on PATH, open {link} to continue.");
+        },
+        Err(error) => Err(error)?,
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{container_folder, dev_container_authority};
+    use std::path::Path;
+
+    #[test]
+    fn test_dev_container_authority() {
+        assert_eq!(
+            dev_container_authority(Path::new("/home/me/project")),
+            "dev-container+2f686f6d652f6d652f70726f6a656374"
+        );
+        assert_eq!(
+            container_folder(Path::new("/nonexistent/tyedev/project")),
+            "/workspaces/project"
+        );
+    }
+}
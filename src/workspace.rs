@@ -0,0 +1,297 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde_json::Value;
+
+use crate::atomic_write;
+use crate::init;
+use crate::jsonc;
+use crate::oci_ref::OciReference;
+
+/// Folder holding the devcontainer configuration of a workspace.
+pub const DEVCONTAINER_DIRECTORY: &str = ".devcontainer";
+
+/// The devcontainer.json files of a workspace, in the locations the specification looks for them:
+/// `.devcontainer/devcontainer.json`, `.devcontainer.json`, and `.devcontainer/<folder>/devcontainer.json`.
+pub fn devcontainer_files(workspace: &Path) -> io::Result<Vec<PathBuf>> {
+    log::debug!("devcontainer_files");
+
+    let directory = workspace.join(DEVCONTAINER_DIRECTORY);
+    let mut files: Vec<PathBuf> = [
+        directory.join("devcontainer.json"),
+        workspace.join(".devcontainer.json"),
+    ]
+    .into_iter()
+    .filter(|path| path.is_file())
+    .collect();
+
+    if directory.is_dir() {
+        let mut nested: Vec<PathBuf> = fs::read_dir(&directory)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join("devcontainer.json"))
+            .filter(|path| path.is_file())
+            .collect();
+
+        nested.sort();
+        files.append(&mut nested);
+    }
+
+    Ok(files)
+}
+
+/// Folders that hold dependencies or build output rather than projects, skipped when looking for workspaces.
+const SKIPPED_DIRECTORIES: [&str; 3] = ["node_modules", "target", "vendor"];
+
+/// The devcontainer.json files of `root` and of every project folder below it, for repositories that hold several
+/// workspaces. Hidden folders and folders of dependencies are not searched.
+pub fn discover_devcontainer_files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    fn walk(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        files.append(&mut devcontainer_files(directory)?);
+
+        let mut subdirectories: Vec<PathBuf> = fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                !name.starts_with('.') && !SKIPPED_DIRECTORIES.contains(&name.as_ref())
+            })
+            .map(|entry| entry.path())
+            .collect();
+
+        subdirectories.sort();
+
+        for subdirectory in subdirectories {
+            walk(&subdirectory, files)?;
+        }
+
+        Ok(())
+    }
+
+    log::debug!("discover_devcontainer_files");
+
+    let mut files = Vec::new();
+    walk(root, &mut files)?;
+
+    Ok(files)
+}
+
+/// Files at the top of a workspace that tell its language, with the names of the templates made for it. A marker
+/// starting with `*` matches by extension.
+const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    ("Cargo.toml", &["rust"]),
+    ("go.mod", &["go"]),
+    ("package.json", &["javascript-node", "typescript-node"]),
+    ("tsconfig.json", &["typescript-node"]),
+    ("pyproject.toml", &["python"]),
+    ("requirements.txt", &["python"]),
+    ("setup.py", &["python"]),
+    ("Pipfile", &["python"]),
+    ("pom.xml", &["java"]),
+    ("build.gradle", &["java"]),
+    ("build.gradle.kts", &["java"]),
+    ("*.csproj", &["dotnet"]),
+    ("*.sln", &["dotnet"]),
+    ("Gemfile", &["ruby"]),
+    ("composer.json", &["php"]),
+    ("CMakeLists.txt", &["cpp"]),
+];
+
+/// Names of the templates suited to the languages of the workspace, judged by the files at its top. A workspace that
+/// cannot be read suggests nothing.
+pub fn suggested_templates(workspace: &Path) -> Vec<&'static str> {
+    log::debug!("suggested_templates");
+
+    let Ok(entries) = fs::read_dir(workspace) else {
+        return Vec::new();
+    };
+    let names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    let mut suggested = Vec::new();
+
+    for (marker, templates) in LANGUAGE_MARKERS {
+        let found = match marker.strip_prefix('*') {
+            Some(extension) => names.iter().any(|name| name.ends_with(extension)),
+            None => names.iter().any(|name| name == marker),
+        };
+
+        if found {
+            for template in *templates {
+                if !suggested.contains(template) {
+                    suggested.push(*template);
+                }
+            }
+        }
+    }
+
+    suggested
+}
+
+/// Every file below `.devcontainer`, along with `.devcontainer.json`, which are the files that affect the container.
+pub fn watched_files(workspace: &Path) -> io::Result<Vec<PathBuf>> {
+    fn walk(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(directory)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+
+            if file_type.is_dir() {
+                walk(&entry.path(), files)?;
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    let directory = workspace.join(DEVCONTAINER_DIRECTORY);
+    let single_file = workspace.join(".devcontainer.json");
+
+    if directory.is_dir() {
+        walk(&directory, &mut files)?;
+    }
+
+    if single_file.is_file() {
+        files.push(single_file);
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// The image of the first `FROM` instruction of a Dockerfile.
+pub fn dockerfile_base_image(contents: &str) -> Option<String> {
+    contents.lines().find_map(|line| {
+        let mut words = line.split_whitespace();
+        words
+            .next()
+            .filter(|instruction| instruction.eq_ignore_ascii_case("FROM"))
+            .and_then(|_| words.find(|word| !word.starts_with("--")))
+            .map(String::from)
+    })
+}
+
+/// The base image of a parsed devcontainer.json, from its `image` or else the `FROM` of the Dockerfile it builds.
+/// Images that still depend on a build argument cannot be known ahead of the build.
+pub fn base_image(file: &Path, config: &Value) -> Option<String> {
+    let directory = file.parent()?;
+    let image = config
+        .get("image")
+        .and_then(Value::as_str)
+        .map(String::from)
+        .or_else(|| {
+            let dockerfile = config
+                .pointer("/build/dockerfile")
+                .or_else(|| config.get("dockerFile"))
+                .and_then(Value::as_str)?;
+
+            dockerfile_base_image(&fs::read_to_string(directory.join(dockerfile)).ok()?)
+        });
+
+    image.filter(|image| !image.contains('$'))
+}
+
+/// Parse a devcontainer.json, which may hold comments and trailing commas.
+pub fn read_config(file: &Path) -> anyhow::Result<Value> {
+    log::debug!("read_config: {}", file.display());
+
+    let contents = fs::read_to_string(file)?;
+    let config = serde_jsonc::from_str(&contents).with_context(|| format!("Invalid JSON in {}", file.display()))?;
+
+    Ok(config)
+}
+
+/// Write `config` over the devcontainer.json that held `contents`, keeping its generation header. Only what changed
+/// is rewritten, so comments and formatting are kept. When that cannot be done the whole file is written anew, and
+/// comments that are lost are pointed out with a warning naming it as `display_name`.
+pub fn write_config(file: &Path, contents: &str, config: &Value, display_name: &str) -> anyhow::Result<()> {
+    log::debug!("write_config: {}", file.display());
+
+    let body = init::without_generation_header(contents.as_bytes());
+    let header = &contents.as_bytes()[..contents.len() - body.len()];
+    let mut bytes = header.to_vec();
+
+    match jsonc::update(&contents[header.len()..], config) {
+        Some(updated) => bytes.extend(updated.into_bytes()),
+        None => {
+            if serde_json::from_slice::<Value>(body).is_err() {
+                log::warn!("Comments have been stripped from {display_name}");
+            }
+
+            init::serde_json_pretty::to_writer_with_tabs(&mut bytes, config)?;
+            bytes.push(b'\n');
+        },
+    }
+
+    atomic_write::write(file, bytes)?;
+
+    Ok(())
+}
+
+/// Where a feature of the `features` object comes from.
+#[derive(Clone, Debug)]
+pub enum FeatureSource {
+    Registry(OciReference),
+    /// A folder relative to the devcontainer.json, written as `./name` or `../name`.
+    Folder(PathBuf),
+    /// A tarball downloaded over HTTP.
+    Url(String),
+}
+
+impl FeatureSource {
+    /// Classify a key of the `features` object, resolving folders relative to `directory`.
+    pub fn parse(key: &str, directory: &Path) -> anyhow::Result<Self> {
+        if key.starts_with("./") || key.starts_with("../") {
+            Ok(Self::Folder(directory.join(key)))
+        } else if key.starts_with("https://") || key.starts_with("http://") {
+            Ok(Self::Url(key.to_string()))
+        } else {
+            Ok(Self::Registry(key.parse()?))
+        }
+    }
+}
+
+/// A feature used by one of the devcontainer.json files of a workspace.
+#[derive(Clone, Debug)]
+pub struct WorkspaceFeature {
+    /// The key as written in the `features` object.
+    pub key: String,
+    pub source: FeatureSource,
+}
+
+/// Every feature of the workspace configuration, once each, in the order of first use.
+pub fn workspace_features(workspace: &Path) -> anyhow::Result<Vec<WorkspaceFeature>> {
+    log::debug!("workspace_features");
+
+    let mut features: Vec<WorkspaceFeature> = Vec::new();
+
+    for file in devcontainer_files(workspace)? {
+        let config = read_config(&file)?;
+        let directory = file.parent().unwrap_or(workspace);
+
+        if let Some(Value::Object(entries)) = config.get("features") {
+            for key in entries.keys() {
+                let source = FeatureSource::parse(key, directory).with_context(|| format!("In {}", file.display()))?;
+                let duplicate = features.iter().any(|feature| match (&feature.source, &source) {
+                    (FeatureSource::Folder(a), FeatureSource::Folder(b)) => a == b,
+                    _ => feature.key == *key,
+                });
+
+                if !duplicate {
+                    features.push(WorkspaceFeature {
+                        key: key.clone(),
+                        source,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(features)
+}